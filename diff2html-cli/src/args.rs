@@ -3,6 +3,7 @@
 //! This module defines all command-line arguments for the diff2html CLI tool.
 
 use clap::{Parser, ValueEnum};
+use diff2html::defaults;
 
 /// CLI arguments for diff2html.
 #[derive(Parser, Debug)]
@@ -36,10 +37,46 @@ pub struct Args {
     #[arg(short = 'F', long)]
     pub file: Option<String>,
 
+    /// Write one HTML page per file and an index page to a directory (overrides output option)
+    #[arg(long = "outputDir", conflicts_with = "file")]
+    pub output_dir: Option<String>,
+
+    /// Browser command to preview the output with (defaults to the BROWSER environment variable)
+    #[arg(long)]
+    pub browser: Option<String>,
+
+    /// Write the preview file and print its path without opening a browser
+    #[arg(long = "noOpen", alias = "no-open", conflicts_with = "browser")]
+    pub no_open: bool,
+
+    /// Directory to write preview files to (defaults to the system temp directory)
+    #[arg(long = "previewDir", alias = "preview-dir")]
+    pub preview_dir: Option<String>,
+
+    /// How long to keep old preview files: never, always, or a number of days like 7d
+    #[arg(long = "keepPreview", alias = "keep-preview", default_value = "always")]
+    pub keep_preview: String,
+
+    /// Compress the output written to a file or stdout
+    #[arg(long, value_enum, conflicts_with = "output_dir")]
+    pub compress: Option<CompressionType>,
+
     /// Page title for HTML output
     #[arg(short = 't', long)]
     pub title: Option<String>,
 
+    /// HTML markup of the diff lines
+    #[arg(long = "markup", value_enum, default_value = "table")]
+    pub markup: MarkupType,
+
+    /// Group the files under a header per directory or status
+    #[arg(long = "groupBy", value_enum, default_value = "none")]
+    pub group_by: GroupByType,
+
+    /// Viewport width in pixels below which the side-by-side panels are stacked
+    #[arg(long = "responsiveBreakpoint")]
+    pub responsive_breakpoint: Option<u32>,
+
     /// Color scheme of HTML output
     #[arg(long = "colorScheme", value_enum, default_value = "auto")]
     pub color_scheme: ColorSchemeType,
@@ -53,11 +90,11 @@ pub struct Args {
     pub matching: LineMatchingType,
 
     /// Diff line matching word threshold
-    #[arg(long = "matchWordsThreshold", default_value = "0.25")]
+    #[arg(long = "matchWordsThreshold", default_value_t = defaults::MATCH_WORDS_THRESHOLD)]
     pub match_words_threshold: f64,
 
     /// Maximum line comparisons of a block of changes
-    #[arg(long = "matchingMaxComparisons", default_value = "1000")]
+    #[arg(long = "matchingMaxComparisons", default_value_t = defaults::MATCHING_MAX_COMPARISONS)]
     pub matching_max_comparisons: usize,
 
     /// Number of changed lines after which a file diff is deemed as too big
@@ -68,34 +105,201 @@ pub struct Args {
     #[arg(long = "diffMaxLineLength")]
     pub diff_max_line_length: Option<usize>,
 
+    /// Number of files after which the remaining files are not shown
+    #[arg(long = "diffMaxFiles")]
+    pub diff_max_files: Option<usize>,
+
+    /// Number of changed lines over all files after which the remaining files are not shown
+    #[arg(long = "diffMaxTotalChanges")]
+    pub diff_max_total_changes: Option<u32>,
+
+    /// Keep the content of too big files and render it collapsed
+    #[arg(long = "keepTooBigContent")]
+    pub keep_too_big_content: bool,
+
     /// Render nothing if the diff shows no change
     #[arg(long = "renderNothingWhenEmpty")]
     pub render_nothing_when_empty: bool,
 
+    /// Show the similarity index and checksums in file headers
+    #[arg(long = "showFileMetadata")]
+    pub show_file_metadata: bool,
+
+    /// Keep file and block headers at the top of the page while scrolling
+    #[arg(long = "stickyHeaders")]
+    pub sticky_headers: bool,
+
+    /// Wrap long lines instead of scrolling horizontally
+    #[arg(long = "wrapLines")]
+    pub wrap_lines: bool,
+
+    /// Show a menu linking to each file in the corner of the page
+    #[arg(long = "jumpMenu")]
+    pub jump_menu: bool,
+
+    /// Show a bar of the changed lines by language above the file list
+    #[arg(long = "languageBar")]
+    pub language_bar: bool,
+
+    /// Link file names to a URL, with {path}, {old_path}, {new_path}, {rev}, {old_rev} and {new_rev} placeholders
+    #[arg(long = "fileUrlTemplate")]
+    pub file_url_template: Option<String>,
+
+    /// Show a table of contents of the files, nested by directory, in a sidebar
+    #[arg(long)]
+    pub toc: bool,
+
+    /// Render added and deleted files in a single column in side-by-side view
+    #[arg(long = "singleColumnAddedDeleted")]
+    pub single_column_added_deleted: bool,
+
+    /// Alternate deleted and inserted lines of changes in line-by-line view
+    #[arg(long = "interleaveChanges")]
+    pub interleave_changes: bool,
+
+    /// Handling of changed lines differing only in whitespace
+    #[arg(long = "whitespaceChanges", value_enum, default_value = "show")]
+    pub whitespace_changes: WhitespaceChangesType,
+
+    /// HTML IDs of the files, linked from the file list
+    #[arg(long = "htmlIdStrategy", value_enum, default_value = "hash")]
+    pub html_id_strategy: HtmlIdStrategyType,
+
+    /// Display of the paths of renamed and copied files in the file header
+    #[arg(long = "renameDisplay", value_enum, default_value = "compact")]
+    pub rename_display: RenameDisplayType,
+
     /// Maximum number of characters of the bigger line in a block to apply comparison
-    #[arg(long = "maxLineSizeInBlockForComparison", default_value = "200")]
+    #[arg(
+        long = "maxLineSizeInBlockForComparison",
+        default_value_t = defaults::MAX_LINE_SIZE_IN_BLOCK_FOR_COMPARISON
+    )]
     pub max_line_size_in_block_for_comparison: usize,
 
     /// Maximum number of characters in a line to apply highlight
-    #[arg(long = "maxLineLengthHighlight", default_value = "10000")]
+    #[arg(long = "maxLineLengthHighlight", default_value_t = defaults::MAX_LINE_LENGTH_HIGHLIGHT)]
     pub max_line_length_highlight: usize,
 
+    /// Maximum number of changed line pairs to highlight in a block
+    #[arg(long = "maxHighlightPairsPerBlock")]
+    pub max_highlight_pairs_per_block: Option<usize>,
+
+    /// Truncate lines wider than this number of columns, with a marker
+    /// showing the full line
+    #[arg(long = "maxRenderedLineLength")]
+    pub max_rendered_line_length: Option<usize>,
+
+    /// Render minified files with truncated lines and without highlighting
+    #[arg(long = "detectMinified")]
+    pub detect_minified: bool,
+
+    /// Number of highlighted line pairs to cache, so identical pairs are highlighted once
+    #[arg(long = "highlightCacheSize", default_value_t = 0)]
+    pub highlight_cache_size: usize,
+
     /// Show viewed checkbox to toggle file content
-    #[arg(long = "fileContentToggle", default_value = "true")]
+    #[arg(
+        long = "fileContentToggle",
+        default_value_t = true,
+        num_args = 0..=1,
+        default_missing_value = "true",
+        action = clap::ArgAction::Set
+    )]
     pub file_content_toggle: bool,
 
+    /// Remember viewed files across page loads, by file ID (see --deterministic)
+    #[arg(long = "persistViewed")]
+    pub persist_viewed: bool,
+
     /// Synchronised horizontal scroll for side-by-side view
-    #[arg(long = "synchronisedScroll", default_value = "true")]
+    #[arg(
+        long = "synchronisedScroll",
+        default_value_t = true,
+        num_args = 0..=1,
+        default_missing_value = "true",
+        action = clap::ArgAction::Set
+    )]
     pub synchronised_scroll: bool,
 
     /// Enable syntax highlighting
-    #[arg(long = "highlightCode", default_value = "true")]
+    #[arg(
+        long = "highlightCode",
+        default_value_t = true,
+        num_args = 0..=1,
+        default_missing_value = "true",
+        action = clap::ArgAction::Set
+    )]
     pub highlight_code: bool,
 
-    /// Use a custom template when generating markup
+    /// Preview binary images using this repository's working tree and history
+    #[arg(long = "imagePreview")]
+    pub image_preview: Option<String>,
+
+    /// Git revision to load the old version of previewed images from
+    #[arg(long = "imagePreviewRev", default_value = "HEAD")]
+    pub image_preview_rev: String,
+
+    /// Show who last changed each line, from git blame (command input only)
+    #[arg(long)]
+    pub blame: bool,
+
+    /// Generate the same HTML on every run, e.g. for golden-file tests
+    #[arg(long)]
+    pub deterministic: bool,
+
+    /// Indent the generated HTML consistently
+    #[arg(long = "prettyPrint")]
+    pub pretty_print: bool,
+
+    /// Language of matching files, as `<extension, file name or glob>=<language>`
+    #[arg(long = "languageOverride", action = clap::ArgAction::Append)]
+    pub language_override: Vec<String>,
+
+    /// Timestamps after the file names to strip: always, never or from the first match of a regex
+    #[arg(long = "timestamps", default_value = "always")]
+    pub timestamps: String,
+
+    /// Exit with code 2 if the diff contains more than this number of files
+    #[arg(long = "failIfMoreThanFiles")]
+    pub fail_if_more_than_files: Option<usize>,
+
+    /// Exit with code 2 if the diff contains more than this number of changed lines
+    #[arg(long = "failIfMoreThanLines")]
+    pub fail_if_more_than_lines: Option<u32>,
+
+    /// Exit with code 1 if the diff contains changed files, like `git diff --exit-code`
+    #[arg(long = "exitCode")]
+    pub exit_code: bool,
+
+    /// Report the parsed files and problems found in malformed diff input on stderr
+    #[arg(short = 'v', long, conflicts_with = "quiet")]
+    pub verbose: bool,
+
+    /// Only report errors on stderr
+    #[arg(short = 'q', long)]
+    pub quiet: bool,
+
+    /// Check crates.io for a newer version of diff2html and exit
+    #[cfg(feature = "self-update")]
+    #[arg(long = "checkUpdate")]
+    pub check_update: bool,
+
+    /// Nonce for the inline styles and scripts, for a Content-Security-Policy
+    #[arg(long = "cspNonce")]
+    pub csp_nonce: Option<String>,
+
+    /// Use a custom template when generating markup, Handlebars if named `*.hbs`
     #[arg(long = "htmlWrapperTemplate")]
     pub html_wrapper_template: Option<String>,
 
+    /// Value of a `<!--diff2html-var:key-->` placeholder of the template, as `key=value`
+    #[arg(long = "templateVar", alias = "template-var", action = clap::ArgAction::Append)]
+    pub template_var: Vec<String>,
+
+    /// Files to render collapsed, with their viewed checkbox checked
+    #[arg(long = "collapse", action = clap::ArgAction::Append)]
+    pub collapse: Vec<String>,
+
     /// Files to exclude from diff
     #[arg(long = "ignore", short = 'g', action = clap::ArgAction::Append)]
     pub ignore: Vec<String>,
@@ -112,6 +316,10 @@ pub enum StyleType {
     Line,
     /// Side-by-side view
     Side,
+    /// Both views, switchable per file
+    Switchable,
+    /// Both views, in two outputs
+    Both,
 }
 
 /// Diff highlighting style type
@@ -130,6 +338,18 @@ pub enum FormatType {
     Html,
     /// JSON output
     Json,
+    /// Markdown table of the changed files, e.g. for pull request descriptions
+    MarkdownSummary,
+    /// PDF of the HTML output, e.g. for archived reviews
+    Pdf,
+    /// SARIF log with a result and fix per changed file, for code scanning
+    Sarif,
+    /// CSV of the path, status, line counts, binary flag and language of the files
+    Csv,
+    /// TSV of the path, status, line counts, binary flag and language of the files
+    Tsv,
+    /// JSON of the line counts of all files, with their changed lines by language
+    StatsJson,
 }
 
 /// Input source type
@@ -150,6 +370,17 @@ pub enum OutputType {
     Preview,
     /// Print to stdout
     Stdout,
+    /// Copy to the system clipboard
+    Clipboard,
+}
+
+/// Output compression type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CompressionType {
+    /// gzip, using the `gzip` program
+    Gzip,
+    /// Brotli, using the `brotli` program
+    Br,
 }
 
 /// Color scheme type
@@ -163,6 +394,55 @@ pub enum ColorSchemeType {
     Dark,
 }
 
+/// Whitespace-only changes type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum WhitespaceChangesType {
+    /// Render them like other changes
+    Show,
+    /// Mark them with the d2h-whitespace-only class
+    Mark,
+    /// Render them as context lines
+    Hide,
+}
+
+/// File HTML ID strategy type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum HtmlIdStrategyType {
+    /// A hash of the file name, like d2h-123456
+    Hash,
+    /// A readable slug of the file path, like d2h-src-lib-rs
+    Slug,
+}
+
+/// Renamed file path display type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RenameDisplayType {
+    /// A single path with the changed part
+    Compact,
+    /// The old and the new path on separate lines, with copy buttons
+    Full,
+}
+
+/// Diff line markup type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MarkupType {
+    /// Table rows
+    Table,
+    /// CSS grid rows of divs with ARIA roles
+    Semantic,
+}
+
+/// File grouping type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GroupByType {
+    /// Order of the diff
+    None,
+    /// Parent directory
+    Directory,
+    /// Added, modified, renamed, copied or deleted
+    Status,
+}
+
 /// File summary visibility type
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
 pub enum SummaryType {
@@ -0,0 +1,172 @@
+//! Generated and vendored file detection for diff2html CLI.
+//!
+//! Runs `git check-attr` on the changed files, so the files marked with the
+//! `linguist-generated` or `linguist-vendored` attributes in `.gitattributes`
+//! are tagged and collapsed in the generated HTML, like on GitHub.
+
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use diff2html::DiffFile;
+use log::debug;
+
+/// Attributes marking generated or vendored files.
+const ATTRIBUTES: [&str; 2] = ["linguist-generated", "linguist-vendored"];
+
+/// Set `is_generated` on the files with a generated or vendored attribute in
+/// the repository containing `dir`. Files are left as they are if git cannot
+/// be run.
+///
+/// The paths of a git diff are relative to the top level of the repository,
+/// so the attributes are checked from there rather than from `dir`.
+pub fn mark_generated_files(dir: &Path, files: &mut [DiffFile]) {
+    let paths: Vec<&str> = files.iter().map(file_path).collect();
+    if paths.is_empty() {
+        return;
+    }
+    let generated = match top_level(dir).and_then(|repo| check_attr(&repo, &paths)) {
+        Ok(generated) => generated,
+        Err(err) => {
+            debug!("Failed to read git attributes: {}", err);
+            return;
+        }
+    };
+    for file in files {
+        if generated.contains(file_path(file)) {
+            file.is_generated = Some(true);
+        }
+    }
+}
+
+/// Path of a file in the new version, or the old one for deleted files.
+fn file_path(file: &DiffFile) -> &str {
+    if file.new_name == "/dev/null" || file.new_name.is_empty() {
+        &file.old_name
+    } else {
+        &file.new_name
+    }
+}
+
+/// Get the top level directory of the repository containing `dir`, with
+/// `git rev-parse --show-toplevel`.
+fn top_level(dir: &Path) -> std::io::Result<PathBuf> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["rev-parse", "--show-toplevel"])
+        .stderr(Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        return Err(std::io::Error::other(format!(
+            "git rev-parse exited with {}",
+            output.status
+        )));
+    }
+    let top_level = String::from_utf8_lossy(&output.stdout);
+    Ok(PathBuf::from(top_level.trim_end_matches(['\n', '\r'])))
+}
+
+/// Get the paths with a generated or vendored attribute set, with
+/// `git check-attr -z --stdin`.
+fn check_attr(repo: &Path, paths: &[&str]) -> std::io::Result<HashSet<String>> {
+    let mut child = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["check-attr", "-z", "--stdin"])
+        .args(ATTRIBUTES)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input: Vec<u8> = paths
+        .iter()
+        .flat_map(|path| path.bytes().chain([0]))
+        .collect();
+    // Written from a thread, git writes the output while reading the paths
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output()?;
+    writer.join().expect("writer thread panicked")?;
+    if !output.status.success() {
+        return Err(std::io::Error::other(format!(
+            "git check-attr exited with {}",
+            output.status
+        )));
+    }
+    Ok(parse_check_attr(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Parse the `<path> NUL <attribute> NUL <value> NUL` records of
+/// `git check-attr -z` into the paths with an attribute set.
+fn parse_check_attr(output: &str) -> HashSet<String> {
+    let fields: Vec<&str> = output.split('\0').collect();
+    fields
+        .chunks_exact(3)
+        .filter(|record| matches!(record[2], "set" | "true"))
+        .map(|record| record[0].to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_check_attr() {
+        let output = "gen.rs\0linguist-generated\0set\0gen.rs\0linguist-vendored\0unspecified\0\
+                      src/a.rs\0linguist-generated\0false\0src/a.rs\0linguist-vendored\0unset\0\
+                      vendor/b.js\0linguist-generated\0unspecified\0vendor/b.js\0linguist-vendored\0true\0";
+        let generated = parse_check_attr(output);
+
+        assert_eq!(
+            generated,
+            HashSet::from(["gen.rs".to_string(), "vendor/b.js".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_mark_generated_files() {
+        let repo = tempfile::tempdir().unwrap();
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .arg("-C")
+                .arg(repo.path())
+                .args(args)
+                .output()
+                .map(|output| output.status.success())
+                .unwrap_or(false)
+        };
+        if !git(&["init", "-q"]) {
+            // git is not available
+            return;
+        }
+        std::fs::write(
+            repo.path().join(".gitattributes"),
+            "*.pb.go linguist-generated\nthird_party/** linguist-vendored\n",
+        )
+        .unwrap();
+
+        let file = |name: &str| DiffFile {
+            old_name: name.to_string(),
+            new_name: name.to_string(),
+            ..Default::default()
+        };
+        let files = vec![
+            file("api/service.pb.go"),
+            file("third_party/lib/x.c"),
+            file("main.go"),
+        ];
+        // The paths are relative to the top level from a subdirectory too
+        let subdirectory = repo.path().join("api");
+        std::fs::create_dir(&subdirectory).unwrap();
+        for dir in [repo.path(), subdirectory.as_path()] {
+            let mut files = files.clone();
+            mark_generated_files(dir, &mut files);
+
+            let generated: Vec<_> = files.iter().map(|file| file.is_generated).collect();
+            assert_eq!(generated, [Some(true), Some(true), None]);
+        }
+    }
+}
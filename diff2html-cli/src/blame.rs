@@ -0,0 +1,219 @@
+//! Blame source for diff2html CLI.
+//!
+//! Runs `git blame` on the old and new versions of a file at the revisions
+//! compared by `git diff`, so the generated HTML can show who last changed
+//! each line.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+use diff2html::{BlameSource, DiffFile, FileBlame, LineBlame};
+
+/// Blame source backed by a git repository and its working tree.
+#[derive(Debug)]
+pub struct GitBlameSource {
+    /// Path to the repository working tree
+    pub repo: PathBuf,
+    /// Revision to blame the old version of files at
+    pub old_rev: String,
+    /// Revision to blame the new version of files at, or `None` for the
+    /// working tree
+    pub new_rev: Option<String>,
+}
+
+impl GitBlameSource {
+    /// Create a blame source for the versions compared by `git diff` with
+    /// the given arguments, in the repository of the current directory, see
+    /// [`diff_revisions`].
+    pub fn for_git_diff(git_args: &[String]) -> Result<Self> {
+        let (old_rev, new_rev) =
+            diff_revisions(git_args).context("--blame needs the revisions compared by git diff")?;
+        Ok(Self {
+            repo: PathBuf::from("."),
+            old_rev,
+            new_rev,
+        })
+    }
+
+    /// Blame a file with `git blame --line-porcelain [<rev>] -- <path>`.
+    fn blame_lines(&self, rev: Option<&str>, path: &str) -> Option<HashMap<u32, LineBlame>> {
+        let mut command = Command::new("git");
+        command
+            .arg("-C")
+            .arg(&self.repo)
+            .arg("blame")
+            .arg("--line-porcelain");
+        if let Some(rev) = rev {
+            command.arg(rev);
+        }
+        let output = command.arg("--").arg(path).output().ok()?;
+        output
+            .status
+            .success()
+            .then(|| parse_line_porcelain(&String::from_utf8_lossy(&output.stdout)))
+    }
+}
+
+/// Get the revisions compared by `git diff` with the given arguments, the new
+/// one being `None` for the working tree.
+///
+/// The revisions are read from `[<rev>]`, `<rev> <rev>` and `<rev>..<rev>`,
+/// the new version being in the working tree with a single revision, and the
+/// old one at `HEAD` without any. The other ways to pick the versions, such
+/// as `--cached` or `<rev>...<rev>`, are rejected, the files at the revisions
+/// would not be the ones of the diff.
+pub fn diff_revisions(git_args: &[String]) -> Result<(String, Option<String>)> {
+    let mut revisions = Vec::new();
+    for arg in git_args.iter().take_while(|arg| *arg != "--") {
+        if matches!(
+            arg.as_str(),
+            "--cached" | "--staged" | "--merge-base" | "--no-index"
+        ) {
+            bail!("The git diff option {} does not compare revisions", arg);
+        }
+        // Like git, arguments naming existing files are paths
+        if !arg.starts_with('-') && !Path::new(arg).exists() {
+            revisions.push(arg.as_str());
+        }
+    }
+
+    let or_head = |rev: &str| match rev {
+        "" => "HEAD".to_string(),
+        rev => rev.to_string(),
+    };
+    Ok(match revisions.as_slice() {
+        [] => ("HEAD".to_string(), None),
+        [range] if range.contains("...") => {
+            bail!("The symmetric range {} does not compare revisions", range)
+        }
+        [range] => match range.split_once("..") {
+            Some((old, new)) => (or_head(old), Some(or_head(new))),
+            None => (range.to_string(), None),
+        },
+        [old, new] => (old.to_string(), Some(new.to_string())),
+        _ => bail!("git diff compares more than two revisions"),
+    })
+}
+
+impl BlameSource for GitBlameSource {
+    fn blame(&self, file: &DiffFile) -> Option<FileBlame> {
+        let old_lines = if file.is_new == Some(true) {
+            None
+        } else {
+            self.blame_lines(Some(&self.old_rev), &file.old_name)
+        };
+        let new_lines = if file.is_deleted == Some(true) {
+            None
+        } else {
+            self.blame_lines(self.new_rev.as_deref(), &file.new_name)
+        };
+
+        if old_lines.is_none() && new_lines.is_none() {
+            return None;
+        }
+        Some(FileBlame {
+            old_lines: old_lines.unwrap_or_default(),
+            new_lines: new_lines.unwrap_or_default(),
+        })
+    }
+}
+
+/// Parse the output of `git blame --line-porcelain` by final line number.
+fn parse_line_porcelain(output: &str) -> HashMap<u32, LineBlame> {
+    let mut lines = HashMap::new();
+    let mut number = None;
+    let mut blame = LineBlame::default();
+
+    for line in output.lines() {
+        if line.starts_with('\t') {
+            // The line content ends the entry
+            if let Some(number) = number.take() {
+                lines.insert(number, std::mem::take(&mut blame));
+            }
+        } else if let Some(author) = line.strip_prefix("author ") {
+            blame.author = author.to_string();
+        } else if let Some(summary) = line.strip_prefix("summary ") {
+            blame.summary = Some(summary.to_string());
+        } else if number.is_none() {
+            let mut fields = line.split(' ');
+            let commit = fields.next().unwrap_or_default();
+            let final_number = fields.nth(1).and_then(|n| n.parse().ok());
+            if commit.len() == 40 && commit.chars().all(|c| c.is_ascii_hexdigit()) {
+                blame.commit = commit.to_string();
+                number = final_number;
+            }
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_revisions() {
+        let revisions = |args: &[&str]| {
+            let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+            diff_revisions(&args)
+        };
+        let revs = |old: &str, new: Option<&str>| (old.to_string(), new.map(str::to_string));
+
+        assert_eq!(revisions(&[]).unwrap(), revs("HEAD", None));
+        assert_eq!(
+            revisions(&["-M", "-C", "HEAD~3"]).unwrap(),
+            revs("HEAD~3", None)
+        );
+        assert_eq!(
+            revisions(&["v1", "v2", "--", "v3"]).unwrap(),
+            revs("v1", Some("v2"))
+        );
+        assert_eq!(revisions(&["v1..v2"]).unwrap(), revs("v1", Some("v2")));
+        assert_eq!(revisions(&["v1.."]).unwrap(), revs("v1", Some("HEAD")));
+        // Paths are not revisions
+        assert_eq!(revisions(&["main", "src"]).unwrap(), revs("main", None));
+
+        assert!(revisions(&["--cached"]).is_err());
+        assert!(revisions(&["main...topic"]).is_err());
+        assert!(revisions(&["v1", "v2", "v3"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_line_porcelain() {
+        let output = "\
+0123456789abcdef0123456789abcdef01234567 1 1 2
+author Jane Doe
+author-mail <jane@example.com>
+summary Add sample
+filename sample
+\tfirst line
+0123456789abcdef0123456789abcdef01234567 2 2
+author Jane Doe
+author-mail <jane@example.com>
+summary Add sample
+filename sample
+\tauthor in content
+0000000000000000000000000000000000000000 3 3 1
+author Not Committed Yet
+summary Version of sample from sample
+filename sample
+\t
+";
+
+        let lines = parse_line_porcelain(output);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[&1],
+            LineBlame {
+                author: "Jane Doe".to_string(),
+                commit: "0123456789abcdef0123456789abcdef01234567".to_string(),
+                summary: Some("Add sample".to_string()),
+            }
+        );
+        assert_eq!(lines[&2].author, "Jane Doe");
+        assert_eq!(lines[&3].author, "Not Committed Yet");
+    }
+}
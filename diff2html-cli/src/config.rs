@@ -1,17 +1,53 @@
 //! Configuration conversion from CLI arguments to library config.
 
+use std::path::PathBuf;
+use std::sync::Arc;
+
 use anyhow::{Result, bail};
 
 use crate::args::{
-    Args, ColorSchemeType, DiffStyleType, FormatType, InputType, LineMatchingType, OutputType,
-    StyleType, SummaryType,
+    Args, ColorSchemeType, CompressionType, DiffStyleType, FormatType, GroupByType,
+    HtmlIdStrategyType, InputType, LineMatchingType, MarkupType, OutputType, RenameDisplayType,
+    StyleType, SummaryType, WhitespaceChangesType,
 };
+use crate::blame::{GitBlameSource, diff_revisions};
+use crate::image::GitImageSource;
 use diff2html::{
-    ColorScheme, Diff2HtmlConfig, DiffStyle, LineMatchingType as LibLineMatchingType, OutputFormat,
+    BlameSource, ColorScheme, Diff2HtmlConfig, DiffStyle, GroupBy, HtmlIdStrategy, ImageSource,
+    LineMatchingType as LibLineMatchingType, MinifiedFileDetection, OutputFormat, OutputMarkup,
+    RenameDisplay, TimestampHandling, WhitespaceChanges,
 };
 
+/// Retention of the old preview files, removed when a new preview is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreviewRetention {
+    /// Remove all old preview files
+    Never,
+    /// Keep preview files forever
+    Always,
+    /// Remove preview files older than this number of days
+    Days(u64),
+}
+
+impl std::str::FromStr for PreviewRetention {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "never" => Ok(Self::Never),
+            "always" => Ok(Self::Always),
+            _ => match s.strip_suffix('d').map(str::parse) {
+                Some(Ok(days)) => Ok(Self::Days(days)),
+                _ => {
+                    bail!("keepPreview must be never, always or a number of days like 7d, got {s}")
+                }
+            },
+        }
+    }
+}
+
 /// CLI-specific configuration for input/output handling.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct CliConfig {
     /// Input source type
     pub input_type: InputType,
@@ -19,36 +55,106 @@ pub struct CliConfig {
     pub format_type: FormatType,
     /// Output destination type
     pub output_type: OutputType,
+    /// Browser command for the preview, or `None` for the system default
+    pub browser: Option<String>,
+    /// Print the path of the preview file instead of opening it
+    pub no_open: bool,
+    /// Directory of the preview files, or `None` for the system temp directory
+    pub preview_dir: Option<PathBuf>,
+    /// Retention of the old preview files
+    pub keep_preview: PreviewRetention,
     /// Output file path (overrides output_type if set)
     pub output_file: Option<String>,
+    /// Output directory for one page per file (overrides output_type if set)
+    pub output_dir: Option<String>,
+    /// Compression of the output written to a file or stdout
+    pub compression: Option<CompressionType>,
     /// HTML page title
     pub page_title: String,
     /// HTML page header
     pub page_header: String,
     /// Custom HTML wrapper template path
     pub html_wrapper_template: Option<String>,
+    /// Values of the `<!--diff2html-var:key-->` placeholders of the template
+    pub template_vars: Vec<(String, String)>,
     /// Whether file list summary is open by default
     pub show_files_open: bool,
     /// Enable file content toggle
     pub file_content_toggle: bool,
+    /// Remember viewed files in localStorage
+    pub persist_viewed: bool,
+    /// Show a table of contents of the files in a sidebar
+    pub toc: bool,
+    /// Enable the buttons switching the view of the switchable style
+    pub view_toggle: bool,
+    /// Write a line-by-line and a side-by-side output
+    pub both_styles: bool,
+    /// Enable the buttons copying the paths of renamed files
+    pub copy_path: bool,
     /// Enable synchronised scroll for side-by-side view
     pub synchronised_scroll: bool,
     /// Enable syntax highlighting
     pub highlight_code: bool,
     /// Color scheme for HTML output
     pub color_scheme: ColorSchemeType,
+    /// Viewport width below which the side-by-side panels are stacked
+    pub responsive_breakpoint: Option<u32>,
     /// Files to ignore
     pub ignore: Vec<String>,
     /// Extra git diff arguments
     pub extra_args: Vec<String>,
+    /// Exit with a distinct code if the diff contains changed files
+    pub exit_code: bool,
+    /// Maximum number of files before failing
+    pub fail_if_more_than_files: Option<usize>,
+    /// Maximum number of changed lines before failing
+    pub fail_if_more_than_lines: Option<u32>,
+    /// Report parser diagnostics on stderr
+    pub verbose: bool,
+    /// Nonce added to the inline styles and scripts of the HTML page
+    pub csp_nonce: Option<String>,
+}
+
+/// Get the browser command from the BROWSER environment variable, using the
+/// first of its colon-separated commands.
+fn browser_from_env() -> Option<String> {
+    let browser = std::env::var("BROWSER").ok()?;
+    browser
+        .split(':')
+        .map(str::trim)
+        .find(|command| !command.is_empty())
+        .map(str::to_string)
 }
 
 /// Parse CLI arguments into library config and CLI-specific config.
 ///
 /// # Errors
 ///
-/// Returns an error if `match_words_threshold` is not in the range 0.0-1.0.
+/// Returns an error if `match_words_threshold` is not in the range 0.0-1.0,
+/// a `language_override` is not of the form `<pattern>=<language>`,
+/// `timestamps` is not `always`, `never` or a valid regex, a
+/// `template_var` is not of the form `<key>=<value>` or
+/// `keep_preview` is not a valid [`PreviewRetention`].
 pub fn parse_args(args: &Args) -> Result<(Diff2HtmlConfig, CliConfig)> {
+    if args.output_dir.is_some() && args.format != FormatType::Html {
+        bail!("--outputDir requires the html format");
+    }
+    if args.format == FormatType::Pdf && args.file.is_none() && args.output == OutputType::Clipboard
+    {
+        bail!("The pdf format cannot be copied to the clipboard");
+    }
+    if args.compress.is_some() && args.file.is_none() && args.output != OutputType::Stdout {
+        bail!("--compress requires --file or the stdout output");
+    }
+    if args.style == StyleType::Both {
+        if args.output_dir.is_some() {
+            bail!("--style both cannot be used with --outputDir");
+        }
+        if args.file.is_none() && args.output != OutputType::Preview {
+            bail!("--style both writes two outputs, it requires --file or the preview output");
+        }
+    }
+
     // Validate match_words_threshold is in range 0.0-1.0
     if !(0.0..=1.0).contains(&args.match_words_threshold) {
         bail!(
@@ -57,10 +163,71 @@ pub fn parse_args(args: &Args) -> Result<(Diff2HtmlConfig, CliConfig)> {
         );
     }
 
+    // Blame the versions compared by the git diff command
+    let blame_source = if args.blame && args.input == InputType::Command {
+        Some(Arc::new(GitBlameSource::for_git_diff(&args.extra_args)?) as Arc<dyn BlameSource>)
+    } else {
+        None
+    };
+
+    // Link the files at the revisions compared by the git diff command, when
+    // the new version is not in the working tree
+    let file_url_revisions = match diff_revisions(&args.extra_args) {
+        Ok((old_rev, Some(new_rev)))
+            if args.file_url_template.is_some() && args.input == InputType::Command =>
+        {
+            Some((old_rev, new_rev))
+        }
+        _ => None,
+    };
+
+    let language_overrides = args
+        .language_override
+        .iter()
+        .map(
+            |language_override| match language_override.split_once('=') {
+                Some((key, language)) if !key.is_empty() && !language.is_empty() => {
+                    Ok((key.to_string(), language.to_string()))
+                }
+                _ => {
+                    bail!("languageOverride must be <pattern>=<language>, got {language_override}")
+                }
+            },
+        )
+        .collect::<Result<_>>()?;
+
+    let timestamps = match args.timestamps.as_str() {
+        "always" => TimestampHandling::Always,
+        "never" => TimestampHandling::Never,
+        pattern => match TimestampHandling::custom(pattern) {
+            Ok(timestamps) => timestamps,
+            Err(err) => bail!("timestamps must be always, never or a regex, got {pattern}: {err}"),
+        },
+    };
+
+    let template_vars = args
+        .template_var
+        .iter()
+        .map(|template_var| match template_var.split_once('=') {
+            Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+            _ => bail!("templateVar must be <key>=<value>, got {template_var}"),
+        })
+        .collect::<Result<_>>()?;
+
     let diff2html_config = Diff2HtmlConfig {
         output_format: match args.style {
-            StyleType::Line => OutputFormat::LineByLine,
+            StyleType::Line | StyleType::Both => OutputFormat::LineByLine,
             StyleType::Side => OutputFormat::SideBySide,
+            StyleType::Switchable => OutputFormat::Switchable,
+        },
+        output_markup: match args.markup {
+            MarkupType::Table => OutputMarkup::Table,
+            MarkupType::Semantic => OutputMarkup::Semantic,
+        },
+        group_by: match args.group_by {
+            GroupByType::None => GroupBy::None,
+            GroupByType::Directory => GroupBy::Directory,
+            GroupByType::Status => GroupBy::Status,
         },
         diff_style: match args.diff_style {
             DiffStyleType::Word => DiffStyle::Word,
@@ -71,7 +238,9 @@ pub fn parse_args(args: &Args) -> Result<(Diff2HtmlConfig, CliConfig)> {
             ColorSchemeType::Light => ColorScheme::Light,
             ColorSchemeType::Dark => ColorScheme::Dark,
         },
+        responsive_breakpoint: args.responsive_breakpoint,
         draw_file_list: args.summary != SummaryType::Hidden,
+        file_list_open: args.summary == SummaryType::Open,
         matching: match args.matching {
             LineMatchingType::None => LibLineMatchingType::None,
             LineMatchingType::Lines => LibLineMatchingType::Lines,
@@ -81,9 +250,52 @@ pub fn parse_args(args: &Args) -> Result<(Diff2HtmlConfig, CliConfig)> {
         matching_max_comparisons: args.matching_max_comparisons,
         diff_max_changes: args.diff_max_changes,
         diff_max_line_length: args.diff_max_line_length,
+        diff_max_files: args.diff_max_files,
+        diff_max_total_changes: args.diff_max_total_changes,
+        keep_too_big_content: args.keep_too_big_content,
         render_nothing_when_empty: args.render_nothing_when_empty,
+        show_file_metadata: args.show_file_metadata,
+        language_overrides,
+        timestamps,
+        deterministic: args.deterministic,
+        html_id_strategy: match args.html_id_strategy {
+            HtmlIdStrategyType::Hash => HtmlIdStrategy::Hash,
+            HtmlIdStrategyType::Slug => HtmlIdStrategy::Slug,
+        },
+        pretty_print: args.pretty_print,
+        image_source: args.image_preview.as_ref().map(|repo| {
+            Arc::new(GitImageSource {
+                repo: PathBuf::from(repo),
+                rev: args.image_preview_rev.clone(),
+            }) as Arc<dyn ImageSource>
+        }),
+        blame_source,
+        single_column_added_deleted: args.single_column_added_deleted,
+        interleave_changes: args.interleave_changes,
+        whitespace_changes: match args.whitespace_changes {
+            WhitespaceChangesType::Show => WhitespaceChanges::Show,
+            WhitespaceChangesType::Mark => WhitespaceChanges::Mark,
+            WhitespaceChangesType::Hide => WhitespaceChanges::Hide,
+        },
+        rename_display: match args.rename_display {
+            RenameDisplayType::Compact => RenameDisplay::Compact,
+            RenameDisplayType::Full => RenameDisplay::Full,
+        },
+        sticky_headers: args.sticky_headers,
+        wrap_lines: args.wrap_lines,
+        draw_jump_menu: args.jump_menu,
+        draw_language_bar: args.language_bar,
+        file_url_template: args.file_url_template.clone(),
+        file_url_revisions,
         max_line_size_in_block_for_comparison: args.max_line_size_in_block_for_comparison,
         max_line_length_highlight: args.max_line_length_highlight,
+        max_highlight_pairs_per_block: args.max_highlight_pairs_per_block,
+        max_rendered_line_length: args.max_rendered_line_length,
+        minified_files: args.detect_minified.then(MinifiedFileDetection::default),
+        highlight_cache_size: args.highlight_cache_size,
+        collapse_by_default: args.collapse.clone(),
+        // Also passed to git diff, but needed for the other input types
+        exclude: args.ignore.clone(),
         ..Default::default()
     };
 
@@ -94,18 +306,243 @@ pub fn parse_args(args: &Args) -> Result<(Diff2HtmlConfig, CliConfig)> {
         input_type: args.input,
         format_type: args.format,
         output_type: args.output,
+        browser: args.browser.clone().or_else(browser_from_env),
+        no_open: args.no_open,
+        preview_dir: args.preview_dir.as_ref().map(PathBuf::from),
+        keep_preview: args.keep_preview.parse()?,
         output_file: args.file.clone(),
+        output_dir: args.output_dir.clone(),
+        compression: args.compress,
         page_title: args.title.as_deref().unwrap_or(default_title).to_string(),
         page_header: args.title.as_deref().unwrap_or(default_header).to_string(),
         html_wrapper_template: args.html_wrapper_template.clone(),
+        template_vars,
         show_files_open: args.summary == SummaryType::Open,
         file_content_toggle: args.file_content_toggle,
+        persist_viewed: args.persist_viewed,
+        toc: args.toc,
+        view_toggle: args.style == StyleType::Switchable,
+        both_styles: args.style == StyleType::Both,
+        copy_path: args.rename_display == RenameDisplayType::Full,
         synchronised_scroll: args.synchronised_scroll,
         highlight_code: args.highlight_code,
         color_scheme: args.color_scheme,
+        responsive_breakpoint: args.responsive_breakpoint,
         ignore: args.ignore.clone(),
         extra_args: args.extra_args.clone(),
+        exit_code: args.exit_code,
+        fail_if_more_than_files: args.fail_if_more_than_files,
+        fail_if_more_than_lines: args.fail_if_more_than_lines,
+        verbose: args.verbose,
+        csp_nonce: args.csp_nonce.clone(),
     };
 
     Ok((diff2html_config, cli_config))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_defaults_match_library() {
+        let args = Args::parse_from(["diff2html"]);
+        let (config, cli_config) = parse_args(&args).unwrap();
+        let default = Diff2HtmlConfig::default();
+
+        assert_eq!(config.output_format, default.output_format);
+        assert_eq!(config.output_markup, default.output_markup);
+        assert_eq!(config.group_by, default.group_by);
+        assert_eq!(config.diff_style, default.diff_style);
+        assert_eq!(config.matching, default.matching);
+        assert_eq!(config.match_words_threshold, default.match_words_threshold);
+        assert_eq!(
+            config.matching_max_comparisons,
+            default.matching_max_comparisons
+        );
+        assert_eq!(
+            config.max_line_size_in_block_for_comparison,
+            default.max_line_size_in_block_for_comparison
+        );
+        assert_eq!(
+            config.max_line_length_highlight,
+            default.max_line_length_highlight
+        );
+        assert_eq!(config.diff_max_changes, default.diff_max_changes);
+        assert_eq!(config.diff_max_files, default.diff_max_files);
+        assert_eq!(config.minified_files, default.minified_files);
+        assert_eq!(config.draw_file_list, default.draw_file_list);
+        assert!(!config.sticky_headers && !config.draw_jump_menu && !config.draw_language_bar);
+        assert!(!config.deterministic && !config.pretty_print);
+        assert!(cli_config.file_content_toggle);
+        assert!(cli_config.synchronised_scroll);
+        assert!(cli_config.highlight_code);
+    }
+
+    #[test]
+    fn test_flags_reach_config() {
+        let args = Args::parse_from([
+            "diff2html",
+            "-s",
+            "side",
+            "-d",
+            "char",
+            "--markup",
+            "semantic",
+            "--responsiveBreakpoint",
+            "800",
+            "--colorScheme",
+            "dark",
+            "--summary",
+            "open",
+            "--matching",
+            "words",
+            "--matchWordsThreshold",
+            "0.5",
+            "--matchingMaxComparisons",
+            "10",
+            "--diffMaxChanges",
+            "1",
+            "--diffMaxLineLength",
+            "2",
+            "--diffMaxFiles",
+            "3",
+            "--diffMaxTotalChanges",
+            "4",
+            "--keepTooBigContent",
+            "--renderNothingWhenEmpty",
+            "--showFileMetadata",
+            "--stickyHeaders",
+            "--wrapLines",
+            "--jumpMenu",
+            "--languageBar",
+            "--fileUrlTemplate",
+            "https://host/blob/main/{path}",
+            "--singleColumnAddedDeleted",
+            "--interleaveChanges",
+            "--whitespaceChanges",
+            "hide",
+            "--renameDisplay",
+            "full",
+            "--htmlIdStrategy",
+            "slug",
+            "--maxLineSizeInBlockForComparison",
+            "5",
+            "--maxLineLengthHighlight",
+            "6",
+            "--maxHighlightPairsPerBlock",
+            "7",
+            "--maxRenderedLineLength",
+            "500",
+            "--detectMinified",
+            "--highlightCacheSize",
+            "8",
+            "--fileContentToggle",
+            "false",
+            "--synchronisedScroll",
+            "false",
+            "--highlightCode",
+            "false",
+            "--imagePreview",
+            ".",
+            "--blame",
+            "--deterministic",
+            "--groupBy",
+            "status",
+            "--prettyPrint",
+            "--languageOverride",
+            "tf=hcl",
+            "--timestamps",
+            r"\t\d+$",
+            "-g",
+            "Cargo.lock",
+            "--collapse",
+            "*.snap",
+        ]);
+        let (config, cli_config) = parse_args(&args).unwrap();
+
+        let parser = config.to_parser_config();
+        assert_eq!(parser.diff_max_changes, Some(1));
+        assert_eq!(parser.diff_max_line_length, Some(2));
+        assert_eq!(parser.diff_max_files, Some(3));
+        assert_eq!(parser.diff_max_total_changes, Some(4));
+        assert!(parser.keep_too_big_content);
+        assert_eq!(parser.language_overrides["tf"], "hcl");
+        assert!(matches!(parser.timestamps, TimestampHandling::Custom(_)));
+        assert_eq!(parser.exclude, ["Cargo.lock"]);
+
+        let renderer = config.to_renderer_config();
+        assert_eq!(renderer.render.matching, LibLineMatchingType::Words);
+        assert_eq!(renderer.render.match_words_threshold, 0.5);
+        assert_eq!(renderer.render.max_line_length_highlight, 6);
+        assert_eq!(renderer.render.diff_style, DiffStyle::Char);
+        assert_eq!(renderer.render.color_scheme, ColorScheme::Dark);
+        assert_eq!(renderer.render.responsive_breakpoint, Some(800));
+        assert!(renderer.render_nothing_when_empty);
+        assert_eq!(renderer.matching_max_comparisons, 10);
+        assert_eq!(renderer.max_line_size_in_block_for_comparison, 5);
+        assert_eq!(renderer.max_highlight_pairs_per_block, Some(7));
+        assert_eq!(renderer.max_rendered_line_length, Some(500));
+        assert_eq!(
+            renderer.minified_files,
+            Some(MinifiedFileDetection::default())
+        );
+        assert_eq!(renderer.highlight_cache_size, 8);
+        assert!(renderer.show_file_metadata);
+        assert!(renderer.single_column_added_deleted);
+        assert!(renderer.interleave_changes);
+        assert_eq!(renderer.collapse_by_default, ["*.snap"]);
+        assert_eq!(renderer.whitespace_changes, WhitespaceChanges::Hide);
+        assert_eq!(renderer.rename_display, RenameDisplay::Full);
+        assert_eq!(renderer.html_id_strategy, HtmlIdStrategy::Slug);
+        assert!(renderer.sticky_headers);
+        assert!(renderer.wrap_lines);
+        assert!(renderer.deterministic);
+        assert_eq!(renderer.output_markup, OutputMarkup::Semantic);
+        assert_eq!(renderer.group_by, GroupBy::Status);
+        assert!(renderer.image_source.is_some());
+        assert!(renderer.blame_source.is_some());
+
+        let file_list = config.to_file_list_config();
+        assert!(file_list.open);
+        assert_eq!(config.output_format, OutputFormat::SideBySide);
+        assert!(config.draw_file_list && config.draw_jump_menu && config.pretty_print);
+        assert!(file_list.language_bar);
+        assert_eq!(file_list.html_id_strategy, HtmlIdStrategy::Slug);
+        assert_eq!(
+            file_list.file_url_template.as_deref(),
+            Some("https://host/blob/main/{path}")
+        );
+
+        assert!(cli_config.show_files_open);
+        assert!(!cli_config.file_content_toggle);
+        assert!(!cli_config.synchronised_scroll);
+        assert!(!cli_config.highlight_code);
+        assert!(cli_config.copy_path);
+        assert_eq!(cli_config.color_scheme, ColorSchemeType::Dark);
+        assert_eq!(cli_config.responsive_breakpoint, Some(800));
+        assert_eq!(cli_config.ignore, ["Cargo.lock"]);
+    }
+
+    #[test]
+    fn test_file_url_revisions() {
+        let revisions = |git_args: &[&str]| {
+            let args = Args::parse_from(
+                ["diff2html", "--fileUrlTemplate", "{rev}/{path}", "--"]
+                    .iter()
+                    .chain(git_args),
+            );
+            parse_args(&args).unwrap().0.file_url_revisions
+        };
+
+        assert_eq!(
+            revisions(&["v1..v2"]),
+            Some(("v1".to_string(), "v2".to_string()))
+        );
+        // The working tree and the index are at no revision, the links use
+        // the checksums of the files
+        assert_eq!(revisions(&["v1"]), None);
+        assert_eq!(revisions(&["--cached"]), None);
+    }
+}
@@ -0,0 +1,88 @@
+//! Image preview source for diff2html CLI.
+//!
+//! Loads the old version of an image from a git revision and the new version
+//! from the working tree of a repository, so binary image files can be
+//! previewed in the generated HTML.
+
+use std::path::PathBuf;
+use std::process::Command;
+
+use diff2html::render::image_data_uri;
+use diff2html::{DiffFile, ImagePreview, ImageSource};
+
+/// Image source backed by a git repository and its working tree.
+#[derive(Debug)]
+pub struct GitImageSource {
+    /// Path to the repository working tree
+    pub repo: PathBuf,
+    /// Revision to load the old version of images from
+    pub rev: String,
+}
+
+impl GitImageSource {
+    /// Load the old version of a file with `git show <rev>:<path>`.
+    fn load_before(&self, path: &str) -> Option<Vec<u8>> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.repo)
+            .arg("show")
+            .arg(format!("{}:{}", self.rev, path))
+            .output()
+            .ok()?;
+        output.status.success().then_some(output.stdout)
+    }
+
+    /// Load the new version of a file from the working tree.
+    fn load_after(&self, path: &str) -> Option<Vec<u8>> {
+        std::fs::read(self.repo.join(path)).ok()
+    }
+}
+
+impl ImageSource for GitImageSource {
+    fn preview(&self, file: &DiffFile) -> Option<ImagePreview> {
+        let before = if file.is_new == Some(true) {
+            None
+        } else {
+            self.load_before(&file.old_name)
+                .and_then(|bytes| image_data_uri(&file.old_name, &bytes))
+        };
+        let after = if file.is_deleted == Some(true) {
+            None
+        } else {
+            self.load_after(&file.new_name)
+                .and_then(|bytes| image_data_uri(&file.new_name, &bytes))
+        };
+
+        Some(ImagePreview { before, after })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preview_reads_new_image_from_worktree() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("logo.gif"), b"GIF89a").unwrap();
+
+        let source = GitImageSource {
+            repo: dir.path().to_path_buf(),
+            rev: "HEAD".to_string(),
+        };
+        let file = DiffFile {
+            old_name: "logo.gif".to_string(),
+            new_name: "logo.gif".to_string(),
+            is_new: Some(true),
+            is_binary: Some(true),
+            ..Default::default()
+        };
+
+        let preview = source.preview(&file).unwrap();
+        assert_eq!(preview.before, None);
+        assert_eq!(
+            preview.after.as_deref(),
+            Some("data:image/gif;base64,R0lGODlh")
+        );
+    }
+}
@@ -1,7 +1,7 @@
 //! Input handling for diff2html CLI.
 //!
 //! This module handles reading diff input from various sources:
-//! - File: Read from a file path
+//! - File: Read from one or more file paths
 //! - Stdin: Read from standard input
 //! - Command: Execute `git diff` with arguments
 
@@ -28,14 +28,23 @@ pub fn get_input(
     }
 }
 
-/// Read diff from a file.
+/// Read diff from files, concatenated in order.
 fn read_file(extra_args: &[String]) -> Result<String> {
-    let file_path = extra_args
-        .first()
-        .context("No file path provided. Use: diff2html -i file -- <path>")?;
+    if extra_args.is_empty() {
+        bail!("No file path provided. Use: diff2html -i file -- <path>...");
+    }
 
-    std::fs::read_to_string(file_path)
-        .with_context(|| format!("Failed to read file: {}", file_path))
+    let mut input = String::new();
+    for file_path in extra_args {
+        let content = std::fs::read_to_string(file_path)
+            .with_context(|| format!("Failed to read file: {}", file_path))?;
+        input.push_str(&content);
+        // Keep the last line of a file from running into the next file
+        if !input.is_empty() && !input.ends_with('\n') {
+            input.push('\n');
+        }
+    }
+    Ok(input)
 }
 
 /// Read diff from stdin.
@@ -0,0 +1,116 @@
+//! Logging to stderr for diff2html CLI.
+//!
+//! Messages are reported through the `log` macros and written to stderr by
+//! [`StderrLogger`]. `--quiet` only shows errors and `--verbose` adds the
+//! parsed files and problems found in the input.
+
+use std::io::Write;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+/// Target of the messages of the CLI, and prefix of the targets of its
+/// modules like `diff2html::output`.
+const TARGET: &str = "diff2html";
+
+/// Modules of the library, whose crate has the same name as the CLI, so
+/// their messages can be told from the ones of the CLI. Messages of the
+/// library and other dependencies are not shown.
+const LIBRARY_MODULES: [&str; 18] = [
+    "builder",
+    "css",
+    "defaults",
+    "engine",
+    "ffi",
+    "fragment",
+    "merge",
+    "parser",
+    "rematch",
+    "render",
+    "sarif",
+    "selection",
+    "stats",
+    "templates",
+    "text",
+    "types",
+    "web",
+    "tests",
+];
+
+/// Check if messages with a target come from the CLI.
+fn is_cli_target(target: &str) -> bool {
+    match target.strip_prefix(TARGET) {
+        Some("") => true,
+        Some(path) => path.strip_prefix("::").is_some_and(|path| {
+            let module = path.split("::").next().unwrap_or_default();
+            !LIBRARY_MODULES.contains(&module)
+        }),
+        None => false,
+    }
+}
+
+/// Logger writing messages to stderr.
+struct StderrLogger {
+    level: LevelFilter,
+}
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level && is_cli_target(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let prefix = match record.level() {
+            Level::Error => "Error: ",
+            Level::Warn => "warning: ",
+            Level::Info | Level::Debug | Level::Trace => "",
+        };
+        let _ = writeln!(std::io::stderr(), "{}{}", prefix, record.args());
+    }
+
+    fn flush(&self) {}
+}
+
+/// Get the log level for the `--quiet` and `--verbose` flags.
+pub fn level(quiet: bool, verbose: bool) -> LevelFilter {
+    if quiet {
+        LevelFilter::Error
+    } else if verbose {
+        LevelFilter::Debug
+    } else {
+        LevelFilter::Info
+    }
+}
+
+/// Install the stderr logger with the given level.
+pub fn init(level: LevelFilter) {
+    let logger: &'static StderrLogger = Box::leak(Box::new(StderrLogger { level }));
+    if log::set_logger(logger).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level() {
+        assert_eq!(level(false, false), LevelFilter::Info);
+        assert_eq!(level(false, true), LevelFilter::Debug);
+        assert_eq!(level(true, false), LevelFilter::Error);
+    }
+
+    #[test]
+    fn test_is_cli_target() {
+        assert!(is_cli_target("diff2html"));
+        assert!(is_cli_target("diff2html::output"));
+        assert!(is_cli_target("diff2html::attributes"));
+        assert!(!is_cli_target("diff2html::parser"));
+        assert!(!is_cli_target("diff2html::render::utils"));
+        assert!(!is_cli_target("diff2html_extra"));
+        assert!(!is_cli_target("ureq::pool"));
+    }
+}
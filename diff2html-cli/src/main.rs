@@ -6,29 +6,63 @@
 //! Supports multiple input sources, output formats, and viewing options.
 
 mod args;
+mod attributes;
+mod blame;
 mod config;
+mod image;
 mod input;
+mod logging;
 mod output;
+#[cfg(feature = "self-update")]
+mod update;
 
+use std::io::Write;
 use std::process::ExitCode;
+use std::time::Instant;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Parser;
+use diff2html::render::utils::format_byte_size;
+use diff2html::{Diff2HtmlConfig, DiffFile, DiffStats, ParseResult};
+use log::{debug, error, info, warn};
 
-use args::{Args, OutputType};
-use config::parse_args;
+use args::{Args, FormatType, OutputType};
+use config::{CliConfig, parse_args};
 use input::get_input;
-use output::{get_output, preview, write_file};
+use output::{
+    copy_to_clipboard, get_output, get_style_outputs, html_to_pdf, parse_files, preview,
+    style_file_path, write_compressed, write_file, write_output_dir,
+};
 
 /// Exit codes matching the original TypeScript implementation.
 mod exit_codes {
     pub const SUCCESS: u8 = 0;
     pub const ERROR: u8 = 1;
+    /// The diff exceeds the `--failIfMoreThanFiles` or `--failIfMoreThanLines` limits
+    pub const TOO_LARGE: u8 = 2;
     pub const EMPTY_INPUT: u8 = 3;
+    /// With `--exitCode`, the diff contains changed files, like with
+    /// `git diff --exit-code`
+    pub const CHANGES: u8 = 1;
 }
 
 fn run() -> Result<u8> {
     let args = Args::parse();
+    logging::init(logging::level(args.quiet, args.verbose));
+
+    #[cfg(feature = "self-update")]
+    if args.check_update {
+        match update::check_update()? {
+            Some(version) => println!(
+                "diff2html {} is available (installed: {}), update with: cargo install diff2html-cli",
+                version,
+                update::CURRENT_VERSION
+            ),
+            None => println!("diff2html {} is up to date", update::CURRENT_VERSION),
+        }
+        return Ok(exit_codes::SUCCESS);
+    }
+
     let (diff2html_config, cli_config) = parse_args(&args)?;
 
     // Get input from specified source
@@ -40,38 +74,165 @@ fn run() -> Result<u8> {
 
     // Check for empty input
     if input.trim().is_empty() {
-        eprintln!(
+        warn!(
             "The input is empty. Try piping diff output to diff2html or specify input arguments."
         );
         return Ok(exit_codes::EMPTY_INPUT);
     }
 
-    // Generate output
-    let content = get_output(&diff2html_config, &cli_config, &input)?;
+    // Parse once for the output and the checks
+    let result = parse_files(&diff2html_config, &cli_config, &input)?;
+
+    // Report the parsed files and problems in malformed input
+    if cli_config.verbose {
+        for diagnostic in &result.diagnostics {
+            warn!("{}", diagnostic);
+        }
+        for file in &result.files {
+            debug!("{}", file);
+        }
+        debug!(
+            "Parsed {} file(s) with {} problem(s): {}",
+            result.files.len(),
+            result.diagnostics.len(),
+            DiffStats::from_files(&result.files)
+        );
+    }
+
+    let start = Instant::now();
+    let large_input = input.len() > LARGE_INPUT_BYTES;
+    if large_input {
+        info!(
+            "Rendering {} of diff input...",
+            format_byte_size(input.len() as u64)
+        );
+    }
 
-    // Write output to appropriate destination
-    if let Some(ref file_path) = cli_config.output_file {
-        write_file(file_path, &content)?;
-        eprintln!("Output written to: {}", file_path);
+    // Write one page per file to the output directory
+    if let Some(ref dir) = cli_config.output_dir {
+        let count = write_output_dir(&diff2html_config, &cli_config, &result, dir.as_ref())?;
+        info!("Output written to: {} ({} file page(s))", dir, count);
+    } else {
+        write_output(&diff2html_config, &cli_config, &result)?;
+    }
+
+    if large_input {
+        info!("Rendered in {:.1?}", start.elapsed());
+    }
+
+    Ok(check_changes(&cli_config, &result.files))
+}
+
+/// Input size in bytes above which the rendering progress is reported.
+const LARGE_INPUT_BYTES: usize = 1024 * 1024;
+
+/// Check the parsed diff against the `--failIfMoreThan*` limits and
+/// `--exitCode`, returning the exit code.
+fn check_changes(cli_config: &CliConfig, diff_files: &[DiffFile]) -> u8 {
+    let max_files = cli_config.fail_if_more_than_files;
+    let max_lines = cli_config.fail_if_more_than_lines;
+    if !cli_config.exit_code && max_files.is_none() && max_lines.is_none() {
+        return exit_codes::SUCCESS;
+    }
+
+    let stats = DiffStats::from_files(diff_files);
+    if let Some(max) = max_files
+        && stats.files > max
+    {
+        error!("The diff is too large: {} (more than {} files)", stats, max);
+        return exit_codes::TOO_LARGE;
+    }
+    if let Some(max) = max_lines
+        && stats.added_lines + stats.deleted_lines > max
+    {
+        error!(
+            "The diff is too large: {} (more than {} changed lines)",
+            stats, max
+        );
+        return exit_codes::TOO_LARGE;
+    }
+
+    if cli_config.exit_code && !diff_files.is_empty() {
+        exit_codes::CHANGES
+    } else {
+        exit_codes::SUCCESS
+    }
+}
+
+/// Generate the output and write it to the configured destination.
+fn write_output(
+    diff2html_config: &Diff2HtmlConfig,
+    cli_config: &CliConfig,
+    result: &ParseResult,
+) -> Result<()> {
+    let is_pdf = cli_config.format_type == FormatType::Pdf;
+    // Convert the HTML page for the pdf format
+    let to_bytes = |content: String| -> Result<Vec<u8>> {
+        if is_pdf {
+            html_to_pdf(&content)
+        } else {
+            Ok(content.into_bytes())
+        }
+    };
+
+    // Write a page per style
+    if cli_config.both_styles && (is_pdf || cli_config.format_type == FormatType::Html) {
+        for (style, content) in get_style_outputs(diff2html_config, cli_config, result)? {
+            let output_file = cli_config
+                .output_file
+                .as_deref()
+                .map(|path| style_file_path(path, style));
+            write_content(&to_bytes(content)?, output_file.as_deref(), cli_config)?;
+        }
+        return Ok(());
+    }
+
+    let content = to_bytes(get_output(diff2html_config, cli_config, result)?)?;
+    write_content(&content, cli_config.output_file.as_deref(), cli_config)
+}
+
+/// Write output to the file, or else the configured destination.
+fn write_content(content: &[u8], output_file: Option<&str>, cli_config: &CliConfig) -> Result<()> {
+    let is_pdf = cli_config.format_type == FormatType::Pdf;
+    if let Some(compression) = cli_config.compression {
+        if let Some(file_path) = output_file {
+            let mut file = std::fs::File::create(file_path)
+                .with_context(|| format!("Failed to create file: {}", file_path))?;
+            write_compressed(&mut file, content, compression)?;
+            info!("Compressed output written to: {}", file_path);
+        } else {
+            write_compressed(&mut std::io::stdout().lock(), content, compression)?;
+        }
+    } else if let Some(file_path) = output_file {
+        write_file(file_path, content)?;
+        info!("Output written to: {}", file_path);
     } else {
         match cli_config.output_type {
             OutputType::Preview => {
-                preview(&content, cli_config.format_type)?;
+                preview(content, cli_config)?;
             }
             OutputType::Stdout => {
-                println!("{}", content);
+                let mut stdout = std::io::stdout().lock();
+                stdout.write_all(content)?;
+                if !is_pdf {
+                    writeln!(stdout)?;
+                }
+            }
+            OutputType::Clipboard => {
+                copy_to_clipboard(content)?;
+                info!("Output copied to the clipboard");
             }
         }
     }
 
-    Ok(exit_codes::SUCCESS)
+    Ok(())
 }
 
 fn main() -> ExitCode {
     match run() {
         Ok(code) => ExitCode::from(code),
         Err(e) => {
-            eprintln!("Error: {:#}", e);
+            error!("{:#}", e);
             ExitCode::from(exit_codes::ERROR)
         }
     }
@@ -3,17 +3,30 @@
 //! This module handles output generation and destinations:
 //! - HTML wrapping with templates
 //! - Preview in browser
-//! - Writing to stdout or files
+//! - Copying to the clipboard
+//! - Writing to stdout or files, optionally compressed
 
+use std::collections::HashSet;
 use std::fs;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::time::Duration;
 
 use anyhow::{Context, Result, bail};
-use diff2html::{Diff2HtmlConfig, html, parse, templates::CSS};
+use diff2html::render::utils::{
+    filename_diff, make_html_id, make_html_ids, make_omitted_files_html,
+};
+use diff2html::{
+    Diff2Html, Diff2HtmlConfig, DiffFile, DiffStats, FileListRenderer, FilePage, HtmlIdStrategy,
+    OutputFormat, ParseResult, SYNCHRONISED_SCROLL_JS, copy_path_script_element, css_responsive,
+    css_style_element, file_content_toggle_script_element, parse_with_diagnostics,
+    sarif_from_diff_files, stats_csv, stats_json, stats_tsv, view_toggle_script_element,
+};
 
-use crate::args::{ColorSchemeType, FormatType};
-use crate::config::CliConfig;
+use crate::args::{ColorSchemeType, CompressionType, FormatType, InputType};
+use crate::attributes::mark_generated_files;
+use crate::config::{CliConfig, PreviewRetention};
 
 /// Default HTML wrapper template.
 const DEFAULT_TEMPLATE: &str = include_str!("../templates/wrapper.html");
@@ -78,7 +91,7 @@ fn escape_html(s: &str) -> String {
 pub fn get_output(
     diff2html_config: &Diff2HtmlConfig,
     cli_config: &CliConfig,
-    input: &str,
+    result: &ParseResult,
 ) -> Result<String> {
     // Validate custom template exists if specified
     if let Some(ref template_path) = cli_config.html_wrapper_template
@@ -87,26 +100,290 @@ pub fn get_output(
         bail!("Template ('{}') not found!", template_path);
     }
 
+    let diff_files = &result.files;
     match cli_config.format_type {
-        FormatType::Html => {
-            let html_content = html(input, diff2html_config);
-            prepare_html(&html_content, cli_config)
+        FormatType::Html | FormatType::Pdf => html_page(diff2html_config, cli_config, result),
+        FormatType::Json => serde_json::to_string(diff_files).context("Failed to serialize JSON"),
+        FormatType::MarkdownSummary => {
+            Ok(markdown_summary(result, diff2html_config.html_id_strategy))
         }
-        FormatType::Json => {
-            let diff_files = parse(input, &diff2html_config.to_parser_config());
-            serde_json::to_string(&diff_files).context("Failed to serialize JSON")
+        FormatType::Sarif => Ok(sarif_from_diff_files(diff_files)),
+        FormatType::Csv => Ok(stats_csv(diff_files)),
+        FormatType::Tsv => Ok(stats_tsv(diff_files)),
+        FormatType::StatsJson => stats_json(diff_files).context("Failed to serialize JSON"),
+    }
+}
+
+/// Parse the input, marking the generated files of the repository when the
+/// diff comes from git. The input is parsed once for all the outputs and
+/// checks of a run.
+pub fn parse_files(
+    diff2html_config: &Diff2HtmlConfig,
+    cli_config: &CliConfig,
+    input: &str,
+) -> Result<ParseResult> {
+    let mut result = parse_with_diagnostics(input, &diff2html_config.to_parser_config())?;
+    if cli_config.input_type == InputType::Command {
+        mark_generated_files(Path::new("."), &mut result.files);
+    }
+    Ok(result)
+}
+
+/// Generate a line-by-line and a side-by-side HTML page from a single parse,
+/// named `line` and `side`.
+pub fn get_style_outputs(
+    diff2html_config: &Diff2HtmlConfig,
+    cli_config: &CliConfig,
+    result: &ParseResult,
+) -> Result<[(&'static str, String); 2]> {
+    let page = |output_format| {
+        let config = Diff2HtmlConfig {
+            output_format,
+            ..diff2html_config.clone()
+        };
+        html_page(&config, cli_config, result)
+    };
+    Ok([
+        ("line", page(OutputFormat::LineByLine)?),
+        ("side", page(OutputFormat::SideBySide)?),
+    ])
+}
+
+/// Insert the name of a style before the extension of a path, e.g.
+/// `out.html` becomes `out.line.html`.
+pub fn style_file_path(path: &str, style: &str) -> String {
+    let path = Path::new(path);
+    let name = match (path.file_stem(), path.extension()) {
+        (Some(stem), Some(extension)) => format!(
+            "{}.{}.{}",
+            stem.to_string_lossy(),
+            style,
+            extension.to_string_lossy()
+        ),
+        _ => format!("{}.{}", path.to_string_lossy(), style),
+    };
+    path.with_file_name(name).to_string_lossy().into_owned()
+}
+
+/// Render parsed files as an HTML page.
+fn html_page(
+    diff2html_config: &Diff2HtmlConfig,
+    cli_config: &CliConfig,
+    result: &ParseResult,
+) -> Result<String> {
+    let engine = Diff2Html::new(diff2html_config.clone());
+    let toc = cli_config.toc.then(|| engine.toc(&result.files));
+    prepare_html(
+        &engine.html_from_parse_result(result),
+        toc.as_deref(),
+        cli_config,
+    )
+}
+
+/// Generate a markdown table of the changed files and their line counts.
+///
+/// The file names link to the files in the HTML output generated with
+/// deterministic ids and the same ID strategy.
+fn markdown_summary(result: &ParseResult, html_id_strategy: HtmlIdStrategy) -> String {
+    let diff_files = &result.files;
+    let mut summary = String::from("| File | Status | Changes |\n| --- | :---: | ---: |\n");
+    let html_ids = make_html_ids(diff_files, true, html_id_strategy);
+    for (file, html_id) in diff_files.iter().zip(html_ids) {
+        summary.push_str(&format!(
+            "| [{}](#{}) | {} | +{} \u{2212}{} |\n",
+            escape_markdown(&filename_diff(file)),
+            html_id,
+            file.status(),
+            file.added_lines,
+            file.deleted_lines
+        ));
+    }
+    match result.omitted_files {
+        0 => {}
+        1 => summary.push_str("| 1 more file not shown | | |\n"),
+        count => summary.push_str(&format!("| {} more files not shown | | |\n", count)),
+    }
+    summary.push_str(&format!("\n**{}**", DiffStats::from_files(diff_files)));
+    summary
+}
+
+/// Escape characters with a meaning in markdown table cells and link texts.
+fn escape_markdown(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '\\' | '|' | '[' | ']' | '*' | '_' | '`' | '<' | '>') {
+            escaped.push('\\');
         }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Name of the index page of an output directory.
+const INDEX_PAGE: &str = "index.html";
+
+/// Name of the search index of an output directory.
+const INDEX_JSON: &str = "index.json";
+
+/// Write one HTML page per file and an index page listing them to a directory.
+///
+/// The pages are named after the sanitized file paths. An `index.json` maps
+/// the file paths to their page and anchor, with the stats of the files, for
+/// static site search or custom frontends. Returns the number of file pages
+/// written.
+pub fn write_output_dir(
+    diff2html_config: &Diff2HtmlConfig,
+    cli_config: &CliConfig,
+    result: &ParseResult,
+    dir: &Path,
+) -> Result<usize> {
+    // Stable ids, so the index can link to the files on their pages
+    let config = Diff2HtmlConfig {
+        draw_file_list: false,
+        file_list_open: true,
+        draw_jump_menu: false,
+        deterministic: true,
+        ..diff2html_config.clone()
+    };
+    let engine = Diff2Html::new(config.clone());
+    let diff_files = &result.files;
+
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+
+    // The page of a file holds only this file, so its ID never has the suffix
+    // of the duplicate IDs of a page of all the files
+    let mut used_names = HashSet::from([INDEX_PAGE.to_string(), INDEX_JSON.to_string()]);
+    let pages: Vec<_> = diff_files
+        .iter()
+        .map(|file| FilePage {
+            url: unique_page_name(file, &mut used_names),
+            html_id: make_html_id(file, true, config.html_id_strategy),
+        })
+        .collect();
+    let mut index_entries = Vec::with_capacity(diff_files.len());
+    for (file, page) in diff_files.iter().zip(&pages) {
+        let page_name = &page.url;
+        index_entries.push(index_entry(file, page_name, &page.html_id));
+
+        let page_config = CliConfig {
+            page_title: file_page_name(file),
+            ..cli_config.clone()
+        };
+        let content = format!(
+            r#"<p><a class="d2h-index-link" href="{INDEX_PAGE}">All files</a></p>{}"#,
+            engine.html_from_diff_files(std::slice::from_ref(file))
+        );
+        write_file(
+            &dir.join(page_name).to_string_lossy(),
+            &prepare_html(&content, None, &page_config)?,
+        )?;
     }
+
+    let index_config = CliConfig {
+        show_files_open: true,
+        ..cli_config.clone()
+    };
+    let lists = FileListRenderer::new(config.to_file_list_config());
+    let file_list = lists.render_with_pages(diff_files, &pages);
+    let toc = cli_config
+        .toc
+        .then(|| lists.render_toc_with_pages(diff_files, &pages));
+    write_file(
+        &dir.join(INDEX_PAGE).to_string_lossy(),
+        &prepare_html(
+            &(file_list + &make_omitted_files_html(result.omitted_files, config.color_scheme)),
+            toc.as_deref(),
+            &index_config,
+        )?,
+    )?;
+    let index = serde_json::json!({
+        "stats": DiffStats::from_files(diff_files),
+        "files": index_entries,
+        "omittedFiles": result.omitted_files,
+    });
+    write_file(
+        &dir.join(INDEX_JSON).to_string_lossy(),
+        serde_json::to_string(&index).context("Failed to serialize JSON")?,
+    )?;
+
+    Ok(diff_files.len())
+}
+
+/// Entry of a file in the `index.json` of an output directory.
+fn index_entry(file: &DiffFile, page_name: &str, html_id: &str) -> serde_json::Value {
+    serde_json::json!({
+        "path": file_page_name(file),
+        "oldPath": file.old_name,
+        "status": file.status().to_string(),
+        "page": page_name,
+        "anchor": html_id,
+        "language": file.language,
+        "addedLines": file.added_lines,
+        "deletedLines": file.deleted_lines,
+    })
+}
+
+/// Get the path shown for a file, which is its old path if it was deleted.
+fn file_page_name(file: &DiffFile) -> String {
+    if file.is_deleted == Some(true) {
+        file.old_name.clone()
+    } else {
+        file.new_name.clone()
+    }
+}
+
+/// Get a page name for a file that is not used yet.
+///
+/// Characters other than ASCII letters, digits, `-`, `_` and `.` in the path
+/// are replaced by `_`, and a number is added if the name is taken.
+fn unique_page_name(file: &DiffFile, used_names: &mut HashSet<String>) -> String {
+    let stem: String = file_page_name(file)
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    let mut name = format!("{stem}.html");
+    let mut number = 1;
+    while !used_names.insert(name.clone()) {
+        number += 1;
+        name = format!("{stem}-{number}.html");
+    }
+    name
+}
+
+/// Placeholder of the diff while the nonce is added to the page.
+const DIFF_PLACEHOLDER: &str = "<!--diff2html-diff-->";
+
+/// Check if a wrapper template is a Handlebars template, from its extension.
+fn is_handlebars_template(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|extension| extension == "hbs" || extension == "handlebars")
 }
 
 /// Wrap diff HTML content in a full HTML page.
-fn prepare_html(diff_content: &str, config: &CliConfig) -> Result<String> {
+///
+/// The default wrapper and the custom templates named `*.hbs` or
+/// `*.handlebars` are Handlebars templates, getting the escaped `title`,
+/// `header` and `vars.<key>` values, the raw `css`, `jsUi`, `toc` and `diff`
+/// HTML, and the `showFilesOpen`, `synchronisedScroll` and `highlightCode`
+/// flags. The comment markers of other custom templates are replaced as in
+/// the original diff2html-cli, leaving the rest of them unchanged.
+fn prepare_html(diff_content: &str, toc: Option<&str>, config: &CliConfig) -> Result<String> {
     // Load template
-    let template = if let Some(ref template_path) = config.html_wrapper_template {
-        fs::read_to_string(template_path)
-            .with_context(|| format!("Failed to read template: {}", template_path))?
+    let (template, handlebars) = if let Some(ref template_path) = config.html_wrapper_template {
+        let template = fs::read_to_string(template_path)
+            .with_context(|| format!("Failed to read template: {}", template_path))?;
+        (template, is_handlebars_template(template_path))
     } else {
-        DEFAULT_TEMPLATE.to_string()
+        (DEFAULT_TEMPLATE.to_string(), true)
     };
 
     // Determine theme-specific content
@@ -117,64 +394,186 @@ fn prepare_html(diff_content: &str, config: &CliConfig) -> Result<String> {
     };
 
     // Build CSS content
-    let css_content = format!(
-        "{}\n{}\n<style>\n{}\n</style>",
-        base_style, github_theme, CSS
+    let mut css_content = format!(
+        "{}\n{}\n{}",
+        base_style,
+        github_theme,
+        css_style_element(None)
     );
+    if let Some(breakpoint) = config.responsive_breakpoint {
+        css_content.push_str(&format!(
+            "\n<style>\n{}</style>",
+            css_responsive(breakpoint)
+        ));
+    }
 
-    // Build JavaScript calls based on configuration
-    let file_list_toggle = format!("diff2htmlUi.fileListToggle({});", config.show_files_open);
-    let file_content_toggle = if config.file_content_toggle {
-        "diff2htmlUi.fileContentToggle();"
-    } else {
-        ""
-    };
-    let synchronised_scroll = if config.synchronised_scroll {
-        "diff2htmlUi.synchronisedScroll();"
-    } else {
-        ""
-    };
-    let highlight_code = if config.highlight_code {
-        "diff2htmlUi.highlightCode();"
-    } else {
-        ""
-    };
+    // Embedded instead of calling diff2html-ui, so they work offline too
+    let mut js_ui = DIFF2HTML_UI_JS.to_string();
+    if config.file_content_toggle {
+        js_ui.push('\n');
+        js_ui.push_str(&file_content_toggle_script_element(
+            config.persist_viewed,
+            None,
+        ));
+    }
+    if config.view_toggle {
+        js_ui.push('\n');
+        js_ui.push_str(&view_toggle_script_element(None));
+    }
+    if config.copy_path {
+        js_ui.push('\n');
+        js_ui.push_str(&copy_path_script_element(None));
+    }
 
     // Escape user-provided values to prevent XSS injection
     let escaped_title = escape_html(&config.page_title);
     let escaped_header = escape_html(&config.page_header);
 
-    // Perform replacements
-    let result = template
-        .replace("<!--diff2html-title-->", &escaped_title)
-        .replace("<!--diff2html-css-->", &css_content)
-        .replace("<!--diff2html-js-ui-->", DIFF2HTML_UI_JS)
-        .replace("//diff2html-fileListToggle", &file_list_toggle)
-        .replace("//diff2html-fileContentToggle", file_content_toggle)
-        .replace("//diff2html-synchronisedScroll", synchronised_scroll)
-        .replace("//diff2html-highlightCode", highlight_code)
-        .replace("<!--diff2html-header-->", &escaped_header)
-        .replace("<!--diff2html-diff-->", diff_content);
+    let page = if handlebars {
+        // The library registry does not escape values, they are escaped above
+        let vars: serde_json::Map<_, _> = config
+            .template_vars
+            .iter()
+            .map(|(key, value)| (key.clone(), escape_html(value).into()))
+            .collect();
+        let data = serde_json::json!({
+            "title": escaped_title,
+            "header": escaped_header,
+            "css": css_content,
+            "jsUi": js_ui,
+            "showFilesOpen": config.show_files_open,
+            "synchronisedScroll": config.synchronised_scroll,
+            "synchronisedScrollJs": SYNCHRONISED_SCROLL_JS,
+            "highlightCode": config.highlight_code,
+            "vars": vars,
+            "toc": toc,
+            "diff": DIFF_PLACEHOLDER,
+        });
+        diff2html::templates::get_registry()
+            .render_template(&template, &data)
+            .context("Failed to render template")?
+    } else {
+        let file_list_toggle = format!("diff2htmlUi.fileListToggle({});", config.show_files_open);
+        let synchronised_scroll = if config.synchronised_scroll {
+            SYNCHRONISED_SCROLL_JS
+        } else {
+            ""
+        };
+        let highlight_code = if config.highlight_code {
+            "diff2htmlUi.highlightCode();"
+        } else {
+            ""
+        };
+        let page = template
+            .replace("<!--diff2html-title-->", &escaped_title)
+            .replace("<!--diff2html-css-->", &css_content)
+            .replace("<!--diff2html-js-ui-->", &js_ui)
+            .replace("//diff2html-fileListToggle", &file_list_toggle)
+            .replace("//diff2html-fileContentToggle", "")
+            .replace("//diff2html-synchronisedScroll", synchronised_scroll)
+            .replace("//diff2html-highlightCode", highlight_code)
+            .replace("<!--diff2html-header-->", &escaped_header);
+        config
+            .template_vars
+            .iter()
+            .fold(page, |page, (key, value)| {
+                page.replace(
+                    &format!("<!--diff2html-var:{}-->", key),
+                    &escape_html(value),
+                )
+            })
+    };
+    let page = match &config.csp_nonce {
+        Some(nonce) => add_nonce(&page, nonce),
+        None => page,
+    };
 
-    Ok(result)
+    // Insert the diff last so the nonce is not added to its content
+    Ok(page.replace(DIFF_PLACEHOLDER, diff_content))
+}
+
+/// Add a nonce attribute to the `<style>` and `<script>` elements of a page.
+fn add_nonce(page: &str, nonce: &str) -> String {
+    let nonce = escape_html(nonce);
+    page.replace("<style>", &format!(r#"<style nonce="{nonce}">"#))
+        .replace("<script ", &format!(r#"<script nonce="{nonce}" "#))
+        .replace("<script>", &format!(r#"<script nonce="{nonce}">"#))
+}
+
+/// Prefix of the names of the preview files.
+const PREVIEW_PREFIX: &str = "diff2html-";
+
+/// Remove the old preview files of a directory that are not retained,
+/// returning the number of removed files.
+fn clean_previews(dir: &Path, retention: PreviewRetention) -> Result<usize> {
+    let max_age = match retention {
+        PreviewRetention::Always => return Ok(0),
+        PreviewRetention::Never => Duration::ZERO,
+        PreviewRetention::Days(days) => Duration::from_secs(days * 24 * 60 * 60),
+    };
+
+    let entries = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read preview directory: {}", dir.display()))?;
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        let is_preview = name.starts_with(PREVIEW_PREFIX)
+            && [".html", ".json", ".md", ".pdf", ".sarif", ".csv", ".tsv"]
+                .iter()
+                .any(|suffix| name.ends_with(suffix));
+        let expired = entry
+            .metadata()
+            .ok()
+            .filter(|metadata| metadata.is_file())
+            .and_then(|metadata| metadata.modified().ok())
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age >= max_age);
+        if is_preview && expired && fs::remove_file(entry.path()).is_ok() {
+            removed += 1;
+        }
+    }
+    if removed > 0 {
+        log::debug!("Removed {} old preview file(s)", removed);
+    }
+    Ok(removed)
 }
 
 /// Preview content in browser by writing to a temp file.
-pub fn preview(content: &str, format: FormatType) -> Result<()> {
-    let suffix = match format {
+///
+/// The file is opened with the configured browser command, or the system
+/// default. With `no_open`, its path is printed instead.
+pub fn preview(content: &[u8], cli_config: &CliConfig) -> Result<()> {
+    let suffix = match cli_config.format_type {
         FormatType::Html => ".html",
-        FormatType::Json => ".json",
+        FormatType::Json | FormatType::StatsJson => ".json",
+        FormatType::MarkdownSummary => ".md",
+        FormatType::Pdf => ".pdf",
+        FormatType::Sarif => ".sarif",
+        FormatType::Csv => ".csv",
+        FormatType::Tsv => ".tsv",
     };
 
+    let dir = match cli_config.preview_dir {
+        Some(ref dir) => {
+            fs::create_dir_all(dir).with_context(|| {
+                format!("Failed to create preview directory: {}", dir.display())
+            })?;
+            dir.clone()
+        }
+        None => std::env::temp_dir(),
+    };
+    clean_previews(&dir, cli_config.keep_preview)?;
+
     // Use tempfile crate for secure temp file creation with random name
     let mut temp_file = tempfile::Builder::new()
-        .prefix("diff2html-")
+        .prefix(PREVIEW_PREFIX)
         .suffix(suffix)
-        .tempfile()
+        .tempfile_in(&dir)
         .context("Failed to create secure temporary file")?;
 
     temp_file
-        .write_all(content.as_bytes())
+        .write_all(content)
         .context("Failed to write to temporary file")?;
 
     // Keep the file around after the handle is dropped so the browser can open it
@@ -182,71 +581,258 @@ pub fn preview(content: &str, format: FormatType) -> Result<()> {
         .keep()
         .context("Failed to persist temporary file")?;
 
-    open::that(&file_path)
-        .with_context(|| format!("Failed to open file in browser: {}", file_path.display()))?;
+    if cli_config.no_open {
+        println!("{}", file_path.display());
+        return Ok(());
+    }
+
+    if let Some(ref browser) = cli_config.browser {
+        let status = Command::new(browser)
+            .arg(&file_path)
+            .status()
+            .with_context(|| format!("Failed to run browser: {}", browser))?;
+        if !status.success() {
+            bail!("Browser {} failed: {}", browser, status);
+        }
+    } else {
+        open::that(&file_path)
+            .with_context(|| format!("Failed to open file in browser: {}", file_path.display()))?;
+    }
 
     Ok(())
 }
 
 /// Write content to a file.
-pub fn write_file(path: &str, content: &str) -> Result<()> {
+pub fn write_file(path: &str, content: impl AsRef<[u8]>) -> Result<()> {
     fs::write(path, content).with_context(|| format!("Failed to write to file: {}", path))
 }
 
+/// Compress content with the `gzip` or `brotli` program and write it to the
+/// writer, e.g. to publish large diffs pre-compressed to static hosting.
+pub fn write_compressed(
+    writer: &mut impl Write,
+    content: &[u8],
+    compression: CompressionType,
+) -> Result<()> {
+    let program = match compression {
+        CompressionType::Gzip => "gzip",
+        CompressionType::Br => "brotli",
+    };
+    let output = run_with_input(
+        Command::new(program)
+            .args(["-c", "-9"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped()),
+        content,
+    )
+    .with_context(|| format!("Failed to run {}, is it installed?", program))?;
+    if !output.status.success() {
+        bail!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    writer
+        .write_all(&output.stdout)
+        .context("Failed to write compressed output")
+}
+
+/// Programs copying their stdin to the clipboard, tried in order.
+const CLIPBOARD_PROGRAMS: &[(&str, &[&str])] = &[
+    ("pbcopy", &[]),
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+    ("clip.exe", &[]),
+];
+
+/// Copy content to the system clipboard using the first clipboard program
+/// found, e.g. to paste rendered fragments into wikis and chat tools.
+pub fn copy_to_clipboard(content: &[u8]) -> Result<()> {
+    // wl-copy fails on X11 and xclip without a display, so try the next one
+    let mut last_error = None;
+    for (program, args) in CLIPBOARD_PROGRAMS {
+        // Clipboard programs on X11 and Wayland keep running in the background
+        // to serve the content, so their output is not read
+        let result = run_with_input(
+            Command::new(program)
+                .args(*args)
+                .stdout(Stdio::null())
+                .stderr(Stdio::null()),
+            content,
+        );
+        match result {
+            Ok(output) if output.status.success() => return Ok(()),
+            Ok(output) => {
+                last_error = Some(anyhow::anyhow!("{} failed: {}", program, output.status));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                last_error =
+                    Some(anyhow::Error::new(e).context(format!("Failed to run {}", program)));
+            }
+        }
+    }
+
+    if let Some(e) = last_error {
+        return Err(e);
+    }
+    let programs: Vec<_> = CLIPBOARD_PROGRAMS.iter().map(|(p, _)| *p).collect();
+    bail!(
+        "No clipboard program found, install one of: {}",
+        programs.join(", ")
+    )
+}
+
+/// Programs converting an HTML page to PDF, tried in order.
+const PDF_PROGRAMS: &[&str] = &[
+    "chromium",
+    "chromium-browser",
+    "google-chrome",
+    "weasyprint",
+];
+
+/// Get the arguments of a PDF program to convert the page to the PDF path.
+fn pdf_args(program: &str, page: &Path, pdf: &Path) -> Vec<String> {
+    if program == "weasyprint" {
+        vec![page.display().to_string(), pdf.display().to_string()]
+    } else {
+        vec![
+            "--headless".to_string(),
+            "--disable-gpu".to_string(),
+            "--no-pdf-header-footer".to_string(),
+            format!("--print-to-pdf={}", pdf.display()),
+            page.display().to_string(),
+        ]
+    }
+}
+
+/// Convert an HTML page to PDF with the first of headless Chromium, Chrome
+/// or WeasyPrint found.
+pub fn html_to_pdf(page: &str) -> Result<Vec<u8>> {
+    let dir = tempfile::Builder::new()
+        .prefix("diff2html-pdf-")
+        .tempdir()
+        .context("Failed to create temporary directory")?;
+    let page_path = dir.path().join("diff.html");
+    let pdf_path = dir.path().join("diff.pdf");
+    fs::write(&page_path, page).context("Failed to write to temporary file")?;
+
+    for program in PDF_PROGRAMS {
+        let status = Command::new(program)
+            .args(pdf_args(program, &page_path, &pdf_path))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+        match status {
+            Ok(status) if status.success() => {
+                return fs::read(&pdf_path)
+                    .with_context(|| format!("{} did not write the PDF", program));
+            }
+            Ok(status) => bail!("{} failed to convert the HTML to PDF: {}", program, status),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e).with_context(|| format!("Failed to run {}", program)),
+        }
+    }
+
+    bail!(
+        "No PDF converter found, install one of: {}",
+        PDF_PROGRAMS.join(", ")
+    )
+}
+
+/// Run a command with content on its stdin and wait for it to exit.
+fn run_with_input(command: &mut Command, content: &[u8]) -> std::io::Result<Output> {
+    let mut child = command.stdin(Stdio::piped()).spawn()?;
+
+    // Feed stdin from another thread so a full stdout pipe cannot block it
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    std::thread::scope(|scope| {
+        let writer = scope.spawn(move || stdin.write_all(content));
+        let output = child.wait_with_output();
+        writer.join().expect("stdin writer panicked")?;
+        output
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use diff2html::FILE_CONTENT_TOGGLE_JS;
+    use diff2html::render::utils::get_stable_html_id;
+    use std::time::SystemTime;
 
-    #[test]
-    fn test_prepare_html_replaces_placeholders() {
-        let config = CliConfig {
+    /// Configuration of the tests, with the optional features disabled.
+    fn test_cli_config() -> CliConfig {
+        CliConfig {
             input_type: crate::args::InputType::Command,
             format_type: FormatType::Html,
             output_type: crate::args::OutputType::Preview,
+            browser: None,
+            no_open: false,
+            preview_dir: None,
+            keep_preview: PreviewRetention::Always,
             output_file: None,
-            page_title: "Test Title".to_string(),
-            page_header: "Test Header".to_string(),
+            output_dir: None,
+            compression: None,
+            page_title: "Test".to_string(),
+            page_header: "Test".to_string(),
             html_wrapper_template: None,
+            template_vars: vec![],
             show_files_open: false,
-            file_content_toggle: true,
-            synchronised_scroll: true,
-            highlight_code: true,
+            file_content_toggle: false,
+            persist_viewed: false,
+            toc: false,
+            view_toggle: false,
+            both_styles: false,
+            copy_path: false,
+            synchronised_scroll: false,
+            highlight_code: false,
             color_scheme: ColorSchemeType::Light,
+            responsive_breakpoint: None,
             ignore: vec![],
             extra_args: vec![],
+            exit_code: false,
+            fail_if_more_than_files: None,
+            fail_if_more_than_lines: None,
+            verbose: false,
+            csp_nonce: None,
+        }
+    }
+
+    #[test]
+    fn test_prepare_html_replaces_placeholders() {
+        let config = CliConfig {
+            page_title: "Test Title".to_string(),
+            page_header: "Test Header".to_string(),
+            file_content_toggle: true,
+            synchronised_scroll: true,
+            highlight_code: true,
+            ..test_cli_config()
         };
 
-        let result = prepare_html("<div>test content</div>", &config).unwrap();
+        let result = prepare_html("<div>test content</div>", None, &config).unwrap();
 
         assert!(result.contains("Test Title"));
         assert!(result.contains("Test Header"));
         assert!(result.contains("<div>test content</div>"));
         assert!(result.contains("diff2htmlUi.fileListToggle(false);"));
-        assert!(result.contains("diff2htmlUi.fileContentToggle();"));
-        assert!(result.contains("diff2htmlUi.synchronisedScroll();"));
+        assert!(result.contains(FILE_CONTENT_TOGGLE_JS));
+        assert!(!result.contains("<script data-d2h-persist>"));
+        assert!(!result.contains("diff2htmlUi.fileContentToggle();"));
+        assert!(result.contains(SYNCHRONISED_SCROLL_JS));
+        assert!(!result.contains("diff2htmlUi.synchronisedScroll();"));
         assert!(result.contains("diff2htmlUi.highlightCode();"));
     }
 
     #[test]
     fn test_prepare_html_light_theme() {
-        let config = CliConfig {
-            input_type: crate::args::InputType::Command,
-            format_type: FormatType::Html,
-            output_type: crate::args::OutputType::Preview,
-            output_file: None,
-            page_title: "Test".to_string(),
-            page_header: "Test".to_string(),
-            html_wrapper_template: None,
-            show_files_open: false,
-            file_content_toggle: false,
-            synchronised_scroll: false,
-            highlight_code: false,
-            color_scheme: ColorSchemeType::Light,
-            ignore: vec![],
-            extra_args: vec![],
-        };
+        let config = test_cli_config();
 
-        let result = prepare_html("", &config).unwrap();
+        let result = prepare_html("", None, &config).unwrap();
         assert!(result.contains("github.min.css"));
         assert!(!result.contains("github-dark.min.css"));
     }
@@ -254,73 +840,125 @@ mod tests {
     #[test]
     fn test_prepare_html_dark_theme() {
         let config = CliConfig {
-            input_type: crate::args::InputType::Command,
-            format_type: FormatType::Html,
-            output_type: crate::args::OutputType::Preview,
-            output_file: None,
-            page_title: "Test".to_string(),
-            page_header: "Test".to_string(),
-            html_wrapper_template: None,
-            show_files_open: false,
-            file_content_toggle: false,
-            synchronised_scroll: false,
-            highlight_code: false,
             color_scheme: ColorSchemeType::Dark,
-            ignore: vec![],
-            extra_args: vec![],
+            ..test_cli_config()
         };
 
-        let result = prepare_html("", &config).unwrap();
+        let result = prepare_html("", None, &config).unwrap();
         assert!(result.contains("github-dark.min.css"));
     }
 
     #[test]
     fn test_prepare_html_auto_theme() {
         let config = CliConfig {
-            input_type: crate::args::InputType::Command,
-            format_type: FormatType::Html,
-            output_type: crate::args::OutputType::Preview,
-            output_file: None,
-            page_title: "Test".to_string(),
-            page_header: "Test".to_string(),
-            html_wrapper_template: None,
-            show_files_open: false,
-            file_content_toggle: false,
-            synchronised_scroll: false,
-            highlight_code: false,
             color_scheme: ColorSchemeType::Auto,
-            ignore: vec![],
-            extra_args: vec![],
+            ..test_cli_config()
         };
 
-        let result = prepare_html("", &config).unwrap();
+        let result = prepare_html("", None, &config).unwrap();
         assert!(result.contains("prefers-color-scheme: light"));
         assert!(result.contains("prefers-color-scheme: dark"));
     }
 
     #[test]
     fn test_prepare_html_disabled_features() {
+        let config = test_cli_config();
+
+        let result = prepare_html("", None, &config).unwrap();
+        assert!(!result.contains(FILE_CONTENT_TOGGLE_JS));
+        assert!(!result.contains(SYNCHRONISED_SCROLL_JS));
+        assert!(!result.contains("diff2htmlUi.highlightCode();"));
+    }
+
+    #[test]
+    fn test_prepare_html_responsive_breakpoint() {
         let config = CliConfig {
-            input_type: crate::args::InputType::Command,
-            format_type: FormatType::Html,
-            output_type: crate::args::OutputType::Preview,
-            output_file: None,
-            page_title: "Test".to_string(),
-            page_header: "Test".to_string(),
-            html_wrapper_template: None,
-            show_files_open: false,
-            file_content_toggle: false,
-            synchronised_scroll: false,
-            highlight_code: false,
-            color_scheme: ColorSchemeType::Light,
-            ignore: vec![],
-            extra_args: vec![],
+            file_content_toggle: true,
+            synchronised_scroll: true,
+            highlight_code: true,
+            responsive_breakpoint: Some(800),
+            ..test_cli_config()
         };
 
-        let result = prepare_html("", &config).unwrap();
-        assert!(!result.contains("diff2htmlUi.fileContentToggle();"));
-        assert!(!result.contains("diff2htmlUi.synchronisedScroll();"));
-        assert!(!result.contains("diff2htmlUi.highlightCode();"));
+        let result = prepare_html("", None, &config).unwrap();
+        assert!(result.contains(&css_responsive(800)));
+    }
+
+    #[test]
+    fn test_prepare_html_template_vars() {
+        let mut template = tempfile::NamedTempFile::new().unwrap();
+        template
+            .write_all(
+                b"<p><!--diff2html-var:build--> <!--diff2html-var:link--> \
+                  <!--diff2html-var:a]b--> <!--diff2html-var:other--></p>\
+                  <script>if (x) {{}}</script><!--diff2html-diff-->",
+            )
+            .unwrap();
+        let config = CliConfig {
+            html_wrapper_template: Some(template.path().to_string_lossy().to_string()),
+            template_vars: vec![
+                ("build".to_string(), "1234".to_string()),
+                ("link".to_string(), "<a>".to_string()),
+                ("a]b".to_string(), "c".to_string()),
+            ],
+            color_scheme: ColorSchemeType::Auto,
+            ..test_cli_config()
+        };
+
+        let result = prepare_html("<!--diff2html-var:build-->", None, &config).unwrap();
+        assert_eq!(
+            result,
+            "<p>1234 &lt;a&gt; c <!--diff2html-var:other--></p>\
+             <script>if (x) {{}}</script><!--diff2html-var:build-->"
+        );
+    }
+
+    #[test]
+    fn test_prepare_html_handlebars_template() {
+        let mut template = tempfile::Builder::new().suffix(".hbs").tempfile().unwrap();
+        template
+            .write_all(
+                b"{{title}}{{#if highlightCode}}<script></script>{{/if}}\
+                  {{vars.build}}{{vars.link}}|{{{diff}}}",
+            )
+            .unwrap();
+        let config = CliConfig {
+            page_title: "<b>".to_string(),
+            html_wrapper_template: Some(template.path().to_string_lossy().to_string()),
+            template_vars: vec![
+                ("build".to_string(), "1234".to_string()),
+                ("link".to_string(), "<a>".to_string()),
+            ],
+            color_scheme: ColorSchemeType::Auto,
+            ..test_cli_config()
+        };
+
+        let result = prepare_html("{{title}}", None, &config).unwrap();
+        assert_eq!(result, "&lt;b&gt;1234&lt;a&gt;|{{title}}");
+    }
+
+    #[test]
+    fn test_prepare_html_csp_nonce() {
+        let config = CliConfig {
+            file_content_toggle: true,
+            persist_viewed: true,
+            synchronised_scroll: true,
+            highlight_code: true,
+            color_scheme: ColorSchemeType::Auto,
+            csp_nonce: Some("{{nonce}}".to_string()),
+            ..test_cli_config()
+        };
+
+        let result = prepare_html("<div><style></style></div>", None, &config).unwrap();
+        assert!(!result.contains("style=\""));
+        assert_eq!(result.matches("<style>").count(), 1, "only in the diff");
+        assert_eq!(result.matches("<script>").count(), 0);
+        assert_eq!(result.matches(r#"<style nonce="{{nonce}}">"#).count(), 3);
+        assert!(result.contains(r#"<script nonce="{{nonce}}" src="#));
+        assert!(result.contains("<script nonce=\"{{nonce}}\">\n"));
+        assert!(result.contains("<script nonce=\"{{nonce}}\" data-d2h-persist>\n"));
+        assert!(!result.contains(r#"nonce="{{nonce}}" nonce"#));
+        assert!(result.contains("<div><style></style></div>"));
     }
 
     #[test]
@@ -334,26 +972,143 @@ mod tests {
         assert_eq!(escape_html("normal text"), "normal text");
     }
 
+    #[test]
+    fn test_markdown_summary() {
+        let diff = "diff --git a/src/lib.rs b/src/lib.rs
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,2 +1,2 @@
+-old
++new
+ same
+diff --git a/a|b_c.txt b/a|b_c.txt
+new file mode 100644
+--- /dev/null
++++ b/a|b_c.txt
+@@ -0,0 +1 @@
++added
+";
+        let mut result = parse_with_diagnostics(diff, &Default::default()).unwrap();
+        let files = result.files.clone();
+
+        assert_eq!(
+            markdown_summary(&result, HtmlIdStrategy::Hash),
+            format!(
+                "| File | Status | Changes |\n\
+                 | --- | :---: | ---: |\n\
+                 | [src/lib.rs](#{}) | M | +1 \u{2212}1 |\n\
+                 | [a\\|b\\_c.txt](#{}) | A | +1 \u{2212}0 |\n\
+                 \n\
+                 **2 files (+2 \u{2212}1)**",
+                get_stable_html_id(&files[0]),
+                get_stable_html_id(&files[1])
+            )
+        );
+
+        assert!(
+            markdown_summary(&result, HtmlIdStrategy::Slug)
+                .contains("| [src/lib.rs](#d2h-src-lib-rs) | M |")
+        );
+        result.omitted_files = 3;
+        assert!(
+            markdown_summary(&result, HtmlIdStrategy::Hash)
+                .contains("| 3 more files not shown | | |\n")
+        );
+    }
+
+    #[test]
+    fn test_write_compressed_gzip() {
+        let mut output = Vec::new();
+        write_compressed(&mut output, b"<html></html>", CompressionType::Gzip).unwrap();
+        // gzip magic number
+        assert_eq!(output[..2], [0x1f, 0x8b]);
+    }
+
+    #[test]
+    fn test_clean_previews() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, days: u64| {
+            let path = dir.path().join(name);
+            let file = fs::File::create(&path).unwrap();
+            let age = Duration::from_secs(days * 24 * 60 * 60);
+            file.set_modified(SystemTime::now() - age).unwrap();
+        };
+        write("diff2html-old.html", 10);
+        write("diff2html-new.json", 1);
+        write("diff2html-notes.txt", 10);
+        write("other.html", 10);
+
+        assert_eq!(
+            clean_previews(dir.path(), PreviewRetention::Always).unwrap(),
+            0
+        );
+        assert_eq!(
+            clean_previews(dir.path(), PreviewRetention::Days(7)).unwrap(),
+            1
+        );
+        assert!(!dir.path().join("diff2html-old.html").exists());
+        assert_eq!(
+            clean_previews(dir.path(), PreviewRetention::Never).unwrap(),
+            1
+        );
+
+        let mut names: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["diff2html-notes.txt", "other.html"]);
+    }
+
+    #[test]
+    fn test_style_file_path() {
+        assert_eq!(style_file_path("out.html", "line"), "out.line.html");
+        assert_eq!(style_file_path("dir/out.pdf", "side"), "dir/out.side.pdf");
+        assert_eq!(style_file_path("out", "line"), "out.line");
+    }
+
+    #[test]
+    fn test_unique_page_name() {
+        let file = |name: &str| DiffFile {
+            old_name: name.to_string(),
+            new_name: name.to_string(),
+            ..Default::default()
+        };
+        let mut used_names = HashSet::from([INDEX_PAGE.to_string()]);
+
+        assert_eq!(
+            unique_page_name(&file("src/a b.rs"), &mut used_names),
+            "src_a_b.rs.html"
+        );
+        assert_eq!(
+            unique_page_name(&file("src/a_b.rs"), &mut used_names),
+            "src_a_b.rs-2.html"
+        );
+        assert_eq!(
+            unique_page_name(&file("index"), &mut used_names),
+            "index-2.html"
+        );
+
+        let deleted = DiffFile {
+            new_name: "/dev/null".to_string(),
+            is_deleted: Some(true),
+            ..file("../old.rs")
+        };
+        assert_eq!(
+            unique_page_name(&deleted, &mut used_names),
+            ".._old.rs.html"
+        );
+    }
+
     #[test]
     fn test_prepare_html_escapes_xss_in_title_and_header() {
         let config = CliConfig {
-            input_type: crate::args::InputType::Command,
-            format_type: FormatType::Html,
-            output_type: crate::args::OutputType::Preview,
-            output_file: None,
             page_title: "<script>alert('xss')</script>".to_string(),
             page_header: "<img src=x onerror=alert('xss')>".to_string(),
-            html_wrapper_template: None,
-            show_files_open: false,
-            file_content_toggle: false,
-            synchronised_scroll: false,
-            highlight_code: false,
-            color_scheme: ColorSchemeType::Light,
-            ignore: vec![],
-            extra_args: vec![],
+            ..test_cli_config()
         };
 
-        let result = prepare_html("", &config).unwrap();
+        let result = prepare_html("", None, &config).unwrap();
 
         // Verify that the raw script tags are NOT in the output
         assert!(!result.contains("<script>alert"));
@@ -0,0 +1,73 @@
+//! Update check for diff2html CLI.
+//!
+//! With the `self-update` feature, `--checkUpdate` looks up the latest version
+//! published on crates.io, for users of standalone binaries installed outside
+//! cargo. The request is made with the `curl` program.
+
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+
+/// crates.io API endpoint of the CLI crate.
+const CRATES_IO_URL: &str = "https://crates.io/api/v1/crates/diff2html-cli";
+
+/// Version of the running binary.
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Get the latest version if it is newer than the running binary.
+pub fn check_update() -> Result<Option<String>> {
+    let latest = latest_version()?;
+    Ok(is_newer(&latest, CURRENT_VERSION).then_some(latest))
+}
+
+/// Get the latest stable version published on crates.io.
+fn latest_version() -> Result<String> {
+    // crates.io rejects requests without a user agent
+    let output = Command::new("curl")
+        .args(["-fsSL", "--max-time", "10", "-A"])
+        .arg(format!("diff2html-cli/{}", CURRENT_VERSION))
+        .arg(CRATES_IO_URL)
+        .output()
+        .context("Failed to run curl, is it installed?")?;
+    if !output.status.success() {
+        bail!(
+            "Failed to query crates.io: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let response: serde_json::Value =
+        serde_json::from_slice(&output.stdout).context("Invalid crates.io response")?;
+    response["crate"]["max_stable_version"]
+        .as_str()
+        .map(str::to_string)
+        .context("No version in the crates.io response")
+}
+
+/// Checks if a `major.minor.patch` version is newer than another.
+fn is_newer(version: &str, current: &str) -> bool {
+    let parse = |version: &str| -> Vec<u64> {
+        version
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    };
+    parse(version) > parse(current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_newer() {
+        assert!(is_newer("0.2.0", "0.1.0"));
+        assert!(is_newer("0.10.0", "0.9.3"));
+        assert!(is_newer("1.0.0", "0.1.0-beta.1"));
+        assert!(!is_newer("0.1.0", "0.1.0"));
+        assert!(!is_newer("0.1.0", "0.2.0"));
+    }
+}
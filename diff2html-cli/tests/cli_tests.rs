@@ -84,6 +84,115 @@ fn test_cli_file_input() {
     );
 }
 
+#[test]
+fn test_cli_multiple_file_inputs() {
+    let output = Command::new(BINARY_PATH)
+        .args(["-i", "file", "-f", "json", "-o", "stdout", "--"])
+        .args([fixture_path("simple.diff"), fixture_path("new_file.diff")])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let files: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(files.as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn test_cli_sarif_format() {
+    let output = Command::new(BINARY_PATH)
+        .args(["-i", "file", "-f", "sarif", "-o", "stdout", "--"])
+        .arg(fixture_path("simple.diff"))
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let log: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(log["version"], "2.1.0");
+    let result = &log["runs"][0]["results"][0];
+    assert!(result["fixes"][0]["artifactChanges"][0]["replacements"].is_array());
+}
+
+#[test]
+fn test_cli_csv_format() {
+    let output = Command::new(BINARY_PATH)
+        .args(["-i", "file", "-f", "csv", "-o", "stdout", "--"])
+        .arg(fixture_path("simple.diff"))
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut lines = stdout.lines();
+    assert_eq!(
+        lines.next(),
+        Some("path,status,additions,deletions,binary,language")
+    );
+    assert!(lines.next().unwrap().contains(",modified,"));
+}
+
+#[test]
+fn test_cli_stats_json_format() {
+    let output = Command::new(BINARY_PATH)
+        .args(["-i", "file", "-f", "stats-json", "-o", "stdout", "--"])
+        .arg(fixture_path("multiple_files.diff"))
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let stats: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(stats["files"], 2);
+    assert_eq!(
+        stats["languages"],
+        serde_json::json!([{"language": "js", "files": 2, "addedLines": 1, "deletedLines": 2}])
+    );
+}
+
+#[test]
+fn test_cli_toc() {
+    let output = Command::new(BINARY_PATH)
+        .args(["-i", "file", "-o", "stdout", "--toc", "--"])
+        .arg(fixture_path("simple.diff"))
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(r#"<body class="d2h-has-toc">"#));
+    assert!(stdout.contains(r#"<nav class="d2h-toc"#));
+    assert!(stdout.contains("d2h-toc-link"));
+}
+
+#[test]
+fn test_cli_switchable_style() {
+    let output = Command::new(BINARY_PATH)
+        .args(["-i", "file", "-o", "stdout", "-s", "switchable", "--"])
+        .arg(fixture_path("simple.diff"))
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(r#"<div class="d2h-view d2h-view-side-by-side d2h-d-none">"#));
+    assert!(stdout.contains(r#"<table class="d2h-diff-table">"#));
+    assert!(stdout.contains("d2h-view-toggle-enabled"));
+}
+
+#[test]
+fn test_cli_collapse() {
+    let run = |pattern: &str| {
+        let output = Command::new(BINARY_PATH)
+            .args(["-i", "file", "-o", "stdout", "--collapse", pattern, "--"])
+            .arg(fixture_path("simple.diff"))
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    assert!(run("sample").contains(r#"value="viewed" checked>"#));
+    assert!(!run("*.txt").contains(r#"value="viewed" checked>"#));
+}
+
 #[test]
 fn test_cli_stdin_input() {
     let diff_content =
@@ -568,6 +677,351 @@ fn test_cli_nonexistent_file() {
     assert!(!output.status.success());
 }
 
+#[test]
+fn test_cli_output_dir() {
+    let dir = tempfile::tempdir().unwrap();
+    let output_dir = dir.path().join("pages");
+
+    let output = Command::new(BINARY_PATH)
+        .args(["-i", "file", "--toc", "--outputDir"])
+        .arg(&output_dir)
+        .args(["--", &fixture_path("multiple_files.diff")])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let index = std::fs::read_to_string(output_dir.join("index.html")).unwrap();
+    assert!(index.contains(r#"class="d2h-file-list-wrapper"#));
+    assert!(index.contains("diff2htmlUi.fileListToggle(true);"));
+    assert!(index.contains(r##"href="src_core_init.js.html#d2h-"##));
+    assert!(index.contains(r##"href="src_event.js.html#d2h-"##));
+    assert!(index.contains(r#"class="d2h-toc-sidebar"#));
+    assert_eq!(
+        index.matches(r##"href="src_event.js.html#d2h-"##).count(),
+        2
+    );
+    assert!(!index.contains(r#"class="d2h-file-wrapper"#));
+
+    let page = std::fs::read_to_string(output_dir.join("src_event.js.html")).unwrap();
+    assert!(page.contains("<title>src/event.js</title>"));
+    assert!(page.contains(r#"href="index.html""#));
+    assert_eq!(page.matches(r#"class="d2h-file-wrapper"#).count(), 1);
+    assert!(!page.contains(r#"class="d2h-file-list-wrapper"#));
+
+    let index = std::fs::read_to_string(output_dir.join("index.json")).unwrap();
+    let index: serde_json::Value = serde_json::from_str(&index).unwrap();
+    assert_eq!(index["stats"]["files"], 2);
+    let entry = &index["files"][1];
+    assert_eq!(entry["path"], "src/event.js");
+    assert_eq!(entry["page"], "src_event.js.html");
+    assert!(entry["anchor"].as_str().unwrap().starts_with("d2h-"));
+    assert!(page.contains(&format!(r#"id="{}""#, entry["anchor"].as_str().unwrap())));
+}
+
+#[test]
+fn test_cli_output_dir_requires_html() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = Command::new(BINARY_PATH)
+        .args(["-i", "file", "-f", "json", "--outputDir"])
+        .arg(dir.path())
+        .args(["--", &fixture_path("simple.diff")])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("--outputDir requires the html format")
+    );
+}
+
+#[test]
+fn test_cli_both_styles() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = Command::new(BINARY_PATH)
+        .args(["-i", "file", "-s", "both", "-F"])
+        .arg(dir.path().join("out.html"))
+        .args(["--", &fixture_path("simple.diff")])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let line = std::fs::read_to_string(dir.path().join("out.line.html")).unwrap();
+    assert!(line.contains(r#"class="d2h-file-diff""#));
+    assert!(!line.contains(r#"class="d2h-files-diff""#));
+    let side = std::fs::read_to_string(dir.path().join("out.side.html")).unwrap();
+    assert!(side.contains(r#"class="d2h-files-diff""#));
+    assert!(!dir.path().join("out.html").exists());
+
+    let output = Command::new(BINARY_PATH)
+        .args(["-i", "file", "-s", "both", "-o", "stdout"])
+        .args(["--", &fixture_path("simple.diff")])
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--style both writes two outputs"));
+}
+
+#[test]
+fn test_cli_compress() {
+    let output = Command::new(BINARY_PATH)
+        .args(["-i", "file", "-o", "stdout", "--compress", "gzip"])
+        .args(["--", &fixture_path("simple.diff")])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    assert_eq!(output.stdout[..2], [0x1f, 0x8b]);
+
+    let output = Command::new(BINARY_PATH)
+        .args(["-i", "file", "-o", "preview", "--compress", "gzip"])
+        .args(["--", &fixture_path("simple.diff")])
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("--compress requires --file or the stdout output")
+    );
+}
+
+#[test]
+fn test_cli_clipboard_without_program() {
+    let output = Command::new(BINARY_PATH)
+        .args(["-i", "file", "-o", "clipboard"])
+        .args(["--", &fixture_path("simple.diff")])
+        .env("PATH", "")
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No clipboard program found"));
+}
+
+#[test]
+fn test_cli_preview_no_open() {
+    let output = Command::new(BINARY_PATH)
+        .args(["-i", "file", "--noOpen"])
+        .args(["--", &fixture_path("simple.diff")])
+        .env("BROWSER", "nonexistent_browser_12345")
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    assert!(path.ends_with(".html"));
+    let page = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(page.contains(r#"class="d2h-file-wrapper"#));
+}
+
+#[test]
+fn test_cli_preview_dir() {
+    let dir = tempfile::tempdir().unwrap();
+    let preview = || {
+        let output = Command::new(BINARY_PATH)
+            .args([
+                "-i",
+                "file",
+                "--noOpen",
+                "--keepPreview",
+                "never",
+                "--previewDir",
+            ])
+            .arg(dir.path())
+            .args(["--", &fixture_path("simple.diff")])
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+
+    let first = preview();
+    assert!(std::path::Path::new(&first).starts_with(dir.path()));
+    let second = preview();
+    assert_ne!(first, second);
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn test_cli_invalid_keep_preview() {
+    let output = Command::new(BINARY_PATH)
+        .args(["-i", "file", "--noOpen", "--keepPreview", "sometimes"])
+        .args(["--", &fixture_path("simple.diff")])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("keepPreview must be never, always"));
+}
+
+#[test]
+fn test_cli_preview_browser() {
+    let output = Command::new(BINARY_PATH)
+        .args(["-i", "file"])
+        .args(["--", &fixture_path("simple.diff")])
+        .env("BROWSER", "nonexistent_browser_12345:firefox")
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("Failed to run browser: nonexistent_browser_12345")
+    );
+
+    let output = Command::new(BINARY_PATH)
+        .args(["-i", "file", "--browser", "nonexistent_browser_67890"])
+        .args(["--", &fixture_path("simple.diff")])
+        .env("BROWSER", "nonexistent_browser_12345")
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("nonexistent_browser_67890"));
+}
+
+#[test]
+fn test_cli_pdf_without_converter() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = Command::new(BINARY_PATH)
+        .args(["-i", "file", "-f", "pdf", "-F"])
+        .arg(dir.path().join("diff.pdf"))
+        .args(["--", &fixture_path("simple.diff")])
+        .env("PATH", "")
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No PDF converter found"));
+    assert!(!dir.path().join("diff.pdf").exists());
+}
+
+#[test]
+fn test_cli_exit_code() {
+    let run = |args: &[&str], fixture: &str| {
+        Command::new(BINARY_PATH)
+            .args(["-i", "file", "-o", "stdout"])
+            .args(args)
+            .args(["--", &fixture_path(fixture)])
+            .output()
+            .expect("Failed to execute command")
+            .status
+            .code()
+    };
+
+    assert_eq!(run(&["--exitCode"], "simple.diff"), Some(1));
+    assert_eq!(run(&[], "simple.diff"), Some(0));
+
+    let dir = tempfile::tempdir().unwrap();
+    let no_files = dir.path().join("no_files.diff");
+    std::fs::write(&no_files, "not a diff\n").unwrap();
+    let output = Command::new(BINARY_PATH)
+        .args(["-i", "file", "-o", "stdout", "--exitCode", "--"])
+        .arg(&no_files)
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("d2h-wrapper"));
+
+    let empty = dir.path().join("empty.diff");
+    std::fs::write(&empty, "").unwrap();
+    let output = Command::new(BINARY_PATH)
+        .args(["-i", "file", "-o", "stdout", "--exitCode", "--"])
+        .arg(&empty)
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn test_cli_fail_if_more_than() {
+    let run = |args: &[&str]| {
+        Command::new(BINARY_PATH)
+            .args(["-i", "file", "-o", "stdout"])
+            .args(args)
+            .args(["--", &fixture_path("multiple_files.diff")])
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    let output = run(&["--failIfMoreThanFiles", "1"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("d2h-wrapper"));
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("The diff is too large: 2 files (+1 \u{2212}2) (more than 1 files)")
+    );
+
+    let output = run(&["--failIfMoreThanLines", "2"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("(more than 2 changed lines)"));
+
+    let output = run(&["--failIfMoreThanFiles", "2", "--failIfMoreThanLines", "3"]);
+    assert_eq!(output.status.code(), Some(0));
+    let output = run(&["--failIfMoreThanFiles", "2", "--exitCode"]);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_cli_quiet() {
+    let dir = tempfile::tempdir().unwrap();
+    let output_file = dir.path().join("out.html");
+    let run = |args: &[&str]| {
+        Command::new(BINARY_PATH)
+            .args(["-i", "file", "-F"])
+            .arg(&output_file)
+            .args(args)
+            .args(["--", &fixture_path("multiple_files.diff")])
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    let output = run(&[]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Output written to: "));
+
+    let output = run(&["-q"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+
+    let output = run(&["-q", "--failIfMoreThanFiles", "1"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("Error: The diff is too large"));
+
+    let output = run(&["-v"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("M src/event.js (+0 \u{2212}1)"));
+
+    assert!(!run(&["-q", "-v"]).status.success());
+}
+
+#[test]
+fn test_cli_verbose_reports_diagnostics() {
+    let diff_content = "--- a/f\n+++ b/f\n@@ -1,3 +1,3 @@\n-old\n+new\n";
+
+    let mut child = Command::new(BINARY_PATH)
+        .args(["-i", "stdin", "-o", "stdout", "--verbose"])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("Failed to spawn command");
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(diff_content.as_bytes())
+        .unwrap();
+
+    let output = child
+        .wait_with_output()
+        .expect("Failed to wait for command");
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr
+            .contains("warning: line 3: hunk header declares 3 old and 3 new lines, found 1 and 1")
+    );
+    assert!(stderr.contains("M f (+1 \u{2212}1)\n"));
+    assert!(stderr.contains("Parsed 1 file(s) with 1 problem(s): 1 file (+1 \u{2212}1)"));
+}
+
 #[test]
 fn test_cli_invalid_style() {
     let output = Command::new(BINARY_PATH)
@@ -578,3 +1032,125 @@ fn test_cli_invalid_style() {
     // Should fail due to invalid argument
     assert!(!output.status.success());
 }
+
+#[test]
+fn test_cli_semantic_markup() {
+    let output = Command::new(BINARY_PATH)
+        .args([
+            "-i",
+            "file",
+            "-o",
+            "stdout",
+            "--markup",
+            "semantic",
+            "--",
+            &fixture_path("simple.diff"),
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(r#"class="d2h-diff-row" role="row""#));
+    assert!(!stdout.contains("<tr>"));
+}
+
+#[test]
+fn test_cli_language_override() {
+    let output = Command::new(BINARY_PATH)
+        .args([
+            "-i",
+            "file",
+            "-o",
+            "stdout",
+            "-f",
+            "json",
+            "--languageOverride",
+            "sample=plaintext",
+            "--",
+            &fixture_path("simple.diff"),
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(r#""language":"plaintext""#));
+}
+
+#[test]
+fn test_cli_invalid_language_override() {
+    let output = Command::new(BINARY_PATH)
+        .args(["--languageOverride", "sample", "-i", "stdin"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("languageOverride must be <pattern>=<language>"));
+}
+
+#[test]
+fn test_cli_invalid_template_var() {
+    let output = Command::new(BINARY_PATH)
+        .args(["--templateVar", "build", "-i", "stdin"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("templateVar must be <key>=<value>"));
+}
+
+#[test]
+fn test_cli_summary() {
+    let render = |summary: &str| {
+        let output = Command::new(BINARY_PATH)
+            .args(["-i", "file", "-o", "stdout", "--summary", summary])
+            .args(["--", &fixture_path("simple.diff")])
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    let open = render("open");
+    assert!(open.contains(r#"<ol class="d2h-file-list">"#));
+    assert!(open.contains("diff2htmlUi.fileListToggle(true);"));
+
+    let closed = render("closed");
+    assert!(closed.contains(r#"<ol class="d2h-file-list" hidden>"#));
+    assert!(closed.contains("diff2htmlUi.fileListToggle(false);"));
+
+    let hidden = render("hidden");
+    assert!(!hidden.contains(r#"class="d2h-file-list-wrapper"#));
+    assert!(hidden.contains(r#"class="d2h-file-wrapper"#));
+}
+
+#[test]
+fn test_cli_ignore_file_input() {
+    let output = Command::new(BINARY_PATH)
+        .args([
+            "-i",
+            "file",
+            "-f",
+            "json",
+            "-o",
+            "stdout",
+            "-g",
+            "src/core/*.js",
+        ])
+        .args(["--", &fixture_path("multiple_files.diff")])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let files: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let names: Vec<_> = files
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|file| file["newName"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["src/event.js"]);
+}
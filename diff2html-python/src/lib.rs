@@ -0,0 +1,75 @@
+//! Python bindings for diff2html.
+//!
+//! The configuration is a dict of options named like those of the JavaScript
+//! diff2html, the same as the JSON configuration of the C API.
+
+use diff2html::{Diff2HtmlConfig, config_from_json};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+/// Convert a dict of options to a configuration, through JSON.
+fn to_config(py: Python<'_>, config: Option<&Bound<'_, PyDict>>) -> PyResult<Diff2HtmlConfig> {
+    let Some(config) = config else {
+        return Ok(Diff2HtmlConfig::default());
+    };
+    let json: String = py
+        .import("json")?
+        .call_method1("dumps", (config,))?
+        .extract()?;
+    config_from_json(&json).map_err(|err| PyValueError::new_err(format!("Invalid config: {}", err)))
+}
+
+/// Render a unified diff as HTML.
+#[pyfunction]
+#[pyo3(signature = (diff, config=None))]
+fn html(py: Python<'_>, diff: &str, config: Option<&Bound<'_, PyDict>>) -> PyResult<String> {
+    let config = to_config(py, config)?;
+    Ok(diff2html::html(diff, &config))
+}
+
+/// Parse a unified diff and serialize the files as JSON.
+#[pyfunction]
+#[pyo3(signature = (diff, config=None))]
+fn json(py: Python<'_>, diff: &str, config: Option<&Bound<'_, PyDict>>) -> PyResult<String> {
+    let config = to_config(py, config)?;
+    diff2html::json(diff, &config).map_err(|err| PyValueError::new_err(err.to_string()))
+}
+
+/// Parse a unified diff into a list of file dicts.
+#[pyfunction]
+#[pyo3(signature = (diff, config=None))]
+fn parse<'py>(
+    py: Python<'py>,
+    diff: &str,
+    config: Option<&Bound<'py, PyDict>>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let files = json(py, diff, config)?;
+    py.import("json")?.call_method1("loads", (files,))
+}
+
+/// Get the line counts of a unified diff, with the changed lines by language,
+/// as a dict.
+#[pyfunction]
+#[pyo3(signature = (diff, config=None))]
+fn stats<'py>(
+    py: Python<'py>,
+    diff: &str,
+    config: Option<&Bound<'py, PyDict>>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let config = to_config(py, config)?;
+    let files = diff2html::parse(diff, &config.to_parser_config());
+    let stats =
+        diff2html::stats_json(&files).map_err(|err| PyValueError::new_err(err.to_string()))?;
+    py.import("json")?.call_method1("loads", (stats,))
+}
+
+#[pymodule]
+#[pyo3(name = "diff2html")]
+fn diff2html_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(parse, m)?)?;
+    m.add_function(wrap_pyfunction!(html, m)?)?;
+    m.add_function(wrap_pyfunction!(json, m)?)?;
+    m.add_function(wrap_pyfunction!(stats, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,78 @@
+//! Rendering time of a large diff, in both output formats.
+//!
+//! Run with `cargo bench -p diff2html --bench render`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use diff2html::render::{LineByLineRenderer, RendererConfig, SideBySideRenderer};
+use diff2html::{DiffFile, DiffParserConfig, parse};
+
+/// Number of renders timed for each output format, after a warm-up render.
+const ITERATIONS: u32 = 20;
+
+/// Generate a diff of `files` files, each with hunks of context, changed
+/// and added lines.
+fn large_diff(files: usize) -> String {
+    let mut diff = String::new();
+    for file in 0..files {
+        diff.push_str(&format!(
+            "diff --git a/src/file{file}.rs b/src/file{file}.rs\n\
+             --- a/src/file{file}.rs\n+++ b/src/file{file}.rs\n"
+        ));
+        for hunk in 0..20 {
+            let start = hunk * 20 + 1;
+            diff.push_str(&format!("@@ -{start},8 +{start},9 @@ fn hunk{hunk}() {{\n"));
+            for line in 0..3 {
+                diff.push_str(&format!(
+                    " let context_{line} = compute({line}, \"<{hunk}>\");\n"
+                ));
+            }
+            for line in 0..2 {
+                diff.push_str(&format!(
+                    "-let value_{line} = old_value({line}) + {hunk};\n"
+                ));
+            }
+            for line in 0..3 {
+                diff.push_str(&format!(
+                    "+let value_{line} = new_value({line}) * {hunk};\n"
+                ));
+            }
+            for line in 0..3 {
+                diff.push_str(&format!(" let trailing_{line} = done({line});\n"));
+            }
+        }
+    }
+    diff
+}
+
+/// Time a render function, returning the mean time of a render.
+fn time(render: impl Fn(&[DiffFile]) -> String, files: &[DiffFile]) -> Duration {
+    black_box(render(files));
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(render(black_box(files)));
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn main() {
+    let files = parse(&large_diff(200), &DiffParserConfig::default());
+    let lines: usize = files
+        .iter()
+        .flat_map(|file| &file.blocks)
+        .map(|block| block.lines.len())
+        .sum();
+    println!("{} files, {} lines", files.len(), lines);
+
+    let line_by_line = LineByLineRenderer::new(RendererConfig::default());
+    let side_by_side = SideBySideRenderer::new(RendererConfig::default());
+    println!(
+        "line-by-line: {:.2?}",
+        time(|files| line_by_line.render(files), &files)
+    );
+    println!(
+        "side-by-side: {:.2?}",
+        time(|files| side_by_side.render(files), &files)
+    );
+}
@@ -2,7 +2,7 @@
 //!
 //! Run with: cargo run --example custom_parser
 
-use diff2html::{Diff2HtmlConfig, DiffParserConfig, html_from_diff_files, parse};
+use diff2html::{Diff2HtmlConfig, DiffParserConfig, ParseMode, html_from_diff_files, parse};
 
 fn main() {
     // Sample diff with prefixes
@@ -25,6 +25,8 @@ fn main() {
         diff_max_changes: Some(50), // Limit to 50 changes
         diff_max_line_length: Some(200),
         diff_too_big_message: None, // Use default message
+        mode: ParseMode::Lenient,   // Recover from malformed input
+        ..Default::default()
     };
 
     // Parse with custom configuration
@@ -0,0 +1,58 @@
+//! Web server rendering the diff POSTed to it, using only the standard
+//! library. The page is streamed one file at a time with chunked transfer
+//! encoding.
+//!
+//! Run with: cargo run --example web_server --features web
+//! Then: git diff | curl --data-binary @- http://127.0.0.1:8080 > diff.html
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+use diff2html::Diff2HtmlConfig;
+use diff2html::web::{HTML_CONTENT_TYPE, html_stream};
+
+fn handle(stream: TcpStream, config: &Diff2HtmlConfig) -> std::io::Result<()> {
+    let mut reader = BufReader::new(&stream);
+
+    // Read the headers for the length of the body, which holds the diff
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':')
+            && name.eq_ignore_ascii_case("content-length")
+        {
+            content_length = value.trim().parse().unwrap_or(0);
+        }
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    let diff = String::from_utf8_lossy(&body);
+
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n",
+        HTML_CONTENT_TYPE
+    )?;
+    for chunk in html_stream(&diff, config) {
+        write!(stream, "{:x}\r\n{}\r\n", chunk.len(), chunk)?;
+    }
+    write!(stream, "0\r\n\r\n")?;
+    stream.flush()
+}
+
+fn main() -> std::io::Result<()> {
+    let config = Diff2HtmlConfig::default();
+    let listener = TcpListener::bind("127.0.0.1:8080")?;
+    println!("Listening on http://127.0.0.1:8080");
+
+    for stream in listener.incoming() {
+        if let Err(err) = stream.and_then(|stream| handle(stream, &config)) {
+            eprintln!("Request failed: {}", err);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,28 @@
+#ifndef DIFF2HTML_H
+#define DIFF2HTML_H
+
+/* Generated by cbindgen from src/ffi.rs. Do not edit. */
+
+// Render a unified diff as HTML.
+//
+// `config_json` is a JSON object of options, e.g.
+// `{"outputFormat": "side-by-side", "colorScheme": "dark"}`, or `NULL` for
+// the defaults. Returns `NULL` if an argument is not valid UTF-8, the
+// configuration is not valid, or rendering panics. The returned string must
+// be released with [`diff2html_free`].
+//
+// # Safety
+//
+// `diff` must be a valid NUL-terminated string, and `config_json` a valid
+// NUL-terminated string or `NULL`.
+char *diff2html_html(const char *diff, const char *config_json);
+
+// Release a string returned by [`diff2html_html`]. Does nothing for `NULL`.
+//
+// # Safety
+//
+// `html` must be `NULL` or a string returned by [`diff2html_html`] that was
+// not released yet.
+void diff2html_free(char *html);
+
+#endif  /* DIFF2HTML_H */
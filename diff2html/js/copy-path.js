@@ -0,0 +1,42 @@
+// Copy the old or the new path of a renamed file to the clipboard with the
+// buttons of the file header.
+//
+// The buttons are hidden by the stylesheet until the script runs.
+(function () {
+  'use strict';
+
+  function copy(button) {
+    if (!navigator.clipboard) {
+      return;
+    }
+    navigator.clipboard.writeText(button.getAttribute('data-d2h-path')).then(function () {
+      button.classList.add('d2h-copied');
+      button.textContent = 'Copied';
+      setTimeout(function () {
+        button.classList.remove('d2h-copied');
+        button.textContent = 'Copy';
+      }, 1500);
+    });
+  }
+
+  function init() {
+    var names = document.querySelectorAll('.d2h-file-name-full');
+    for (var i = 0; i < names.length; i++) {
+      names[i].classList.add('d2h-copy-path-enabled');
+    }
+  }
+
+  document.addEventListener('click', function (event) {
+    var button = event.target.closest ? event.target.closest('.d2h-copy-path') : null;
+    if (button !== null) {
+      event.preventDefault();
+      copy(button);
+    }
+  });
+
+  if (document.readyState === 'loading') {
+    document.addEventListener('DOMContentLoaded', init);
+  } else {
+    init();
+  }
+})();
@@ -0,0 +1,86 @@
+// Collapse the content of a file when its "Viewed" checkbox is checked.
+//
+// With the `data-d2h-persist` attribute on the script element, the viewed
+// files are remembered in localStorage by the ID of their wrapper. Generate
+// the HTML in deterministic mode to keep the IDs stable across renders.
+(function () {
+  'use strict';
+
+  var KEY_PREFIX = 'd2h-viewed:';
+  var persist =
+    document.currentScript !== null && document.currentScript.hasAttribute('data-d2h-persist');
+
+  function storageKey(input) {
+    var wrapper = input.closest('.d2h-file-wrapper');
+    return persist && wrapper !== null && wrapper.id ? KEY_PREFIX + wrapper.id : null;
+  }
+
+  function loadViewed(key) {
+    try {
+      return window.localStorage.getItem(key) !== null;
+    } catch (e) {
+      return false;
+    }
+  }
+
+  function saveViewed(key, viewed) {
+    try {
+      if (viewed) {
+        window.localStorage.setItem(key, '1');
+      } else {
+        window.localStorage.removeItem(key);
+      }
+    } catch (e) {
+      // Storage is disabled or full, the state is only lost on reload
+    }
+  }
+
+  function update(input) {
+    var label = input.closest('.d2h-file-collapse');
+    if (label !== null) {
+      label.classList.toggle('d2h-selected', input.checked);
+    }
+    var wrapper = input.closest('.d2h-file-wrapper');
+    if (wrapper === null) {
+      return;
+    }
+    var contents = wrapper.querySelectorAll('.d2h-file-diff, .d2h-files-diff');
+    for (var i = 0; i < contents.length; i++) {
+      contents[i].classList.toggle('d2h-d-none', input.checked);
+    }
+  }
+
+  function init() {
+    var inputs = document.querySelectorAll('.d2h-file-collapse-input');
+    for (var i = 0; i < inputs.length; i++) {
+      var input = inputs[i];
+      var label = input.closest('.d2h-file-collapse');
+      if (label !== null) {
+        label.classList.add('d2h-file-collapse-enabled');
+      }
+      var key = storageKey(input);
+      if (key !== null && loadViewed(key)) {
+        input.checked = true;
+      }
+      update(input);
+    }
+  }
+
+  document.addEventListener('change', function (event) {
+    var input = event.target;
+    if (!input.classList || !input.classList.contains('d2h-file-collapse-input')) {
+      return;
+    }
+    update(input);
+    var key = storageKey(input);
+    if (key !== null) {
+      saveViewed(key, input.checked);
+    }
+  });
+
+  if (document.readyState === 'loading') {
+    document.addEventListener('DOMContentLoaded', init);
+  } else {
+    init();
+  }
+})();
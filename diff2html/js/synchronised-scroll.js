@@ -0,0 +1,30 @@
+// Synchronise the horizontal scroll of the two sides of side-by-side diffs.
+//
+// Scroll events do not bubble, so a single listener capturing them on the
+// document also handles diffs added to the page after this script ran.
+(function () {
+  'use strict';
+
+  document.addEventListener(
+    'scroll',
+    function (event) {
+      var side = event.target;
+      if (!side.classList || !side.classList.contains('d2h-file-side-diff')) {
+        return;
+      }
+
+      var sides = side.parentElement.children;
+      for (var i = 0; i < sides.length; i++) {
+        var other = sides[i];
+        if (
+          other !== side &&
+          other.classList.contains('d2h-file-side-diff') &&
+          other.scrollLeft !== side.scrollLeft
+        ) {
+          other.scrollLeft = side.scrollLeft;
+        }
+      }
+    },
+    true
+  );
+})();
@@ -0,0 +1,45 @@
+// Switch the files rendered in both views between the line-by-line and the
+// side-by-side view with the buttons above them.
+//
+// The buttons are hidden by the stylesheet until the script runs.
+(function () {
+  'use strict';
+
+  function show(button) {
+    var container = button.closest('.d2h-view-switch');
+    if (container === null) {
+      return;
+    }
+    var view = 'd2h-view-' + button.getAttribute('data-d2h-view');
+    var views = container.querySelectorAll(':scope > .d2h-view');
+    for (var i = 0; i < views.length; i++) {
+      views[i].classList.toggle('d2h-d-none', !views[i].classList.contains(view));
+    }
+    var buttons = container.querySelectorAll('.d2h-view-toggle-button');
+    for (var j = 0; j < buttons.length; j++) {
+      var selected = buttons[j] === button;
+      buttons[j].classList.toggle('d2h-selected', selected);
+      buttons[j].setAttribute('aria-pressed', selected ? 'true' : 'false');
+    }
+  }
+
+  function init() {
+    var toggles = document.querySelectorAll('.d2h-view-toggle');
+    for (var i = 0; i < toggles.length; i++) {
+      toggles[i].classList.add('d2h-view-toggle-enabled');
+    }
+  }
+
+  document.addEventListener('click', function (event) {
+    var button = event.target.closest ? event.target.closest('.d2h-view-toggle-button') : null;
+    if (button !== null) {
+      show(button);
+    }
+  });
+
+  if (document.readyState === 'loading') {
+    document.addEventListener('DOMContentLoaded', init);
+  } else {
+    init();
+  }
+})();
@@ -0,0 +1,534 @@
+//! Builders for constructing diffs programmatically.
+//!
+//! Applications that synthesize diffs, e.g. from database records or API
+//! responses, can use [`DiffFile::builder`] and [`DiffBlock::builder`] instead
+//! of filling in every field by hand. The builders number the lines, compute
+//! the line counts and hunk headers, and check that the result is consistent.
+//! Line diffs computed with the `similar` crate can be converted with
+//! [`DiffFile::from_text_diff`].
+//!
+//! # Example
+//!
+//! ```
+//! use diff2html::{DiffBlock, DiffFile};
+//!
+//! let file = DiffFile::builder("src/lib.rs")
+//!     .block(
+//!         DiffBlock::builder(10, 10)
+//!             .context("fn main() {")
+//!             .delete("    old();")
+//!             .insert("    new();")
+//!             .insert("    newer();")
+//!             .context("}"),
+//!     )
+//!     .build()
+//!     .unwrap();
+//!
+//! assert_eq!(file.to_string(), "M src/lib.rs (+2 \u{2212}1)");
+//! assert_eq!(file.blocks[0].header, "@@ -10,3 +10,4 @@");
+//! assert_eq!(file.blocks[0].lines[3].new_number, Some(12));
+//! ```
+
+use similar::{ChangeTag, TextDiff};
+use thiserror::Error;
+
+use crate::parser::get_extension;
+use crate::types::{DiffBlock, DiffFile, DiffLine, LineType};
+
+/// Name of the missing side of added and deleted files.
+const DEV_NULL: &str = "/dev/null";
+
+/// Errors that can occur when building a diff.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum BuildError {
+    #[error("file name is empty")]
+    EmptyName,
+    #[error("block starts at line 0 but has lines on that side")]
+    ZeroStartLine,
+    #[error("block {0} starts before the end of the previous block")]
+    OverlappingBlocks(usize),
+    #[error("new file has deleted or context lines")]
+    OldLinesInNewFile,
+    #[error("deleted file has inserted or context lines")]
+    NewLinesInDeletedFile,
+}
+
+/// A line added to a [`DiffBlockBuilder`].
+#[derive(Debug, Clone)]
+struct BuilderLine {
+    line_type: LineType,
+    content: String,
+    no_newline_at_eof: bool,
+}
+
+/// Builder for a [`DiffBlock`], created by [`DiffBlock::builder`].
+#[derive(Debug, Clone)]
+pub struct DiffBlockBuilder {
+    old_start_line: u32,
+    new_start_line: u32,
+    header: Option<String>,
+    lines: Vec<BuilderLine>,
+}
+
+impl DiffBlock {
+    /// Start building a block whose first lines have the given numbers in the
+    /// old and new file.
+    ///
+    /// Like in a unified diff, a side without lines starts at the line before
+    /// the block, which is 0 at the start of the file.
+    pub fn builder(old_start_line: u32, new_start_line: u32) -> DiffBlockBuilder {
+        DiffBlockBuilder {
+            old_start_line,
+            new_start_line,
+            header: None,
+            lines: Vec::new(),
+        }
+    }
+}
+
+impl DiffBlockBuilder {
+    /// Set the block header instead of generating `@@ -a,b +c,d @@`.
+    pub fn header(mut self, header: impl Into<String>) -> Self {
+        self.header = Some(header.into());
+        self
+    }
+
+    /// Add a line. The content does not include the `+`, `-` or ` ` prefix.
+    pub fn line(mut self, line_type: LineType, content: impl Into<String>) -> Self {
+        self.lines.push(BuilderLine {
+            line_type,
+            content: content.into(),
+            no_newline_at_eof: false,
+        });
+        self
+    }
+
+    /// Add a line present in both files.
+    pub fn context(self, content: impl Into<String>) -> Self {
+        self.line(LineType::Context, content)
+    }
+
+    /// Add a line of the new file.
+    pub fn insert(self, content: impl Into<String>) -> Self {
+        self.line(LineType::Insert, content)
+    }
+
+    /// Add a line of the old file.
+    pub fn delete(self, content: impl Into<String>) -> Self {
+        self.line(LineType::Delete, content)
+    }
+
+    /// Mark the last added line as missing a trailing newline.
+    pub fn no_newline_at_eof(mut self) -> Self {
+        if let Some(line) = self.lines.last_mut() {
+            line.no_newline_at_eof = true;
+        }
+        self
+    }
+
+    /// Build the block, numbering its lines.
+    ///
+    /// Fails if a side with lines starts at line 0.
+    pub fn build(self) -> Result<DiffBlock, BuildError> {
+        let mut old_line = self.old_start_line;
+        let mut new_line = self.new_start_line;
+
+        let lines: Vec<DiffLine> = self
+            .lines
+            .into_iter()
+            .map(|line| {
+                let (prefix, old_number, new_number) = match line.line_type {
+                    LineType::Context => (' ', Some(old_line), Some(new_line)),
+                    LineType::Insert => ('+', None, Some(new_line)),
+                    LineType::Delete => ('-', Some(old_line), None),
+                };
+                old_line += u32::from(old_number.is_some());
+                new_line += u32::from(new_number.is_some());
+                DiffLine {
+                    line_type: line.line_type,
+                    content: format!("{}{}", prefix, line.content),
+                    old_number,
+                    new_number,
+                    no_newline_at_eof: line.no_newline_at_eof,
+                    word_diff: None,
+                }
+            })
+            .collect();
+
+        let old_count = old_line - self.old_start_line;
+        let new_count = new_line - self.new_start_line;
+        if (self.old_start_line == 0 && old_count > 0)
+            || (self.new_start_line == 0 && new_count > 0)
+        {
+            return Err(BuildError::ZeroStartLine);
+        }
+
+        Ok(DiffBlock {
+            old_start_line: self.old_start_line,
+            old_start_line2: None,
+            new_start_line: self.new_start_line,
+            old_lines: Some(old_count),
+            new_lines: Some(new_count),
+            header: self.header.unwrap_or_else(|| {
+                format_hunk_header(
+                    self.old_start_line,
+                    old_count,
+                    self.new_start_line,
+                    new_count,
+                )
+            }),
+            lines,
+            source_lines: None,
+        })
+    }
+}
+
+/// Format a unified diff hunk header, e.g. `@@ -1,3 +1,4 @@`.
+fn format_hunk_header(old_start: u32, old_count: u32, new_start: u32, new_count: u32) -> String {
+    format!("@@ -{old_start},{old_count} +{new_start},{new_count} @@")
+}
+
+/// Builder for a [`DiffFile`], created by [`DiffFile::builder`].
+#[derive(Debug, Clone)]
+pub struct DiffFileBuilder {
+    file: DiffFile,
+    blocks: Vec<DiffBlockBuilder>,
+}
+
+impl DiffFile {
+    /// Start building a modified file with the given name.
+    pub fn builder(name: impl Into<String>) -> DiffFileBuilder {
+        let name = name.into();
+        DiffFileBuilder {
+            file: DiffFile {
+                old_name: name.clone(),
+                new_name: name,
+                ..Default::default()
+            },
+            blocks: Vec::new(),
+        }
+    }
+}
+
+impl DiffFile {
+    /// Convert a line diff computed with the `similar` crate into a file.
+    ///
+    /// Changes are grouped into blocks with up to `context_lines` unchanged
+    /// lines around them, like `diff -U<context_lines>`. The file is marked as
+    /// renamed if the names differ.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use diff2html::DiffFile;
+    /// use similar::TextDiff;
+    ///
+    /// let diff = TextDiff::from_lines("a\nb\nc\nd\n", "a\nB\nc\nd\n");
+    /// let file = DiffFile::from_text_diff("f.txt", "f.txt", &diff, 1);
+    ///
+    /// assert_eq!(file.to_string(), "M f.txt (+1 \u{2212}1)");
+    /// assert_eq!(file.blocks[0].header, "@@ -1,3 +1,3 @@");
+    /// ```
+    pub fn from_text_diff(
+        old_name: impl Into<String>,
+        new_name: impl Into<String>,
+        diff: &TextDiff<'_, '_, '_, str>,
+        context_lines: usize,
+    ) -> DiffFile {
+        let old_name = old_name.into();
+        let new_name = new_name.into();
+        let mut file = DiffFile {
+            language: get_extension(&new_name, ""),
+            is_rename: (old_name != new_name).then_some(true),
+            old_name,
+            new_name,
+            ..Default::default()
+        };
+
+        for group in diff.grouped_ops(context_lines) {
+            let (Some(first), Some(last)) = (group.first(), group.last()) else {
+                continue;
+            };
+            let old_range = first.old_range().start..last.old_range().end;
+            let new_range = first.new_range().start..last.new_range().end;
+
+            let mut lines = Vec::new();
+            for change in group.iter().flat_map(|op| diff.iter_changes(op)) {
+                let (line_type, prefix) = match change.tag() {
+                    ChangeTag::Equal => (LineType::Context, ' '),
+                    ChangeTag::Insert => {
+                        file.added_lines += 1;
+                        (LineType::Insert, '+')
+                    }
+                    ChangeTag::Delete => {
+                        file.deleted_lines += 1;
+                        (LineType::Delete, '-')
+                    }
+                };
+                let value = change.value();
+                let content = value
+                    .strip_suffix('\n')
+                    .map_or(value, |value| value.strip_suffix('\r').unwrap_or(value));
+                lines.push(DiffLine {
+                    line_type,
+                    content: format!("{}{}", prefix, content),
+                    old_number: change.old_index().map(|index| index as u32 + 1),
+                    new_number: change.new_index().map(|index| index as u32 + 1),
+                    no_newline_at_eof: change.missing_newline(),
+                    word_diff: None,
+                });
+            }
+
+            // A side without lines starts at the line before the block
+            let old_count = old_range.len() as u32;
+            let new_count = new_range.len() as u32;
+            let old_start = old_range.start as u32 + u32::from(old_count > 0);
+            let new_start = new_range.start as u32 + u32::from(new_count > 0);
+            file.blocks.push(DiffBlock {
+                old_start_line: old_start,
+                old_start_line2: None,
+                new_start_line: new_start,
+                old_lines: Some(old_count),
+                new_lines: Some(new_count),
+                header: format_hunk_header(old_start, old_count, new_start, new_count),
+                lines,
+                source_lines: None,
+            });
+        }
+
+        file
+    }
+}
+
+impl DiffFileBuilder {
+    /// Mark the file as renamed from the given name.
+    pub fn renamed_from(mut self, old_name: impl Into<String>) -> Self {
+        self.file.old_name = old_name.into();
+        self.file.is_rename = Some(true);
+        self
+    }
+
+    /// Mark the file as copied from the given name.
+    pub fn copied_from(mut self, old_name: impl Into<String>) -> Self {
+        self.file.old_name = old_name.into();
+        self.file.is_copy = Some(true);
+        self
+    }
+
+    /// Mark the file as added.
+    pub fn added(mut self) -> Self {
+        self.file.old_name = DEV_NULL.to_string();
+        self.file.is_new = Some(true);
+        self
+    }
+
+    /// Mark the file as deleted.
+    pub fn deleted(mut self) -> Self {
+        self.file.new_name = DEV_NULL.to_string();
+        self.file.is_deleted = Some(true);
+        self
+    }
+
+    /// Set the language used for syntax highlighting instead of the file
+    /// extension.
+    pub fn language(mut self, language: impl Into<String>) -> Self {
+        self.file.language = language.into();
+        self
+    }
+
+    /// Add a block. Blocks must be added in file order.
+    pub fn block(mut self, block: DiffBlockBuilder) -> Self {
+        self.blocks.push(block);
+        self
+    }
+
+    /// Build the file, computing its line counts.
+    ///
+    /// Fails if a block is invalid, the blocks overlap or are out of order,
+    /// or an added or deleted file has lines on its missing side.
+    pub fn build(self) -> Result<DiffFile, BuildError> {
+        let mut file = self.file;
+        let name = if file.is_deleted == Some(true) {
+            &file.old_name
+        } else {
+            &file.new_name
+        };
+        if name.is_empty() || file.old_name.is_empty() {
+            return Err(BuildError::EmptyName);
+        }
+        if file.language.is_empty() {
+            file.language = get_extension(name, "");
+        }
+
+        // First line after the previous block in the old and new file
+        let mut old_end = 0;
+        let mut new_end = 0;
+        for (index, block) in self.blocks.into_iter().enumerate() {
+            let block = block.build()?;
+            let old_count = block.old_lines.unwrap_or(0);
+            let new_count = block.new_lines.unwrap_or(0);
+            // A side without lines starts at the line before the block
+            let old_begin = block.old_start_line + u32::from(old_count == 0);
+            let new_begin = block.new_start_line + u32::from(new_count == 0);
+            if old_begin < old_end || new_begin < new_end {
+                return Err(BuildError::OverlappingBlocks(index));
+            }
+            old_end = old_begin + old_count;
+            new_end = new_begin + new_count;
+
+            if file.is_new == Some(true) && old_count > 0 {
+                return Err(BuildError::OldLinesInNewFile);
+            }
+            if file.is_deleted == Some(true) && new_count > 0 {
+                return Err(BuildError::NewLinesInDeletedFile);
+            }
+
+            for line in &block.lines {
+                match line.line_type {
+                    LineType::Insert => file.added_lines += 1,
+                    LineType::Delete => file.deleted_lines += 1,
+                    LineType::Context => {}
+                }
+            }
+            file.blocks.push(block);
+        }
+
+        Ok(file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_block() {
+        let block = DiffBlock::builder(3, 4)
+            .context("a")
+            .delete("b")
+            .insert("c")
+            .insert("d")
+            .no_newline_at_eof()
+            .build()
+            .unwrap();
+
+        assert_eq!(block.header, "@@ -3,2 +4,3 @@");
+        assert_eq!((block.old_lines, block.new_lines), (Some(2), Some(3)));
+        let numbers: Vec<_> = block
+            .lines
+            .iter()
+            .map(|line| (line.content.as_str(), line.old_number, line.new_number))
+            .collect();
+        assert_eq!(
+            numbers,
+            [
+                (" a", Some(3), Some(4)),
+                ("-b", Some(4), None),
+                ("+c", None, Some(5)),
+                ("+d", None, Some(6)),
+            ]
+        );
+        assert!(block.lines[3].no_newline_at_eof);
+
+        let block = DiffBlock::builder(1, 1).header("@@ custom @@").build();
+        assert_eq!(block.unwrap().header, "@@ custom @@");
+        assert_eq!(
+            DiffBlock::builder(0, 1).delete("a").build(),
+            Err(BuildError::ZeroStartLine)
+        );
+    }
+
+    #[test]
+    fn test_build_file() {
+        let file = DiffFile::builder("new.rs")
+            .added()
+            .block(DiffBlock::builder(0, 1).insert("a").insert("b"))
+            .build()
+            .unwrap();
+        assert_eq!(file.old_name, "/dev/null");
+        assert_eq!(file.language, "rs");
+        assert_eq!((file.added_lines, file.deleted_lines), (2, 0));
+        assert_eq!(file.blocks[0].header, "@@ -0,0 +1,2 @@");
+
+        let file = DiffFile::builder("b.txt")
+            .renamed_from("a.txt")
+            .language("plaintext")
+            .block(DiffBlock::builder(1, 1).delete("a"))
+            .block(DiffBlock::builder(5, 4).context("b").insert("c"))
+            .build()
+            .unwrap();
+        assert_eq!(file.to_string(), "R a.txt \u{2192} b.txt (+1 \u{2212}1)");
+        assert_eq!(file.language, "plaintext");
+    }
+
+    #[test]
+    fn test_from_text_diff() {
+        let old = "a\nb\nc\nd\ne\nf\ng\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh";
+        let diff = TextDiff::from_lines(old, new);
+
+        let file = DiffFile::from_text_diff("old.txt", "new.txt", &diff, 1);
+        assert_eq!(file.language, "txt");
+        assert_eq!(file.is_rename, Some(true));
+        assert_eq!((file.added_lines, file.deleted_lines), (2, 1));
+
+        let headers: Vec<_> = file.blocks.iter().map(|b| b.header.as_str()).collect();
+        assert_eq!(headers, ["@@ -1,3 +1,3 @@", "@@ -7,1 +7,2 @@"]);
+        let last = &file.blocks[1];
+        let lines: Vec<_> = last
+            .lines
+            .iter()
+            .map(|line| (line.content.as_str(), line.old_number, line.new_number))
+            .collect();
+        assert_eq!(lines, [(" g", Some(7), Some(7)), ("+h", None, Some(8))]);
+        assert!(!last.lines[0].no_newline_at_eof);
+        assert!(last.lines[1].no_newline_at_eof);
+
+        let diff = TextDiff::from_lines("", "a\r\n");
+        let file = DiffFile::from_text_diff("f", "f", &diff, 3);
+        assert_eq!(file.is_rename, None);
+        assert_eq!(file.blocks[0].header, "@@ -0,0 +1,1 @@");
+        assert_eq!(file.blocks[0].lines[0].content, "+a");
+
+        let diff = TextDiff::from_lines("same\n", "same\n");
+        assert!(
+            DiffFile::from_text_diff("f", "f", &diff, 3)
+                .blocks
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_build_file_errors() {
+        assert_eq!(DiffFile::builder("").build(), Err(BuildError::EmptyName));
+        assert_eq!(
+            DiffFile::builder("a")
+                .block(DiffBlock::builder(1, 1).context("a").context("b"))
+                .block(DiffBlock::builder(2, 2).context("b"))
+                .build(),
+            Err(BuildError::OverlappingBlocks(1))
+        );
+        assert_eq!(
+            DiffFile::builder("a")
+                .added()
+                .block(DiffBlock::builder(1, 1).context("a"))
+                .build(),
+            Err(BuildError::OldLinesInNewFile)
+        );
+        assert_eq!(
+            DiffFile::builder("a")
+                .deleted()
+                .block(DiffBlock::builder(1, 0).delete("a"))
+                .build()
+                .map(|file| file.deleted_lines),
+            Ok(1)
+        );
+        assert_eq!(
+            DiffFile::builder("a")
+                .deleted()
+                .block(DiffBlock::builder(1, 1).insert("a"))
+                .build(),
+            Err(BuildError::NewLinesInDeletedFile)
+        );
+    }
+}
@@ -0,0 +1,601 @@
+//! Variants of the diff2html stylesheet.
+//!
+//! [`CSS`] contains the rules for every output format and color scheme.
+//! [`css_scoped`] nests it under a selector of the host page,
+//! [`css_for_output`] trims it to the rules a configuration needs,
+//! [`css_for_html`] to the rules of some rendered HTML, and
+//! [`css_responsive`] adds the rules for a responsive side-by-side view.
+
+use std::collections::HashSet;
+
+use crate::render::RenderConfig;
+use crate::templates::CSS;
+use crate::types::{ColorScheme, OutputFormat};
+
+/// Classes only used by the side-by-side renderer.
+const SIDE_BY_SIDE_CLASSES: &[&str] = &[
+    "d2h-files-diff",
+    "d2h-files-diff-single",
+    "d2h-file-side-diff",
+    "d2h-code-side-line",
+    "d2h-code-side-linenumber",
+    "d2h-code-side-emptyplaceholder",
+    "d2h-emptyplaceholder",
+];
+
+/// Classes only used by the line-by-line renderer.
+const LINE_BY_LINE_CLASSES: &[&str] = &[
+    "d2h-file-diff",
+    "d2h-code-line",
+    "d2h-code-linenumber",
+    "line-num1",
+    "line-num2",
+];
+
+/// Classes only used by the switchable renderer.
+const SWITCHABLE_CLASSES: &[&str] = &[
+    "d2h-view-switch",
+    "d2h-view-toggle",
+    "d2h-view-toggle-button",
+    "d2h-view",
+];
+
+/// Prefix of the custom properties used by the dark color scheme.
+const DARK_PROPERTY_PREFIX: &str = "--d2h-dark-";
+
+/// Prelude of the rules for the auto color scheme in dark mode.
+const DARK_MEDIA_QUERY: &str = "@media (prefers-color-scheme: dark)";
+
+/// Rewrite the [`CSS`] stylesheet so every rule only applies inside the
+/// elements matching `selector_prefix`, e.g. `#review-pane`.
+///
+/// The custom properties defined for `:root` are defined on the matching
+/// elements instead, so the stylesheet does not change the host page.
+///
+/// # Example
+///
+/// ```
+/// use diff2html::css_scoped;
+///
+/// let css = css_scoped("#review-pane");
+/// assert!(css.contains("#review-pane .d2h-wrapper {"));
+/// assert!(!css.contains(":root"));
+/// ```
+pub fn css_scoped(selector_prefix: &str) -> String {
+    let mut result = String::with_capacity(CSS.len() * 2);
+    rewrite_rules(CSS, &Scope(selector_prefix.trim()), &mut result);
+    result
+}
+
+/// Get the rules of the [`CSS`] stylesheet needed to display the output of the
+/// given format and render configuration.
+///
+/// Rules for the other output format, for unused color schemes and for
+/// disabled features like autolinks are left out. The rules of
+/// [`css_responsive`] are added for a side-by-side output with a
+/// `responsive_breakpoint`.
+///
+/// # Example
+///
+/// ```
+/// use diff2html::{CSS, OutputFormat, RenderConfig, css_for_output};
+///
+/// let css = css_for_output(OutputFormat::LineByLine, &RenderConfig::default());
+/// assert!(css.len() < CSS.len());
+/// assert!(!css.contains(".d2h-code-side-line"));
+/// assert!(!css.contains(".d2h-dark-color-scheme"));
+/// ```
+pub fn css_for_output(output_format: OutputFormat, config: &RenderConfig) -> String {
+    trimmed_css(output_format, config, None)
+}
+
+/// Get the rules of the [`CSS`] stylesheet needed to display the given HTML,
+/// rendered with the output format and render configuration.
+///
+/// Like [`css_for_output`], but rules for classes missing from the `class`
+/// attributes of the HTML are left out too, e.g. for a single file.
+///
+/// # Example
+///
+/// ```
+/// use diff2html::{OutputFormat, RenderConfig, css_for_html, css_for_output};
+///
+/// let config = RenderConfig::default();
+/// let html = r#"<div class="d2h-wrapper d2h-light-color-scheme"></div>"#;
+/// let css = css_for_html(html, OutputFormat::LineByLine, &config);
+/// assert!(css.contains(".d2h-wrapper {"));
+/// assert!(!css.contains(".d2h-file-header"));
+/// assert!(css.len() < css_for_output(OutputFormat::LineByLine, &config).len());
+/// ```
+pub fn css_for_html(html: &str, output_format: OutputFormat, config: &RenderConfig) -> String {
+    let used_classes = html
+        .match_indices("class=\"")
+        .flat_map(|(i, attribute)| {
+            let value = &html[i + attribute.len()..];
+            value[..value.find('"').unwrap_or(value.len())].split_ascii_whitespace()
+        })
+        .collect();
+    trimmed_css(output_format, config, Some(used_classes))
+}
+
+/// Trim the [`CSS`] stylesheet for an output, and to the used classes if known.
+fn trimmed_css(
+    output_format: OutputFormat,
+    config: &RenderConfig,
+    used_classes: Option<HashSet<&str>>,
+) -> String {
+    let mut unused_classes = match output_format {
+        OutputFormat::LineByLine => [SIDE_BY_SIDE_CLASSES, SWITCHABLE_CLASSES].concat(),
+        OutputFormat::SideBySide => [LINE_BY_LINE_CLASSES, SWITCHABLE_CLASSES].concat(),
+        OutputFormat::Switchable => Vec::new(),
+    };
+    match config.color_scheme {
+        ColorScheme::Light => {
+            unused_classes.extend(["d2h-dark-color-scheme", "d2h-auto-color-scheme"])
+        }
+        ColorScheme::Dark => unused_classes.push("d2h-auto-color-scheme"),
+        ColorScheme::Auto => unused_classes.push("d2h-dark-color-scheme"),
+    }
+    if config.autolink.is_none() {
+        unused_classes.push("d2h-autolink");
+    }
+
+    let trim = Trim {
+        unused_classes,
+        used_classes,
+        dark: config.color_scheme != ColorScheme::Light,
+        auto: config.color_scheme == ColorScheme::Auto,
+    };
+    let mut result = String::with_capacity(CSS.len());
+    rewrite_rules(CSS, &trim, &mut result);
+    if let (OutputFormat::SideBySide | OutputFormat::Switchable, Some(breakpoint)) =
+        (output_format, config.responsive_breakpoint)
+    {
+        result.push_str(&css_responsive(breakpoint));
+    }
+    result
+}
+
+/// Get the rules stacking the side-by-side panels, old above new, when the
+/// viewport is narrower than `breakpoint` CSS pixels.
+///
+/// Media queries cannot use custom properties, so the rules are generated for
+/// the breakpoint instead of being part of [`CSS`]. They only apply to the
+/// output rendered with a `responsive_breakpoint`.
+///
+/// # Example
+///
+/// ```
+/// use diff2html::css_responsive;
+///
+/// let css = css_responsive(768);
+/// assert!(css.starts_with("@media (max-width: 767.98px) {"));
+/// ```
+pub fn css_responsive(breakpoint: u32) -> String {
+    format!(
+        "@media (max-width: {}.98px) {{
+  .d2h-files-diff-responsive {{
+    flex-direction: column;
+  }}
+
+  .d2h-files-diff-responsive > .d2h-file-side-diff {{
+    width: 100%;
+  }}
+}}
+",
+        breakpoint.saturating_sub(1)
+    )
+}
+
+/// Changes made to the rules of a stylesheet by [`rewrite_rules`].
+trait Rewrite {
+    /// Rewrite a selector, or return `None` to remove it from its rule.
+    fn selector(&self, selector: &str) -> Option<String>;
+
+    /// Whether to keep a declaration like `color: red` of a rule.
+    fn keep_declaration(&self, _declaration: &str) -> bool {
+        true
+    }
+
+    /// Whether to keep a conditional group rule like `@media`.
+    fn keep_group(&self, _prelude: &str) -> bool {
+        true
+    }
+}
+
+/// Nest every rule under a selector.
+struct Scope<'a>(&'a str);
+
+impl Rewrite for Scope<'_> {
+    fn selector(&self, selector: &str) -> Option<String> {
+        Some(match selector {
+            ":root" | ":host" => self.0.to_string(),
+            _ => format!("{} {selector}", self.0),
+        })
+    }
+}
+
+/// Leave out the rules not needed for an output.
+struct Trim<'a> {
+    /// Rules for elements with these classes are left out.
+    unused_classes: Vec<&'a str>,
+    /// If set, rules for elements with other classes are left out too.
+    used_classes: Option<HashSet<&'a str>>,
+    /// Whether the dark color scheme is used.
+    dark: bool,
+    /// Whether the color scheme follows the system preference.
+    auto: bool,
+}
+
+impl Rewrite for Trim<'_> {
+    fn selector(&self, selector: &str) -> Option<String> {
+        let unused =
+            self.unused_classes
+                .iter()
+                .any(|class| has_class(selector, class))
+                || self.used_classes.as_ref().is_some_and(|used| {
+                    selector_classes(selector).any(|class| !used.contains(class))
+                });
+        (!unused).then(|| selector.to_string())
+    }
+
+    fn keep_declaration(&self, declaration: &str) -> bool {
+        self.dark || !declaration.starts_with(DARK_PROPERTY_PREFIX)
+    }
+
+    fn keep_group(&self, prelude: &str) -> bool {
+        self.auto || prelude != DARK_MEDIA_QUERY
+    }
+}
+
+/// Check if a selector matches elements with the given class.
+fn has_class(selector: &str, class: &str) -> bool {
+    selector.match_indices(class).any(|(i, _)| {
+        let is_class = selector[..i].ends_with('.');
+        let ends = selector[i + class.len()..]
+            .chars()
+            .next()
+            .is_none_or(|c| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        is_class && ends
+    })
+}
+
+/// Get the classes of the elements a selector matches, or is relative to.
+/// Classes in the arguments of pseudo-classes like `:not()` are skipped.
+fn selector_classes(selector: &str) -> impl Iterator<Item = &str> {
+    let mut depth = 0;
+    selector.char_indices().filter_map(move |(i, c)| {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            '.' if depth == 0 => {
+                let class = &selector[i + 1..];
+                let end = class
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+                    .unwrap_or(class.len());
+                return (end > 0).then(|| &class[..end]);
+            }
+            _ => {}
+        }
+        None
+    })
+}
+
+/// Rewrite the rules in `css`, recursing into conditional group rules like
+/// `@media`.
+///
+/// Comments and whitespace before a rule are left out together with the rule.
+fn rewrite_rules(css: &str, rewrite: &dyn Rewrite, result: &mut String) {
+    let mut rest = css;
+    // Whitespace and comments before the next rule
+    let mut pending = String::new();
+
+    loop {
+        let trimmed = rest.trim_start();
+        pending.push_str(&rest[..rest.len() - trimmed.len()]);
+        rest = trimmed;
+        if let Some(comment) = rest.strip_prefix("/*") {
+            let end = comment.find("*/").map_or(rest.len(), |i| i + 4);
+            pending.push_str(&rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+        if rest.is_empty() {
+            result.push_str(&pending);
+            return;
+        }
+
+        let Some(open) = rest.find(['{', ';']) else {
+            result.push_str(&pending);
+            result.push_str(rest);
+            return;
+        };
+        if rest.as_bytes()[open] == b';' {
+            // Statement at-rule like `@import`
+            result.push_str(&std::mem::take(&mut pending));
+            result.push_str(&rest[..=open]);
+            rest = &rest[open + 1..];
+            continue;
+        }
+
+        let prelude = &rest[..open];
+        let close = find_block_end(rest, open);
+        let block = &rest[open + 1..close];
+        rest = rest.get(close + 1..).unwrap_or_default();
+
+        let rule = if prelude.starts_with('@') {
+            rewrite_at_rule(prelude, block, rewrite)
+        } else {
+            rewrite_style_rule(prelude, block, rewrite)
+        };
+        match rule {
+            Some(rule) => {
+                result.push_str(&std::mem::take(&mut pending));
+                result.push_str(&rule);
+            }
+            None => pending.clear(),
+        }
+    }
+}
+
+/// Rewrite an at-rule with a block, or return `None` to leave it out.
+fn rewrite_at_rule(prelude: &str, block: &str, rewrite: &dyn Rewrite) -> Option<String> {
+    let name = prelude.split(|c: char| c.is_ascii_whitespace()).next();
+    if !matches!(name, Some("@media" | "@supports" | "@container" | "@layer")) {
+        return Some(format!("{prelude}{{{block}}}"));
+    }
+    if !rewrite.keep_group(prelude.trim_end()) {
+        return None;
+    }
+
+    let mut rules = String::new();
+    rewrite_rules(block, rewrite, &mut rules);
+    rules.contains('{').then(|| format!("{prelude}{{{rules}}}"))
+}
+
+/// Rewrite a style rule, or return `None` if none of its selectors are kept.
+fn rewrite_style_rule(prelude: &str, block: &str, rewrite: &dyn Rewrite) -> Option<String> {
+    let selectors = prelude.trim_end();
+    let mut kept: Vec<String> = Vec::new();
+    for selector in split_selectors(selectors) {
+        if let Some(selector) = rewrite.selector(selector.trim())
+            && !kept.contains(&selector)
+        {
+            kept.push(selector);
+        }
+    }
+    if kept.is_empty() {
+        return None;
+    }
+
+    let declarations: String = block
+        .split_inclusive(';')
+        .filter(|declaration| rewrite.keep_declaration(skip_comments(declaration)))
+        .collect();
+    Some(format!(
+        "{}{}{{{declarations}}}",
+        kept.join(",\n"),
+        &prelude[selectors.len()..]
+    ))
+}
+
+/// Skip the whitespace and comments at the start of `css`.
+fn skip_comments(css: &str) -> &str {
+    let mut rest = css.trim_start();
+    while let Some(comment) = rest.strip_prefix("/*") {
+        rest = comment
+            .find("*/")
+            .map_or("", |i| comment[i + 2..].trim_start());
+    }
+    rest
+}
+
+/// Split a selector list at the commas outside of parentheses and brackets.
+fn split_selectors(selectors: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in selectors.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&selectors[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&selectors[start..]);
+    parts
+}
+
+/// Find the closing brace of the block opened at `open`.
+fn find_block_end(css: &str, open: usize) -> usize {
+    let mut depth = 0;
+    for (i, c) in css[open..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return open + i;
+                }
+            }
+            _ => {}
+        }
+    }
+    css.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "/* a, b */\n:root,\n:host {\n  --x: 1;\n  --d2h-dark-x: 2;\n}\n\n\
+                          .a,\n.b:is(.c, .d) > .e {\n  color: red;\n}\n\n\
+                          /* Dark */\n.d2h-dark-color-scheme .a {\n  color: blue;\n}\n\n\
+                          @media (prefers-color-scheme: dark) {\n  .a .f { color: blue; }\n}\n\n\
+                          @keyframes spin {\n  from { opacity: 0; }\n}\n";
+
+    #[test]
+    fn test_rewrite_rules_scope() {
+        let mut result = String::new();
+        rewrite_rules(SAMPLE, &Scope("#pane"), &mut result);
+
+        assert_eq!(
+            result,
+            "/* a, b */\n#pane {\n  --x: 1;\n  --d2h-dark-x: 2;\n}\n\n\
+             #pane .a,\n#pane .b:is(.c, .d) > .e {\n  color: red;\n}\n\n\
+             /* Dark */\n#pane .d2h-dark-color-scheme .a {\n  color: blue;\n}\n\n\
+             @media (prefers-color-scheme: dark) {\n  #pane .a .f { color: blue; }\n}\n\n\
+             @keyframes spin {\n  from { opacity: 0; }\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_rewrite_rules_trim() {
+        let trim = Trim {
+            unused_classes: vec!["e", "d2h-dark-color-scheme"],
+            used_classes: None,
+            dark: false,
+            auto: false,
+        };
+        let mut result = String::new();
+        rewrite_rules(SAMPLE, &trim, &mut result);
+
+        assert_eq!(
+            result,
+            "/* a, b */\n:root,\n:host {\n  --x: 1;\n}\n\n\
+             .a {\n  color: red;\n}\n\n\
+             @keyframes spin {\n  from { opacity: 0; }\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_skip_comments() {
+        assert_eq!(skip_comments("\n  /**\n * a\n */\n\n  --x: 1;"), "--x: 1;");
+        assert_eq!(skip_comments("  color: red;"), "color: red;");
+        assert_eq!(skip_comments("/* a */ /* b"), "");
+    }
+
+    #[test]
+    fn test_has_class() {
+        assert!(has_class(".d2h-code-line", "d2h-code-line"));
+        assert!(has_class(".a .d2h-code-line del", "d2h-code-line"));
+        assert!(has_class(".d2h-code-line:after", "d2h-code-line"));
+        assert!(!has_class(".d2h-code-line-ctn", "d2h-code-line"));
+        assert!(!has_class(".x-d2h-code-line", "d2h-code-line"));
+    }
+
+    #[test]
+    fn test_css_scoped() {
+        let css = css_scoped("#review-pane");
+
+        assert!(css.starts_with("/*"));
+        assert!(css.contains("#review-pane {\n  --d2h-bg-color: #fff;"));
+        assert!(css.contains("#review-pane .d2h-file-header {"));
+        assert!(css.contains("  #review-pane .d2h-auto-color-scheme .d2h-file-header {"));
+        assert!(!css.contains(":root"));
+        assert_eq!(css.matches('{').count(), CSS.matches('{').count());
+        // Every rule is scoped
+        for line in css
+            .lines()
+            .filter(|line| line.trim_end().ends_with(['{', ',']))
+        {
+            let line = line.trim();
+            assert!(
+                line.starts_with("#review-pane") || line.starts_with('@'),
+                "unscoped selector: {line}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_css_for_output() {
+        let light = RenderConfig::default();
+        let css = css_for_output(OutputFormat::LineByLine, &light);
+        assert!(css.contains(".d2h-code-line {"));
+        assert!(css.contains(".d2h-code-line-ctn {"));
+        assert!(!css.contains("d2h-code-side-"));
+        assert!(!css.contains("dark"));
+        assert!(!css.contains(".d2h-autolink"));
+
+        let css = css_for_output(OutputFormat::SideBySide, &light);
+        assert!(css.contains(".d2h-code-side-line {"));
+        assert!(!css.contains(".d2h-code-linenumber"));
+        assert!(css.contains(".d2h-code-line-prefix"));
+        assert!(!css.contains("d2h-view-toggle"));
+
+        let css = css_for_output(OutputFormat::Switchable, &light);
+        assert!(css.contains(".d2h-code-linenumber {"));
+        assert!(css.contains(".d2h-code-side-linenumber {"));
+        assert!(css.contains(".d2h-view-toggle.d2h-view-toggle-enabled {"));
+
+        let dark = RenderConfig {
+            color_scheme: ColorScheme::Dark,
+            autolink: Some(Default::default()),
+            ..Default::default()
+        };
+        let css = css_for_output(OutputFormat::SideBySide, &dark);
+        assert!(css.contains("--d2h-dark-bg-color"));
+        assert!(css.contains(".d2h-dark-color-scheme .d2h-code-side-linenumber"));
+        assert!(!css.contains("d2h-auto-color-scheme"));
+        assert!(!css.contains(DARK_MEDIA_QUERY));
+        assert!(css.contains(".d2h-autolink"));
+
+        let auto = RenderConfig {
+            color_scheme: ColorScheme::Auto,
+            ..Default::default()
+        };
+        let css = css_for_output(OutputFormat::LineByLine, &auto);
+        assert!(css.contains(DARK_MEDIA_QUERY));
+        assert!(css.contains(".d2h-auto-color-scheme .d2h-code-linenumber"));
+        assert!(!css.contains("d2h-dark-color-scheme"));
+        assert_eq!(css.matches('{').count(), css.matches('}').count());
+
+        let responsive = RenderConfig {
+            responsive_breakpoint: Some(600),
+            ..Default::default()
+        };
+        let css = css_for_output(OutputFormat::SideBySide, &responsive);
+        assert!(css.ends_with(&css_responsive(600)));
+        let css = css_for_output(OutputFormat::LineByLine, &responsive);
+        assert!(!css.contains("d2h-files-diff-responsive"));
+    }
+
+    #[test]
+    fn test_selector_classes() {
+        let classes: Vec<_> =
+            selector_classes(".d2h-dark-color-scheme .d2h-ins.d2h-change:hover > td").collect();
+        assert_eq!(classes, ["d2h-dark-color-scheme", "d2h-ins", "d2h-change"]);
+        let classes: Vec<_> =
+            selector_classes(".d2h-diff-tbody tr:not(.d2h-collapsed-diff)").collect();
+        assert_eq!(classes, ["d2h-diff-tbody"]);
+        assert_eq!(selector_classes(":root").count(), 0);
+    }
+
+    #[test]
+    fn test_css_for_html() {
+        let config = RenderConfig::default();
+        let html = r#"<div class="d2h-wrapper d2h-light-color-scheme">
+            <td class="d2h-code-linenumber d2h-ins"></td></div>"#;
+        let css = css_for_html(html, OutputFormat::LineByLine, &config);
+
+        assert!(css.contains("--d2h-bg-color"));
+        assert!(css.contains(".d2h-code-linenumber {"));
+        assert!(css.contains(".d2h-ins {"));
+        assert!(!css.contains(".d2h-del"));
+        assert!(!css.contains(".d2h-ins.d2h-change"));
+        assert!(!css.contains("d2h-code-side-linenumber"));
+        assert_eq!(css.matches('{').count(), css.matches('}').count());
+    }
+
+    #[test]
+    fn test_css_responsive() {
+        let css = css_responsive(600);
+        assert!(css.starts_with("@media (max-width: 599.98px) {"));
+        assert!(css.contains(".d2h-files-diff-responsive > .d2h-file-side-diff {"));
+        assert_eq!(css.matches('{').count(), css.matches('}').count());
+    }
+}
@@ -0,0 +1,26 @@
+//! Default values of the configuration options.
+//!
+//! These are shared by [`Diff2HtmlConfig`](crate::Diff2HtmlConfig), the
+//! lower-level renderer and matching configurations, and the CLI flags, so
+//! the same diff renders the same way whichever is used.
+
+/// Threshold for word matching, from 0.0 to 1.0.
+pub const MATCH_WORDS_THRESHOLD: f64 = 0.25;
+
+/// Maximum number of line comparisons of a block of changes.
+pub const MATCHING_MAX_COMPARISONS: usize = 2500;
+
+/// Maximum number of characters of the bigger line in a block to compare lines.
+pub const MAX_LINE_SIZE_IN_BLOCK_FOR_COMPARISON: usize = 200;
+
+/// Maximum number of characters in a line to highlight changes.
+pub const MAX_LINE_LENGTH_HIGHLIGHT: usize = 10000;
+
+/// Message shown for files without changes.
+pub const EMPTY_DIFF_MESSAGE: &str = "File without changes";
+
+/// Message shown for binary files, whose changes are not shown.
+pub const BINARY_DIFF_MESSAGE: &str = "Binary files differ";
+
+/// Block header of files too big to be displayed.
+pub const DIFF_TOO_BIG_MESSAGE: &str = "Diff too big to be displayed";
@@ -0,0 +1,330 @@
+//! Reusable diff2html engine.
+//!
+//! [`Diff2Html`] owns a configuration together with the parser configuration
+//! and renderers derived from it, so they are set up once and reused for every
+//! diff. This is the recommended API for long-lived users such as servers.
+
+use crate::css::css_for_output;
+use crate::parser::{DiffParserConfig, ParseResult, parse, parse_lenient};
+use crate::render::{
+    FileListRenderer, LineByLineRenderer, RenderTree, SideBySideRenderer, SwitchableRenderer,
+    make_omitted_files_html, pretty_print_html,
+};
+use crate::selection::Selection;
+use crate::types::{DiffFile, OutputFormat};
+use crate::{Diff2HtmlConfig, json_from_diff_files};
+
+/// Renderer for the configured output format.
+#[derive(Debug)]
+enum DiffRenderer {
+    LineByLine(LineByLineRenderer),
+    SideBySide(SideBySideRenderer),
+    Switchable(Box<SwitchableRenderer>),
+}
+
+impl DiffRenderer {
+    fn render(&self, diff_files: &[DiffFile]) -> String {
+        match self {
+            Self::LineByLine(renderer) => renderer.render(diff_files),
+            Self::SideBySide(renderer) => renderer.render(diff_files),
+            Self::Switchable(renderer) => renderer.render(diff_files),
+        }
+    }
+
+    fn render_tree(&self, diff_files: &[DiffFile]) -> RenderTree {
+        match self {
+            Self::LineByLine(renderer) => renderer.render_tree(diff_files),
+            Self::SideBySide(renderer) => renderer.render_tree(diff_files),
+            Self::Switchable(renderer) => renderer.render_tree(diff_files),
+        }
+    }
+
+    #[cfg(feature = "web")]
+    fn render_file(&self, file: &DiffFile, html_id: &str) -> String {
+        match self {
+            Self::LineByLine(renderer) => renderer.render_file(file, html_id),
+            Self::SideBySide(renderer) => renderer.render_file(file, html_id),
+            Self::Switchable(renderer) => renderer.render_file(file, html_id),
+        }
+    }
+}
+
+/// Parser and renderers built from a [`Diff2HtmlConfig`].
+///
+/// # Example
+///
+/// ```
+/// use diff2html::{Diff2Html, Diff2HtmlConfig, OutputFormat};
+///
+/// let engine = Diff2Html::new(Diff2HtmlConfig {
+///     output_format: OutputFormat::SideBySide,
+///     ..Default::default()
+/// });
+///
+/// let diff = "--- a/file.txt\n+++ b/file.txt\n@@ -1 +1 @@\n-old\n+new\n";
+/// let html_output = engine.html(diff);
+/// assert!(html_output.contains("d2h-file-side-diff"));
+///
+/// let files = engine.parse(diff);
+/// assert_eq!(engine.html_from_diff_files(&files), html_output);
+/// ```
+#[derive(Debug)]
+pub struct Diff2Html {
+    config: Diff2HtmlConfig,
+    parser_config: DiffParserConfig,
+    file_list: Option<FileListRenderer>,
+    jump_menu: Option<FileListRenderer>,
+    renderer: DiffRenderer,
+}
+
+impl Default for Diff2Html {
+    fn default() -> Self {
+        Self::new(Diff2HtmlConfig::default())
+    }
+}
+
+impl Diff2Html {
+    /// Create an engine for the given configuration.
+    pub fn new(config: Diff2HtmlConfig) -> Self {
+        let parser_config = config.to_parser_config();
+        let file_list = config
+            .draw_file_list
+            .then(|| FileListRenderer::new(config.to_file_list_config()));
+        let jump_menu = config
+            .draw_jump_menu
+            .then(|| FileListRenderer::new(config.to_file_list_config()));
+        let renderer_config = config.to_renderer_config();
+        let renderer = match config.output_format {
+            OutputFormat::SideBySide => {
+                DiffRenderer::SideBySide(SideBySideRenderer::new(renderer_config))
+            }
+            OutputFormat::LineByLine => {
+                DiffRenderer::LineByLine(LineByLineRenderer::new(renderer_config))
+            }
+            OutputFormat::Switchable => {
+                DiffRenderer::Switchable(Box::new(SwitchableRenderer::new(renderer_config)))
+            }
+        };
+
+        Self {
+            config,
+            parser_config,
+            file_list,
+            jump_menu,
+            renderer,
+        }
+    }
+
+    /// Get the configuration of the engine.
+    pub fn config(&self) -> &Diff2HtmlConfig {
+        &self.config
+    }
+
+    /// Parse a diff string into a list of diff files.
+    pub fn parse(&self, diff_input: &str) -> Vec<DiffFile> {
+        parse(diff_input, &self.parser_config)
+    }
+
+    /// Parse a diff string like [`html`](Self::html), keeping the
+    /// diagnostics and the number of omitted files.
+    #[cfg(feature = "web")]
+    pub(crate) fn parse_result(&self, diff_input: &str) -> ParseResult {
+        parse_lenient(diff_input, &self.parser_config)
+    }
+
+    /// Render a file without the wrapper of the rendered files, with the
+    /// given HTML ID, so files rendered one at a time get unique IDs.
+    #[cfg(feature = "web")]
+    pub(crate) fn render_file(&self, file: &DiffFile, html_id: &str) -> String {
+        let html = self.renderer.render_file(file, html_id);
+        if self.config.pretty_print {
+            pretty_print_html(&html)
+        } else {
+            html
+        }
+    }
+
+    /// Parse a diff string and render it as HTML.
+    pub fn html(&self, diff_input: &str) -> String {
+        self.html_from_parse_result(&parse_lenient(diff_input, &self.parser_config))
+    }
+
+    /// Render already-parsed diff files as HTML.
+    pub fn html_from_diff_files(&self, diff_files: &[DiffFile]) -> String {
+        self.render_html(diff_files, 0)
+    }
+
+    /// Render the files of a parse result as HTML, followed by a notice of
+    /// the files left out by the `diff_max_files` or `diff_max_total_changes`
+    /// limits.
+    pub fn html_from_parse_result(&self, result: &ParseResult) -> String {
+        self.render_html(&result.files, result.omitted_files)
+    }
+
+    /// Render diff files as HTML, with the notice of the omitted files.
+    fn render_html(&self, diff_files: &[DiffFile], omitted_files: usize) -> String {
+        #[cfg(feature = "log")]
+        let start = std::time::Instant::now();
+        let file_list = self
+            .file_list
+            .as_ref()
+            .map(|renderer| renderer.render(diff_files))
+            .unwrap_or_default();
+
+        let jump_menu = self
+            .jump_menu
+            .as_ref()
+            .map(|renderer| renderer.render_jump_menu(diff_files))
+            .unwrap_or_default();
+
+        let html = jump_menu
+            + &file_list
+            + &self.renderer.render(diff_files)
+            + &make_omitted_files_html(omitted_files, self.config.color_scheme);
+        let html = if self.config.pretty_print {
+            pretty_print_html(&html)
+        } else {
+            html
+        };
+
+        #[cfg(feature = "log")]
+        log::debug!(
+            "rendered {} files to {} bytes of HTML in {:?}",
+            diff_files.len(),
+            html.len(),
+            start.elapsed()
+        );
+        html
+    }
+
+    /// Render the selected files and blocks of already-parsed diff files as
+    /// HTML.
+    ///
+    /// The file list and jump menu, if enabled, only include the selected
+    /// files. Enable `deterministic` ids to match the fragments to the files
+    /// of the full diff.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use diff2html::{Diff2Html, Selection};
+    ///
+    /// let diff = "--- a/a.txt\n+++ b/a.txt\n@@ -1 +1 @@\n-a\n+b\n\
+    ///             --- a/b.txt\n+++ b/b.txt\n@@ -1 +1 @@\n-c\n+d\n";
+    /// let engine = Diff2Html::default();
+    /// let files = engine.parse(diff);
+    ///
+    /// let html_output = engine.render_selection(&files, &Selection::new().file("b.txt"));
+    /// assert!(html_output.contains("b.txt"));
+    /// assert!(!html_output.contains("a.txt"));
+    /// ```
+    pub fn render_selection(&self, diff_files: &[DiffFile], selection: &Selection) -> String {
+        self.html_from_diff_files(&selection.apply(diff_files))
+    }
+
+    /// Render already-parsed diff files as a tree of elements instead of
+    /// HTML, for consumers transforming the structure.
+    ///
+    /// See [`RenderTree`].
+    pub fn render_tree(&self, diff_files: &[DiffFile]) -> RenderTree {
+        self.renderer.render_tree(diff_files)
+    }
+
+    /// Render a table of contents of already-parsed diff files, nested by
+    /// directory, for a sidebar next to the HTML of the engine.
+    ///
+    /// See [`FileListRenderer::render_toc`].
+    pub fn toc(&self, diff_files: &[DiffFile]) -> String {
+        FileListRenderer::new(self.config.to_file_list_config()).render_toc(diff_files)
+    }
+
+    /// Get the stylesheet rules needed to display the HTML of the engine.
+    ///
+    /// See [`css_for_output`].
+    pub fn css(&self) -> String {
+        css_for_output(
+            self.config.output_format,
+            &self.config.to_renderer_config().render,
+        )
+    }
+
+    /// Parse a diff string and return JSON output.
+    pub fn json(&self, diff_input: &str) -> Result<String, serde_json::Error> {
+        json_from_diff_files(&self.parse(diff_input))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "--- a/file.txt\n+++ b/file.txt\n@@ -1 +1 @@\n-old\n+new\n";
+
+    #[test]
+    fn test_engine_matches_functions() {
+        for output_format in [OutputFormat::LineByLine, OutputFormat::SideBySide] {
+            for draw_file_list in [true, false] {
+                let config = Diff2HtmlConfig {
+                    output_format,
+                    draw_file_list,
+                    ..Default::default()
+                };
+                let engine = Diff2Html::new(config.clone());
+
+                assert_eq!(engine.html(DIFF), crate::html(DIFF, &config));
+                assert_eq!(
+                    engine.json(DIFF).unwrap(),
+                    crate::json(DIFF, &config).unwrap()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_engine_is_reusable() {
+        let engine = Diff2Html::default();
+        let first = engine.html(DIFF);
+        let second = engine.html(DIFF);
+
+        assert_eq!(first, second);
+        assert_eq!(engine.parse(DIFF).len(), 1);
+        assert!(engine.config().draw_file_list);
+    }
+
+    #[test]
+    fn test_engine_render_selection() {
+        let engine = Diff2Html::new(Diff2HtmlConfig {
+            deterministic: true,
+            ..Default::default()
+        });
+        let files = engine.parse(DIFF);
+
+        let selection = Selection::new().file("file.txt");
+        assert_eq!(
+            engine.render_selection(&files, &selection),
+            engine.html(DIFF)
+        );
+        assert_eq!(
+            engine.render_selection(&files, &Selection::new().file(1)),
+            engine.html_from_diff_files(&[])
+        );
+    }
+
+    #[test]
+    fn test_engine_css() {
+        let engine = Diff2Html::new(Diff2HtmlConfig {
+            output_format: OutputFormat::SideBySide,
+            ..Default::default()
+        });
+
+        let css = engine.css();
+        assert!(css.contains(".d2h-code-side-line {"));
+        assert!(!css.contains(".d2h-code-linenumber {"));
+    }
+
+    #[test]
+    fn test_engine_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Diff2Html>();
+    }
+}
@@ -0,0 +1,139 @@
+//! C ABI for embedding diff2html in other languages.
+//!
+//! Enabled with the `ffi` feature. Build a shared library with
+//! `cargo rustc -p diff2html --release --features ffi --crate-type cdylib`
+//! and include the header `include/diff2html.h`, generated with
+//! `cbindgen --config cbindgen.toml --output include/diff2html.h`. CI checks
+//! that the header is up to date with the functions.
+//!
+//! Panics do not unwind into the caller: a function that panics returns
+//! like for an invalid argument.
+//!
+//! ```c
+//! char *html = diff2html_html(diff, "{\"outputFormat\": \"side-by-side\"}");
+//! if (html != NULL) {
+//!     puts(html);
+//!     diff2html_free(html);
+//! }
+//! ```
+
+use std::ffi::{CStr, CString, c_char};
+use std::panic::catch_unwind;
+use std::ptr;
+
+use crate::{Diff2HtmlConfig, config_from_json, html};
+
+/// Render a unified diff as HTML.
+///
+/// `config_json` is a JSON object of options, e.g.
+/// `{"outputFormat": "side-by-side", "colorScheme": "dark"}`, or `NULL` for
+/// the defaults. Returns `NULL` if an argument is not valid UTF-8, the
+/// configuration is not valid, or rendering panics. The returned string must
+/// be released with [`diff2html_free`].
+///
+/// # Safety
+///
+/// `diff` must be a valid NUL-terminated string, and `config_json` a valid
+/// NUL-terminated string or `NULL`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn diff2html_html(
+    diff: *const c_char,
+    config_json: *const c_char,
+) -> *mut c_char {
+    // SAFETY: the caller guarantees the requirements of `render_html`.
+    catch_unwind(|| unsafe { render_html(diff, config_json) }).unwrap_or(ptr::null_mut())
+}
+
+/// Body of [`diff2html_html`], which may panic.
+///
+/// # Safety
+///
+/// See [`diff2html_html`].
+unsafe fn render_html(diff: *const c_char, config_json: *const c_char) -> *mut c_char {
+    if diff.is_null() {
+        return ptr::null_mut();
+    }
+    // SAFETY: the caller guarantees `diff` is a valid NUL-terminated string.
+    let Ok(diff) = unsafe { CStr::from_ptr(diff) }.to_str() else {
+        return ptr::null_mut();
+    };
+    let config = if config_json.is_null() {
+        Diff2HtmlConfig::default()
+    } else {
+        // SAFETY: the caller guarantees `config_json` is a valid
+        // NUL-terminated string.
+        let Ok(json) = unsafe { CStr::from_ptr(config_json) }.to_str() else {
+            return ptr::null_mut();
+        };
+        match config_from_json(json) {
+            Ok(config) => config,
+            Err(_) => return ptr::null_mut(),
+        }
+    };
+
+    // Input containing NUL bytes cannot be passed in, so neither can the HTML.
+    CString::new(html(diff, &config)).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+/// Release a string returned by [`diff2html_html`]. Does nothing for `NULL`.
+///
+/// # Safety
+///
+/// `html` must be `NULL` or a string returned by [`diff2html_html`] that was
+/// not released yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn diff2html_free(html: *mut c_char) {
+    if html.is_null() {
+        return;
+    }
+    // A panic while releasing the string leaks it rather than unwinding into
+    // the caller.
+    let _ = catch_unwind(|| {
+        // SAFETY: the caller guarantees `html` was allocated by
+        // `diff2html_html` with `CString::into_raw`.
+        drop(unsafe { CString::from_raw(html) });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &CStr = c"--- a/a.txt\n+++ b/a.txt\n@@ -1 +1 @@\n-a\n+b\n";
+
+    fn render(config_json: Option<&CStr>) -> Option<String> {
+        let config_json = config_json.map_or(ptr::null(), CStr::as_ptr);
+        unsafe {
+            let html = diff2html_html(DIFF.as_ptr(), config_json);
+            if html.is_null() {
+                return None;
+            }
+            let result = CStr::from_ptr(html).to_str().unwrap().to_string();
+            diff2html_free(html);
+            Some(result)
+        }
+    }
+
+    #[test]
+    fn test_diff2html_html() {
+        let html = render(None).unwrap();
+        assert!(html.contains("d2h-file-list-wrapper"));
+        assert!(!html.contains("d2h-files-diff"));
+
+        let html = render(Some(
+            cr#"{"outputFormat": "side-by-side", "drawFileList": false}"#,
+        ))
+        .unwrap();
+        assert!(html.contains("d2h-files-diff"));
+        assert!(!html.contains("d2h-file-list-wrapper"));
+    }
+
+    #[test]
+    fn test_diff2html_html_invalid_input() {
+        assert_eq!(render(Some(c"{")), None);
+        assert_eq!(render(Some(cr#"{"outputFormat": "columns"}"#)), None);
+        assert_eq!(render(Some(cr#"{"unknown": true}"#)), None);
+        assert!(unsafe { diff2html_html(ptr::null(), ptr::null()) }.is_null());
+        unsafe { diff2html_free(ptr::null_mut()) };
+    }
+}
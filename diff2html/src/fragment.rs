@@ -0,0 +1,91 @@
+//! Standalone HTML fragments of single files.
+//!
+//! [`render_file_fragment`] renders one file together with the rules of the
+//! stylesheet its HTML uses, to embed single-file diffs in other documents
+//! such as documentation pages or chat messages.
+
+use crate::css::css_for_html;
+use crate::types::DiffFile;
+use crate::{Diff2Html, Diff2HtmlConfig};
+
+/// The HTML of a file and the CSS needed to display it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fragment {
+    pub html: String,
+    /// Rules of the stylesheet for the classes used in `html`.
+    pub css: String,
+}
+
+/// Render a file as a standalone fragment, without file list or jump menu.
+///
+/// # Example
+///
+/// ```
+/// use diff2html::{Diff2HtmlConfig, DiffParserConfig, css_for_output, parse, render_file_fragment};
+///
+/// let diff = "--- a/file.txt\n+++ b/file.txt\n@@ -1 +1 @@\n-old\n+new\n";
+/// let files = parse(diff, &DiffParserConfig::default());
+/// let config = Diff2HtmlConfig::default();
+/// let fragment = render_file_fragment(&files[0], &config);
+/// assert!(fragment.html.contains("file.txt"));
+/// assert!(fragment.css.len() < css_for_output(config.output_format, &config.to_renderer_config().render).len());
+/// ```
+pub fn render_file_fragment(file: &DiffFile, config: &Diff2HtmlConfig) -> Fragment {
+    let config = Diff2HtmlConfig {
+        draw_file_list: false,
+        draw_jump_menu: false,
+        ..config.clone()
+    };
+    let html = Diff2Html::new(config.clone()).html_from_diff_files(std::slice::from_ref(file));
+    let css = css_for_html(
+        &html,
+        config.output_format,
+        &config.to_renderer_config().render,
+    );
+    Fragment { html, css }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::css::css_for_output;
+    use crate::parser::{DiffParserConfig, parse};
+    use crate::types::{ColorScheme, OutputFormat};
+
+    fn file() -> DiffFile {
+        let diff = "--- a/a.txt\n+++ b/a.txt\n@@ -1,2 +1,2 @@\n a\n-b\n+c\n";
+        parse(diff, &DiffParserConfig::default()).remove(0)
+    }
+
+    #[test]
+    fn test_render_file_fragment() {
+        let config = Diff2HtmlConfig::default();
+        let fragment = render_file_fragment(&file(), &config);
+
+        assert!(fragment.html.contains("a.txt"));
+        assert!(!fragment.html.contains("Files changed"));
+        assert!(fragment.css.contains(".d2h-file-header {"));
+        assert!(fragment.css.contains(".d2h-code-linenumber {"));
+        assert!(!fragment.css.contains("d2h-file-list"));
+        assert!(!fragment.css.contains("d2h-toc"));
+        assert!(!fragment.css.contains("d2h-code-side-linenumber"));
+        assert!(
+            fragment.css.len()
+                < css_for_output(config.output_format, &config.to_renderer_config().render).len()
+        );
+    }
+
+    #[test]
+    fn test_render_file_fragment_side_by_side_dark() {
+        let config = Diff2HtmlConfig {
+            output_format: OutputFormat::SideBySide,
+            color_scheme: ColorScheme::Dark,
+            ..Default::default()
+        };
+        let fragment = render_file_fragment(&file(), &config);
+
+        assert!(fragment.css.contains(".d2h-code-side-linenumber {"));
+        assert!(fragment.css.contains(".d2h-dark-color-scheme"));
+        assert!(!fragment.css.contains(".d2h-code-linenumber {"));
+    }
+}
@@ -1,4 +1,5 @@
-#![forbid(unsafe_code)]
+#![cfg_attr(not(feature = "ffi"), forbid(unsafe_code))]
+#![cfg_attr(feature = "ffi", deny(unsafe_code))]
 
 //! diff2html - Parse unified diffs and generate HTML.
 //!
@@ -25,6 +26,21 @@
 //! assert!(html_output.contains("d2h-wrapper"));
 //! ```
 //!
+//! # Reusable Engine
+//!
+//! Long-lived users, e.g. servers rendering many diffs, should create a
+//! [`Diff2Html`] engine once and reuse it:
+//!
+//! ```
+//! use diff2html::{Diff2Html, Diff2HtmlConfig};
+//!
+//! let engine = Diff2Html::new(Diff2HtmlConfig::default());
+//!
+//! let diff = "--- a/file.txt\n+++ b/file.txt\n@@ -1 +1 @@\n-old\n+new\n";
+//! let html_output = engine.html(diff);
+//! assert!(html_output.contains("d2h-wrapper"));
+//! ```
+//!
 //! # Two-Stage Processing
 //!
 //! For more control, you can parse and render separately:
@@ -88,26 +104,96 @@
 //! let html_output = html(diff, &config);
 //! assert!(html_output.contains("d2h-dark-color-scheme"));
 //! ```
-
+//!
+//! # Content Security Policy
+//!
+//! The generated HTML is styled with classes only. It contains no `style`
+//! attributes, `<style>` or `<script>` elements and no event handler
+//! attributes, so it can be used under a strict `Content-Security-Policy`.
+//! HTML added through hooks like [`RenderHook`] is not checked. Include the
+//! stylesheet with [`css_style_element`], passing the nonce of the page:
+//!
+//! ```
+//! use diff2html::css_style_element;
+//!
+//! let style = css_style_element(Some("r4nd0m"));
+//! assert!(style.starts_with(r#"<style nonce="r4nd0m">"#));
+//! ```
+//!
+//! Image previews use `data:` URIs, which need `img-src data:` in the policy.
+//!
+//! Synchronised horizontal scrolling of the side-by-side view and the
+//! "Viewed" checkboxes collapsing files need scripts. Include them with
+//! [`synchronised_scroll_script_element`] and
+//! [`file_content_toggle_script_element`], which take the nonce as well.
+//!
+//! # Logging
+//!
+//! With the default `log` feature, the parser and the engine report the
+//! problems found in the input and the time spent parsing and rendering as
+//! debug messages through the [`log`](https://docs.rs/log) crate, targeted at
+//! their module paths, e.g. `diff2html::parser`. Install a logger in the host
+//! application to see them.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+pub mod builder;
+pub mod css;
+pub mod defaults;
+pub mod engine;
+#[cfg(feature = "ffi")]
+#[allow(unsafe_code)]
+pub mod ffi;
+pub mod fragment;
+pub mod merge;
+pub mod options;
 pub mod parser;
 pub mod rematch;
 pub mod render;
+pub mod sarif;
+pub mod selection;
+pub mod stats;
 pub mod templates;
+pub mod text;
 pub mod types;
-
-pub use parser::{DiffParserConfig, parse};
+#[cfg(feature = "web")]
+pub mod web;
+
+pub use builder::{BuildError, DiffBlockBuilder, DiffFileBuilder};
+pub use css::{css_for_html, css_for_output, css_responsive, css_scoped};
+pub use engine::Diff2Html;
+pub use fragment::{Fragment, render_file_fragment};
+pub use merge::{MergeStrategy, merge_diff_files};
+pub use options::config_from_json;
+pub use parser::{
+    DiagnosticKind, DiffParserConfig, FilenameDecoding, ParseDiagnostic, ParseError, ParseMode,
+    ParseResult, PrefixMode, TimestampHandling, parse, parse_summary, parse_with_diagnostics,
+};
 pub use rematch::{
     BestMatch, MatchConfig, MatchGroup, levenshtein, match_lines, match_lines_with_config,
     new_distance_fn, string_distance,
 };
-pub use render::utils::{CSSLineClass, HighlightedLines, RenderConfig};
+pub use render::utils::{CSSLineClass, FileModeChange, HighlightedLines, RenderConfig};
 pub use render::{
-    FileListConfig, FileListRenderer, LineByLineRenderer, RendererConfig, SideBySideRenderer,
+    AutolinkConfig, AutolinkReference, BlameSource, FileBlame, FileConfigOverride, FileHeaderExtra,
+    FileIcon, FileIconMatch, FileListConfig, FileListRenderer, FilePage, ImagePreview, ImageSource,
+    LineBlame, LineByLineRenderer, LineGroup, MinifiedFileDetection, RenderElement, RenderHook,
+    RenderNode, RenderTree, RendererConfig, SideBySideRenderer, SwitchableRenderer,
+};
+pub use sarif::sarif_from_diff_files;
+pub use selection::{FileSelection, FileSelector, Selection};
+pub use stats::{stats_csv, stats_json, stats_tsv};
+pub use templates::{
+    COPY_PATH_JS, CSS, FILE_CONTENT_TOGGLE_JS, SYNCHRONISED_SCROLL_JS, TemplateName,
+    VIEW_TOGGLE_JS, copy_path_script_element, css_style_element,
+    file_content_toggle_script_element, render as render_template, render_by_name,
+    synchronised_scroll_script_element, view_toggle_script_element,
 };
-pub use templates::{CSS, TemplateName, render as render_template, render_by_name};
 pub use types::{
-    Checksum, ColorScheme, DiffBlock, DiffFile, DiffLine, DiffLineParts, DiffStyle, FileMode,
-    LineMatchingType, LineType, OutputFormat,
+    Checksum, ColorScheme, DiffBlock, DiffFile, DiffLine, DiffLineParts, DiffStats, DiffStyle,
+    FileMode, GroupBy, HtmlIdStrategy, LanguageStats, LineMatchingType, LineType, Message,
+    OutputFormat, OutputMarkup, RawHtml, RenameDisplay, WhitespaceChanges, WordDiffSegment,
 };
 
 /// Unified configuration for diff2html.
@@ -137,16 +223,51 @@ pub struct Diff2HtmlConfig {
     pub diff_max_changes: Option<u32>,
     /// Maximum line length before marking file as "too big".
     pub diff_max_line_length: Option<usize>,
+    /// Maximum number of files to show before summarizing the rest.
+    pub diff_max_files: Option<usize>,
+    /// Maximum number of changed lines over all files.
+    pub diff_max_total_changes: Option<u32>,
+    /// Keep the content of too big files and render it collapsed.
+    pub keep_too_big_content: bool,
+    /// How malformed input is handled by [`parse_with_diagnostics`].
+    pub parse_mode: ParseMode,
+    /// How escape sequences in quoted file names are handled.
+    pub filename_decoding: FilenameDecoding,
+    /// Which path prefixes are stripped from file names.
+    pub prefix_mode: PrefixMode,
+    /// How timestamps after the file names are handled.
+    pub timestamps: TimestampHandling,
+    /// Languages of files by extension, file name or path glob, used for
+    /// `data-lang` and syntax highlighting.
+    pub language_overrides: HashMap<String, String>,
+    /// Paths or globs of files to leave out, see [`DiffParserConfig::exclude`].
+    pub exclude: Vec<String>,
 
     // Renderer options
     /// Output format: line-by-line or side-by-side view.
     pub output_format: OutputFormat,
+    /// HTML markup of the diff lines: table rows or CSS grid `<div>`s.
+    pub output_markup: OutputMarkup,
+    /// Grouping of the rendered files, each group starting with a header.
+    pub group_by: GroupBy,
     /// Whether to draw the file list summary at the top.
     pub draw_file_list: bool,
+    /// Whether the file list summary starts expanded. A closed list is hidden
+    /// until shown with the diff2html-ui `fileListToggle`.
+    pub file_list_open: bool,
+    /// Whether to draw a bar of the changed lines by language at the top of
+    /// the file list summary.
+    pub draw_language_bar: bool,
+    /// Whether to draw a menu linking to each file, staying in the corner of
+    /// the page while scrolling.
+    pub draw_jump_menu: bool,
     /// Diff highlighting style: word or character level.
     pub diff_style: DiffStyle,
     /// Color scheme for the output.
     pub color_scheme: ColorScheme,
+    /// Viewport width in CSS pixels below which the side-by-side panels are
+    /// stacked, old above new. Needs the rules of [`css_responsive`].
+    pub responsive_breakpoint: Option<u32>,
     /// Line matching algorithm for pairing similar lines.
     pub matching: LineMatchingType,
     /// Threshold for word matching (0.0 to 1.0, default 0.25).
@@ -159,6 +280,93 @@ pub struct Diff2HtmlConfig {
     pub matching_max_comparisons: usize,
     /// Maximum line size in a block for comparison.
     pub max_line_size_in_block_for_comparison: usize,
+    /// Maximum number of line pairs highlighted in a block, so that huge
+    /// blocks of changes, e.g. of generated files, render quickly.
+    pub max_highlight_pairs_per_block: Option<usize>,
+    /// Maximum number of columns rendered of a line, wide characters such as
+    /// CJK ideographs counting as two, so that minified one-line files do not
+    /// slow down the page. Longer lines are truncated
+    /// with an ellipsis revealing the rest of the line, kept hidden in the HTML.
+    pub max_rendered_line_length: Option<usize>,
+    /// Number of highlighted line pairs cached by the renderers, so the
+    /// pairs repeated in diffs of generated code are highlighted once, or 0
+    /// for none.
+    pub highlight_cache_size: usize,
+    /// Whether to show the similarity index and checksums in file headers.
+    pub show_file_metadata: bool,
+    /// Render added and deleted files in a single full-width column in the
+    /// side-by-side view, instead of next to an empty column.
+    pub single_column_added_deleted: bool,
+    /// Alternate the deleted and inserted lines of changes in the
+    /// line-by-line view, instead of all deletions before all insertions.
+    pub interleave_changes: bool,
+    /// Handling of deleted and inserted lines differing only in whitespace.
+    pub whitespace_changes: WhitespaceChanges,
+    /// Display of the paths of renamed and copied files in the file header.
+    pub rename_display: RenameDisplay,
+    /// Message shown for files without changes.
+    pub empty_diff_message: String,
+    /// Message shown for binary files without image preview.
+    pub binary_diff_message: String,
+    /// Block header of files too big to be displayed, instead of
+    /// "Diff too big to be displayed". Plain text is escaped, wrap trusted
+    /// markup in [`RawHtml`] to render it as is.
+    pub diff_too_big_message: Option<Message>,
+    /// Keep the file and block headers at the top of the page while scrolling.
+    /// Long lines wrap instead of scrolling horizontally, since the headers
+    /// cannot stick outside of a scrolling element.
+    pub sticky_headers: bool,
+    /// Wrap long lines instead of scrolling horizontally, with the
+    /// continuation lines indented and the line numbers kept in the gutter.
+    /// In side-by-side output, the rows of both sides wrap independently.
+    pub wrap_lines: bool,
+    /// Leave out the `d2h-wrapper` element around the rendered files, for
+    /// hosts providing their own container. The host should then set the
+    /// color scheme class, e.g. `d2h-dark-color-scheme`, on its container.
+    pub no_wrapper: bool,
+    /// Paths or globs of the files to render collapsed, with their "Viewed"
+    /// checkbox checked, see [`RendererConfig::collapse_by_default`].
+    pub collapse_by_default: Vec<String>,
+    /// Render the generated files collapsed, see [`DiffFile::is_generated`].
+    pub collapse_generated: bool,
+    /// Detection of the minified files, rendered with their lines truncated,
+    /// without highlighting and with a note, since their huge lines freeze
+    /// browsers. `None`, the default, renders them like other files.
+    pub minified_files: Option<MinifiedFileDetection>,
+    /// Options overridden for the files matching a path or glob, see
+    /// [`RendererConfig::per_file_overrides`].
+    pub per_file_overrides: Vec<(String, FileConfigOverride)>,
+    /// Icons and tags replacing the templates for the matching files, see
+    /// [`FileIcon`].
+    pub file_icons: Vec<(FileIconMatch, FileIcon)>,
+    /// URL of the files linked from the file headers and the file list, see
+    /// [`RendererConfig::file_url_template`].
+    pub file_url_template: Option<String>,
+    /// Revisions of the links of the files, see
+    /// [`RendererConfig::file_url_revisions`].
+    pub file_url_revisions: Option<(String, String)>,
+    /// Generate byte-identical HTML across runs, platforms and Rust versions,
+    /// e.g. for golden-file tests.
+    pub deterministic: bool,
+    /// HTML IDs of the file diffs, hashes or readable slugs of their paths.
+    pub html_id_strategy: HtmlIdStrategy,
+    /// Render diff files from untrusted sources, e.g. deserialized from JSON:
+    /// the headers of too big files, otherwise trusted as the HTML of
+    /// [`diff_too_big_message`](Self::diff_too_big_message), are escaped too,
+    /// and the HTML is [`deterministic`](Self::deterministic).
+    pub hardened: bool,
+    /// Re-indent the HTML consistently by nesting depth.
+    pub pretty_print: bool,
+    /// Source of before/after images for previewing binary image files.
+    pub image_source: Option<Arc<dyn ImageSource>>,
+    /// Provider of extra HTML for file headers, e.g. buttons or badges.
+    pub file_header_extra: Option<Arc<dyn FileHeaderExtra>>,
+    /// Link URLs and references in line content.
+    pub autolink: Option<AutolinkConfig>,
+    /// Hooks transforming the rendered HTML, run in order.
+    pub render_hooks: Vec<Arc<dyn RenderHook>>,
+    /// Source of per-line authorship shown in a gutter next to line numbers.
+    pub blame_source: Option<Arc<dyn BlameSource>>,
 }
 
 impl Default for Diff2HtmlConfig {
@@ -169,18 +377,63 @@ impl Default for Diff2HtmlConfig {
             dst_prefix: None,
             diff_max_changes: None,
             diff_max_line_length: None,
+            diff_max_files: None,
+            diff_max_total_changes: None,
+            keep_too_big_content: false,
+            parse_mode: ParseMode::Lenient,
+            filename_decoding: FilenameDecoding::Decode,
+            prefix_mode: PrefixMode::Auto,
+            timestamps: TimestampHandling::Always,
+            language_overrides: HashMap::new(),
+            exclude: Vec::new(),
 
             // Renderer defaults
             output_format: OutputFormat::LineByLine,
+            output_markup: OutputMarkup::Table,
+            group_by: GroupBy::None,
             draw_file_list: true,
+            file_list_open: true,
+            draw_language_bar: false,
+            draw_jump_menu: false,
             diff_style: DiffStyle::Word,
             color_scheme: ColorScheme::Light,
+            responsive_breakpoint: None,
             matching: LineMatchingType::None,
-            match_words_threshold: 0.25,
-            max_line_length_highlight: 10000,
+            match_words_threshold: defaults::MATCH_WORDS_THRESHOLD,
+            max_line_length_highlight: defaults::MAX_LINE_LENGTH_HIGHLIGHT,
             render_nothing_when_empty: false,
-            matching_max_comparisons: 2500,
-            max_line_size_in_block_for_comparison: 200,
+            matching_max_comparisons: defaults::MATCHING_MAX_COMPARISONS,
+            max_line_size_in_block_for_comparison: defaults::MAX_LINE_SIZE_IN_BLOCK_FOR_COMPARISON,
+            max_highlight_pairs_per_block: None,
+            max_rendered_line_length: None,
+            highlight_cache_size: 0,
+            show_file_metadata: false,
+            single_column_added_deleted: false,
+            interleave_changes: false,
+            whitespace_changes: WhitespaceChanges::Show,
+            rename_display: RenameDisplay::Compact,
+            empty_diff_message: defaults::EMPTY_DIFF_MESSAGE.to_string(),
+            binary_diff_message: defaults::BINARY_DIFF_MESSAGE.to_string(),
+            diff_too_big_message: None,
+            sticky_headers: false,
+            wrap_lines: false,
+            no_wrapper: false,
+            collapse_by_default: Vec::new(),
+            collapse_generated: true,
+            minified_files: None,
+            per_file_overrides: Vec::new(),
+            file_icons: Vec::new(),
+            file_url_template: None,
+            file_url_revisions: None,
+            deterministic: false,
+            html_id_strategy: HtmlIdStrategy::Hash,
+            hardened: false,
+            pretty_print: false,
+            image_source: None,
+            file_header_extra: None,
+            autolink: None,
+            render_hooks: Vec::new(),
+            blame_source: None,
         }
     }
 }
@@ -198,7 +451,19 @@ impl Diff2HtmlConfig {
             dst_prefix: self.dst_prefix.clone(),
             diff_max_changes: self.diff_max_changes,
             diff_max_line_length: self.diff_max_line_length,
-            diff_too_big_message: None,
+            diff_max_files: self.diff_max_files,
+            diff_max_total_changes: self.diff_max_total_changes,
+            diff_too_big_message: self.diff_too_big_message.clone().map(|message| {
+                Box::new(move |_: usize| message.clone())
+                    as Box<dyn Fn(usize) -> Message + Send + Sync>
+            }),
+            keep_too_big_content: self.keep_too_big_content,
+            mode: self.parse_mode,
+            filename_decoding: self.filename_decoding,
+            prefix_mode: self.prefix_mode,
+            timestamps: self.timestamps.clone(),
+            language_overrides: self.language_overrides.clone(),
+            exclude: self.exclude.clone(),
         }
     }
 
@@ -211,10 +476,42 @@ impl Diff2HtmlConfig {
                 max_line_length_highlight: self.max_line_length_highlight,
                 diff_style: self.diff_style,
                 color_scheme: self.color_scheme,
+                autolink: self.autolink.clone(),
+                responsive_breakpoint: self.responsive_breakpoint,
             },
             render_nothing_when_empty: self.render_nothing_when_empty,
             matching_max_comparisons: self.matching_max_comparisons,
             max_line_size_in_block_for_comparison: self.max_line_size_in_block_for_comparison,
+            max_highlight_pairs_per_block: self.max_highlight_pairs_per_block,
+            max_rendered_line_length: self.max_rendered_line_length,
+            highlight_cache_size: self.highlight_cache_size,
+            show_file_metadata: self.show_file_metadata,
+            single_column_added_deleted: self.single_column_added_deleted,
+            interleave_changes: self.interleave_changes,
+            whitespace_changes: self.whitespace_changes,
+            rename_display: self.rename_display,
+            empty_diff_message: self.empty_diff_message.clone(),
+            binary_diff_message: self.binary_diff_message.clone(),
+            sticky_headers: self.sticky_headers,
+            wrap_lines: self.wrap_lines,
+            no_wrapper: self.no_wrapper,
+            collapsed: false,
+            collapse_by_default: self.collapse_by_default.clone(),
+            collapse_generated: self.collapse_generated,
+            minified_files: self.minified_files.clone(),
+            per_file_overrides: self.per_file_overrides.clone(),
+            file_icons: self.file_icons.clone(),
+            file_url_template: self.file_url_template.clone(),
+            file_url_revisions: self.file_url_revisions.clone(),
+            deterministic: self.deterministic || self.hardened,
+            html_id_strategy: self.html_id_strategy,
+            hardened: self.hardened,
+            output_markup: self.output_markup,
+            group_by: self.group_by,
+            image_source: self.image_source.clone(),
+            file_header_extra: self.file_header_extra.clone(),
+            render_hooks: self.render_hooks.clone(),
+            blame_source: self.blame_source.clone(),
         }
     }
 
@@ -222,6 +519,14 @@ impl Diff2HtmlConfig {
     pub fn to_file_list_config(&self) -> FileListConfig {
         FileListConfig {
             color_scheme: self.color_scheme,
+            deterministic: self.deterministic || self.hardened,
+            html_id_strategy: self.html_id_strategy,
+            open: self.file_list_open,
+            language_bar: self.draw_language_bar,
+            file_icons: self.file_icons.clone(),
+            file_url_template: self.file_url_template.clone(),
+            file_url_revisions: self.file_url_revisions.clone(),
+            group_by: self.group_by,
         }
     }
 }
@@ -255,8 +560,7 @@ impl Diff2HtmlConfig {
 /// assert!(html_output.contains("d2h-file-side-diff"));
 /// ```
 pub fn html(diff_input: &str, config: &Diff2HtmlConfig) -> String {
-    let diff_files = parse(diff_input, &config.to_parser_config());
-    html_from_diff_files(&diff_files, config)
+    Diff2Html::new(config.clone()).html(diff_input)
 }
 
 /// Render already-parsed diff files as HTML.
@@ -293,28 +597,18 @@ pub fn html(diff_input: &str, config: &Diff2HtmlConfig) -> String {
 /// let html2 = html_from_diff_files(&files, &config2);
 /// ```
 pub fn html_from_diff_files(diff_files: &[DiffFile], config: &Diff2HtmlConfig) -> String {
-    let renderer_config = config.to_renderer_config();
-
-    let file_list = if config.draw_file_list {
-        let file_list_config = config.to_file_list_config();
-        let file_list_renderer = FileListRenderer::new(file_list_config);
-        file_list_renderer.render(diff_files)
-    } else {
-        String::new()
-    };
-
-    let diff_output = match config.output_format {
-        OutputFormat::SideBySide => {
-            let renderer = SideBySideRenderer::new(renderer_config);
-            renderer.render(diff_files)
-        }
-        OutputFormat::LineByLine => {
-            let renderer = LineByLineRenderer::new(renderer_config);
-            renderer.render(diff_files)
-        }
-    };
+    Diff2Html::new(config.clone()).html_from_diff_files(diff_files)
+}
 
-    file_list + &diff_output
+/// Render the selected files and blocks of already-parsed diff files as HTML.
+///
+/// See [`Selection`] and [`Diff2Html::render_selection`].
+pub fn render_selection(
+    diff_files: &[DiffFile],
+    selection: &Selection,
+    config: &Diff2HtmlConfig,
+) -> String {
+    Diff2Html::new(config.clone()).render_selection(diff_files, selection)
 }
 
 /// Parse a diff string and return JSON output.
@@ -322,6 +616,10 @@ pub fn html_from_diff_files(diff_files: &[DiffFile], config: &Diff2HtmlConfig) -
 /// This function parses the diff and serializes the result to JSON format,
 /// which can be useful for integration with other tools or for debugging.
 ///
+/// The JSON is the array of files, like the JSON of the JavaScript diff2html.
+/// The number of files left out because of the file limits is not part of it,
+/// get it from [`parse_with_diagnostics`] in [`ParseResult::omitted_files`].
+///
 /// # Arguments
 ///
 /// * `diff_input` - The unified diff text to parse
@@ -348,7 +646,8 @@ pub fn json(diff_input: &str, config: &Diff2HtmlConfig) -> Result<String, serde_
 /// Serialize already-parsed diff files to JSON.
 ///
 /// Use this function when you have already parsed the diff and want to
-/// get JSON output without re-parsing.
+/// get JSON output without re-parsing. Like [`json`], the JSON leaves out
+/// [`ParseResult::omitted_files`].
 ///
 /// # Arguments
 ///
@@ -0,0 +1,194 @@
+//! Merging of the files of several diffs.
+//!
+//! Diffs parsed separately, like per-service patches or the batches of an
+//! incremental pipeline, can be rendered as one diff with
+//! [`merge_diff_files`].
+
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+
+use crate::types::DiffFile;
+
+/// How the entries of the same file in several diffs are merged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergeStrategy {
+    /// Keep every entry, in order.
+    KeepAll,
+    /// Combine the entries into one, concatenating their blocks and summing
+    /// their line counts.
+    #[default]
+    Combine,
+    /// Keep the entry of the last diff only, in place of the first entry.
+    KeepLast,
+}
+
+/// Merge the files of several diffs, keeping the order in which the files
+/// first appear.
+///
+/// Files are the same if they have the same new name, or old name for
+/// deleted files.
+/// Combined files lose their [`DiffFile::source_lines`], as their blocks come
+/// from different inputs.
+///
+/// # Example
+///
+/// ```
+/// use diff2html::{DiffParserConfig, MergeStrategy, merge_diff_files, parse};
+///
+/// let config = DiffParserConfig::default();
+/// let first = parse("--- a/f\n+++ b/f\n@@ -1 +1 @@\n-a\n+b\n", &config);
+/// let second = parse("--- a/f\n+++ b/f\n@@ -9 +9,2 @@\n-a\n+b\n+c\n", &config);
+///
+/// let files = merge_diff_files(vec![first, second], MergeStrategy::Combine);
+/// assert_eq!(files.len(), 1);
+/// assert_eq!(files[0].blocks.len(), 2);
+/// assert_eq!((files[0].added_lines, files[0].deleted_lines), (3, 2));
+/// ```
+pub fn merge_diff_files(diffs: Vec<Vec<DiffFile>>, strategy: MergeStrategy) -> Vec<DiffFile> {
+    let mut merged: Vec<DiffFile> = Vec::new();
+    let mut indices: HashMap<String, usize> = HashMap::new();
+
+    for file in diffs.into_iter().flatten() {
+        if strategy == MergeStrategy::KeepAll {
+            merged.push(file);
+            continue;
+        }
+        match indices.entry(file_path(&file).to_string()) {
+            Entry::Occupied(entry) => {
+                let existing = &mut merged[*entry.get()];
+                if strategy == MergeStrategy::KeepLast {
+                    *existing = file;
+                } else {
+                    combine(existing, file);
+                }
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(merged.len());
+                merged.push(file);
+            }
+        }
+    }
+
+    merged
+}
+
+/// Get the path identifying a file: its new name, or old name if deleted.
+fn file_path(file: &DiffFile) -> &str {
+    if file.is_deleted == Some(true) || file.new_name == "/dev/null" {
+        &file.old_name
+    } else {
+        &file.new_name
+    }
+}
+
+/// Add the blocks and line counts of a later entry of the same file.
+///
+/// The old side of the file is kept and the new side is taken from the later
+/// entry.
+fn combine(file: &mut DiffFile, later: DiffFile) {
+    file.added_lines += later.added_lines;
+    file.deleted_lines += later.deleted_lines;
+    file.blocks.extend(later.blocks);
+    file.source_lines = None;
+
+    // A later deletion, or a later entry of a deleted file, decides if the
+    // file still exists
+    file.new_name = later.new_name;
+    file.new_timestamp = later.new_timestamp;
+    file.is_deleted = later.is_deleted;
+    file.deleted_file_mode = later.deleted_file_mode;
+    file.new_mode = later.new_mode.or(file.new_mode.take());
+    file.checksum_after = later.checksum_after.or(file.checksum_after.take());
+    file.binary_size_after = later.binary_size_after.or(file.binary_size_after);
+    for (flag, later_flag) in [
+        (&mut file.is_binary, later.is_binary),
+        (&mut file.is_too_big, later.is_too_big),
+        (&mut file.is_too_big_text, later.is_too_big_text),
+        (&mut file.is_collapsed, later.is_collapsed),
+        (&mut file.is_generated, later.is_generated),
+    ] {
+        if later_flag == Some(true) {
+            *flag = Some(true);
+        }
+    }
+    file.is_mode_change = (file.blocks.is_empty()
+        && (file.is_mode_change == Some(true) || later.is_mode_change == Some(true)))
+    .then_some(true);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{DiffParserConfig, parse};
+
+    fn names(files: &[DiffFile]) -> Vec<(&str, usize)> {
+        files
+            .iter()
+            .map(|file| (file.new_name.as_str(), file.blocks.len()))
+            .collect()
+    }
+
+    fn diffs() -> Vec<Vec<DiffFile>> {
+        let config = DiffParserConfig::default();
+        vec![
+            parse(
+                "--- a/a\n+++ b/a\n@@ -1 +1 @@\n-a\n+b\n\
+                 --- a/b\n+++ b/b\n@@ -1 +1 @@\n-a\n+b\n",
+                &config,
+            ),
+            parse("--- a/b\n+++ b/b\n@@ -5 +5 @@\n-a\n+b\n", &config),
+            parse(
+                "--- a/c\n+++ /dev/null\n@@ -1 +0,0 @@\n-a\n\
+                 --- a/a\n+++ b/a\n@@ -9 +9,2 @@\n-a\n+b\n+c\n",
+                &config,
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_merge_combine() {
+        let files = merge_diff_files(diffs(), MergeStrategy::Combine);
+        assert_eq!(names(&files), [("a", 2), ("b", 2), ("/dev/null", 1)]);
+        assert_eq!((files[0].added_lines, files[0].deleted_lines), (3, 2));
+        assert_eq!(files[0].blocks[1].header, "@@ -9 +9,2 @@");
+        assert_eq!(files[0].source_lines, None);
+        assert!(files[2].source_lines.is_some());
+    }
+
+    #[test]
+    fn test_merge_combine_deleted() {
+        let config = DiffParserConfig::default();
+        let modified = parse(
+            "diff --git a/a b/a\n--- a/a\n+++ b/a\n@@ -1,2 +1,2 @@\n-a\n+b\n c\n",
+            &config,
+        );
+        let deleted = parse(
+            "diff --git a/a b/a\ndeleted file mode 100644\n--- a/a\n+++ /dev/null\n\
+             @@ -1,2 +0,0 @@\n-b\n-c\n",
+            &config,
+        );
+
+        let files = merge_diff_files(vec![modified, deleted], MergeStrategy::Combine);
+        assert_eq!(names(&files), [("/dev/null", 2)]);
+        assert_eq!(files[0].old_name, "a");
+        assert_eq!(files[0].is_deleted, Some(true));
+        assert_eq!(files[0].deleted_file_mode.as_deref(), Some("100644"));
+        assert_eq!(files[0].status(), 'D');
+        assert_eq!((files[0].added_lines, files[0].deleted_lines), (1, 3));
+    }
+
+    #[test]
+    fn test_merge_keep_last() {
+        let files = merge_diff_files(diffs(), MergeStrategy::KeepLast);
+        assert_eq!(names(&files), [("a", 1), ("b", 1), ("/dev/null", 1)]);
+        assert_eq!(files[0].blocks[0].header, "@@ -9 +9,2 @@");
+        assert_eq!(files[1].blocks[0].header, "@@ -5 +5 @@");
+    }
+
+    #[test]
+    fn test_merge_keep_all() {
+        let files = merge_diff_files(diffs(), MergeStrategy::KeepAll);
+        assert_eq!(files.len(), 5);
+        assert!(merge_diff_files(Vec::new(), MergeStrategy::Combine).is_empty());
+    }
+}
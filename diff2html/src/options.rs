@@ -0,0 +1,98 @@
+//! Configuration from JSON, for bindings to other languages.
+//!
+//! The options are named like the options of the JavaScript diff2html, the
+//! same for the C API of the `ffi` feature and the Python bindings.
+
+use serde::Deserialize;
+
+use crate::Diff2HtmlConfig;
+use crate::types::{ColorScheme, DiffStyle, GroupBy, LineMatchingType, OutputFormat, OutputMarkup};
+
+/// Options accepted in the configuration JSON, named like the options of the
+/// JavaScript diff2html. Options left out keep their default.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase", deny_unknown_fields)]
+struct JsonConfig {
+    src_prefix: Option<String>,
+    dst_prefix: Option<String>,
+    diff_max_changes: Option<u32>,
+    diff_max_line_length: Option<usize>,
+    diff_max_files: Option<usize>,
+    output_format: Option<OutputFormat>,
+    output_markup: Option<OutputMarkup>,
+    group_by: Option<GroupBy>,
+    draw_file_list: Option<bool>,
+    file_list_open: Option<bool>,
+    diff_style: Option<DiffStyle>,
+    color_scheme: Option<ColorScheme>,
+    no_wrapper: Option<bool>,
+    matching: Option<LineMatchingType>,
+    match_words_threshold: Option<f64>,
+    max_line_length_highlight: Option<usize>,
+    render_nothing_when_empty: Option<bool>,
+    matching_max_comparisons: Option<usize>,
+    max_line_size_in_block_for_comparison: Option<usize>,
+    deterministic: Option<bool>,
+    hardened: Option<bool>,
+}
+
+impl JsonConfig {
+    /// Apply the given options to the default configuration.
+    fn into_config(self) -> Diff2HtmlConfig {
+        let default = Diff2HtmlConfig::default();
+        Diff2HtmlConfig {
+            src_prefix: self.src_prefix.or(default.src_prefix),
+            dst_prefix: self.dst_prefix.or(default.dst_prefix),
+            diff_max_changes: self.diff_max_changes.or(default.diff_max_changes),
+            diff_max_line_length: self.diff_max_line_length.or(default.diff_max_line_length),
+            diff_max_files: self.diff_max_files.or(default.diff_max_files),
+            output_format: self.output_format.unwrap_or(default.output_format),
+            output_markup: self.output_markup.unwrap_or(default.output_markup),
+            group_by: self.group_by.unwrap_or(default.group_by),
+            draw_file_list: self.draw_file_list.unwrap_or(default.draw_file_list),
+            file_list_open: self.file_list_open.unwrap_or(default.file_list_open),
+            diff_style: self.diff_style.unwrap_or(default.diff_style),
+            color_scheme: self.color_scheme.unwrap_or(default.color_scheme),
+            no_wrapper: self.no_wrapper.unwrap_or(default.no_wrapper),
+            matching: self.matching.unwrap_or(default.matching),
+            match_words_threshold: self
+                .match_words_threshold
+                .unwrap_or(default.match_words_threshold),
+            max_line_length_highlight: self
+                .max_line_length_highlight
+                .unwrap_or(default.max_line_length_highlight),
+            render_nothing_when_empty: self
+                .render_nothing_when_empty
+                .unwrap_or(default.render_nothing_when_empty),
+            matching_max_comparisons: self
+                .matching_max_comparisons
+                .unwrap_or(default.matching_max_comparisons),
+            max_line_size_in_block_for_comparison: self
+                .max_line_size_in_block_for_comparison
+                .unwrap_or(default.max_line_size_in_block_for_comparison),
+            deterministic: self.deterministic.unwrap_or(default.deterministic),
+            hardened: self.hardened.unwrap_or(default.hardened),
+            ..default
+        }
+    }
+}
+
+/// Read a configuration from a JSON object of options, e.g.
+/// `{"outputFormat": "side-by-side"}`, for bindings to other languages.
+pub fn config_from_json(json: &str) -> Result<Diff2HtmlConfig, serde_json::Error> {
+    serde_json::from_str::<JsonConfig>(json).map(JsonConfig::into_config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_from_json() {
+        let config = config_from_json(r#"{"colorScheme": "dark", "diffMaxFiles": 3}"#).unwrap();
+        assert_eq!(config.color_scheme, ColorScheme::Dark);
+        assert_eq!(config.diff_max_files, Some(3));
+        assert!(config.draw_file_list);
+        assert!(config_from_json(r#"{"colorScheme": 1}"#).is_err());
+    }
+}
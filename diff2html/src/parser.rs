@@ -4,11 +4,156 @@
 //! - Unified: https://www.gnu.org/software/diffutils/manual/html_node/Unified-Format.html
 //! - Git Diff: https://git-scm.com/docs/git-diff-tree#_raw_output_format
 //! - Git Combined Diff: https://git-scm.com/docs/git-diff-tree#_combined_diff_format
+//! - Git Numstat: https://git-scm.com/docs/git-diff#_other_diff_formats
+//! - Git Word Diff: https://git-scm.com/docs/git-diff#Documentation/git-diff.txt---word-diffltmodegt
 
 use regex::Regex;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt;
 use std::sync::LazyLock;
+use thiserror::Error;
+
+use crate::defaults;
+use crate::types::{
+    Checksum, DiffBlock, DiffFile, DiffLine, FileMode, LineType, Message, RawHtml, WordDiffSegment,
+};
+
+/// How the parser handles malformed input.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ParseMode {
+    /// Recover from malformed input and record diagnostics.
+    #[default]
+    Lenient,
+    /// Fail on the first malformed hunk or inconsistent line count.
+    Strict,
+}
+
+/// How escape sequences in quoted file names are handled.
+///
+/// Git quotes paths containing special characters, e.g. `"a/caf\303\251.txt"`,
+/// using C-style escapes and octal escapes for the bytes of non-ASCII characters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FilenameDecoding {
+    /// Decode escape sequences, interpreting octal escapes as UTF-8 bytes.
+    #[default]
+    Decode,
+    /// Only remove the surrounding quotes and keep escape sequences as-is.
+    Keep,
+}
+
+/// Which path prefixes are stripped from file names.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PrefixMode {
+    /// Strip the standard git prefixes (`a/`, `b/`, `i/`, `w/`, `c/`, `o/`) as
+    /// well as the configured source and destination prefixes.
+    #[default]
+    Auto,
+    /// Only strip the configured source and destination prefixes, for diffs
+    /// made with `git diff --no-prefix` or custom `--src-prefix`/`--dst-prefix`.
+    Configured,
+}
+
+/// How timestamps after the file names of the `---` and `+++` lines are
+/// handled. Stripped timestamps are kept in [`DiffFile::old_timestamp`] and
+/// [`DiffFile::new_timestamp`].
+#[derive(Debug, Clone, Default)]
+pub enum TimestampHandling {
+    /// Strip the timestamps written by diff tools: ISO-like ones such as
+    /// `2016-10-25 11:37:14.000000000 +0200`, and after a tab, ctime-like
+    /// ones in any language such as `Tue Oct 25 11:37:14 2016` and Unix
+    /// epoch seconds such as `1477388234`.
+    #[default]
+    Always,
+    /// Keep anything after the file name as part of it.
+    Never,
+    /// Strip from the first match of a regex to the end of the file name.
+    Custom(Regex),
+}
 
-use crate::types::{Checksum, DiffBlock, DiffFile, DiffLine, FileMode, LineType};
+impl TimestampHandling {
+    /// Strip from the first match of a pattern, e.g. `\t.*` for anything
+    /// after a tab.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use diff2html::{DiffParserConfig, TimestampHandling, parse};
+    ///
+    /// let config = DiffParserConfig {
+    ///     timestamps: TimestampHandling::custom(r"\t\(.+\)$").unwrap(),
+    ///     ..Default::default()
+    /// };
+    /// let diff = "--- a.txt\t(revision 12)\n+++ a.txt\t(working copy)\n@@ -1 +1 @@\n-a\n+b\n";
+    /// let files = parse(diff, &config);
+    /// assert_eq!(files[0].old_name, "a.txt");
+    /// assert_eq!(files[0].old_timestamp.as_deref(), Some("(revision 12)"));
+    /// assert_eq!(files[0].new_timestamp.as_deref(), Some("(working copy)"));
+    /// ```
+    pub fn custom(pattern: &str) -> Result<Self, regex::Error> {
+        Ok(Self::Custom(Regex::new(pattern)?))
+    }
+
+    /// Split a file name into the name and the stripped timestamp, if any.
+    fn split(&self, name: &str) -> (String, Option<String>) {
+        let regex = match self {
+            Self::Always => &*TIMESTAMP_SUFFIX,
+            Self::Never => return (name.to_string(), None),
+            Self::Custom(regex) => regex,
+        };
+        match regex.find(name) {
+            Some(m) if m.start() > 0 => {
+                let timestamp = name[m.start()..].trim();
+                (
+                    name[..m.start()].to_string(),
+                    (!timestamp.is_empty()).then(|| timestamp.to_string()),
+                )
+            }
+            _ => (name.to_string(), None),
+        }
+    }
+}
+
+/// The kind of problem found in a malformed diff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// A hunk header starting with `@@` that could not be parsed.
+    MalformedHunkHeader,
+    /// A hunk whose lines do not match the counts declared in its header.
+    LineCountMismatch,
+}
+
+/// A problem found while parsing a diff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDiagnostic {
+    /// The 1-based line number in the diff input.
+    pub line: usize,
+    pub kind: DiagnosticKind,
+    pub message: String,
+}
+
+impl fmt::Display for ParseDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Parsed diff files together with the diagnostics recorded while parsing.
+#[derive(Debug, Clone, Default)]
+pub struct ParseResult {
+    pub files: Vec<DiffFile>,
+    pub diagnostics: Vec<ParseDiagnostic>,
+    /// Number of files left out because of the `diff_max_files` or
+    /// `diff_max_total_changes` limits.
+    pub omitted_files: usize,
+}
+
+/// Errors that can occur when parsing in [`ParseMode::Strict`].
+#[derive(Debug, Error)]
+pub enum ParseError {
+    #[error("Malformed diff at {0}")]
+    Malformed(ParseDiagnostic),
+}
 
 /// Configuration for the diff parser.
 #[derive(Default)]
@@ -21,8 +166,39 @@ pub struct DiffParserConfig {
     pub diff_max_changes: Option<u32>,
     /// Maximum line length before marking file as "too big".
     pub diff_max_line_length: Option<usize>,
-    /// Custom message for files that are too big.
-    pub diff_too_big_message: Option<Box<dyn Fn(usize) -> String + Send + Sync>>,
+    /// Maximum number of files to parse. Further files are left out and
+    /// counted in [`ParseResult::omitted_files`].
+    pub diff_max_files: Option<usize>,
+    /// Maximum number of changed lines over all files. The file exceeding the
+    /// limit is marked as "too big" and further files are left out.
+    pub diff_max_total_changes: Option<u32>,
+    /// Custom message for files that are too big, given the index of the
+    /// file. The message is the block header of the file, flagged with
+    /// `is_too_big_text` when it is plain text.
+    pub diff_too_big_message: Option<Box<dyn Fn(usize) -> Message + Send + Sync>>,
+    /// Keep the blocks of files that are too big instead of discarding them.
+    /// Such files are flagged with `is_collapsed`.
+    pub keep_too_big_content: bool,
+    /// How malformed input is handled by [`parse_with_diagnostics`].
+    pub mode: ParseMode,
+    /// How escape sequences in quoted file names are handled.
+    pub filename_decoding: FilenameDecoding,
+    /// Which path prefixes are stripped from file names.
+    pub prefix_mode: PrefixMode,
+    /// Languages of files, keyed by extension (`tf`), file name or path
+    /// (`Dockerfile`), or path glob (`scripts/*`, `**/*.tpl`).
+    ///
+    /// Exact file names and paths take precedence over globs, and globs over
+    /// extensions. Globs without a `/` are matched against the file name.
+    pub language_overrides: HashMap<String, String>,
+    /// Paths or globs of files to leave out, like `Cargo.lock`, `vendor/` or
+    /// `src/*.generated.rs`.
+    ///
+    /// Patterns without a `/` are matched against each directory and the file
+    /// name, and a matching directory excludes all the files under it.
+    pub exclude: Vec<String>,
+    /// How timestamps after the file names are handled.
+    pub timestamps: TimestampHandling,
 }
 
 impl std::fmt::Debug for DiffParserConfig {
@@ -32,10 +208,19 @@ impl std::fmt::Debug for DiffParserConfig {
             .field("dst_prefix", &self.dst_prefix)
             .field("diff_max_changes", &self.diff_max_changes)
             .field("diff_max_line_length", &self.diff_max_line_length)
+            .field("diff_max_files", &self.diff_max_files)
+            .field("diff_max_total_changes", &self.diff_max_total_changes)
             .field(
                 "diff_too_big_message",
                 &self.diff_too_big_message.as_ref().map(|_| "<fn>"),
             )
+            .field("keep_too_big_content", &self.keep_too_big_content)
+            .field("mode", &self.mode)
+            .field("filename_decoding", &self.filename_decoding)
+            .field("prefix_mode", &self.prefix_mode)
+            .field("language_overrides", &self.language_overrides)
+            .field("exclude", &self.exclude)
+            .field("timestamps", &self.timestamps)
             .finish()
     }
 }
@@ -48,12 +233,11 @@ static DELETED_FILE_MODE: LazyLock<Regex> =
 static NEW_FILE_MODE: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"^new file mode (\d{6})").unwrap());
 
-static COPY_FROM: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"^copy from "?(.+)"?"#).unwrap());
-static COPY_TO: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"^copy to "?(.+)"?"#).unwrap());
+static COPY_FROM: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^copy from (.+)").unwrap());
+static COPY_TO: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^copy to (.+)").unwrap());
 
-static RENAME_FROM: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r#"^rename from "?(.+)"?"#).unwrap());
-static RENAME_TO: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"^rename to "?(.+)"?"#).unwrap());
+static RENAME_FROM: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^rename from (.+)").unwrap());
+static RENAME_TO: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^rename to (.+)").unwrap());
 
 static SIMILARITY_INDEX: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"^similarity index (\d+)%").unwrap());
@@ -65,6 +249,8 @@ static INDEX: LazyLock<Regex> =
 static BINARY_FILES: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"^Binary files (.*) and (.*) differ").unwrap());
 static BINARY_DIFF: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^GIT binary patch").unwrap());
+static BINARY_PATCH_SIZE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(literal|delta) (\d+)$").unwrap());
 
 // Combined diff patterns
 static COMBINED_INDEX: LazyLock<Regex> =
@@ -76,9 +262,26 @@ static COMBINED_NEW_FILE: LazyLock<Regex> =
 static COMBINED_DELETED_FILE: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"^deleted file mode (\d{6}),(\d{6})").unwrap());
 
+// Recursive diff (diff -r) patterns
+static RECURSIVE_DIFF_START: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^diff (?:-\S+ )+(\S+) (\S+)$").unwrap());
+static ONLY_IN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^Only in (.+?): (.+)$").unwrap());
+
+// Summary output patterns (--raw and --numstat)
+static RAW_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^:(\d{6}) (\d{6}) ([\da-f]+)\.*\s+([\da-f]+)\.*\s+([ACDMRTUX])(\d*)\t([^\t]+)(?:\t([^\t]+))?$",
+    )
+    .unwrap()
+});
+static NUMSTAT_LINE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d+|-)\t(\d+|-)\t(.+)$").unwrap());
+static NUMSTAT_RENAME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(.*)\{(.*) => (.*)\}(.*)$").unwrap());
+
 // Hunk header patterns
 static HUNK_HEADER: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"^@@ -(\d+)(?:,\d+)? \+(\d+)(?:,\d+)? @@.*").unwrap());
+    LazyLock::new(|| Regex::new(r"^@@ -(\d+)(?:,(\d+))? \+(\d+)(?:,(\d+))? @@.*").unwrap());
 static COMBINED_HUNK_HEADER: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"^@@@ -(\d+)(?:,\d+)? -(\d+)(?:,\d+)? \+(\d+)(?:,\d+)? @@@.*").unwrap()
 });
@@ -98,7 +301,12 @@ static FILENAME_WITH_DST_PREFIX: LazyLock<Regex> =
 static FILENAME_NO_PREFIX: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r#"^"?(.+?)"?$"#).unwrap());
 static TIMESTAMP_SUFFIX: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(r"\s+\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2}(?:\.\d+)? [+-]\d{4}.*$").unwrap()
+    Regex::new(concat!(
+        r"(?:\s+\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2}(?:\.\d+)? [+-]\d{4}.*",
+        r"|\t\p{L}+\.? +\p{L}+\.? +\d{1,2} \d{1,2}:\d{2}:\d{2}(?:\.\d+)? \d{4}.*",
+        r"|\t\d+(?:\.\d+)?(?: [+-]\d{4})?)$",
+    ))
+    .unwrap()
 });
 
 /// Base prefixes used in diff file paths.
@@ -108,6 +316,9 @@ const BASE_DIFF_FILENAME_PREFIXES: &[&str] = &["a/", "b/", "i/", "w/", "c/", "o/
 const OLD_FILE_NAME_HEADER: &str = "--- ";
 const NEW_FILE_NAME_HEADER: &str = "+++ ";
 const HUNK_HEADER_PREFIX: &str = "@@";
+const NO_NEWLINE_MARKER_PREFIX: &str = "\\ ";
+/// Line ending marker in `--word-diff=porcelain` output.
+const WORD_DIFF_NEWLINE: &str = "~";
 
 /// Escapes special regex characters in a string.
 fn escape_for_regexp(s: &str) -> String {
@@ -126,7 +337,7 @@ fn escape_for_regexp(s: &str) -> String {
 }
 
 /// Gets file extension from filename.
-fn get_extension(filename: &str, language: &str) -> String {
+pub(crate) fn get_extension(filename: &str, language: &str) -> String {
     filename
         .rsplit('.')
         .next()
@@ -135,20 +346,276 @@ fn get_extension(filename: &str, language: &str) -> String {
         .to_string()
 }
 
+/// Gets the language of a file from the configured overrides.
+fn get_language_override<'a>(
+    path: &str,
+    overrides: &'a HashMap<String, String>,
+) -> Option<&'a str> {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    let extension = file_name.rsplit_once('.').map(|(_, extension)| extension);
+
+    overrides
+        .iter()
+        .filter_map(|(key, language)| {
+            let rank = if key == path || key == file_name {
+                3
+            } else if key.contains(['*', '?']) {
+                let target = if key.contains('/') { path } else { file_name };
+                if !glob_match(key.as_bytes(), target.as_bytes()) {
+                    return None;
+                }
+                2
+            } else if Some(key.as_str()) == extension {
+                1
+            } else {
+                return None;
+            };
+            // Longer keys are more specific, the key itself breaks ties
+            Some(((rank, key.len(), Reverse(key)), language.as_str()))
+        })
+        .max()
+        .map(|(_, language)| language)
+}
+
+/// Matches a path against a glob.
+///
+/// `*` matches any characters except `/`, `**` any characters and `?` a single
+/// character except `/`.
+fn glob_match(pattern: &[u8], path: &[u8]) -> bool {
+    match pattern {
+        [] => path.is_empty(),
+        [b'*', b'*', rest @ ..] => {
+            let rest = rest.strip_prefix(b"/").unwrap_or(rest);
+            (0..=path.len()).any(|i| glob_match(rest, &path[i..]))
+        }
+        [b'*', rest @ ..] => (0..=path.len())
+            .take_while(|&i| i == 0 || path[i - 1] != b'/')
+            .any(|i| glob_match(rest, &path[i..])),
+        [b'?', rest @ ..] => match path {
+            [c, path_rest @ ..] if *c != b'/' => {
+                // Skip the continuation bytes of a multi-byte character
+                let len = path_rest
+                    .iter()
+                    .take_while(|&&b| (b & 0xC0) == 0x80)
+                    .count();
+                glob_match(rest, &path_rest[len..])
+            }
+            _ => false,
+        },
+        [c, rest @ ..] => path.first() == Some(c) && glob_match(rest, &path[1..]),
+    }
+}
+
+/// Checks if a path matches one of the exclude patterns.
+fn is_excluded(path: &str, exclude: &[String]) -> bool {
+    exclude.iter().any(|pattern| path_matches(path, pattern))
+}
+
+/// Checks if a path matches a path or glob, like the exclude patterns.
+///
+/// Patterns without a `/` are matched against each directory and the file
+/// name, and a matching directory matches all the files under it.
+pub(crate) fn path_matches(path: &str, pattern: &str) -> bool {
+    let pattern = pattern.trim_end_matches('/');
+    if pattern.is_empty() {
+        return false;
+    }
+    if pattern.contains('/') {
+        // The path itself or any of its parent directories
+        path.match_indices('/')
+            .map(|(index, _)| &path[..index])
+            .chain([path])
+            .any(|prefix| glob_match(pattern.as_bytes(), prefix.as_bytes()))
+    } else {
+        path.split('/')
+            .any(|component| glob_match(pattern.as_bytes(), component.as_bytes()))
+    }
+}
+
+/// Sets the language of files with a configured override.
+fn apply_language_overrides(files: &mut [DiffFile], overrides: &HashMap<String, String>) {
+    if overrides.is_empty() {
+        return;
+    }
+    for file in files {
+        let path = if file.new_name == "/dev/null" || file.new_name.is_empty() {
+            &file.old_name
+        } else {
+            &file.new_name
+        };
+        if let Some(language) = get_language_override(path, overrides) {
+            file.language = language.to_string();
+        }
+    }
+}
+
 /// Checks if string starts with any of the given prefixes.
 fn starts_with_any(s: &str, prefixes: &[&str]) -> bool {
     prefixes.iter().any(|p| s.starts_with(p))
 }
 
+/// Parses a file name quoted by git at the start of a string.
+///
+/// Returns the unquoted file name and the length of the quoted part, or `None`
+/// if the string does not start with a complete quoted file name.
+fn parse_quoted_filename(s: &str, decoding: FilenameDecoding) -> Option<(String, usize)> {
+    let bytes = s.as_bytes();
+    if bytes.first() != Some(&b'"') {
+        return None;
+    }
+
+    let mut name = Vec::with_capacity(bytes.len());
+    let mut i = 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => return Some((String::from_utf8_lossy(&name).into_owned(), i + 1)),
+            b'\\' if i + 1 < bytes.len() => {
+                let escape = bytes[i + 1];
+                if decoding == FilenameDecoding::Keep {
+                    name.extend_from_slice(&[b'\\', escape]);
+                    i += 2;
+                    continue;
+                }
+                if (b'0'..=b'7').contains(&escape) {
+                    // Octal escape of up to three digits, e.g. \303
+                    let digits = bytes[i + 1..]
+                        .iter()
+                        .take(3)
+                        .take_while(|b| (b'0'..=b'7').contains(b))
+                        .count();
+                    let value = s[i + 1..i + 1 + digits]
+                        .bytes()
+                        .fold(0u32, |acc, b| acc * 8 + u32::from(b - b'0'));
+                    name.push(value as u8);
+                    i += 1 + digits;
+                    continue;
+                }
+                name.push(match escape {
+                    b'a' => 0x07,
+                    b'b' => 0x08,
+                    b't' => b'\t',
+                    b'n' => b'\n',
+                    b'v' => 0x0b,
+                    b'f' => 0x0c,
+                    b'r' => b'\r',
+                    other => other,
+                });
+                i += 2;
+            }
+            byte => {
+                name.push(byte);
+                i += 1;
+            }
+        }
+    }
+    None
+}
+
+/// Removes the quotes around a file name quoted by git and handles its escape
+/// sequences. File names that are not quoted are returned unchanged.
+fn unquote_filename(name: &str, decoding: FilenameDecoding) -> String {
+    match parse_quoted_filename(name, decoding) {
+        Some((unquoted, len)) if len == name.len() => unquoted,
+        _ => name.to_string(),
+    }
+}
+
+/// Splits the paths of a `diff --git` header when at least one of them is
+/// quoted. Returns `None` for unquoted paths, which are handled by
+/// [`GIT_DIFF_START`].
+fn split_quoted_git_diff_paths(
+    paths: &str,
+    decoding: FilenameDecoding,
+) -> Option<(String, String)> {
+    if let Some((old_name, len)) = parse_quoted_filename(paths, decoding) {
+        let new_name = paths[len..].strip_prefix(' ')?;
+        return Some((old_name, unquote_filename(new_name, decoding)));
+    }
+
+    // An unquoted path cannot contain quotes, so the quoted one starts at the first quote
+    let index = paths.find(" \"")?;
+    let (new_name, len) = parse_quoted_filename(&paths[index + 1..], decoding)?;
+    (index + 1 + len == paths.len()).then(|| (paths[..index].to_string(), new_name))
+}
+
+/// Splits the unquoted paths of a `diff --git` header that does not use the
+/// standard prefixes.
+///
+/// The paths are split before the configured destination prefix if there is
+/// one, otherwise in the middle when both paths are the same (no rename).
+fn split_git_diff_paths(paths: &str, config: &DiffParserConfig) -> Option<(String, String)> {
+    let index = match config.dst_prefix.as_deref().filter(|p| !p.is_empty()) {
+        Some(dst_prefix) => paths
+            .match_indices(&format!(" {}", dst_prefix))
+            .map(|(index, _)| index)
+            .find(|&index| {
+                config
+                    .src_prefix
+                    .as_deref()
+                    .is_none_or(|src_prefix| paths[..index].starts_with(src_prefix))
+            })?,
+        None => {
+            let index = paths.len() / 2;
+            let (old_name, new_name) = (paths.get(..index)?, paths.get(index + 1..)?);
+            (paths.as_bytes().get(index) == Some(&b' ') && old_name == new_name).then_some(index)?
+        }
+    };
+    Some((paths[..index].to_string(), paths[index + 1..].to_string()))
+}
+
+/// Removes a known path prefix (e.g. `a/`) from a file name.
+fn strip_filename_prefix(filename: String, extra_prefix: Option<&str>, mode: PrefixMode) -> String {
+    let base_prefixes = match mode {
+        PrefixMode::Auto => BASE_DIFF_FILENAME_PREFIXES,
+        PrefixMode::Configured => &[],
+    };
+    base_prefixes
+        .iter()
+        .copied()
+        .chain(extra_prefix.filter(|p| !p.is_empty()))
+        .find(|p| filename.starts_with(*p))
+        .map(|p| filename[p.len()..].to_string())
+        .unwrap_or(filename)
+}
+
 /// Extracts filename from a diff line, removing prefixes and timestamps.
-fn get_filename(line: &str, line_prefix: Option<&str>, extra_prefix: Option<&str>) -> String {
-    let prefixes: Vec<&str> = if let Some(extra) = extra_prefix {
-        let mut p: Vec<&str> = BASE_DIFF_FILENAME_PREFIXES.to_vec();
-        p.push(extra);
-        p
-    } else {
-        BASE_DIFF_FILENAME_PREFIXES.to_vec()
+fn get_filename(
+    line: &str,
+    line_prefix: Option<&str>,
+    extra_prefix: Option<&str>,
+    config: &DiffParserConfig,
+) -> String {
+    get_filename_and_timestamp(line, line_prefix, extra_prefix, config).0
+}
+
+/// Extracts filename from a diff line, removing prefixes, and the timestamp
+/// stripped from it.
+fn get_filename_and_timestamp(
+    line: &str,
+    line_prefix: Option<&str>,
+    extra_prefix: Option<&str>,
+    config: &DiffParserConfig,
+) -> (String, Option<String>) {
+    // Quoted file names end at the closing quote, anything after it (such as a
+    // timestamp) is not part of the name
+    let value = match line_prefix {
+        Some(prefix) => line
+            .strip_prefix(prefix)
+            .and_then(|rest| rest.strip_prefix(' ')),
+        None => Some(line),
     };
+    if let Some((value, (filename, end))) = value
+        .and_then(|v| parse_quoted_filename(v, config.filename_decoding).map(|quoted| (v, quoted)))
+    {
+        let rest = value[end..].trim();
+        let timestamp = (!matches!(config.timestamps, TimestampHandling::Never)
+            && !rest.is_empty())
+        .then(|| rest.to_string());
+        return (
+            strip_filename_prefix(filename, extra_prefix, config.prefix_mode),
+            timestamp,
+        );
+    }
 
     let filename = match line_prefix {
         Some("---") => FILENAME_WITH_SRC_PREFIX
@@ -177,27 +644,20 @@ fn get_filename(line: &str, line_prefix: Option<&str>, extra_prefix: Option<&str
             .unwrap_or_default(),
     };
 
-    // Remove matching prefix
-    let fname_without_prefix = prefixes
-        .iter()
-        .find(|p| filename.starts_with(*p))
-        .map(|p| filename[p.len()..].to_string())
-        .unwrap_or(filename);
+    let fname_without_prefix = strip_filename_prefix(filename, extra_prefix, config.prefix_mode);
 
     // Remove timestamp suffix (e.g., "2016-10-25 11:37:14.000000000 +0200")
-    TIMESTAMP_SUFFIX
-        .replace(&fname_without_prefix, "")
-        .to_string()
+    config.timestamps.split(&fname_without_prefix)
 }
 
-/// Gets source filename from a "--- " line.
-fn get_src_filename(line: &str, src_prefix: Option<&str>) -> String {
-    get_filename(line, Some("---"), src_prefix)
+/// Gets source filename and timestamp from a "--- " line.
+fn get_src_filename(line: &str, config: &DiffParserConfig) -> (String, Option<String>) {
+    get_filename_and_timestamp(line, Some("---"), config.src_prefix.as_deref(), config)
 }
 
-/// Gets destination filename from a "+++ " line.
-fn get_dst_filename(line: &str, dst_prefix: Option<&str>) -> String {
-    get_filename(line, Some("+++"), dst_prefix)
+/// Gets destination filename and timestamp from a "+++ " line.
+fn get_dst_filename(line: &str, config: &DiffParserConfig) -> (String, Option<String>) {
+    get_filename_and_timestamp(line, Some("+++"), config.dst_prefix.as_deref(), config)
 }
 
 /// Parser state for tracking current file and block.
@@ -210,10 +670,42 @@ struct ParserState {
     new_line: Option<u32>,
     possible_old_name: Option<String>,
     possible_new_name: Option<String>,
+    /// Whether the current block is in `--word-diff=porcelain` format.
+    word_diff: bool,
+    /// Segments of the `--word-diff=porcelain` line being parsed.
+    word_segments: Vec<String>,
+    /// Old and new root directories of a recursive `diff -r`.
+    diff_roots: Option<(String, String)>,
+    /// Files from `Only in <dir>: <file>` lines, with their directory.
+    only_in: Vec<(usize, String)>,
+    /// Problems found in the input so far.
+    diagnostics: Vec<ParseDiagnostic>,
+    /// The 1-based number of the line being parsed.
+    line_number: usize,
+    /// Line number of the current block header.
+    block_line_number: usize,
+    /// End of the source lines of the current file and block: the index after
+    /// the last non-empty line before the line being parsed.
+    source_end: usize,
+    /// Maximum number of files to keep, from the parser configuration.
+    max_files: Option<usize>,
+    /// Patterns of the files to leave out, from the parser configuration.
+    exclude: Vec<String>,
+    /// Number of changed lines in the saved files.
+    total_changes: u32,
+    /// Whether the total changes limit was exceeded.
+    total_changes_exceeded: bool,
+    /// Whether the current file is left out because of the file limits.
+    omitting: bool,
+    /// Number of files left out because of the file limits.
+    omitted_files: usize,
+    /// Number of `literal` or `delta` records seen in the current
+    /// `GIT binary patch`.
+    binary_patches: usize,
 }
 
 impl ParserState {
-    fn new() -> Self {
+    fn new(config: &DiffParserConfig) -> Self {
         Self {
             files: Vec::new(),
             current_file: None,
@@ -223,19 +715,94 @@ impl ParserState {
             new_line: None,
             possible_old_name: None,
             possible_new_name: None,
+            word_diff: false,
+            word_segments: Vec::new(),
+            diff_roots: None,
+            only_in: Vec::new(),
+            diagnostics: Vec::new(),
+            line_number: 0,
+            block_line_number: 0,
+            source_end: 0,
+            max_files: config.diff_max_files,
+            exclude: config.exclude.clone(),
+            total_changes: 0,
+            total_changes_exceeded: false,
+            omitting: false,
+            omitted_files: 0,
+            binary_patches: 0,
+        }
+    }
+
+    /// Checks if a file is left out because of the exclude patterns.
+    fn is_excluded(&self, file: &DiffFile) -> bool {
+        [&file.old_name, &file.new_name]
+            .into_iter()
+            .any(|name| name != "/dev/null" && is_excluded(name, &self.exclude))
+    }
+
+    /// Checks if further files are left out because of the file limits.
+    fn file_limit_reached(&self) -> bool {
+        self.total_changes_exceeded || self.max_files.is_some_and(|max| self.files.len() >= max)
+    }
+
+    /// Records a problem found at the given line.
+    fn add_diagnostic(&mut self, line: usize, kind: DiagnosticKind, message: String) {
+        self.diagnostics.push(ParseDiagnostic {
+            line,
+            kind,
+            message,
+        });
+    }
+
+    /// Checks the lines of the current block against the counts declared in
+    /// its hunk header, reporting a mismatch as a diagnostic.
+    fn check_line_counts(&mut self) {
+        let Some(block) = &self.current_block else {
+            return;
+        };
+        let Some((old_count, new_count)) = block.old_lines.zip(block.new_lines) else {
+            return;
+        };
+
+        let old_lines = self
+            .old_line
+            .unwrap_or(0)
+            .saturating_sub(block.old_start_line);
+        let new_lines = self
+            .new_line
+            .unwrap_or(0)
+            .saturating_sub(block.new_start_line);
+        if old_lines != old_count || new_lines != new_count {
+            self.add_diagnostic(
+                self.block_line_number,
+                DiagnosticKind::LineCountMismatch,
+                format!(
+                    "hunk header declares {} old and {} new lines, found {} and {}",
+                    old_count, new_count, old_lines, new_lines
+                ),
+            );
         }
     }
 
     /// Saves current block to current file.
     fn save_block(&mut self) {
-        if let (Some(block), Some(file)) = (self.current_block.take(), &mut self.current_file) {
+        if !self.word_segments.is_empty() {
+            self.finish_word_line();
+        }
+        self.check_line_counts();
+        if let (Some(mut block), Some(file)) = (self.current_block.take(), &mut self.current_file) {
+            if let Some(lines) = &mut block.source_lines {
+                lines.end = self.source_end;
+            }
             file.blocks.push(block);
         }
     }
 
     /// Saves current file to files list.
     fn save_file(&mut self) {
-        if let Some(mut file) = self.current_file.take() {
+        if let Some(mut file) = self.current_file.take()
+            && !self.omitting
+        {
             if file.old_name.is_empty()
                 && let Some(name) = self.possible_old_name.take()
             {
@@ -246,7 +813,14 @@ impl ParserState {
             {
                 file.new_name = name;
             }
-            if !file.new_name.is_empty() {
+            if file.blocks.is_empty() && file.old_mode.is_some() && file.new_mode.is_some() {
+                file.is_mode_change = Some(true);
+            }
+            if let Some(lines) = &mut file.source_lines {
+                lines.end = self.source_end;
+            }
+            if !file.new_name.is_empty() && !self.is_excluded(&file) {
+                self.total_changes += file.added_lines + file.deleted_lines;
                 self.files.push(file);
             }
         }
@@ -254,23 +828,90 @@ impl ParserState {
         self.possible_new_name = None;
     }
 
+    /// Adds a file from an `Only in <dir>: <file>` line of a recursive diff.
+    ///
+    /// Whether the file was added or deleted depends on the root directories,
+    /// which may only be known later, so it is resolved by [`Self::resolve_only_in`].
+    fn add_only_in(&mut self, dir: &str, name: &str) {
+        self.save_block();
+        self.save_file();
+        self.omitting = false;
+        if self.file_limit_reached() {
+            self.omitted_files += 1;
+            return;
+        }
+
+        let path = format!("{}/{}", dir.trim_end_matches('/'), name);
+        if is_excluded(&path, &self.exclude) {
+            return;
+        }
+        self.only_in.push((self.files.len(), dir.to_string()));
+        self.files.push(DiffFile {
+            language: get_extension(&path, ""),
+            old_name: path.clone(),
+            new_name: path,
+            source_lines: Some(self.line_number - 1..self.line_number),
+            ..Default::default()
+        });
+    }
+
+    /// Marks the files from `Only in` lines as added or deleted.
+    ///
+    /// Files in the old root directory are deleted. All other files are
+    /// considered added, including when the root directories are unknown.
+    fn resolve_only_in(&mut self) {
+        for (index, dir) in std::mem::take(&mut self.only_in) {
+            let is_deleted = self
+                .diff_roots
+                .as_ref()
+                .is_some_and(|(old_root, _)| is_in_directory(&dir, old_root));
+            let file = &mut self.files[index];
+            if is_deleted {
+                file.new_name = "/dev/null".to_string();
+                file.is_deleted = Some(true);
+            } else {
+                file.old_name = "/dev/null".to_string();
+                file.is_new = Some(true);
+            }
+        }
+    }
+
     /// Starts a new file.
+    ///
+    /// Once the file limits are reached, the new file is only counted and its
+    /// contents are skipped.
     fn start_file(&mut self) {
         self.save_block();
         self.save_file();
-        self.current_file = Some(DiffFile::default());
+        self.omitting = self.file_limit_reached();
+        if self.omitting {
+            self.omitted_files += 1;
+        }
+        self.current_file = Some(DiffFile {
+            source_lines: Some(self.line_number - 1..self.line_number),
+            ..Default::default()
+        });
     }
 
     /// Starts a new block (hunk).
     fn start_block(&mut self, line: &str) {
         self.save_block();
+        self.block_line_number = self.line_number;
+        let mut line_counts = None;
 
         if let Some(file) = &mut self.current_file {
             if let Some(caps) = HUNK_HEADER.captures(line) {
                 file.is_combined = false;
                 self.old_line = caps.get(1).and_then(|m| m.as_str().parse().ok());
-                self.new_line = caps.get(2).and_then(|m| m.as_str().parse().ok());
+                self.new_line = caps.get(3).and_then(|m| m.as_str().parse().ok());
                 self.old_line2 = None;
+
+                // A missing count means a single line
+                let count = |index| {
+                    caps.get(index)
+                        .map_or(Some(1), |m: regex::Match| m.as_str().parse().ok())
+                };
+                line_counts = count(2).zip(count(4));
             } else if let Some(caps) = COMBINED_HUNK_HEADER.captures(line) {
                 file.is_combined = true;
                 self.old_line = caps.get(1).and_then(|m| m.as_str().parse().ok());
@@ -278,7 +919,11 @@ impl ParserState {
                 self.new_line = caps.get(3).and_then(|m| m.as_str().parse().ok());
             } else {
                 if line.starts_with(HUNK_HEADER_PREFIX) {
-                    eprintln!("Failed to parse hunk header, starting at 0!");
+                    self.diagnostics.push(ParseDiagnostic {
+                        line: self.line_number,
+                        kind: DiagnosticKind::MalformedHunkHeader,
+                        message: format!("failed to parse hunk header {:?}, starting at 0", line),
+                    });
                 }
                 self.old_line = Some(0);
                 self.new_line = Some(0);
@@ -287,15 +932,30 @@ impl ParserState {
             }
         }
 
+        self.word_diff = false;
         self.current_block = Some(DiffBlock {
             lines: Vec::new(),
             old_start_line: self.old_line.unwrap_or(0),
             old_start_line2: self.old_line2,
             new_start_line: self.new_line.unwrap_or(0),
+            old_lines: line_counts.map(|(old, _)| old),
+            new_lines: line_counts.map(|(_, new)| new),
             header: line.to_string(),
+            source_lines: Some(self.line_number - 1..self.line_number),
         });
     }
 
+    /// Marks the last line of the current block as missing a trailing newline.
+    fn mark_no_newline_at_eof(&mut self) {
+        if let Some(line) = self
+            .current_block
+            .as_mut()
+            .and_then(|block| block.lines.last_mut())
+        {
+            line.no_newline_at_eof = true;
+        }
+    }
+
     /// Creates a diff line from a source line.
     fn create_line(&mut self, line: &str) {
         let (Some(file), Some(block), Some(old_line), Some(new_line)) = (
@@ -325,6 +985,8 @@ impl ParserState {
                 content: line.to_string(),
                 old_number: None,
                 new_number: Some(*new_line),
+                no_newline_at_eof: false,
+                word_diff: None,
             };
             *new_line += 1;
             ln
@@ -335,6 +997,8 @@ impl ParserState {
                 content: line.to_string(),
                 old_number: Some(*old_line),
                 new_number: None,
+                no_newline_at_eof: false,
+                word_diff: None,
             };
             *old_line += 1;
             ln
@@ -344,6 +1008,8 @@ impl ParserState {
                 content: line.to_string(),
                 old_number: Some(*old_line),
                 new_number: Some(*new_line),
+                no_newline_at_eof: false,
+                word_diff: None,
             };
             *old_line += 1;
             *new_line += 1;
@@ -352,6 +1018,157 @@ impl ParserState {
 
         block.lines.push(diff_line);
     }
+
+    /// Completes a `--word-diff=porcelain` line from the buffered segments.
+    ///
+    /// A line without added or removed segments becomes a context line.
+    /// Otherwise the old line is built from the context and removed segments
+    /// and the new line from the context and added segments.
+    fn finish_word_line(&mut self) {
+        let segments = std::mem::take(&mut self.word_segments);
+        let (Some(file), Some(block), Some(old_line), Some(new_line)) = (
+            &mut self.current_file,
+            &mut self.current_block,
+            &mut self.old_line,
+            &mut self.new_line,
+        ) else {
+            return;
+        };
+
+        let has_removed = segments.iter().any(|s| s.starts_with('-'));
+        let has_added = segments.iter().any(|s| s.starts_with('+'));
+        let has_context = segments.iter().any(|s| s.starts_with(' ') && s.len() > 1);
+
+        if !has_removed && !has_added {
+            let text: String = segments.iter().map(|s| &s[1..]).collect();
+            block.lines.push(DiffLine {
+                line_type: LineType::Context,
+                content: format!(" {}", text),
+                old_number: Some(*old_line),
+                new_number: Some(*new_line),
+                no_newline_at_eof: false,
+                word_diff: None,
+            });
+            *old_line += 1;
+            *new_line += 1;
+            return;
+        }
+
+        // Keep the segments of one side, marking removed or added words as changed
+        let side_segments = |excluded: char| -> Vec<WordDiffSegment> {
+            segments
+                .iter()
+                .filter(|s| !s.starts_with(excluded))
+                .map(|s| WordDiffSegment {
+                    changed: !s.starts_with(' '),
+                    text: s[1..].to_string(),
+                })
+                .collect()
+        };
+
+        if has_removed || has_context {
+            let old_segments = side_segments('+');
+            let text: String = old_segments.iter().map(|s| s.text.as_str()).collect();
+            file.deleted_lines += 1;
+            block.lines.push(DiffLine {
+                line_type: LineType::Delete,
+                content: format!("-{}", text),
+                old_number: Some(*old_line),
+                new_number: None,
+                no_newline_at_eof: false,
+                word_diff: Some(old_segments),
+            });
+            *old_line += 1;
+        }
+
+        if has_added || has_context {
+            let new_segments = side_segments('-');
+            let text: String = new_segments.iter().map(|s| s.text.as_str()).collect();
+            file.added_lines += 1;
+            block.lines.push(DiffLine {
+                line_type: LineType::Insert,
+                content: format!("+{}", text),
+                old_number: None,
+                new_number: Some(*new_line),
+                no_newline_at_eof: false,
+                word_diff: Some(new_segments),
+            });
+            *new_line += 1;
+        }
+    }
+}
+
+/// Gets the old and new root directories of a recursive diff from the paths
+/// of a compared file, by removing their common trailing path components.
+fn get_diff_roots(old_path: &str, new_path: &str) -> Option<(String, String)> {
+    let old_parts: Vec<&str> = old_path.split('/').collect();
+    let new_parts: Vec<&str> = new_path.split('/').collect();
+    let common = old_parts
+        .iter()
+        .rev()
+        .zip(new_parts.iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+
+    let old_root = old_parts[..old_parts.len() - common].join("/");
+    let new_root = new_parts[..new_parts.len() - common].join("/");
+    (!old_root.is_empty() && !new_root.is_empty()).then_some((old_root, new_root))
+}
+
+/// Checks if a directory is the given root directory or inside it.
+fn is_in_directory(dir: &str, root: &str) -> bool {
+    let dir = dir.trim_end_matches('/');
+    let root = root.trim_end_matches('/');
+    dir == root
+        || dir
+            .strip_prefix(root)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Checks if the hunk starting at the given line is in `--word-diff=porcelain`
+/// format, i.e. it contains line ending markers.
+fn is_word_diff_hunk(lines: &[&str], start_idx: usize) -> bool {
+    lines
+        .iter()
+        .skip(start_idx)
+        .take_while(|line| !line.starts_with(HUNK_HEADER_PREFIX) && !line.starts_with("diff "))
+        .any(|line| *line == WORD_DIFF_NEWLINE)
+}
+
+/// Iterator over the lines of a diff, ending lines at `\n`, `\r\n` or `\r`.
+///
+/// Like [`str::split`], a final empty line is returned when the input ends with
+/// a line ending. The lines borrow from the input, so mixed line endings are
+/// handled without normalizing a copy of it.
+struct DiffLines<'a> {
+    remaining: Option<&'a str>,
+}
+
+impl<'a> DiffLines<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            remaining: Some(input),
+        }
+    }
+}
+
+impl<'a> Iterator for DiffLines<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let rest = self.remaining?;
+        let Some(end) = rest.find(['\r', '\n']) else {
+            self.remaining = None;
+            return Some(rest);
+        };
+        let ending_len = if rest[end..].starts_with("\r\n") {
+            2
+        } else {
+            1
+        };
+        self.remaining = Some(&rest[end + ending_len..]);
+        Some(&rest[..end])
+    }
 }
 
 /// Checks if there's a hunk header before the next file starts.
@@ -373,22 +1190,65 @@ fn exist_hunk_header(lines: &[&str], start_idx: usize) -> bool {
 }
 
 /// Parses a unified diff string into a list of DiffFile structures.
+///
+/// Malformed input is always recovered from. Use [`parse_with_diagnostics`]
+/// to find out about problems in the input.
 pub fn parse(diff_input: &str, config: &DiffParserConfig) -> Vec<DiffFile> {
-    let mut state = ParserState::new();
+    parse_lenient(diff_input, config).files
+}
+
+/// Parses a unified diff string, reporting problems in malformed input.
+///
+/// In [`ParseMode::Lenient`] the parser recovers and the problems are returned
+/// as diagnostics. In [`ParseMode::Strict`] the first problem is returned as
+/// an error.
+///
+/// # Example
+///
+/// ```
+/// use diff2html::{DiffParserConfig, ParseMode, parse_with_diagnostics};
+///
+/// let diff = "--- a/f\n+++ b/f\n@@ -1,2 +1,2 @@\n-old\n+new\n";
+/// let result = parse_with_diagnostics(diff, &DiffParserConfig::default()).unwrap();
+/// assert_eq!(result.files.len(), 1);
+/// assert_eq!(result.diagnostics[0].line, 3);
+///
+/// let config = DiffParserConfig {
+///     mode: ParseMode::Strict,
+///     ..Default::default()
+/// };
+/// assert!(parse_with_diagnostics(diff, &config).is_err());
+/// ```
+pub fn parse_with_diagnostics(
+    diff_input: &str,
+    config: &DiffParserConfig,
+) -> Result<ParseResult, ParseError> {
+    let mut result = parse_lenient(diff_input, config);
+    if config.mode == ParseMode::Strict && !result.diagnostics.is_empty() {
+        return Err(ParseError::Malformed(result.diagnostics.swap_remove(0)));
+    }
+    Ok(result)
+}
 
-    // Normalize line endings and remove "No newline at end of file" markers
-    let normalized = diff_input
-        .replace("\\ No newline at end of file", "")
-        .replace("\r\n", "\n")
-        .replace('\r', "\n");
+/// Parses a unified diff string, recovering from malformed input.
+pub(crate) fn parse_lenient(diff_input: &str, config: &DiffParserConfig) -> ParseResult {
+    #[cfg(feature = "log")]
+    let start = std::time::Instant::now();
+    let mut state = ParserState::new(config);
+    let decoding = config.filename_decoding;
 
-    let diff_lines: Vec<&str> = normalized.split('\n').collect();
+    let diff_lines: Vec<&str> = DiffLines::new(diff_input).collect();
+    let mut last_content_end = 0;
 
     for (line_index, line) in diff_lines.iter().enumerate() {
+        state.line_number = line_index + 1;
+
         // Skip empty lines and unmerged paths markers
         if line.is_empty() || line.starts_with('*') {
             continue;
         }
+        state.source_end = last_content_end;
+        last_content_end = line_index + 1;
 
         let prev_line = if line_index > 0 {
             Some(diff_lines[line_index - 1])
@@ -402,13 +1262,35 @@ pub fn parse(diff_input: &str, config: &DiffParserConfig) -> Vec<DiffFile> {
         if line.starts_with("diff --git") || line.starts_with("diff --combined") {
             state.start_file();
 
-            if let Some(caps) = GIT_DIFF_START.captures(line) {
+            let paths = line.strip_prefix("diff --git ");
+            let split_paths = paths
+                .and_then(|paths| split_quoted_git_diff_paths(paths, decoding))
+                .or_else(|| {
+                    paths
+                        .filter(|_| {
+                            config.prefix_mode == PrefixMode::Configured
+                                || !GIT_DIFF_START.is_match(line)
+                        })
+                        .and_then(|paths| split_git_diff_paths(paths, config))
+                });
+            if let Some((old_name, new_name)) = split_paths {
+                state.possible_old_name = Some(strip_filename_prefix(
+                    old_name,
+                    config.src_prefix.as_deref(),
+                    config.prefix_mode,
+                ));
+                state.possible_new_name = Some(strip_filename_prefix(
+                    new_name,
+                    config.dst_prefix.as_deref(),
+                    config.prefix_mode,
+                ));
+            } else if let Some(caps) = GIT_DIFF_START.captures(line) {
                 state.possible_old_name = caps
                     .get(1)
-                    .map(|m| get_filename(m.as_str(), None, config.dst_prefix.as_deref()));
+                    .map(|m| get_filename(m.as_str(), None, config.dst_prefix.as_deref(), config));
                 state.possible_new_name = caps
                     .get(2)
-                    .map(|m| get_filename(m.as_str(), None, config.src_prefix.as_deref()));
+                    .map(|m| get_filename(m.as_str(), None, config.src_prefix.as_deref(), config));
             }
 
             if let Some(file) = &mut state.current_file {
@@ -429,10 +1311,10 @@ pub fn parse(diff_input: &str, config: &DiffParserConfig) -> Vec<DiffFile> {
             if let Some(caps) = UNIX_DIFF_BINARY_START.captures(line) {
                 state.possible_old_name = caps
                     .get(1)
-                    .map(|m| get_filename(m.as_str(), None, config.dst_prefix.as_deref()));
+                    .map(|m| get_filename(m.as_str(), None, config.dst_prefix.as_deref(), config));
                 state.possible_new_name = caps
                     .get(2)
-                    .map(|m| get_filename(m.as_str(), None, config.src_prefix.as_deref()));
+                    .map(|m| get_filename(m.as_str(), None, config.src_prefix.as_deref(), config));
             }
             if let Some(file) = &mut state.current_file {
                 file.is_binary = Some(true);
@@ -440,6 +1322,22 @@ pub fn parse(diff_input: &str, config: &DiffParserConfig) -> Vec<DiffFile> {
             continue;
         }
 
+        // Handle recursive diff (diff -r) file headers and "Only in" lines
+        let in_git_diff = state.current_file.as_ref().is_some_and(|f| f.is_git_diff);
+        if !in_git_diff
+            && state.current_block.is_none()
+            && let Some(caps) = RECURSIVE_DIFF_START.captures(line)
+        {
+            if state.diff_roots.is_none() {
+                state.diff_roots = get_diff_roots(&caps[1], &caps[2]);
+            }
+            continue;
+        }
+        if !in_git_diff && let Some(caps) = ONLY_IN.captures(line) {
+            state.add_only_in(&caps[1], &caps[2]);
+            continue;
+        }
+
         // Start new file if needed for non-git diff
         let should_start_file = state.current_file.is_none()
             || (state
@@ -455,38 +1353,62 @@ pub fn parse(diff_input: &str, config: &DiffParserConfig) -> Vec<DiffFile> {
             state.start_file();
         }
 
+        // Skip files beyond the file limits
+        if state.omitting {
+            continue;
+        }
+
         // Skip if file is marked as too big
-        if state
-            .current_file
-            .as_ref()
-            .is_some_and(|f| f.is_too_big == Some(true))
+        if !config.keep_too_big_content
+            && state
+                .current_file
+                .as_ref()
+                .is_some_and(|f| f.is_too_big == Some(true))
         {
             continue;
         }
 
         // Check for too big threshold
-        if let Some(file) = &mut state.current_file {
+        if let Some(file) = &mut state.current_file
+            && file.is_too_big != Some(true)
+        {
             let too_many_changes = config
                 .diff_max_changes
                 .is_some_and(|max| file.added_lines + file.deleted_lines > max);
             let line_too_long = config
                 .diff_max_line_length
                 .is_some_and(|max| line.len() > max);
+            let too_many_total_changes = config.diff_max_total_changes.is_some_and(|max| {
+                state.total_changes + file.added_lines + file.deleted_lines > max
+            });
 
-            if too_many_changes || line_too_long {
+            if too_many_changes || line_too_long || too_many_total_changes {
+                state.total_changes_exceeded |= too_many_total_changes;
                 file.is_too_big = Some(true);
-                file.added_lines = 0;
-                file.deleted_lines = 0;
-                file.blocks.clear();
-                state.current_block = None;
-
-                let message = config
-                    .diff_too_big_message
-                    .as_ref()
-                    .map(|f| f(state.files.len()))
-                    .unwrap_or_else(|| "Diff too big to be displayed".to_string());
-                state.start_block(&message);
-                continue;
+                if config.keep_too_big_content {
+                    file.is_collapsed = Some(true);
+                } else {
+                    file.added_lines = 0;
+                    file.deleted_lines = 0;
+                    file.blocks.clear();
+                    state.current_block = None;
+
+                    let message = config
+                        .diff_too_big_message
+                        .as_ref()
+                        .map(|f| f(state.files.len()))
+                        .unwrap_or_else(|| defaults::DIFF_TOO_BIG_MESSAGE.into());
+                    // The message is escaped when rendered, if it is text
+                    let header = match message {
+                        Message::Text(text) => {
+                            file.is_too_big_text = Some(true);
+                            text
+                        }
+                        Message::Html(RawHtml(html)) => html,
+                    };
+                    state.start_block(&header);
+                    continue;
+                }
             }
         }
 
@@ -500,16 +1422,18 @@ pub fn parse(diff_input: &str, config: &DiffParserConfig) -> Vec<DiffFile> {
             && let Some(file) = &mut state.current_file
         {
             if file.old_name.is_empty() && line.starts_with("--- ") {
-                let name = get_src_filename(line, config.src_prefix.as_deref());
+                let (name, timestamp) = get_src_filename(line, config);
                 file.language = get_extension(&name, &file.language);
                 file.old_name = name;
+                file.old_timestamp = timestamp;
                 continue;
             }
 
             if file.new_name.is_empty() && line.starts_with("+++ ") {
-                let name = get_dst_filename(line, config.dst_prefix.as_deref());
+                let (name, timestamp) = get_dst_filename(line, config);
                 file.language = get_extension(&name, &file.language);
                 file.new_name = name;
+                file.new_timestamp = timestamp;
                 continue;
             }
         }
@@ -524,6 +1448,25 @@ pub fn parse(diff_input: &str, config: &DiffParserConfig) -> Vec<DiffFile> {
 
             if is_hunk_header || should_start_block {
                 state.start_block(line);
+                state.word_diff = is_hunk_header && is_word_diff_hunk(&diff_lines, line_index + 1);
+                continue;
+            }
+        }
+
+        // Handle "\ No newline at end of file" markers (the message may be localized)
+        if state.current_block.is_some() && line.starts_with(NO_NEWLINE_MARKER_PREFIX) {
+            state.mark_no_newline_at_eof();
+            continue;
+        }
+
+        // Handle --word-diff=porcelain segments and line endings
+        if state.word_diff && state.current_block.is_some() {
+            if *line == WORD_DIFF_NEWLINE {
+                state.finish_word_line();
+                continue;
+            }
+            if line.starts_with(['+', '-', ' ']) {
+                state.word_segments.push(line.to_string());
                 continue;
             }
         }
@@ -559,7 +1502,7 @@ pub fn parse(diff_input: &str, config: &DiffParserConfig) -> Vec<DiffFile> {
             if does_not_exist_hunk_header {
                 file.old_name = caps
                     .get(1)
-                    .map(|m| m.as_str().to_string())
+                    .map(|m| unquote_filename(m.as_str(), decoding))
                     .unwrap_or_default();
             }
             file.is_copy = Some(true);
@@ -567,7 +1510,7 @@ pub fn parse(diff_input: &str, config: &DiffParserConfig) -> Vec<DiffFile> {
             if does_not_exist_hunk_header {
                 file.new_name = caps
                     .get(1)
-                    .map(|m| m.as_str().to_string())
+                    .map(|m| unquote_filename(m.as_str(), decoding))
                     .unwrap_or_default();
             }
             file.is_copy = Some(true);
@@ -575,7 +1518,7 @@ pub fn parse(diff_input: &str, config: &DiffParserConfig) -> Vec<DiffFile> {
             if does_not_exist_hunk_header {
                 file.old_name = caps
                     .get(1)
-                    .map(|m| m.as_str().to_string())
+                    .map(|m| unquote_filename(m.as_str(), decoding))
                     .unwrap_or_default();
             }
             file.is_rename = Some(true);
@@ -583,7 +1526,7 @@ pub fn parse(diff_input: &str, config: &DiffParserConfig) -> Vec<DiffFile> {
             if does_not_exist_hunk_header {
                 file.new_name = caps
                     .get(1)
-                    .map(|m| m.as_str().to_string())
+                    .map(|m| unquote_filename(m.as_str(), decoding))
                     .unwrap_or_default();
             }
             file.is_rename = Some(true);
@@ -591,16 +1534,32 @@ pub fn parse(diff_input: &str, config: &DiffParserConfig) -> Vec<DiffFile> {
             file.is_binary = Some(true);
             file.old_name = caps
                 .get(1)
-                .map(|m| get_filename(m.as_str(), None, config.src_prefix.as_deref()))
+                .map(|m| get_filename(m.as_str(), None, config.src_prefix.as_deref(), config))
                 .unwrap_or_default();
             file.new_name = caps
                 .get(2)
-                .map(|m| get_filename(m.as_str(), None, config.dst_prefix.as_deref()))
+                .map(|m| get_filename(m.as_str(), None, config.dst_prefix.as_deref(), config))
                 .unwrap_or_default();
             state.start_block("Binary file");
         } else if BINARY_DIFF.is_match(line) {
             file.is_binary = Some(true);
+            state.binary_patches = 0;
             state.start_block(line);
+        } else if file.is_binary == Some(true)
+            && let Some(caps) = BINARY_PATCH_SIZE.captures(line)
+        {
+            // The forward patch (old -> new) comes first, followed by the
+            // reverse patch. Only a `literal` holds a whole version, the size
+            // of a `delta` says nothing about the size of the file.
+            let size = caps.get(2).and_then(|m| m.as_str().parse().ok());
+            if &caps[1] == "literal" {
+                match state.binary_patches {
+                    0 => file.binary_size_after = size,
+                    1 => file.binary_size_before = size,
+                    _ => {}
+                }
+            }
+            state.binary_patches += 1;
         } else if let Some(caps) = SIMILARITY_INDEX.captures(line) {
             file.unchanged_percentage = caps.get(1).and_then(|m| m.as_str().parse().ok());
         } else if let Some(caps) = DISSIMILARITY_INDEX.captures(line) {
@@ -640,10 +1599,180 @@ pub fn parse(diff_input: &str, config: &DiffParserConfig) -> Vec<DiffFile> {
         }
     }
 
+    state.source_end = last_content_end;
     state.save_block();
     state.save_file();
+    state.resolve_only_in();
+    apply_language_overrides(&mut state.files, &config.language_overrides);
+
+    #[cfg(feature = "log")]
+    {
+        for diagnostic in &state.diagnostics {
+            log::debug!("{}", diagnostic);
+        }
+        log::debug!(
+            "parsed {} files with {} problems from {} lines in {:?}",
+            state.files.len(),
+            state.diagnostics.len(),
+            diff_lines.len(),
+            start.elapsed()
+        );
+    }
+
+    ParseResult {
+        files: state.files,
+        diagnostics: state.diagnostics,
+        omitted_files: state.omitted_files,
+    }
+}
+
+/// Mode of files that do not exist in `--raw` output.
+const MISSING_FILE_MODE: &str = "000000";
+
+/// Splits a `--numstat` path into the old and new file names.
+///
+/// Renames are shown either as `old => new` or with the changed part of the
+/// path in braces, e.g. `src/{old => new}/file.rs`.
+fn split_numstat_path(path: &str) -> (String, String) {
+    if let Some(caps) = NUMSTAT_RENAME.captures(path) {
+        let (prefix, old, new, suffix) = (&caps[1], &caps[2], &caps[3], &caps[4]);
+        let join = |part: &str| {
+            if part.is_empty() {
+                format!("{}{}", prefix, suffix.strip_prefix('/').unwrap_or(suffix))
+            } else {
+                format!("{}{}{}", prefix, part, suffix)
+            }
+        };
+        (join(old), join(new))
+    } else if let Some((old, new)) = path.split_once(" => ") {
+        (old.to_string(), new.to_string())
+    } else {
+        (path.to_string(), path.to_string())
+    }
+}
+
+/// Creates a file from a `--raw` output line.
+fn parse_raw_line(caps: &regex::Captures) -> DiffFile {
+    let old_mode = &caps[1];
+    let new_mode = &caps[2];
+    let score = caps.get(6).and_then(|m| m.as_str().parse().ok());
+    let path = unquote_filename(&caps[7], FilenameDecoding::Decode);
+
+    let mut file = DiffFile {
+        old_name: path.clone(),
+        new_name: caps.get(8).map_or(path, |m| {
+            unquote_filename(m.as_str(), FilenameDecoding::Decode)
+        }),
+        is_git_diff: true,
+        checksum_before: Some(Checksum::Single(caps[3].to_string())),
+        checksum_after: Some(caps[4].to_string()),
+        ..Default::default()
+    };
+
+    match &caps[5] {
+        "A" => {
+            file.old_name = "/dev/null".to_string();
+            file.is_new = Some(true);
+            file.new_file_mode = Some(new_mode.to_string());
+        }
+        "D" => {
+            file.new_name = "/dev/null".to_string();
+            file.is_deleted = Some(true);
+            file.deleted_file_mode = Some(old_mode.to_string());
+        }
+        "R" => {
+            file.is_rename = Some(true);
+            file.unchanged_percentage = score;
+        }
+        "C" => {
+            file.is_copy = Some(true);
+            file.unchanged_percentage = score;
+        }
+        _ => file.changed_percentage = score,
+    }
+
+    if old_mode != new_mode && old_mode != MISSING_FILE_MODE && new_mode != MISSING_FILE_MODE {
+        file.old_mode = Some(FileMode::Single(old_mode.to_string()));
+        file.new_mode = Some(new_mode.to_string());
+    } else if old_mode == new_mode {
+        file.mode = Some(new_mode.to_string());
+    }
 
-    state.files
+    file
+}
+
+/// Parses `git diff --numstat` and/or `git diff --raw` output into a list of
+/// DiffFile structures without blocks.
+///
+/// This is much cheaper to produce than a full diff for enormous changes and
+/// is enough to render a file list summary. When both formats are combined
+/// (`git diff --raw --numstat`), the line counts are merged into the files
+/// from the raw output.
+///
+/// # Example
+///
+/// ```
+/// use diff2html::parse_summary;
+///
+/// let files = parse_summary("3\t1\tsrc/lib.rs\n-\t-\tlogo.png\n");
+/// assert_eq!(files.len(), 2);
+/// assert_eq!(files[0].added_lines, 3);
+/// assert_eq!(files[1].is_binary, Some(true));
+/// ```
+pub fn parse_summary(summary_input: &str) -> Vec<DiffFile> {
+    let mut files: Vec<DiffFile> = Vec::new();
+
+    for line in summary_input.lines() {
+        let line = line.trim_end_matches('\r');
+
+        if let Some(caps) = RAW_LINE.captures(line) {
+            let mut file = parse_raw_line(&caps);
+            let name = if file.is_deleted == Some(true) {
+                &file.old_name
+            } else {
+                &file.new_name
+            };
+            file.language = get_extension(name, "");
+            files.push(file);
+        } else if let Some(caps) = NUMSTAT_LINE.captures(line) {
+            let path = unquote_filename(&caps[3], FilenameDecoding::Decode);
+            let (old_name, new_name) = split_numstat_path(&path);
+            let is_binary = &caps[1] == "-" && &caps[2] == "-";
+
+            // Merge into the file from the --raw output, if any
+            let index = files.iter().position(|f| {
+                f.blocks.is_empty()
+                    && f.added_lines == 0
+                    && f.deleted_lines == 0
+                    && f.is_binary.is_none()
+                    && (f.new_name == new_name
+                        || (f.is_deleted == Some(true) && f.old_name == old_name))
+            });
+            let file = match index {
+                Some(index) => &mut files[index],
+                None => {
+                    files.push(DiffFile {
+                        language: get_extension(&new_name, ""),
+                        is_git_diff: true,
+                        is_rename: (old_name != new_name).then_some(true),
+                        old_name,
+                        new_name,
+                        ..Default::default()
+                    });
+                    files.last_mut().unwrap()
+                }
+            };
+
+            if is_binary {
+                file.is_binary = Some(true);
+            } else {
+                file.added_lines = caps[1].parse().unwrap_or(0);
+                file.deleted_lines = caps[2].parse().unwrap_or(0);
+            }
+        }
+    }
+
+    files
 }
 
 #[cfg(test)]
@@ -828,6 +1957,17 @@ index 1234567..abcdefg 100644
         assert_eq!(lines[2].new_number, Some(2));
     }
 
+    #[test]
+    fn test_diff_lines() {
+        let lines = |input| DiffLines::new(input).collect::<Vec<_>>();
+        assert_eq!(lines(""), vec![""]);
+        assert_eq!(lines("a"), vec!["a"]);
+        assert_eq!(lines("a\nb\n"), vec!["a", "b", ""]);
+        assert_eq!(lines("a\r\nb\rc\n\nd"), vec!["a", "b", "c", "", "d"]);
+        assert_eq!(lines("a\r\r\nb"), vec!["a", "", "b"]);
+        assert_eq!(lines("\r\n"), vec!["", ""]);
+    }
+
     #[test]
     fn test_diff_too_big() {
         let diff = r#"diff --git a/test.txt b/test.txt
@@ -852,6 +1992,508 @@ index 1234567..abcdefg 100644
         assert_eq!(files[0].added_lines, 0);
     }
 
+    #[test]
+    fn test_diff_too_big_keep_content() {
+        let diff = r#"diff --git a/test.txt b/test.txt
+--- a/test.txt
++++ b/test.txt
+@@ -1,3 +1,5 @@
+ context
++line1
++line2
++line3
++line4
+"#;
+
+        let config = DiffParserConfig {
+            diff_max_changes: Some(2),
+            keep_too_big_content: true,
+            ..Default::default()
+        };
+
+        let files = parse(diff, &config);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].is_too_big, Some(true));
+        assert_eq!(files[0].is_collapsed, Some(true));
+        assert_eq!(files[0].added_lines, 4);
+        assert_eq!(files[0].blocks.len(), 1);
+        assert_eq!(files[0].blocks[0].header, "@@ -1,3 +1,5 @@");
+        assert_eq!(files[0].blocks[0].lines.len(), 5);
+    }
+
+    #[test]
+    fn test_diff_max_files() {
+        let diff = r#"diff --git a/a.txt b/a.txt
+--- a/a.txt
++++ b/a.txt
+@@ -1 +1 @@
+-a
++b
+diff --git a/b.txt b/b.txt
+--- a/b.txt
++++ b/b.txt
+@@ -1 +1 @@
+-a
++b
+diff --git a/c.txt b/c.txt
+--- a/c.txt
++++ b/c.txt
+@@ -1 +1 @@
+-a
++b
+"#;
+
+        let config = DiffParserConfig {
+            diff_max_files: Some(1),
+            ..Default::default()
+        };
+
+        let result = parse_with_diagnostics(diff, &config).unwrap();
+        assert_eq!(result.files.len(), 1);
+        assert_eq!(result.files[0].new_name, "a.txt");
+        assert_eq!(result.files[0].added_lines, 1);
+        assert_eq!(result.omitted_files, 2);
+    }
+
+    #[test]
+    fn test_diff_max_total_changes() {
+        let diff = r#"diff --git a/a.txt b/a.txt
+--- a/a.txt
++++ b/a.txt
+@@ -1,2 +1,2 @@
+-a
+-b
++c
++d
+diff --git a/b.txt b/b.txt
+--- a/b.txt
++++ b/b.txt
+@@ -1,2 +1,2 @@
+-a
+-b
++c
++d
+diff --git a/c.txt b/c.txt
+--- a/c.txt
++++ b/c.txt
+@@ -1 +1 @@
+-a
++b
+"#;
+
+        let config = DiffParserConfig {
+            diff_max_total_changes: Some(6),
+            ..Default::default()
+        };
+
+        let result = parse_with_diagnostics(diff, &config).unwrap();
+        let files = &result.files;
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].is_too_big, None);
+        assert_eq!(files[0].added_lines, 2);
+        assert_eq!(files[1].new_name, "b.txt");
+        assert_eq!(files[1].is_too_big, Some(true));
+        assert_eq!(files[1].added_lines, 0);
+        assert_eq!(result.omitted_files, 1);
+    }
+
+    #[test]
+    fn test_parse_mode_only_change() {
+        let diff = r#"diff --git a/script.sh b/script.sh
+old mode 100644
+new mode 100755
+"#;
+
+        let files = parse(diff, &DiffParserConfig::default());
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].is_mode_change, Some(true));
+        assert!(files[0].blocks.is_empty());
+    }
+
+    #[test]
+    fn test_parse_mode_change_with_hunks() {
+        let diff = r#"diff --git a/script.sh b/script.sh
+old mode 100644
+new mode 100755
+--- a/script.sh
++++ b/script.sh
+@@ -1 +1 @@
+-echo old
++echo new
+"#;
+
+        let files = parse(diff, &DiffParserConfig::default());
+        assert_eq!(files[0].is_mode_change, None);
+    }
+
+    #[test]
+    fn test_parse_git_binary_patch_sizes() {
+        let diff = r#"diff --git a/logo.png b/logo.png
+index 3f2a1b4..9c8d7e6 100644
+GIT binary patch
+literal 13414
+zcmV-QH+|klVK+mQ
+
+delta 120
+zcmV-QH+|klVK+mQ
+
+"#;
+
+        let files = parse(diff, &DiffParserConfig::default());
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].is_binary, Some(true));
+        assert_eq!(files[0].binary_size_after, Some(13414));
+        assert_eq!(files[0].binary_size_before, None);
+        assert_eq!(files[0].blocks[0].header, "GIT binary patch");
+
+        let diff = "diff --git a/logo.png b/logo.png\n\
+                    index 3f2a1b4..9c8d7e6 100644\n\
+                    GIT binary patch\n\
+                    delta 60\n\
+                    zcmV-QH+|klVK+mQ\n\
+                    \n\
+                    literal 9000\n\
+                    zcmV-QH+|klVK+mQ\n\
+                    \n";
+        let files = parse(diff, &DiffParserConfig::default());
+        assert_eq!(files[0].binary_size_after, None);
+        assert_eq!(files[0].binary_size_before, Some(9000));
+    }
+
+    #[test]
+    fn test_parse_source_lines() {
+        let diff = "diff --git a/a.txt b/a.txt
+index 1234567..89abcde 100644
+--- a/a.txt
++++ b/a.txt
+@@ -1 +1 @@
+-old
++new
+@@ -10 +10 @@
+-old
+\\ No newline at end of file
+
+diff --git a/b.txt b/b.txt
+deleted file mode 100644
+";
+
+        let files = parse(diff, &DiffParserConfig::default());
+        let lines: Vec<&str> = diff.lines().collect();
+        assert_eq!(files.len(), 2);
+
+        assert_eq!(files[0].source_lines, Some(0..10));
+        assert_eq!(files[0].blocks[0].source_lines, Some(4..7));
+        assert_eq!(files[0].blocks[1].source_lines, Some(7..10));
+        assert_eq!(
+            lines[7..10],
+            ["@@ -10 +10 @@", "-old", "\\ No newline at end of file"]
+        );
+
+        assert_eq!(files[1].source_lines, Some(11..13));
+    }
+
+    #[test]
+    fn test_parse_no_newline_at_eof_markers() {
+        let diff = r#"diff --git a/test.txt b/test.txt
+--- a/test.txt
++++ b/test.txt
+@@ -1,2 +1,2 @@
+ same
+-old
+\ No newline at end of file
++new
+\ No newline at end of file
+"#;
+
+        let files = parse(diff, &DiffParserConfig::default());
+        let lines = &files[0].blocks[0].lines;
+        assert_eq!(lines.len(), 3);
+        assert!(!lines[0].no_newline_at_eof);
+        assert!(lines[1].no_newline_at_eof);
+        assert!(lines[2].no_newline_at_eof);
+        assert_eq!(lines[2].content, "+new");
+    }
+
+    #[test]
+    fn test_is_word_diff_hunk() {
+        let lines = ["@@ -1 +1 @@", "-old", "+new", "~", "@@ -5 +5 @@", " same"];
+        assert!(is_word_diff_hunk(&lines, 1));
+        assert!(!is_word_diff_hunk(&lines, 5));
+
+        let lines = ["@@ -1 +1 @@", "-old", "+new", "diff --git a/b b/b", "~"];
+        assert!(!is_word_diff_hunk(&lines, 1));
+    }
+
+    #[test]
+    fn test_split_numstat_path() {
+        assert_eq!(
+            split_numstat_path("src/lib.rs"),
+            ("src/lib.rs".to_string(), "src/lib.rs".to_string())
+        );
+        assert_eq!(
+            split_numstat_path("old.txt => new.txt"),
+            ("old.txt".to_string(), "new.txt".to_string())
+        );
+        assert_eq!(
+            split_numstat_path("src/{old => new}/mod.rs"),
+            ("src/old/mod.rs".to_string(), "src/new/mod.rs".to_string())
+        );
+        assert_eq!(
+            split_numstat_path("src/{ => nested}/mod.rs"),
+            ("src/mod.rs".to_string(), "src/nested/mod.rs".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_summary_raw() {
+        let input = ":100644 100755 1e6b3a1 5c4d2e9 M\tscript.sh\n\
+                     :000000 100644 0000000 5c4d2e9 A\tnew.rs\n\
+                     :100644 000000 1e6b3a1 0000000 D\told.rs\n\
+                     :100644 100644 1e6b3a1 5c4d2e9 R086\ta.txt\tb.txt\n";
+        let files = parse_summary(input);
+
+        assert_eq!(files.len(), 4);
+        assert_eq!(files[0].new_mode.as_deref(), Some("100755"));
+        assert_eq!(files[1].is_new, Some(true));
+        assert_eq!(files[1].old_name, "/dev/null");
+        assert_eq!(files[1].language, "rs");
+        assert_eq!(files[2].is_deleted, Some(true));
+        assert_eq!(files[2].new_name, "/dev/null");
+        assert_eq!(files[3].is_rename, Some(true));
+        assert_eq!(files[3].old_name, "a.txt");
+        assert_eq!(files[3].new_name, "b.txt");
+        assert_eq!(files[3].unchanged_percentage, Some(86));
+        assert!(files.iter().all(|f| f.blocks.is_empty()));
+    }
+
+    #[test]
+    fn test_parse_summary_raw_and_numstat() {
+        let input = ":100644 100644 1e6b3a1 5c4d2e9 M\tsrc/lib.rs\n\
+                     :100644 000000 1e6b3a1 0000000 D\told.rs\n\
+                     4\t2\tsrc/lib.rs\n\
+                     0\t10\told.rs\n";
+        let files = parse_summary(input);
+
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].added_lines, 4);
+        assert_eq!(files[0].deleted_lines, 2);
+        assert_eq!(files[0].checksum_after.as_deref(), Some("5c4d2e9"));
+        assert_eq!(files[1].deleted_lines, 10);
+    }
+
+    #[test]
+    fn test_get_diff_roots() {
+        assert_eq!(
+            get_diff_roots("old/src/main.c", "new/src/main.c"),
+            Some(("old".to_string(), "new".to_string()))
+        );
+        assert_eq!(
+            get_diff_roots("/tmp/a/x", "b/x"),
+            Some(("/tmp/a".to_string(), "b".to_string()))
+        );
+        assert_eq!(get_diff_roots("same/x", "same/x"), None);
+    }
+
+    #[test]
+    fn test_is_in_directory() {
+        assert!(is_in_directory("old", "old"));
+        assert!(is_in_directory("old/src/", "old"));
+        assert!(!is_in_directory("older", "old"));
+        assert!(!is_in_directory("new/old", "old"));
+    }
+
+    #[test]
+    fn test_parse_with_diagnostics_line_count_mismatch() {
+        let diff = "--- a/f\n+++ b/f\n@@ -1,3 +1,2 @@\n a\n-b\n+c\n@@ -10 +9 @@\n-d\n+e\n";
+        let result = parse_with_diagnostics(diff, &DiffParserConfig::default()).unwrap();
+
+        assert_eq!(result.files[0].blocks.len(), 2);
+        assert_eq!(result.diagnostics.len(), 1);
+        assert_eq!(result.diagnostics[0].line, 3);
+        assert_eq!(
+            result.diagnostics[0].kind,
+            DiagnosticKind::LineCountMismatch
+        );
+    }
+
+    #[test]
+    fn test_parse_with_diagnostics_malformed_hunk_header() {
+        let diff = "--- a/f\n+++ b/f\n@@ garbage @@\n-a\n+b\n";
+        let result = parse_with_diagnostics(diff, &DiffParserConfig::default()).unwrap();
+
+        assert_eq!(result.files[0].blocks[0].lines.len(), 2);
+        assert_eq!(result.diagnostics.len(), 1);
+        assert_eq!(
+            result.diagnostics[0].kind,
+            DiagnosticKind::MalformedHunkHeader
+        );
+
+        let config = DiffParserConfig {
+            mode: ParseMode::Strict,
+            ..Default::default()
+        };
+        let err = parse_with_diagnostics(diff, &config).unwrap_err();
+        assert!(err.to_string().starts_with("Malformed diff at line 3:"));
+    }
+
+    #[test]
+    fn test_parse_with_diagnostics_valid_diff() {
+        let diff = "--- a/f\n+++ b/f\n@@ -0,0 +1,2 @@\n+a\n+b\n";
+        let config = DiffParserConfig {
+            mode: ParseMode::Strict,
+            ..Default::default()
+        };
+        let result = parse_with_diagnostics(diff, &config).unwrap();
+        assert!(result.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_parse_hunk_line_counts() {
+        let diff = "--- a/f\n+++ b/f\n@@ -1,2 +1 @@\n-a\n b\n@@ -0,0 +5,2 @@ fn main\n+c\n+d\n";
+        let result = parse_with_diagnostics(diff, &DiffParserConfig::default()).unwrap();
+        let blocks = &result.files[0].blocks;
+
+        assert_eq!(blocks[0].old_lines, Some(2));
+        assert_eq!(blocks[0].new_lines, Some(1));
+        assert_eq!(blocks[1].old_lines, Some(0));
+        assert_eq!(blocks[1].new_lines, Some(2));
+        assert!(result.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_unquote_filename() {
+        assert_eq!(
+            unquote_filename(r#""caf\303\251 menu.txt""#, FilenameDecoding::Decode),
+            "café menu.txt"
+        );
+        assert_eq!(
+            unquote_filename(r#""say \"hi\"\t\\.txt""#, FilenameDecoding::Decode),
+            "say \"hi\"\t\\.txt"
+        );
+        assert_eq!(
+            unquote_filename(r#""caf\303\251.txt""#, FilenameDecoding::Keep),
+            r"caf\303\251.txt"
+        );
+        assert_eq!(
+            unquote_filename("plain.txt", FilenameDecoding::Decode),
+            "plain.txt"
+        );
+        assert_eq!(
+            unquote_filename(r#""unterminated"#, FilenameDecoding::Decode),
+            r#""unterminated"#
+        );
+    }
+
+    #[test]
+    fn test_split_quoted_git_diff_paths() {
+        let decoding = FilenameDecoding::Decode;
+        assert_eq!(
+            split_quoted_git_diff_paths(r#""a/x y.txt" "b/x y.txt""#, decoding),
+            Some(("a/x y.txt".to_string(), "b/x y.txt".to_string()))
+        );
+        assert_eq!(
+            split_quoted_git_diff_paths(r#"a/plain.txt "b/\303\251.txt""#, decoding),
+            Some(("a/plain.txt".to_string(), "b/é.txt".to_string()))
+        );
+        assert_eq!(
+            split_quoted_git_diff_paths(r#""a/\303\251.txt" b/plain.txt"#, decoding),
+            Some(("a/é.txt".to_string(), "b/plain.txt".to_string()))
+        );
+        assert_eq!(
+            split_quoted_git_diff_paths("a/plain.txt b/plain.txt", decoding),
+            None
+        );
+    }
+
+    #[test]
+    fn test_split_git_diff_paths() {
+        let config = DiffParserConfig::default();
+        assert_eq!(
+            split_git_diff_paths("src/main.rs src/main.rs", &config),
+            Some(("src/main.rs".to_string(), "src/main.rs".to_string()))
+        );
+        assert_eq!(
+            split_git_diff_paths("with space.txt with space.txt", &config),
+            Some(("with space.txt".to_string(), "with space.txt".to_string()))
+        );
+        assert_eq!(split_git_diff_paths("old.txt new.txt", &config), None);
+
+        let config = DiffParserConfig {
+            src_prefix: Some("left/".to_string()),
+            dst_prefix: Some("right/".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            split_git_diff_paths("left/old name.txt right/new.txt", &config),
+            Some(("left/old name.txt".to_string(), "right/new.txt".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_get_filename_quoted() {
+        assert_eq!(
+            get_filename(
+                r#"--- "a/caf\303\251.txt""#,
+                Some("---"),
+                None,
+                &DiffParserConfig::default()
+            ),
+            "café.txt"
+        );
+        assert_eq!(
+            get_filename(
+                "+++ \"b/with space.txt\"\t2016-10-25 11:37:14.000000000 +0200",
+                Some("+++"),
+                None,
+                &DiffParserConfig::default()
+            ),
+            "with space.txt"
+        );
+    }
+
+    #[test]
+    fn test_timestamp_handling() {
+        let split = |name: &str| TimestampHandling::Always.split(name);
+        assert_eq!(
+            split("a.txt\t2016-10-25 11:37:14.000000000 +0200"),
+            (
+                "a.txt".to_string(),
+                Some("2016-10-25 11:37:14.000000000 +0200".to_string())
+            )
+        );
+        assert_eq!(
+            split("a.txt\tTue Oct 25 11:37:14 2016"),
+            (
+                "a.txt".to_string(),
+                Some("Tue Oct 25 11:37:14 2016".to_string())
+            )
+        );
+        assert_eq!(
+            split("a.txt\tmar. oct. 25 11:37:14 2016"),
+            (
+                "a.txt".to_string(),
+                Some("mar. oct. 25 11:37:14 2016".to_string())
+            )
+        );
+        assert_eq!(
+            split("a.txt\t1477388234.5"),
+            ("a.txt".to_string(), Some("1477388234.5".to_string()))
+        );
+        // Numbers separated by a space are part of the name
+        assert_eq!(split("notes 2016"), ("notes 2016".to_string(), None));
+
+        assert_eq!(
+            TimestampHandling::Never.split("a.txt\t1477388234"),
+            ("a.txt\t1477388234".to_string(), None)
+        );
+        assert_eq!(
+            TimestampHandling::custom(r"@\d+$")
+                .unwrap()
+                .split("a.txt@1234"),
+            ("a.txt".to_string(), Some("@1234".to_string()))
+        );
+    }
+
     #[test]
     fn test_escape_for_regexp() {
         assert_eq!(escape_for_regexp("a.b"), "a\\.b");
@@ -860,6 +2502,77 @@ index 1234567..abcdefg 100644
         assert_eq!(escape_for_regexp("[test]"), "\\[test\\]");
     }
 
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match(b"*.tf", b"main.tf"));
+        assert!(!glob_match(b"*.tf", b"infra/main.tf"));
+        assert!(glob_match(b"**/*.tf", b"infra/main.tf"));
+        assert!(glob_match(b"**/*.tf", b"main.tf"));
+        assert!(glob_match(b"scripts/*", b"scripts/deploy"));
+        assert!(!glob_match(b"scripts/*", b"scripts/ci/deploy"));
+        assert!(glob_match(b"file.?s", b"file.ts"));
+        assert!(glob_match(b"?.rs", "é.rs".as_bytes()));
+        assert!(!glob_match(b"?", b"/"));
+    }
+
+    #[test]
+    fn test_get_language_override() {
+        let overrides: HashMap<String, String> = [
+            ("tf", "hcl"),
+            ("Dockerfile", "dockerfile"),
+            ("scripts/*", "bash"),
+            ("*.test.js", "jest"),
+            ("js", "javascript"),
+        ]
+        .into_iter()
+        .map(|(key, language)| (key.to_string(), language.to_string()))
+        .collect();
+
+        let language = |path| get_language_override(path, &overrides);
+        assert_eq!(language("infra/main.tf"), Some("hcl"));
+        assert_eq!(language("docker/Dockerfile"), Some("dockerfile"));
+        assert_eq!(language("scripts/deploy"), Some("bash"));
+        assert_eq!(language("src/app.test.js"), Some("jest"));
+        assert_eq!(language("src/app.js"), Some("javascript"));
+        assert_eq!(language("src/main.rs"), None);
+        assert_eq!(language("Makefile"), None);
+    }
+
+    #[test]
+    fn test_is_excluded() {
+        let exclude: Vec<String> = ["Cargo.lock", "vendor/", "src/*.generated.rs", "*.min.js"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+
+        let excluded = |path| is_excluded(path, &exclude);
+        assert!(excluded("Cargo.lock"));
+        assert!(excluded("crates/cli/Cargo.lock"));
+        assert!(excluded("vendor/lib/a.c"));
+        assert!(excluded("third_party/vendor/a.c"));
+        assert!(excluded("src/parser.generated.rs"));
+        assert!(!excluded("src/mod/parser.generated.rs"));
+        assert!(excluded("static/app.min.js"));
+        assert!(!excluded("src/main.rs"));
+        assert!(!excluded("vendored/a.c"));
+        assert!(!is_excluded("src/main.rs", &[String::new()]));
+    }
+
+    #[test]
+    fn test_parse_exclude() {
+        let diff = "--- a/Cargo.lock\n+++ b/Cargo.lock\n@@ -1 +1 @@\n-a\n+b\n\
+                    --- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1 +1 @@\n-a\n+b\n\
+                    --- /dev/null\n+++ b/vendor/a.c\n@@ -0,0 +1 @@\n+a\n";
+        let config = DiffParserConfig {
+            exclude: vec!["Cargo.lock".to_string(), "vendor".to_string()],
+            ..Default::default()
+        };
+
+        let files = parse(diff, &config);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].new_name, "src/lib.rs");
+    }
+
     #[test]
     fn test_get_extension() {
         assert_eq!(get_extension("file.rs", "txt"), "rs");
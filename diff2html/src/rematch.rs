@@ -10,6 +10,8 @@
 
 use std::collections::HashMap;
 
+use crate::defaults;
+
 /// Result of finding the best match between two sequences.
 #[derive(Debug, Clone, PartialEq)]
 pub struct BestMatch {
@@ -270,8 +272,8 @@ pub struct MatchConfig {
 impl Default for MatchConfig {
     fn default() -> Self {
         Self {
-            max_comparisons: 2500,
-            max_line_size: 200,
+            max_comparisons: defaults::MATCHING_MAX_COMPARISONS,
+            max_line_size: defaults::MAX_LINE_SIZE_IN_BLOCK_FOR_COMPARISON,
         }
     }
 }
@@ -0,0 +1,371 @@
+//! Grouping of the lines of a block, as done by the renderers.
+//!
+//! Renderers show context lines one by one, and pair runs of deleted lines
+//! with the inserted lines directly following them to highlight the changes
+//! between them. [`group_lines`] exposes this grouping so that other renderers
+//! can reuse the exact same semantics, and [`is_whitespace_only_change`] tells
+//! the paired lines differing only in whitespace apart.
+//!
+//! With line matching enabled, [`match_changes`] further splits the runs of
+//! changes around the most similar deleted and inserted lines, so that they
+//! are paired even when not at the same position in the run.
+
+use super::utils::{RendererConfig, deconstruct_line, is_truncated_line};
+use crate::rematch::{MatchConfig, match_lines_with_config, string_distance};
+use crate::types::{DiffBlock, DiffLine, LineMatchingType, LineType};
+
+/// A group of lines of a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineGroup<'a> {
+    /// A context line, unchanged between both versions.
+    Context(&'a DiffLine),
+    /// A run of deleted lines and the inserted lines following them. Either
+    /// may be empty, but not both.
+    Changes {
+        deleted: &'a [DiffLine],
+        inserted: &'a [DiffLine],
+    },
+}
+
+/// Group the lines of a block into context lines and runs of changes.
+///
+/// The groups are slices of the block lines, since deletions are always
+/// directly followed by the insertions they are paired with. Insertions not
+/// preceded by deletions are grouped one line at a time.
+///
+/// # Example
+///
+/// ```
+/// use diff2html::render::analysis::{LineGroup, group_lines};
+/// use diff2html::{DiffParserConfig, parse};
+///
+/// let diff = "--- a/f\n+++ b/f\n@@ -1,2 +1,2 @@\n a\n-b\n+c\n";
+/// let files = parse(diff, &DiffParserConfig::default());
+/// let groups = group_lines(&files[0].blocks[0]);
+///
+/// assert!(matches!(groups[0], LineGroup::Context(line) if line.content == " a"));
+/// assert!(matches!(
+///     groups[1],
+///     LineGroup::Changes { deleted, inserted } if deleted.len() == 1 && inserted.len() == 1
+/// ));
+/// ```
+pub fn group_lines(block: &DiffBlock) -> Vec<LineGroup<'_>> {
+    let lines = block.lines.as_slice();
+    let mut groups = Vec::new();
+    // Pending deletions are `lines[start..mid]`, insertions `lines[mid..i]`
+    let mut start = 0;
+    let mut mid = 0;
+
+    for (i, line) in lines.iter().enumerate() {
+        let has_old_lines = mid > start;
+        let has_new_lines = i > mid;
+
+        // Flush accumulated lines when we hit a context line or switch patterns
+        if (line.line_type != LineType::Insert && has_new_lines)
+            || (line.line_type == LineType::Context && has_old_lines)
+        {
+            groups.push(LineGroup::Changes {
+                deleted: &lines[start..mid],
+                inserted: &lines[mid..i],
+            });
+            start = i;
+            mid = i;
+        }
+
+        match line.line_type {
+            LineType::Context => {
+                groups.push(LineGroup::Context(line));
+                start = i + 1;
+                mid = i + 1;
+            }
+            LineType::Insert if mid == start => {
+                groups.push(LineGroup::Changes {
+                    deleted: &[],
+                    inserted: &lines[i..=i],
+                });
+                start = i + 1;
+                mid = i + 1;
+            }
+            LineType::Insert => {}
+            LineType::Delete => {
+                mid = i + 1;
+            }
+        }
+    }
+
+    // Flush any remaining lines
+    if lines.len() > start {
+        groups.push(LineGroup::Changes {
+            deleted: &lines[start..mid],
+            inserted: &lines[mid..],
+        });
+    }
+
+    groups
+}
+
+/// Deleted and inserted lines of a run of changes, paired by position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatchedChanges<'a> {
+    pub deleted: &'a [DiffLine],
+    pub inserted: &'a [DiffLine],
+    /// Whether line matching paired the single deleted and inserted line as
+    /// the most similar ones, rather than for being at the same position.
+    pub matched: bool,
+}
+
+/// Split a run of changes into groups of lines paired by position, using
+/// line matching if enabled in the configuration and the run is within the
+/// matching limits.
+///
+/// Without line matching, the whole run is a single group.
+pub fn match_changes<'a>(
+    deleted: &'a [DiffLine],
+    inserted: &'a [DiffLine],
+    is_combined: bool,
+    config: &RendererConfig,
+) -> Vec<MatchedChanges<'a>> {
+    let unmatched = vec![MatchedChanges {
+        deleted,
+        inserted,
+        matched: false,
+    }];
+    if config.render.matching == LineMatchingType::None || deleted.is_empty() || inserted.is_empty()
+    {
+        return unmatched;
+    }
+
+    let old_lines: Vec<_> = deleted.iter().collect();
+    let new_lines: Vec<_> = inserted.iter().collect();
+    let groups = match_lines_with_config(
+        &old_lines,
+        &new_lines,
+        |old, new| line_distance(old, new, is_combined),
+        &MatchConfig {
+            max_comparisons: config.matching_max_comparisons,
+            max_line_size: config.max_line_size_in_block_for_comparison,
+        },
+        |line| line.content.as_str(),
+    );
+    if groups.len() < 2 {
+        return unmatched;
+    }
+
+    // Groups are consecutive lines of the run, in order
+    let (mut old_start, mut new_start) = (0, 0);
+    groups
+        .iter()
+        .map(|(old_group, new_group)| {
+            let changes = MatchedChanges {
+                deleted: &deleted[old_start..old_start + old_group.len()],
+                inserted: &inserted[new_start..new_start + new_group.len()],
+                matched: old_group.len() == 1 && new_group.len() == 1,
+            };
+            old_start += old_group.len();
+            new_start += new_group.len();
+            changes
+        })
+        .collect()
+}
+
+/// Check whether a deleted line and the inserted line paired with it get
+/// diff highlighting, taking one of `highlight_budget` if so.
+///
+/// Lines with a word diff of their own or truncated are not highlighted,
+/// and lines paired by matching only if similar enough.
+pub(crate) fn take_highlight(
+    old: &DiffLine,
+    new: &DiffLine,
+    changes: &MatchedChanges,
+    highlight_budget: &mut usize,
+    is_combined: bool,
+    config: &RendererConfig,
+) -> bool {
+    let highlight = old.word_diff.is_none()
+        && new.word_diff.is_none()
+        && *highlight_budget > 0
+        && !is_truncated_line(old, is_combined, config)
+        && !is_truncated_line(new, is_combined, config)
+        && (!changes.matched
+            || line_distance(old, new, is_combined) < config.render.match_words_threshold);
+    if highlight {
+        *highlight_budget -= 1;
+    }
+    highlight
+}
+
+/// Distance between the contents of two lines, from 0.0 for identical lines
+/// to 1.0 for lines without anything in common.
+pub fn line_distance(old: &DiffLine, new: &DiffLine, is_combined: bool) -> f64 {
+    string_distance(
+        &deconstruct_line(&old.content, is_combined, false).content,
+        &deconstruct_line(&new.content, is_combined, false).content,
+    )
+}
+
+/// Check if a deleted line and the inserted line paired with it differ only
+/// in whitespace, ignoring all whitespace like `git diff -w`.
+///
+/// # Example
+///
+/// ```
+/// use diff2html::render::analysis::{LineGroup, is_whitespace_only_change};
+/// use diff2html::render::group_lines;
+/// use diff2html::{DiffParserConfig, parse};
+///
+/// let diff = "--- a/f\n+++ b/f\n@@ -1 +1 @@\n-if x {\n+if  x  {\n";
+/// let files = parse(diff, &DiffParserConfig::default());
+/// let LineGroup::Changes { deleted, inserted } = group_lines(&files[0].blocks[0])[0] else {
+///     unreachable!()
+/// };
+/// assert!(is_whitespace_only_change(&deleted[0], &inserted[0], false));
+/// ```
+pub fn is_whitespace_only_change(old: &DiffLine, new: &DiffLine, is_combined: bool) -> bool {
+    let non_whitespace = |line: &DiffLine| {
+        deconstruct_line(&line.content, is_combined, false)
+            .content
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+    };
+    old.line_type == LineType::Delete
+        && new.line_type == LineType::Insert
+        && non_whitespace(old) == non_whitespace(new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{DiffParserConfig, parse};
+
+    /// Contents of the lines of each group, context lines in the first slot.
+    fn group_contents(diff: &str) -> Vec<[Vec<String>; 3]> {
+        let files = parse(diff, &DiffParserConfig::default());
+        let contents = |lines: &[DiffLine]| -> Vec<String> {
+            lines.iter().map(|line| line.content.clone()).collect()
+        };
+        group_lines(&files[0].blocks[0])
+            .into_iter()
+            .map(|group| match group {
+                LineGroup::Context(line) => [contents(std::slice::from_ref(line)), vec![], vec![]],
+                LineGroup::Changes { deleted, inserted } => {
+                    [vec![], contents(deleted), contents(inserted)]
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_group_lines() {
+        assert_eq!(
+            group_contents(
+                "--- a/f\n+++ b/f\n@@ -1,3 +1,3 @@\n context\n-old\n+new\n another context\n"
+            ),
+            vec![
+                [vec![" context".to_string()], vec![], vec![]],
+                [vec![], vec!["-old".to_string()], vec!["+new".to_string()]],
+                [vec![" another context".to_string()], vec![], vec![]],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_group_lines_changes_without_context() {
+        let groups = group_contents("--- a/f\n+++ b/f\n@@ -1,4 +1,3 @@\n+a\n-b\n-c\n+d\n-e\n+f\n");
+        let groups: Vec<_> = groups
+            .iter()
+            .map(|group| group.each_ref().map(|lines| lines.join(",")))
+            .collect();
+
+        assert_eq!(
+            groups,
+            vec![["", "", "+a"], ["", "-b,-c", "+d"], ["", "-e", "+f"]]
+        );
+    }
+
+    #[test]
+    fn test_is_whitespace_only_change() {
+        let files = parse(
+            "--- a/f\n+++ b/f\n@@ -1,3 +1,3 @@\n-\ta b\n-c\n-d\n+  ab \n+c;\n+d\n",
+            &DiffParserConfig::default(),
+        );
+        let lines = &files[0].blocks[0].lines;
+
+        assert!(is_whitespace_only_change(&lines[0], &lines[3], false));
+        assert!(!is_whitespace_only_change(&lines[1], &lines[4], false));
+        assert!(is_whitespace_only_change(&lines[2], &lines[5], false));
+        // Only a deleted line and an inserted line are a change
+        assert!(!is_whitespace_only_change(&lines[3], &lines[0], false));
+    }
+
+    #[test]
+    fn test_match_changes() {
+        let files = parse(
+            "--- a/f\n+++ b/f\n@@ -1,2 +1,3 @@\n\
+             -let x = 1;\n-let y = 2;\n+// values\n+let x = 10;\n+let y = 20;\n",
+            &DiffParserConfig::default(),
+        );
+        let lines = &files[0].blocks[0].lines;
+        let (deleted, inserted) = lines.split_at(2);
+        let contents = |config: &RendererConfig| -> Vec<(Vec<&str>, Vec<&str>, bool)> {
+            match_changes(deleted, inserted, false, config)
+                .iter()
+                .map(|changes| {
+                    (
+                        changes.deleted.iter().map(|l| l.content.as_str()).collect(),
+                        changes
+                            .inserted
+                            .iter()
+                            .map(|l| l.content.as_str())
+                            .collect(),
+                        changes.matched,
+                    )
+                })
+                .collect()
+        };
+
+        let mut config = RendererConfig::default();
+        assert_eq!(contents(&config).len(), 1);
+
+        config.render.matching = LineMatchingType::Lines;
+        assert_eq!(
+            contents(&config),
+            vec![
+                (vec![], vec!["+// values"], false),
+                (vec!["-let x = 1;"], vec!["+let x = 10;"], true),
+                (vec!["-let y = 2;"], vec!["+let y = 20;"], true),
+            ]
+        );
+
+        // Over the limits, the run is left as is
+        config.matching_max_comparisons = 5;
+        assert_eq!(contents(&config).len(), 1);
+    }
+
+    #[test]
+    fn test_line_distance() {
+        let files = parse(
+            "--- a/f\n+++ b/f\n@@ -1,2 +1,2 @@\n-abcd\n-efgh\n+abcd\n+wxyz\n",
+            &DiffParserConfig::default(),
+        );
+        let lines = &files[0].blocks[0].lines;
+
+        assert_eq!(line_distance(&lines[0], &lines[2], false), 0.0);
+        assert_eq!(line_distance(&lines[1], &lines[3], false), 0.5);
+    }
+
+    #[test]
+    fn test_group_lines_trailing_deletions() {
+        let files = parse(
+            "--- a/f\n+++ b/f\n@@ -1,2 +1 @@\n a\n-b\n",
+            &DiffParserConfig::default(),
+        );
+        let groups = group_lines(&files[0].blocks[0]);
+
+        assert_eq!(groups.len(), 2);
+        assert!(matches!(
+            groups[1],
+            LineGroup::Changes { deleted, inserted } if deleted.len() == 1 && inserted.is_empty()
+        ));
+    }
+}
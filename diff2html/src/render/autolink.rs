@@ -0,0 +1,277 @@
+//! Links for URLs and references in line content.
+//!
+//! When [`RenderConfig::autolink`](super::RenderConfig::autolink) is set, the
+//! renderers turn `http://` and `https://` URLs and configured reference
+//! patterns, e.g. issue numbers like `#1234` or `JIRA-123`, into `<a>` links.
+//! Only the text of the escaped line content is searched, existing tags such
+//! as the highlighting of changed parts are kept as they are.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use super::utils::escape_for_html;
+
+/// URLs to link, up to the first character that cannot be part of a URL.
+static URL: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"https?://[^\s<>"'`]+"#).unwrap());
+
+/// Characters that end a sentence rather than a URL.
+const URL_TRAILING_PUNCTUATION: &[char] = &['.', ',', ';', ':', '!', '?', ')', ']', '}'];
+
+/// A pattern of references to link and the URL they link to.
+///
+/// The URL can refer to capture groups of the pattern like
+/// [`Regex::replace`], e.g. `$1` or `${id}`.
+#[derive(Debug, Clone)]
+pub struct AutolinkReference {
+    pattern: Regex,
+    url: String,
+}
+
+impl AutolinkReference {
+    /// Create a reference pattern linking matches to the given URL.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use diff2html::AutolinkReference;
+    ///
+    /// let issues = AutolinkReference::new(r"#(\d+)", "https://example.com/issues/$1").unwrap();
+    /// let jira = AutolinkReference::new(r"\b[A-Z]+-\d+\b", "https://jira.example.com/browse/$0");
+    /// assert!(jira.is_ok());
+    /// ```
+    pub fn new(pattern: &str, url: &str) -> Result<Self, regex::Error> {
+        Ok(Self {
+            pattern: Regex::new(pattern)?,
+            url: url.to_string(),
+        })
+    }
+}
+
+/// Configuration for linking URLs and references in line content.
+#[derive(Debug, Clone, Default)]
+pub struct AutolinkConfig {
+    /// Link `http://` and `https://` URLs.
+    pub urls: bool,
+    /// Reference patterns to link.
+    pub references: Vec<AutolinkReference>,
+}
+
+/// A link found in the unescaped text.
+struct Link {
+    start: usize,
+    end: usize,
+    href: String,
+}
+
+impl AutolinkConfig {
+    /// Add links to escaped line content.
+    pub(crate) fn linkify(&self, html: &str) -> String {
+        let mut result = String::with_capacity(html.len());
+        let mut rest = html;
+        let mut in_link = false;
+
+        while let Some(tag_start) = rest.find('<') {
+            let text = &rest[..tag_start];
+            if in_link {
+                result.push_str(text);
+            } else {
+                self.linkify_text(text, &mut result);
+            }
+
+            let tag_end = rest[tag_start..]
+                .find('>')
+                .map_or(rest.len(), |end| tag_start + end + 1);
+            let tag = &rest[tag_start..tag_end];
+            if tag.starts_with("<a ") || tag == "<a>" {
+                in_link = true;
+            } else if tag == "</a>" {
+                in_link = false;
+            }
+            result.push_str(tag);
+            rest = &rest[tag_end..];
+        }
+
+        if in_link {
+            result.push_str(rest);
+        } else {
+            self.linkify_text(rest, &mut result);
+        }
+        result
+    }
+
+    /// Add links to escaped text without tags.
+    fn linkify_text(&self, escaped: &str, result: &mut String) {
+        let (text, offsets) = unescape_with_offsets(escaped);
+        let links = self.find_links(&text);
+        if links.is_empty() {
+            result.push_str(escaped);
+            return;
+        }
+
+        let mut position = 0;
+        for link in links {
+            let start = offsets[link.start];
+            let end = offsets[link.end];
+            result.push_str(&escaped[position..start]);
+            result.push_str(&format!(
+                r#"<a class="d2h-autolink" href="{}" target="_blank" rel="noopener noreferrer">{}</a>"#,
+                escape_for_html(&link.href),
+                &escaped[start..end]
+            ));
+            position = end;
+        }
+        result.push_str(&escaped[position..]);
+    }
+
+    /// Find the links in a text, ordered by position and without overlaps.
+    fn find_links(&self, text: &str) -> Vec<Link> {
+        let mut links = Vec::new();
+
+        if self.urls {
+            for url in URL.find_iter(text) {
+                let url_text = url.as_str().trim_end_matches(URL_TRAILING_PUNCTUATION);
+                if url_text.len() > "https://".len() {
+                    links.push(Link {
+                        start: url.start(),
+                        end: url.start() + url_text.len(),
+                        href: url_text.to_string(),
+                    });
+                }
+            }
+        }
+
+        for reference in &self.references {
+            for caps in reference.pattern.captures_iter(text) {
+                let matched = caps.get(0).expect("group 0 is always present");
+                if matched.is_empty() {
+                    continue;
+                }
+                let mut href = String::new();
+                caps.expand(&reference.url, &mut href);
+                links.push(Link {
+                    start: matched.start(),
+                    end: matched.end(),
+                    href,
+                });
+            }
+        }
+
+        // Earlier links win over later overlapping ones, URLs over references
+        links.sort_by_key(|link| link.start);
+        let mut position = 0;
+        links.retain(|link| {
+            let keep = link.start >= position;
+            if keep {
+                position = link.end;
+            }
+            keep
+        });
+        links
+    }
+}
+
+/// Unescape text escaped by [`escape_for_html`].
+///
+/// Returns the text and for each of its byte offsets (and its end) the
+/// corresponding offset in the escaped text.
+fn unescape_with_offsets(escaped: &str) -> (String, Vec<usize>) {
+    const ENTITIES: &[(&str, char)] = &[
+        ("&amp;", '&'),
+        ("&lt;", '<'),
+        ("&gt;", '>'),
+        ("&quot;", '"'),
+        ("&#x27;", '\''),
+        ("&#x2F;", '/'),
+    ];
+
+    let mut text = String::with_capacity(escaped.len());
+    let mut offsets = Vec::with_capacity(escaped.len() + 1);
+    let mut index = 0;
+
+    while let Some(c) = escaped[index..].chars().next() {
+        let (unescaped, len) = ENTITIES
+            .iter()
+            .find(|(entity, _)| escaped[index..].starts_with(entity))
+            .map_or((c, c.len_utf8()), |(entity, unescaped)| {
+                (*unescaped, entity.len())
+            });
+
+        text.push(unescaped);
+        offsets.extend(std::iter::repeat_n(index, unescaped.len_utf8()));
+        index += len;
+    }
+    offsets.push(escaped.len());
+
+    (text, offsets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn linkify(config: &AutolinkConfig, text: &str) -> String {
+        config.linkify(&escape_for_html(text))
+    }
+
+    fn urls() -> AutolinkConfig {
+        AutolinkConfig {
+            urls: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_unescape_with_offsets() {
+        let (text, offsets) = unescape_with_offsets("a&lt;b&#x2F;é");
+        assert_eq!(text, "a<b/é");
+        assert_eq!(offsets, vec![0, 1, 5, 6, 12, 12, 14]);
+    }
+
+    #[test]
+    fn test_linkify_urls() {
+        assert_eq!(
+            linkify(&urls(), "see https://example.com/a?b=1&c=2."),
+            "see <a class=\"d2h-autolink\" href=\"https:&#x2F;&#x2F;example.com&#x2F;a?b=1&amp;c=2\" \
+             target=\"_blank\" rel=\"noopener noreferrer\">https:&#x2F;&#x2F;example.com&#x2F;a?b=1&amp;c=2</a>."
+        );
+        assert_eq!(linkify(&urls(), "no links here"), "no links here");
+        assert_eq!(linkify(&urls(), "http://"), "http:&#x2F;&#x2F;");
+        assert_eq!(
+            linkify(&AutolinkConfig::default(), "https://example.com"),
+            "https:&#x2F;&#x2F;example.com"
+        );
+    }
+
+    #[test]
+    fn test_linkify_keeps_tags() {
+        let html = "<ins>https:&#x2F;&#x2F;a.io</ins> <a href=\"x\">http:&#x2F;&#x2F;b.io</a>";
+        let result = urls().linkify(html);
+
+        assert!(
+            result.starts_with("<ins><a class=\"d2h-autolink\" href=\"https:&#x2F;&#x2F;a.io\"")
+        );
+        assert!(result.ends_with("<a href=\"x\">http:&#x2F;&#x2F;b.io</a>"));
+    }
+
+    #[test]
+    fn test_linkify_references() {
+        let config = AutolinkConfig {
+            urls: true,
+            references: vec![
+                AutolinkReference::new(r"#(\d+)", "https://example.com/issues/$1").unwrap(),
+                AutolinkReference::new(r"\b[A-Z]+-\d+\b", "https://jira.example.com/browse/$0")
+                    .unwrap(),
+            ],
+        };
+
+        let result = linkify(&config, "fix #12 and ABC-3, see https://x.io/#4");
+        assert!(result.contains(
+            "href=\"https:&#x2F;&#x2F;example.com&#x2F;issues&#x2F;12\" \
+             target=\"_blank\" rel=\"noopener noreferrer\">#12</a>"
+        ));
+        assert!(result.contains(">ABC-3</a>"));
+        assert!(result.contains(">https:&#x2F;&#x2F;x.io&#x2F;#4</a>"));
+        assert_eq!(result.matches("<a ").count(), 3);
+    }
+}
@@ -0,0 +1,159 @@
+//! Authorship annotations for diff lines.
+//!
+//! The renderers can show who last changed each line in an extra gutter next
+//! to the line numbers. Since a diff does not contain authorship data, the
+//! caller provides it through a [`BlameSource`], e.g. from `git blame`.
+
+use std::collections::HashMap;
+
+use crate::types::DiffFile;
+
+use super::utils::{RendererConfig, escape_for_html};
+
+/// Authorship of a single line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LineBlame {
+    /// Name of the author who last changed the line
+    pub author: String,
+    /// Commit that last changed the line
+    pub commit: String,
+    /// Summary of the commit message
+    pub summary: Option<String>,
+}
+
+/// Authorship of the lines of a file, by line number.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileBlame {
+    /// Authorship of the lines of the old version of the file
+    pub old_lines: HashMap<u32, LineBlame>,
+    /// Authorship of the lines of the new version of the file
+    pub new_lines: HashMap<u32, LineBlame>,
+}
+
+impl FileBlame {
+    /// Get the authorship of a line, preferring the new version of the file.
+    pub fn get(&self, old_number: Option<u32>, new_number: Option<u32>) -> Option<&LineBlame> {
+        match new_number {
+            Some(number) => self.new_lines.get(&number),
+            None => old_number.and_then(|number| self.old_lines.get(&number)),
+        }
+    }
+}
+
+/// Provider of per-line authorship data.
+///
+/// # Example
+///
+/// ```
+/// use diff2html::{BlameSource, DiffFile, FileBlame, LineBlame};
+///
+/// #[derive(Debug)]
+/// struct SingleAuthor;
+///
+/// impl BlameSource for SingleAuthor {
+///     fn blame(&self, _file: &DiffFile) -> Option<FileBlame> {
+///         let line = LineBlame {
+///             author: "Jane Doe".to_string(),
+///             commit: "1a2b3c4".to_string(),
+///             summary: None,
+///         };
+///         let mut blame = FileBlame::default();
+///         blame.new_lines.insert(1, line);
+///         Some(blame)
+///     }
+/// }
+/// ```
+pub trait BlameSource: std::fmt::Debug + Send + Sync {
+    /// Returns the authorship of the lines of a file, or `None` to render the
+    /// file without the blame gutter.
+    fn blame(&self, file: &DiffFile) -> Option<FileBlame>;
+}
+
+/// Get the authorship of a file from the configured blame source.
+pub(crate) fn get_file_blame(file: &DiffFile, config: &RendererConfig) -> Option<FileBlame> {
+    config.blame_source.as_ref()?.blame(file)
+}
+
+/// Generate the blame gutter of a line.
+///
+/// Returns an empty string if the file has no blame. Lines without authorship
+/// get an empty gutter to keep the line numbers aligned.
+pub(crate) fn make_blame_html(
+    blame: Option<&FileBlame>,
+    old_number: Option<u32>,
+    new_number: Option<u32>,
+) -> String {
+    let Some(blame) = blame else {
+        return String::new();
+    };
+
+    match blame.get(old_number, new_number) {
+        Some(line) => {
+            let short_commit: String = line.commit.chars().take(7).collect();
+            let title = match &line.summary {
+                Some(summary) => format!("{short_commit} {summary}"),
+                None => short_commit,
+            };
+            format!(
+                r#"<div class="d2h-blame" title="{}">{}</div>"#,
+                escape_for_html(&title),
+                escape_for_html(&line.author)
+            )
+        }
+        None => r#"<div class="d2h-blame"></div>"#.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blame() -> FileBlame {
+        let mut blame = FileBlame::default();
+        blame.old_lines.insert(
+            1,
+            LineBlame {
+                author: "Old <Author>".to_string(),
+                commit: "0123456789abcdef".to_string(),
+                summary: None,
+            },
+        );
+        blame.new_lines.insert(
+            1,
+            LineBlame {
+                author: "New Author".to_string(),
+                commit: "fedcba9876543210".to_string(),
+                summary: Some("Fix \"bug\"".to_string()),
+            },
+        );
+        blame
+    }
+
+    #[test]
+    fn test_file_blame_get() {
+        let blame = blame();
+        assert_eq!(blame.get(Some(1), Some(1)).unwrap().author, "New Author");
+        assert_eq!(blame.get(Some(1), None).unwrap().author, "Old <Author>");
+        assert_eq!(blame.get(Some(1), Some(2)), None);
+        assert_eq!(blame.get(None, None), None);
+    }
+
+    #[test]
+    fn test_make_blame_html() {
+        let blame = blame();
+
+        assert_eq!(make_blame_html(None, Some(1), Some(1)), "");
+        assert_eq!(
+            make_blame_html(Some(&blame), Some(1), None),
+            r#"<div class="d2h-blame" title="0123456">Old &lt;Author&gt;</div>"#
+        );
+        assert_eq!(
+            make_blame_html(Some(&blame), None, Some(1)),
+            r#"<div class="d2h-blame" title="fedcba9 Fix &quot;bug&quot;">New Author</div>"#
+        );
+        assert_eq!(
+            make_blame_html(Some(&blame), None, Some(5)),
+            r#"<div class="d2h-blame"></div>"#
+        );
+    }
+}
@@ -0,0 +1,143 @@
+//! Cache of the highlighted line pairs.
+//!
+//! Diffs of generated code often change the same line the same way many
+//! times. With [`RendererConfig::highlight_cache_size`] set, the renderers
+//! keep the most recently highlighted pairs, so identical pairs are only
+//! highlighted once.
+//!
+//! [`RendererConfig::highlight_cache_size`]: super::RendererConfig::highlight_cache_size
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use super::utils::{HighlightedLines, RenderConfig, diff_highlight};
+use crate::types::DiffStyle;
+
+/// Inputs of [`diff_highlight`] the highlighted lines depend on.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Key {
+    old: String,
+    new: String,
+    is_combined: bool,
+    diff_style: DiffStyle,
+    max_line_length_highlight: usize,
+}
+
+/// Least recently used cache of highlighted line pairs.
+#[derive(Debug)]
+pub(crate) struct HighlightCache {
+    capacity: usize,
+    /// Highlighted lines with the tick they were last used at.
+    entries: HashMap<Key, (HighlightedLines, u64)>,
+    /// Keys by the tick they were last used at, oldest first.
+    recency: BTreeMap<u64, Key>,
+    tick: u64,
+}
+
+impl HighlightCache {
+    /// Create a shared cache of `capacity` pairs, or `None` for a capacity of
+    /// 0.
+    pub(crate) fn shared(capacity: usize) -> Option<Arc<Mutex<Self>>> {
+        (capacity > 0).then(|| {
+            Arc::new(Mutex::new(Self {
+                capacity,
+                entries: HashMap::new(),
+                recency: BTreeMap::new(),
+                tick: 0,
+            }))
+        })
+    }
+
+    /// Get the highlighted lines of a pair, highlighting it if not cached.
+    fn get_or_highlight(&mut self, key: Key, config: &RenderConfig) -> HighlightedLines {
+        self.tick += 1;
+        if let Some((lines, tick)) = self.entries.get_mut(&key) {
+            let key = self.recency.remove(tick).expect("entries are in recency");
+            *tick = self.tick;
+            self.recency.insert(self.tick, key);
+            return lines.clone();
+        }
+
+        let lines = diff_highlight(&key.old, &key.new, key.is_combined, config);
+        if self.entries.len() >= self.capacity
+            && let Some((_, oldest)) = self.recency.pop_first()
+        {
+            self.entries.remove(&oldest);
+        }
+        self.recency.insert(self.tick, key.clone());
+        self.entries.insert(key, (lines.clone(), self.tick));
+        lines
+    }
+}
+
+/// Highlight the differences between two diff lines like [`diff_highlight`],
+/// through the cache if any.
+pub(crate) fn cached_diff_highlight(
+    cache: Option<&Mutex<HighlightCache>>,
+    diff_line1: &str,
+    diff_line2: &str,
+    is_combined: bool,
+    config: &RenderConfig,
+) -> HighlightedLines {
+    let Some(cache) = cache else {
+        return diff_highlight(diff_line1, diff_line2, is_combined, config);
+    };
+    let key = Key {
+        old: diff_line1.to_string(),
+        new: diff_line2.to_string(),
+        is_combined,
+        diff_style: config.diff_style,
+        max_line_length_highlight: config.max_line_length_highlight,
+    };
+    cache
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get_or_highlight(key, config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cached_diff_highlight() {
+        let cache = HighlightCache::shared(2).unwrap();
+        let config = RenderConfig::default();
+        let highlight =
+            |old: &str, new: &str| cached_diff_highlight(Some(&cache), old, new, false, &config);
+
+        let first = highlight("-a b", "+a c");
+        assert_eq!(first, diff_highlight("-a b", "+a c", false, &config));
+        assert_eq!(highlight("-a b", "+a c"), first);
+        assert_eq!(cache.lock().unwrap().entries.len(), 1);
+
+        // The least recently used pair is evicted
+        highlight("-x", "+y");
+        highlight("-a b", "+a c");
+        highlight("-1", "+2");
+        let cache = cache.lock().unwrap();
+        assert_eq!(cache.entries.len(), 2);
+        assert!(cache.recency.values().all(|key| key.old != "-x"));
+        assert_eq!(cache.recency.len(), 2);
+    }
+
+    #[test]
+    fn test_cached_diff_highlight_style() {
+        let cache = HighlightCache::shared(4).unwrap();
+        let word = RenderConfig::default();
+        let char = RenderConfig {
+            diff_style: DiffStyle::Char,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            cached_diff_highlight(Some(&cache), "-abc", "+abd", false, &word),
+            diff_highlight("-abc", "+abd", false, &word)
+        );
+        assert_eq!(
+            cached_diff_highlight(Some(&cache), "-abc", "+abd", false, &char),
+            diff_highlight("-abc", "+abd", false, &char)
+        );
+        assert!(HighlightCache::shared(0).is_none());
+    }
+}
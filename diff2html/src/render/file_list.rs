@@ -3,28 +3,88 @@
 //! This module provides a renderer for generating a summary list of changed files
 //! with their add/delete statistics.
 
+use std::collections::BTreeMap;
+
 use serde_json::json;
 
 use crate::templates::{self, TemplateName};
-use crate::types::{ColorScheme, DiffFile};
+use crate::types::{ColorScheme, DiffFile, GroupBy, HtmlIdStrategy, LanguageStats};
+
+use super::icons::{FileIcon, FileIconMatch, file_icon_html};
 
-use super::utils::{color_scheme_to_css, filename_diff, get_file_icon, get_html_id};
+use super::utils::{
+    binary_size_change, color_scheme_to_css, escape_for_html, escape_path_for_html, file_url,
+    file_url_path, filename_diff, group_files, make_html_ids, make_similarity_tag_html,
+};
 
 /// Configuration for the file list renderer.
 #[derive(Debug, Clone)]
 pub struct FileListConfig {
     pub color_scheme: ColorScheme,
+    /// Generate the same HTML across runs, platforms and Rust versions.
+    pub deterministic: bool,
+    /// HTML IDs of the file diffs linked from the list, the same as the
+    /// renderers for the links to work.
+    pub html_id_strategy: HtmlIdStrategy,
+    /// Render the list expanded, or hidden until shown by diff2html-ui.
+    pub open: bool,
+    /// Draw a bar of the changed lines by language above the list.
+    pub language_bar: bool,
+    /// Icons replacing the templates for the matching files, see [`FileIcon`].
+    pub file_icons: Vec<(FileIconMatch, FileIcon)>,
+    /// URL of the files linked next to their name, see
+    /// [`RendererConfig::file_url_template`](super::RendererConfig::file_url_template).
+    pub file_url_template: Option<String>,
+    /// Revisions of the links of the files, see
+    /// [`RendererConfig::file_url_revisions`](super::RendererConfig::file_url_revisions).
+    pub file_url_revisions: Option<(String, String)>,
+    /// Order of the files in the list and the jump menu, the same as the
+    /// groups of the renderers, see
+    /// [`RendererConfig::group_by`](super::RendererConfig::group_by).
+    pub group_by: GroupBy,
 }
 
 impl Default for FileListConfig {
     fn default() -> Self {
         Self {
             color_scheme: ColorScheme::Light,
+            deterministic: false,
+            html_id_strategy: HtmlIdStrategy::Hash,
+            open: true,
+            language_bar: false,
+            file_icons: Vec::new(),
+            file_url_template: None,
+            file_url_revisions: None,
+            group_by: GroupBy::None,
         }
     }
 }
 
+/// Number of colors of the language bar, as `d2h-language-color-<index>`
+/// classes, reused in order for more languages.
+const LANGUAGE_COLORS: usize = 8;
+
+/// Page of a diff file rendered apart from the file list, such as the pages
+/// of an output directory, linked from the list instead of the diff on the
+/// same page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilePage {
+    /// URL of the page, relative to the page of the list.
+    pub url: String,
+    /// HTML ID of the file on its page.
+    pub html_id: String,
+}
+
+/// Directory of the table of contents, with its subdirectories by name and
+/// its files, with their pages, in the order of the diff.
+#[derive(Debug, Default)]
+struct TocDirectory<'a> {
+    directories: BTreeMap<&'a str, TocDirectory<'a>>,
+    files: Vec<(&'a str, &'a DiffFile, &'a FilePage)>,
+}
+
 /// File list renderer for generating a summary of changed files.
+#[derive(Debug)]
 pub struct FileListRenderer {
     config: FileListConfig,
 }
@@ -43,23 +103,42 @@ impl FileListRenderer {
 
     /// Render a list of diff files to a summary HTML.
     pub fn render(&self, diff_files: &[DiffFile]) -> String {
-        let files_html: String = diff_files
-            .iter()
-            .map(|file| {
-                let file_icon = get_file_icon(file);
-                let file_icon_html =
-                    templates::render_by_name(&format!("icon-{}", file_icon), &json!({}));
+        self.render_with_pages(diff_files, &self.same_page(diff_files))
+    }
+
+    /// Render a list of diff files rendered on other pages, linking each
+    /// file to the page at the same index of `pages`.
+    pub fn render_with_pages(&self, diff_files: &[DiffFile], pages: &[FilePage]) -> String {
+        let files_html: String = self
+            .order(diff_files)
+            .into_iter()
+            .map(|index| (&diff_files[index], &pages[index]))
+            .map(|(file, page)| {
+                let (file_icon_html, _) = file_icon_html(file, &self.config.file_icons);
 
                 templates::render(
                     TemplateName::FileSummaryLine,
                     &json!({
-                        "fileHtmlId": get_html_id(file),
+                        "filePage": escape_for_html(&page.url),
+                        "fileHtmlId": page.html_id,
                         "oldName": file.old_name,
                         "newName": file.new_name,
-                        "fileName": filename_diff(file),
+                        "fileName": escape_path_for_html(&filename_diff(file)),
                         "deletedLines": format!("-{}", file.deleted_lines),
                         "addedLines": format!("+{}", file.added_lines),
                         "fileIcon": file_icon_html,
+                        "similarityTag": make_similarity_tag_html(file),
+                        "generated": file.is_generated == Some(true),
+                        "binary": file.is_binary == Some(true),
+                        "binarySize": binary_size_change(file),
+                        "fileUrl": self.config.file_url_template.as_ref().map(|template| {
+                            escape_path_for_html(&file_url(
+                                template,
+                                file,
+                                file_url_path(file),
+                                self.config.file_url_revisions.as_ref(),
+                            ))
+                        }),
                     }),
                 )
             })
@@ -72,15 +151,179 @@ impl FileListRenderer {
                 "colorScheme": color_scheme_to_css(self.config.color_scheme),
                 "filesNumber": diff_files.len(),
                 "files": files_html,
+                "open": self.config.open,
+                "languageBar": self.config.language_bar.then(|| self.render_language_bar(diff_files)),
             }),
         )
     }
+
+    /// Render a bar of the changed lines by language, like the language bar
+    /// of GitHub, with a legend of the languages and their shares.
+    ///
+    /// The bar is an SVG sized with attributes, allowed under a
+    /// `Content-Security-Policy` without `style-src 'unsafe-inline'`.
+    pub fn render_language_bar(&self, diff_files: &[DiffFile]) -> String {
+        let languages = LanguageStats::from_files(diff_files);
+        let total: u32 = languages.iter().map(LanguageStats::changed_lines).sum();
+        if total == 0 {
+            return String::new();
+        }
+
+        let mut offset = 0.0;
+        let languages: Vec<_> = languages
+            .iter()
+            .filter(|language| language.changed_lines() > 0)
+            .enumerate()
+            .map(|(index, language)| {
+                let percent = f64::from(language.changed_lines()) * 100.0 / f64::from(total);
+                let entry = json!({
+                    "language": escape_for_html(&language.language),
+                    "colorIndex": index % LANGUAGE_COLORS,
+                    "offset": format!("{:.2}", offset),
+                    "width": format!("{:.2}", percent),
+                    "percent": format!("{:.1}", percent),
+                    "changedLines": language.changed_lines(),
+                });
+                offset += percent;
+                entry
+            })
+            .collect();
+
+        templates::render(
+            TemplateName::FileLanguageBar,
+            &json!({ "languages": languages }),
+        )
+    }
+
+    /// Render a menu linking to each of the diff files, staying in the corner
+    /// of the page while scrolling.
+    pub fn render_jump_menu(&self, diff_files: &[DiffFile]) -> String {
+        let html_ids = self.html_ids(diff_files);
+        let files: Vec<_> = self
+            .order(diff_files)
+            .into_iter()
+            .map(|index| (&diff_files[index], &html_ids[index]))
+            .map(|(file, html_id)| {
+                json!({
+                    "fileHtmlId": html_id,
+                    "fileName": escape_for_html(&filename_diff(file)),
+                })
+            })
+            .collect();
+
+        templates::render(
+            TemplateName::FileJumpMenu,
+            &json!({
+                "colorScheme": color_scheme_to_css(self.config.color_scheme),
+                "files": files,
+            }),
+        )
+    }
+
+    /// Render a table of contents of the diff files, nested by directory, for
+    /// a sidebar next to the diff.
+    ///
+    /// Directories are sorted by name and listed before the files. Chains of
+    /// directories holding a single directory are shown as one, e.g. `src/render`.
+    pub fn render_toc(&self, diff_files: &[DiffFile]) -> String {
+        self.render_toc_with_pages(diff_files, &self.same_page(diff_files))
+    }
+
+    /// Render a table of contents of diff files rendered on other pages,
+    /// linking each file to the page at the same index of `pages`.
+    pub fn render_toc_with_pages(&self, diff_files: &[DiffFile], pages: &[FilePage]) -> String {
+        let mut root = TocDirectory::default();
+        for (file, page) in diff_files.iter().zip(pages) {
+            let path = if file.is_deleted == Some(true) {
+                &file.old_name
+            } else {
+                &file.new_name
+            };
+            let mut components: Vec<_> = path.split('/').collect();
+            let name = components.pop().unwrap_or_default();
+            let directory = components
+                .into_iter()
+                .fold(&mut root, |directory, component| {
+                    directory.directories.entry(component).or_default()
+                });
+            directory.files.push((name, file, page));
+        }
+
+        templates::render(
+            TemplateName::FileToc,
+            &json!({
+                "colorScheme": color_scheme_to_css(self.config.color_scheme),
+                "entries": self.render_toc_entries(&root),
+            }),
+        )
+    }
+
+    /// Render the subdirectories and files of a directory of the table of
+    /// contents.
+    fn render_toc_entries(&self, directory: &TocDirectory) -> String {
+        let directories = directory.directories.iter().map(|(name, mut child)| {
+            let mut name = name.to_string();
+            while child.files.is_empty() && child.directories.len() == 1 {
+                let (child_name, grandchild) = child.directories.iter().next().unwrap();
+                name = format!("{}/{}", name, child_name);
+                child = grandchild;
+            }
+            templates::render(
+                TemplateName::FileTocDirectory,
+                &json!({
+                    "name": escape_for_html(&name),
+                    "entries": self.render_toc_entries(child),
+                }),
+            )
+        });
+        let files = directory.files.iter().map(|(name, file, page)| {
+            templates::render(
+                TemplateName::FileTocFile,
+                &json!({
+                    "filePage": escape_for_html(&page.url),
+                    "fileHtmlId": page.html_id,
+                    "fileName": escape_for_html(name),
+                    "title": escape_for_html(&filename_diff(file)),
+                }),
+            )
+        });
+        directories.chain(files).collect::<Vec<_>>().join("\n")
+    }
+
+    /// Generate the HTML IDs of the diff files, the same as the renderers.
+    fn html_ids(&self, diff_files: &[DiffFile]) -> Vec<String> {
+        make_html_ids(
+            diff_files,
+            self.config.deterministic,
+            self.config.html_id_strategy,
+        )
+    }
+
+    /// Get the indexes of the diff files in the order of their groups.
+    fn order(&self, diff_files: &[DiffFile]) -> Vec<usize> {
+        group_files(diff_files, self.config.group_by)
+            .into_iter()
+            .flat_map(|(_, indexes)| indexes)
+            .collect()
+    }
+
+    /// Get the pages of the diff files rendered on the page of the list.
+    fn same_page(&self, diff_files: &[DiffFile]) -> Vec<FilePage> {
+        self.html_ids(diff_files)
+            .into_iter()
+            .map(|html_id| FilePage {
+                url: String::new(),
+                html_id,
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::parser::{DiffParserConfig, parse};
+    use crate::render::get_stable_html_id;
 
     fn sample_diff() -> &'static str {
         r#"diff --git a/test.txt b/test.txt
@@ -106,6 +349,186 @@ index 1234567..abcdefg 100644
         assert!(html.contains("test.txt"));
     }
 
+    #[test]
+    fn test_render_jump_menu() {
+        let diff = sample_diff().replace("test.txt", "<b>.txt");
+        let files = parse(&diff, &DiffParserConfig::default());
+        let renderer = FileListRenderer::new(FileListConfig {
+            deterministic: true,
+            ..Default::default()
+        });
+        let html = renderer.render_jump_menu(&files);
+
+        assert!(html.contains("d2h-file-jump"));
+        assert!(html.contains(&format!(
+            r##"<a href="#{}" class="d2h-file-jump-link">&lt;b&gt;.txt</a>"##,
+            get_stable_html_id(&files[0])
+        )));
+    }
+
+    #[test]
+    fn test_render_language_bar() {
+        let diff = "--- a/a.rs\n+++ b/a.rs\n@@ -1 +1,3 @@\n-a\n+b\n+c\n+d\n\
+                    --- a/b.md\n+++ b/b.md\n@@ -1 +0,0 @@\n-a\n";
+        let files = parse(diff, &DiffParserConfig::default());
+
+        assert!(
+            !FileListRenderer::default()
+                .render(&files)
+                .contains("d2h-language-bar")
+        );
+        let html = FileListRenderer::new(FileListConfig {
+            language_bar: true,
+            ..Default::default()
+        })
+        .render(&files);
+        assert!(html.contains(r#"<rect class="d2h-language-color-0" x="0.00%" width="80.00%""#));
+        assert!(html.contains(r#"<rect class="d2h-language-color-1" x="80.00%" width="20.00%""#));
+        assert!(html.contains(
+            r#"<span class="d2h-language-name">md</span> <span class="d2h-language-percent">20.0%</span>"#
+        ));
+    }
+
+    #[test]
+    fn test_render_custom_file_icons() {
+        let files = parse(sample_diff(), &DiffParserConfig::default());
+        let renderer = FileListRenderer::new(FileListConfig {
+            file_icons: vec![(
+                FileIconMatch::Path("*.txt".to_string()),
+                FileIcon {
+                    icon: Some(r#"<img class="d2h-icon" src="text.svg">"#.to_string()),
+                    ..Default::default()
+                },
+            )],
+            ..Default::default()
+        });
+        let html = renderer.render(&files);
+
+        assert!(html.contains(r#"<img class="d2h-icon" src="text.svg">"#));
+        assert!(!html.contains("<svg"));
+    }
+
+    #[test]
+    fn test_render_binary_file() {
+        let diff = "diff --git a/logo.png b/logo.png\n\
+                    index 1234567..abcdefa 100644\n\
+                    GIT binary patch\n\
+                    literal 13414\n\
+                    zcmeAS\n\
+                    \n\
+                    literal 12698\n\
+                    zcmeAS\n";
+        let files = parse(diff, &DiffParserConfig::default());
+        let html = FileListRenderer::default().render(&files);
+
+        assert!(html.contains(r#"<span class="d2h-tag d2h-binary-tag">BIN</span>"#));
+        assert!(html.contains("12.4 KB \u{2192} 13.1 KB"));
+        assert!(!html.contains("d2h-lines-added"));
+
+        let diff = "diff --git a/logo.png b/logo.png\n\
+                    index 1234567..abcdefa 100644\n\
+                    Binary files a/logo.png and b/logo.png differ\n";
+        let files = parse(diff, &DiffParserConfig::default());
+        let html = FileListRenderer::default().render(&files);
+
+        assert!(html.contains("d2h-binary-tag"));
+        assert!(!html.contains("d2h-binary-size"));
+    }
+
+    #[test]
+    fn test_render_rename_similarity() {
+        let diff = "diff --git a/old.txt b/new.txt\n\
+                    similarity index 98%\n\
+                    rename from old.txt\n\
+                    rename to new.txt\n";
+        let files = parse(diff, &DiffParserConfig::default());
+        let html = FileListRenderer::default().render(&files);
+
+        assert!(html.contains("d2h-similarity-tag"));
+        assert!(html.contains(">R98%<"));
+    }
+
+    #[test]
+    fn test_render_toc_omitted_files() {
+        let diff = "--- a/a.txt\n+++ b/a.txt\n@@ -1 +1 @@\n-a\n+b\n\
+                    --- a/b.txt\n+++ b/b.txt\n@@ -1 +1 @@\n-c\n+d\n";
+        let config = DiffParserConfig {
+            diff_max_files: Some(1),
+            ..Default::default()
+        };
+        let result = crate::parser::parse_with_diagnostics(diff, &config).unwrap();
+        assert_eq!(result.omitted_files, 1);
+        let html = FileListRenderer::default().render_toc(&result.files);
+
+        // The omitted files are counted apart, without an entry to link
+        assert_eq!(html.matches("class=\"d2h-toc-link\"").count(), 1);
+        assert!(html.contains("a.txt") && !html.contains("b.txt"));
+        assert!(!html.contains("not shown"));
+    }
+
+    #[test]
+    fn test_render_with_pages() {
+        let diff = "--- a/a.txt\n+++ b/a.txt\n@@ -1 +1 @@\n-a\n+b\n\
+                    --- a/a.txt\n+++ b/a.txt\n@@ -1 +1 @@\n-c\n+d\n";
+        let files = parse(diff, &DiffParserConfig::default());
+        let pages = ["a.txt.html", "a.txt-2.html"].map(|url| FilePage {
+            url: url.to_string(),
+            html_id: "d2h-a".to_string(),
+        });
+        let renderer = FileListRenderer::default();
+
+        for html in [
+            renderer.render_with_pages(&files, &pages),
+            renderer.render_toc_with_pages(&files, &pages),
+        ] {
+            assert!(html.contains(r##"href="a.txt.html#d2h-a""##));
+            assert!(html.contains(r##"href="a.txt-2.html#d2h-a""##));
+            assert!(!html.contains(r##"href="#"##));
+        }
+    }
+
+    #[test]
+    fn test_render_toc() {
+        let diff = "--- a/src/render/a.rs\n+++ b/src/render/a.rs\n@@ -1 +1 @@\n-a\n+b\n\
+                    --- a/README.md\n+++ b/README.md\n@@ -1 +1 @@\n-a\n+b\n\
+                    --- a/src/render/<b>.rs\n+++ b/src/render/<b>.rs\n@@ -1 +1 @@\n-a\n+b\n\
+                    --- a/docs/x.md\n+++ b/docs/x.md\n@@ -1 +1 @@\n-a\n+b\n";
+        let files = parse(diff, &DiffParserConfig::default());
+        let renderer = FileListRenderer::new(FileListConfig {
+            deterministic: true,
+            ..Default::default()
+        });
+        let html = renderer.render_toc(&files);
+
+        let outline: Vec<_> = html
+            .lines()
+            .map(str::trim)
+            .filter_map(|line| {
+                line.strip_prefix(r#"<span class="d2h-toc-directory-name">"#)
+                    .map(|name| name.trim_end_matches("</span>").to_string())
+                    .or_else(|| {
+                        let name = line.strip_suffix("</a></li>")?;
+                        Some(format!("- {}", &name[name.rfind('>')? + 1..]))
+                    })
+            })
+            .collect();
+        assert_eq!(
+            outline,
+            [
+                "docs",
+                "- x.md",
+                "src&#x2F;render",
+                "- a.rs",
+                "- &lt;b&gt;.rs",
+                "- README.md"
+            ]
+        );
+        assert!(html.contains(&format!(
+            r##"<a href="#{}" class="d2h-toc-link" title="README.md">"##,
+            get_stable_html_id(&files[1])
+        )));
+    }
+
     #[test]
     fn test_render_empty_file_list() {
         let renderer = FileListRenderer::default();
@@ -139,6 +562,31 @@ diff --git a/file2.txt b/file2.txt
         assert!(html.contains("file2.txt"));
     }
 
+    #[test]
+    fn test_render_group_by_order() {
+        let diff = "diff --git a/old.txt b/old.txt\ndeleted file mode 100644\n\
+                    --- a/old.txt\n+++ /dev/null\n@@ -1 +0,0 @@\n-a\n\
+                    diff --git a/kept.txt b/kept.txt\n\
+                    --- a/kept.txt\n+++ b/kept.txt\n@@ -1 +1 @@\n-a\n+b\n\
+                    diff --git a/new.txt b/new.txt\nnew file mode 100644\n\
+                    --- /dev/null\n+++ b/new.txt\n@@ -0,0 +1 @@\n+a\n";
+        let files = parse(diff, &DiffParserConfig::default());
+        let renderer = FileListRenderer::new(FileListConfig {
+            group_by: GroupBy::Status,
+            ..Default::default()
+        });
+
+        // Added, modified then deleted files, like the groups of the diffs
+        for html in [renderer.render(&files), renderer.render_jump_menu(&files)] {
+            let new = html.find("new.txt").unwrap();
+            let kept = html.find("kept.txt").unwrap();
+            let old = html.find("old.txt").unwrap();
+            assert!(new < kept && kept < old);
+        }
+        let html = FileListRenderer::default().render(&files);
+        assert!(html.find("old.txt").unwrap() < html.find("new.txt").unwrap());
+    }
+
     #[test]
     fn test_render_with_line_counts() {
         let diff = r#"diff --git a/test.txt b/test.txt
@@ -219,10 +667,24 @@ rename to new-name.txt
         assert!(html.contains("old-name.txt") || html.contains("new-name.txt"));
     }
 
+    #[test]
+    fn test_closed_file_list() {
+        let html = FileListRenderer::default().render(&[]);
+        assert!(html.contains(r#"<ol class="d2h-file-list">"#));
+
+        let renderer = FileListRenderer::new(FileListConfig {
+            open: false,
+            ..Default::default()
+        });
+        let html = renderer.render(&[]);
+        assert!(html.contains(r#"<ol class="d2h-file-list" hidden>"#));
+    }
+
     #[test]
     fn test_color_scheme_configuration() {
         let config = FileListConfig {
             color_scheme: ColorScheme::Dark,
+            ..Default::default()
         };
         let renderer = FileListRenderer::new(config);
         let html = renderer.render(&[]);
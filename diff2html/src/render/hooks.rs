@@ -0,0 +1,154 @@
+//! Extension points for customizing the rendered HTML.
+//!
+//! Applications implement these traits and set them on the
+//! [`RendererConfig`](super::RendererConfig) to add their own HTML to the
+//! output without overriding templates or post-processing the HTML.
+
+use crate::types::{DiffFile, DiffLine};
+
+use super::utils::RendererConfig;
+
+/// Provider of extra HTML for the header of each file.
+///
+/// The HTML is inserted as-is next to the file name, so it can contain
+/// buttons, links or badges. Any text taken from the diff must be escaped by
+/// the implementation, e.g. with [`escape_for_html`](super::escape_for_html).
+///
+/// # Example
+///
+/// ```
+/// use diff2html::{DiffFile, FileHeaderExtra};
+/// use diff2html::render::escape_for_html;
+///
+/// #[derive(Debug)]
+/// struct OpenInEditor;
+///
+/// impl FileHeaderExtra for OpenInEditor {
+///     fn html(&self, file: &DiffFile) -> String {
+///         format!(
+///             r#"<a href="editor://open?path={}">Open in editor</a>"#,
+///             escape_for_html(&file.new_name)
+///         )
+///     }
+/// }
+/// ```
+pub trait FileHeaderExtra: std::fmt::Debug + Send + Sync {
+    /// Returns the HTML to add to the header of a file, or an empty string to
+    /// add nothing.
+    fn html(&self, file: &DiffFile) -> String;
+}
+
+/// Transformation of the rendered HTML.
+///
+/// Hooks are called by the line-by-line and side-by-side renderers with the
+/// HTML they produced and return the HTML to use instead. Both methods return
+/// the HTML unchanged by default, so a hook only implements what it needs.
+/// Hooks configured together run in order, each one getting the output of the
+/// previous one.
+///
+/// # Example
+///
+/// ```
+/// use diff2html::{DiffLine, RenderHook};
+///
+/// /// Highlights TODO comments in the line content.
+/// #[derive(Debug)]
+/// struct HighlightTodos;
+///
+/// impl RenderHook for HighlightTodos {
+///     fn on_line_content(&self, _line: &DiffLine, html: String) -> String {
+///         html.replace("TODO", "<mark>TODO</mark>")
+///     }
+/// }
+/// ```
+pub trait RenderHook: std::fmt::Debug + Send + Sync {
+    /// Transform the HTML of the content of a line, without its prefix.
+    ///
+    /// The content is already escaped and may contain highlighting of the
+    /// changed parts.
+    fn on_line_content(&self, line: &DiffLine, html: String) -> String {
+        let _ = line;
+        html
+    }
+
+    /// Transform the HTML of a whole file, including its header.
+    fn on_file_html(&self, file: &DiffFile, html: String) -> String {
+        let _ = file;
+        html
+    }
+}
+
+/// Run the configured line content hooks.
+///
+/// Links are added before the hooks run, so hooks see them.
+pub(crate) fn apply_line_content_hooks(
+    config: &RendererConfig,
+    line: &DiffLine,
+    html: String,
+) -> String {
+    let html = match &config.render.autolink {
+        Some(autolink) => autolink.linkify(&html),
+        None => html,
+    };
+    config
+        .render_hooks
+        .iter()
+        .fold(html, |html, hook| hook.on_line_content(line, html))
+}
+
+/// Run the configured file hooks.
+pub(crate) fn apply_file_html_hooks(
+    config: &RendererConfig,
+    file: &DiffFile,
+    html: String,
+) -> String {
+    config
+        .render_hooks
+        .iter()
+        .fold(html, |html, hook| hook.on_file_html(file, html))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[derive(Debug)]
+    struct Wrap(&'static str);
+
+    impl RenderHook for Wrap {
+        fn on_line_content(&self, _line: &DiffLine, html: String) -> String {
+            format!("<{0}>{html}</{0}>", self.0)
+        }
+    }
+
+    #[derive(Debug)]
+    struct Noop;
+
+    impl RenderHook for Noop {}
+
+    #[test]
+    fn test_hooks_run_in_order() {
+        let config = RendererConfig {
+            render_hooks: vec![Arc::new(Wrap("a")), Arc::new(Noop), Arc::new(Wrap("b"))],
+            ..Default::default()
+        };
+        let line = DiffLine {
+            line_type: crate::types::LineType::Context,
+            content: " x".to_string(),
+            old_number: Some(1),
+            new_number: Some(1),
+            no_newline_at_eof: false,
+            word_diff: None,
+        };
+
+        assert_eq!(
+            apply_line_content_hooks(&config, &line, "x".to_string()),
+            "<b><a>x</a></b>"
+        );
+        assert_eq!(
+            apply_file_html_hooks(&config, &DiffFile::default(), "<div></div>".to_string()),
+            "<div></div>"
+        );
+    }
+}
@@ -0,0 +1,145 @@
+//! Custom icons and tags of the files.
+//!
+//! The file headers and the file list show an icon and a tag for the status
+//! of each file, rendered from the `icon-<status>` and `tag-<status>`
+//! templates named by [`get_file_icon`]. [`RendererConfig::file_icons`]
+//! replaces them for the files with a status or matching a path, e.g. with
+//! another SVG set or localized tags.
+//!
+//! [`RendererConfig::file_icons`]: super::RendererConfig::file_icons
+
+use serde_json::json;
+
+use super::utils::get_file_icon;
+use crate::parser::path_matches;
+use crate::templates;
+use crate::types::DiffFile;
+
+/// Files whose icon or tag is replaced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileIconMatch {
+    /// Files with a status, named like the icon templates, e.g. `file-added`,
+    /// see [`get_file_icon`].
+    Status(String),
+    /// Files whose path matches a path or glob, matched like
+    /// [`DiffParserConfig::exclude`](crate::DiffParserConfig::exclude).
+    Path(String),
+}
+
+impl FileIconMatch {
+    /// Check if a file is matched.
+    fn matches(&self, file: &DiffFile) -> bool {
+        match self {
+            Self::Status(status) => status == get_file_icon(file),
+            Self::Path(pattern) => {
+                let path = if file.is_deleted == Some(true) {
+                    &file.old_name
+                } else {
+                    &file.new_name
+                };
+                path_matches(path, pattern)
+            }
+        }
+    }
+}
+
+/// Icon and tag HTML of the matching files. Parts left to `None` keep the
+/// HTML of the templates.
+///
+/// # Example
+///
+/// ```
+/// use diff2html::render::{FileIcon, FileIconMatch, LineByLineRenderer, RendererConfig};
+/// use diff2html::{DiffParserConfig, parse};
+///
+/// let renderer = LineByLineRenderer::new(RendererConfig {
+///     file_icons: vec![(
+///         FileIconMatch::Status("file-added".to_string()),
+///         FileIcon {
+///             tag: Some(r#"<span class="d2h-tag d2h-added d2h-added-tag">NEU</span>"#.to_string()),
+///             ..Default::default()
+///         },
+///     )],
+///     ..Default::default()
+/// });
+/// let diff = "diff --git a/a.txt b/a.txt\nnew file mode 100644\n\
+///             --- /dev/null\n+++ b/a.txt\n@@ -0,0 +1 @@\n+a\n";
+/// let html = renderer.render(&parse(diff, &DiffParserConfig::default()));
+/// assert!(html.contains(">NEU</span>"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileIcon {
+    /// HTML of the icon, inserted as is.
+    pub icon: Option<String>,
+    /// HTML of the tag, inserted as is, or an empty string for no tag.
+    pub tag: Option<String>,
+}
+
+/// Render the icon and the tag of a file, with the parts of the matching
+/// custom icons replacing the templates, later ones first.
+pub(crate) fn file_icon_html(
+    file: &DiffFile,
+    file_icons: &[(FileIconMatch, FileIcon)],
+) -> (String, String) {
+    let mut icon = None;
+    let mut tag = None;
+    for (_, file_icon) in file_icons.iter().filter(|(m, _)| m.matches(file)) {
+        icon = file_icon.icon.as_ref().or(icon);
+        tag = file_icon.tag.as_ref().or(tag);
+    }
+
+    let status = get_file_icon(file);
+    (
+        icon.cloned()
+            .unwrap_or_else(|| templates::render_by_name(&format!("icon-{}", status), &json!({}))),
+        tag.cloned()
+            .unwrap_or_else(|| templates::render_by_name(&format!("tag-{}", status), &json!({}))),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(old_name: &str, new_name: &str) -> DiffFile {
+        DiffFile {
+            old_name: old_name.to_string(),
+            new_name: new_name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_file_icon_html() {
+        let file_icons = vec![
+            (
+                FileIconMatch::Status("file-renamed".to_string()),
+                FileIcon {
+                    icon: Some("<i>moved</i>".to_string()),
+                    tag: Some("<b>VERSCHOBEN</b>".to_string()),
+                },
+            ),
+            (
+                FileIconMatch::Path("*.md".to_string()),
+                FileIcon {
+                    tag: Some(String::new()),
+                    ..Default::default()
+                },
+            ),
+        ];
+
+        let (icon, tag) = file_icon_html(&file("a.rs", "b.rs"), &file_icons);
+        assert_eq!(
+            (icon.as_str(), tag.as_str()),
+            ("<i>moved</i>", "<b>VERSCHOBEN</b>")
+        );
+
+        // Later matches win, part by part
+        let (icon, tag) = file_icon_html(&file("a.md", "b.md"), &file_icons);
+        assert_eq!((icon.as_str(), tag.as_str()), ("<i>moved</i>", ""));
+
+        let (icon, tag) = file_icon_html(&file("a.rs", "a.rs"), &file_icons);
+        assert!(icon.contains("d2h-icon"));
+        assert!(tag.contains("CHANGED"));
+    }
+}
@@ -0,0 +1,227 @@
+//! Image previews for binary files.
+//!
+//! The renderers can embed the old and new versions of binary image files
+//! instead of the generic binary file header. Since a diff does not contain
+//! the image data, the caller provides it through an [`ImageSource`].
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde_json::json;
+
+use crate::templates::{self, TemplateName};
+use crate::types::{DiffFile, OutputMarkup};
+
+use super::utils::{CSSLineClass, RendererConfig, escape_for_html};
+
+/// File extensions that are rendered as image previews, with their MIME type.
+const IMAGE_TYPES: &[(&str, &str)] = &[
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("bmp", "image/bmp"),
+    ("ico", "image/x-icon"),
+    ("svg", "image/svg+xml"),
+];
+
+/// Image `src` values for the old and new version of a file.
+///
+/// Each value is used as-is for the `src` attribute of an `<img>` element, so
+/// it can be a data URI (see [`image_data_uri`]), a relative path or a URL.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImagePreview {
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+/// Provider of image contents for binary image previews.
+///
+/// # Example
+///
+/// ```
+/// use diff2html::{DiffFile, ImagePreview, ImageSource};
+///
+/// #[derive(Debug)]
+/// struct StaticImages;
+///
+/// impl ImageSource for StaticImages {
+///     fn preview(&self, file: &DiffFile) -> Option<ImagePreview> {
+///         Some(ImagePreview {
+///             before: Some(format!("old/{}", file.old_name)),
+///             after: Some(format!("new/{}", file.new_name)),
+///         })
+///     }
+/// }
+/// ```
+pub trait ImageSource: std::fmt::Debug + Send + Sync {
+    /// Returns the images to show for a binary image file, or `None` to fall
+    /// back to the regular binary file rendering.
+    fn preview(&self, file: &DiffFile) -> Option<ImagePreview>;
+}
+
+/// Get the MIME type of an image file name based on its extension.
+pub fn image_mime_type(filename: &str) -> Option<&'static str> {
+    let (_, extension) = filename.rsplit_once('.')?;
+    IMAGE_TYPES
+        .iter()
+        .find(|(ext, _)| ext.eq_ignore_ascii_case(extension))
+        .map(|(_, mime)| *mime)
+}
+
+/// Check if a diff file is a binary image that can be previewed.
+pub fn is_image_file(file: &DiffFile) -> bool {
+    let name = if file.is_deleted == Some(true) {
+        &file.old_name
+    } else {
+        &file.new_name
+    };
+    file.is_binary == Some(true) && image_mime_type(name).is_some()
+}
+
+/// Encode image bytes as a base64 data URI, using the file name to determine
+/// the MIME type.
+///
+/// Returns `None` if the file name does not have a known image extension.
+pub fn image_data_uri(filename: &str, bytes: &[u8]) -> Option<String> {
+    let mime = image_mime_type(filename)?;
+    Some(format!("data:{};base64,{}", mime, STANDARD.encode(bytes)))
+}
+
+/// Get the image preview of a file from the configured image source.
+///
+/// Returns `None` if no image source is configured, the file is not a binary
+/// image or the source has no image for either side.
+pub(crate) fn get_image_preview(file: &DiffFile, config: &RendererConfig) -> Option<ImagePreview> {
+    let source = config.image_source.as_ref()?;
+    if !is_image_file(file) {
+        return None;
+    }
+    source
+        .preview(file)
+        .filter(|preview| preview.before.is_some() || preview.after.is_some())
+}
+
+/// Generate an image preview row with the given before and after images.
+pub(crate) fn make_image_preview_html(
+    before: Option<&str>,
+    after: Option<&str>,
+    content_class: &str,
+    markup: OutputMarkup,
+) -> String {
+    let images: Vec<_> = [
+        (before, "d2h-image-before", "Before"),
+        (after, "d2h-image-after", "After"),
+    ]
+    .into_iter()
+    .filter_map(|(src, class_name, label)| {
+        src.map(|src| {
+            json!({
+                "className": class_name,
+                "src": escape_for_html(src),
+                "label": label,
+            })
+        })
+    })
+    .collect();
+
+    templates::render(
+        TemplateName::GenericImagePreview.for_markup(markup),
+        &json!({
+            "contentClass": content_class,
+            "CSSLineClass": {
+                "INFO": CSSLineClass::Info.as_str(),
+            },
+            "images": images,
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_image_mime_type() {
+        assert_eq!(image_mime_type("logo.png"), Some("image/png"));
+        assert_eq!(image_mime_type("photo.JPG"), Some("image/jpeg"));
+        assert_eq!(image_mime_type("icon.svg"), Some("image/svg+xml"));
+        assert_eq!(image_mime_type("archive.zip"), None);
+        assert_eq!(image_mime_type("png"), None);
+    }
+
+    #[test]
+    fn test_is_image_file() {
+        let mut file = DiffFile {
+            old_name: "logo.png".to_string(),
+            new_name: "logo.png".to_string(),
+            ..Default::default()
+        };
+        assert!(!is_image_file(&file));
+
+        file.is_binary = Some(true);
+        assert!(is_image_file(&file));
+
+        file.new_name = "/dev/null".to_string();
+        file.is_deleted = Some(true);
+        assert!(is_image_file(&file));
+
+        file.old_name = "data.bin".to_string();
+        assert!(!is_image_file(&file));
+    }
+
+    #[test]
+    fn test_image_data_uri() {
+        assert_eq!(
+            image_data_uri("a.gif", b"GIF89a").as_deref(),
+            Some("data:image/gif;base64,R0lGODlh")
+        );
+        assert_eq!(image_data_uri("a.txt", b"text"), None);
+    }
+
+    #[derive(Debug)]
+    struct TestImages;
+
+    impl ImageSource for TestImages {
+        fn preview(&self, file: &DiffFile) -> Option<ImagePreview> {
+            (file.new_name != "missing.png").then(|| ImagePreview {
+                before: None,
+                after: Some(file.new_name.clone()),
+            })
+        }
+    }
+
+    #[test]
+    fn test_get_image_preview() {
+        let mut file = DiffFile {
+            old_name: "logo.png".to_string(),
+            new_name: "logo.png".to_string(),
+            is_binary: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(get_image_preview(&file, &RendererConfig::default()), None);
+
+        let config = RendererConfig {
+            image_source: Some(std::sync::Arc::new(TestImages)),
+            ..Default::default()
+        };
+        let preview = get_image_preview(&file, &config).unwrap();
+        assert_eq!(preview.after.as_deref(), Some("logo.png"));
+
+        file.new_name = "missing.png".to_string();
+        assert_eq!(get_image_preview(&file, &config), None);
+    }
+
+    #[test]
+    fn test_make_image_preview_html() {
+        let html = make_image_preview_html(
+            None,
+            Some("data:image/png;base64,AA=="),
+            "d2h-code-line",
+            OutputMarkup::Table,
+        );
+        assert!(html.contains("d2h-image-after"));
+        assert!(!html.contains("d2h-image-before"));
+        assert!(html.contains("data:image&#x2F;png;base64,AA=="));
+    }
+}
@@ -3,19 +3,39 @@
 //! This module provides a single-column renderer that shows all diff lines
 //! sequentially with old and new line numbers.
 
+use std::borrow::Cow;
+use std::sync::{Arc, Mutex};
+
 use serde_json::json;
 
-use crate::templates::{self, TemplateName};
-use crate::types::{DiffBlock, DiffFile, DiffLine, LineType};
+use crate::templates::{self, GenericLine, LineNumbers, TemplateName};
+use crate::types::{DiffFile, DiffLine, LineType, WhitespaceChanges};
 
+use super::analysis::{
+    LineGroup, MatchedChanges, group_lines, is_whitespace_only_change, match_changes,
+    take_highlight,
+};
+use super::blame::{FileBlame, get_file_blame, make_blame_html};
+use super::cache::{HighlightCache, cached_diff_highlight};
+use super::hooks::{apply_file_html_hooks, apply_line_content_hooks};
+use super::image::{ImagePreview, get_image_preview, make_image_preview_html};
+use super::overrides::file_config;
+use super::tree::{
+    RenderElement, RenderTree, TreeLine, block_header_row, block_line_pairs, code_table,
+    collapsed_row, file_wrapper, message_row, tree_line_row, wrap_files,
+};
 use super::utils::{
-    CSSLineClass, RendererConfig, color_scheme_to_css, deconstruct_line, diff_highlight,
-    escape_for_html, filename_diff, get_file_icon, get_html_id, to_css_class,
+    CSSLineClass, FileModeChange, RendererConfig, color_scheme_to_css, deconstruct_line,
+    empty_diff_message, escape_for_html, format_block_header, get_file_mode_change,
+    join_file_diffs, make_collapsed_diff_html, make_file_path_html, make_html_ids,
+    prepare_changed_line, with_no_newline_marker, with_truncated_content,
 };
 
 /// Line-by-line renderer for generating single-column diff HTML.
+#[derive(Debug)]
 pub struct LineByLineRenderer {
     config: RendererConfig,
+    highlight_cache: Option<Arc<Mutex<HighlightCache>>>,
 }
 
 impl Default for LineByLineRenderer {
@@ -27,23 +47,26 @@ impl Default for LineByLineRenderer {
 impl LineByLineRenderer {
     /// Create a new LineByLineRenderer with the given configuration.
     pub fn new(config: RendererConfig) -> Self {
-        Self { config }
+        let highlight_cache = HighlightCache::shared(config.highlight_cache_size);
+        Self {
+            config,
+            highlight_cache,
+        }
     }
 
     /// Render a list of diff files to HTML.
     pub fn render(&self, diff_files: &[DiffFile]) -> String {
-        let diffs_html: String = diff_files
-            .iter()
-            .map(|file| {
-                let diffs = if !file.blocks.is_empty() {
-                    self.generate_file_html(file)
-                } else {
-                    self.generate_empty_diff()
-                };
-                self.make_file_diff_html(file, &diffs)
-            })
-            .collect::<Vec<_>>()
-            .join("\n");
+        let html_ids = make_html_ids(
+            diff_files,
+            self.config.deterministic,
+            self.config.html_id_strategy,
+        );
+        let diffs_html = join_file_diffs(diff_files, self.config.group_by, |index, file| {
+            self.render_file(file, &html_ids[index])
+        });
+        if self.config.no_wrapper {
+            return diffs_html;
+        }
 
         templates::render(
             TemplateName::GenericWrapper,
@@ -54,165 +77,307 @@ impl LineByLineRenderer {
         )
     }
 
+    /// Render a list of diff files as a tree of elements instead of HTML.
+    ///
+    /// See [`RenderTree`] for the parts of the HTML left out.
+    pub fn render_tree(&self, diff_files: &[DiffFile]) -> RenderTree {
+        let html_ids = make_html_ids(
+            diff_files,
+            self.config.deterministic,
+            self.config.html_id_strategy,
+        );
+        let files = diff_files
+            .iter()
+            .zip(&html_ids)
+            .filter_map(|(file, html_id)| self.render_file_tree(file, html_id))
+            .collect();
+        wrap_files(files, &self.config)
+    }
+
+    /// Render a file as a tree with the given HTML ID, and with the
+    /// configuration overridden for it if any, or `None` if nothing is
+    /// rendered for it.
+    pub(crate) fn render_file_tree(&self, file: &DiffFile, html_id: &str) -> Option<RenderElement> {
+        match file_config(&self.config, file) {
+            Cow::Borrowed(_) => self.make_file_diff_tree(file, html_id),
+            Cow::Owned(config) => Self {
+                config,
+                highlight_cache: self.highlight_cache.clone(),
+            }
+            .make_file_diff_tree(file, html_id),
+        }
+    }
+
+    /// Render a file as a tree with the configuration of the renderer.
+    fn make_file_diff_tree(&self, file: &DiffFile, html_id: &str) -> Option<RenderElement> {
+        if self.config.render_nothing_when_empty
+            && file.blocks.is_empty()
+            && file.is_mode_change != Some(true)
+        {
+            return None;
+        }
+
+        let rows = if !file.blocks.is_empty() {
+            self.generate_file_rows(file)
+        } else if let Some(change) =
+            get_file_mode_change(file).filter(|_| file.is_mode_change == Some(true))
+        {
+            let message = format!(
+                "File mode changed from {} to {}",
+                change.old_mode, change.new_mode
+            );
+            vec![message_row("d2h-code-line", &message)]
+        } else {
+            let message = empty_diff_message(file, &self.config);
+            vec![message_row("d2h-code-line", message)]
+        };
+        let diff = RenderElement::new("div")
+            .with_class("d2h-file-diff")
+            .with_child(code_table(rows));
+        Some(file_wrapper(file, html_id, &self.config, diff))
+    }
+
+    /// Generate the rows of all blocks in a file for its tree.
+    fn generate_file_rows(&self, file: &DiffFile) -> Vec<RenderElement> {
+        let (line_class, content_class) = ("d2h-code-linenumber", "d2h-code-line");
+        let mut rows = Vec::new();
+        if file.is_collapsed == Some(true) {
+            rows.push(collapsed_row(line_class, content_class, true));
+        }
+
+        for block in &file.blocks {
+            rows.push(block_header_row(
+                &block.header,
+                Some(file),
+                line_class,
+                content_class,
+                &self.config,
+            ));
+            for pairs in block_line_pairs(block, file.is_combined, &self.config) {
+                // Insertions follow the deletions of their group, unless
+                // interleaved, and come before hidden whitespace changes
+                let mut left = Vec::new();
+                let mut right = Vec::new();
+                for pair in pairs {
+                    if pair.context {
+                        left.append(&mut right);
+                        if let (Some(old), Some(new)) = (pair.old, pair.new) {
+                            left.push(TreeLine {
+                                old_number: old.old_number,
+                                ..new
+                            });
+                        }
+                    } else if self.config.interleave_changes {
+                        left.extend(pair.old.into_iter().chain(pair.new));
+                    } else {
+                        left.extend(pair.old);
+                        right.extend(pair.new);
+                    }
+                }
+                rows.extend(left.into_iter().chain(right).map(|line| {
+                    let line_numbers = LineNumbers::OldAndNew(line.old_number, line.new_number);
+                    tree_line_row(line, line_class, content_class, line_numbers)
+                }));
+            }
+        }
+        rows
+    }
+
+    /// Render a file with the given HTML ID, and with the configuration
+    /// overridden for it if any, without wrapper.
+    pub(crate) fn render_file(&self, file: &DiffFile, html_id: &str) -> String {
+        match file_config(&self.config, file) {
+            Cow::Borrowed(_) => self.render_file_html(file, html_id),
+            Cow::Owned(config) => Self {
+                config,
+                highlight_cache: self.highlight_cache.clone(),
+            }
+            .render_file_html(file, html_id),
+        }
+    }
+
+    /// Render a file with the configuration of the renderer.
+    fn render_file_html(&self, file: &DiffFile, html_id: &str) -> String {
+        let diffs = if let Some(preview) = get_image_preview(file, &self.config) {
+            self.generate_image_diff(&preview)
+        } else if !file.blocks.is_empty() {
+            self.generate_file_html(file)
+        } else if let Some(change) =
+            get_file_mode_change(file).filter(|_| file.is_mode_change == Some(true))
+        {
+            self.generate_mode_change_diff(&change)
+        } else {
+            self.generate_empty_diff(file)
+        };
+        self.make_file_diff_html(file, &diffs, html_id)
+    }
+
     /// Generate the HTML for a single file diff.
-    fn make_file_diff_html(&self, file: &DiffFile, diffs: &str) -> String {
-        if self.config.render_nothing_when_empty && file.blocks.is_empty() {
+    fn make_file_diff_html(&self, file: &DiffFile, diffs: &str, html_id: &str) -> String {
+        if self.config.render_nothing_when_empty
+            && file.blocks.is_empty()
+            && file.is_mode_change != Some(true)
+        {
             return String::new();
         }
 
-        let file_icon = get_file_icon(file);
-        let file_icon_html = templates::render_by_name(&format!("icon-{}", file_icon), &json!({}));
-        let file_tag_html = templates::render_by_name(&format!("tag-{}", file_icon), &json!({}));
+        let file_path_html = make_file_path_html(file, &self.config);
 
-        let file_path_html = templates::render(
-            TemplateName::GenericFilePath,
+        let html = templates::render(
+            TemplateName::LineByLineFileDiff.for_markup(self.config.output_markup),
             &json!({
-                "fileDiffName": filename_diff(file),
-                "fileIcon": file_icon_html,
-                "fileTag": file_tag_html,
+                "file": {
+                    "language": escape_for_html(&file.language),
+                },
+                "fileHtmlId": html_id,
+                "stickyHeaders": self.config.sticky_headers,
+                "wrapLines": self.config.wrap_lines,
+                "diffs": diffs,
+                "filePath": file_path_html,
             }),
         );
 
+        apply_file_html_hooks(&self.config, file, html)
+    }
+
+    /// Generate HTML for an empty diff (file with no changes).
+    fn generate_empty_diff(&self, file: &DiffFile) -> String {
         templates::render(
-            TemplateName::LineByLineFileDiff,
+            TemplateName::GenericEmptyDiff.for_markup(self.config.output_markup),
             &json!({
-                "file": {
-                    "language": file.language,
+                "contentClass": "d2h-code-line",
+                "message": escape_for_html(empty_diff_message(file, &self.config)),
+                "CSSLineClass": {
+                    "INFO": CSSLineClass::Info.as_str(),
                 },
-                "fileHtmlId": get_html_id(file),
-                "diffs": diffs,
-                "filePath": file_path_html,
             }),
         )
     }
 
-    /// Generate HTML for an empty diff (file with no changes).
-    fn generate_empty_diff(&self) -> String {
+    /// Generate HTML for a file whose permissions changed without content changes.
+    fn generate_mode_change_diff(&self, change: &FileModeChange) -> String {
         templates::render(
-            TemplateName::GenericEmptyDiff,
+            TemplateName::GenericModeChange.for_markup(self.config.output_markup),
             &json!({
                 "contentClass": "d2h-code-line",
                 "CSSLineClass": {
                     "INFO": CSSLineClass::Info.as_str(),
                 },
+                "oldMode": change.old_mode,
+                "newMode": change.new_mode,
             }),
         )
     }
 
+    /// Generate HTML showing the old and new version of a binary image.
+    fn generate_image_diff(&self, preview: &ImagePreview) -> String {
+        make_image_preview_html(
+            preview.before.as_deref(),
+            preview.after.as_deref(),
+            "d2h-code-line",
+            self.config.output_markup,
+        )
+    }
+
     /// Generate HTML for all blocks in a file.
     fn generate_file_html(&self, file: &DiffFile) -> String {
-        file.blocks
+        let blame = get_file_blame(file, &self.config);
+        let blocks_html = file
+            .blocks
             .iter()
             .map(|block| {
                 let mut lines = templates::render(
-                    TemplateName::GenericBlockHeader,
+                    TemplateName::GenericBlockHeader.for_markup(self.config.output_markup),
                     &json!({
                         "CSSLineClass": {
                             "INFO": CSSLineClass::Info.as_str(),
                         },
-                        "blockHeader": if file.is_too_big == Some(true) {
-                            block.header.clone()
-                        } else {
-                            escape_for_html(&block.header)
-                        },
+                        "blockHeader": format_block_header(&block.header, Some(file), self.config.hardened),
+                        "sticky": self.config.sticky_headers,
                         "lineClass": "d2h-code-linenumber",
                         "contentClass": "d2h-code-line",
                     }),
                 );
 
-                for (context_lines, old_lines, new_lines) in self.apply_line_grouping(block) {
-                    if !old_lines.is_empty() && !new_lines.is_empty() && context_lines.is_empty() {
-                        // Changed lines - apply diff highlighting
-                        let (left, right) = self.process_changed_lines(
-                            file,
-                            file.is_combined,
-                            &old_lines,
-                            &new_lines,
-                        );
-                        lines.push_str(&left);
-                        lines.push_str(&right);
-                    } else if !context_lines.is_empty() {
-                        // Context lines
-                        for line in &context_lines {
+                // Pairs of lines left to highlight in the block
+                let mut highlight_budget = self
+                    .config
+                    .max_highlight_pairs_per_block
+                    .unwrap_or(usize::MAX);
+                for group in group_lines(block) {
+                    match group {
+                        LineGroup::Context(line) => {
                             let parts = deconstruct_line(&line.content, file.is_combined, true);
-                            lines.push_str(&self.generate_single_line_html(
+                            self.generate_single_line_html(
+                                &mut lines,
                                 CSSLineClass::Context,
                                 &parts.prefix,
-                                &parts.content,
-                                line.old_number,
-                                line.new_number,
-                            ));
+                                &with_no_newline_marker(
+                                    apply_line_content_hooks(
+                                        &self.config,
+                                        line,
+                                        with_truncated_content(
+                                            parts.content,
+                                            line,
+                                            file.is_combined,
+                                            &self.config,
+                                        ),
+                                    ),
+                                    line,
+                                ),
+                                line,
+                                blame.as_ref(),
+                            );
+                        }
+                        LineGroup::Changes { deleted, inserted } => {
+                            // Deletions paired with insertions get diff highlighting
+                            for changes in
+                                match_changes(deleted, inserted, file.is_combined, &self.config)
+                            {
+                                let (left, right) = self.process_changed_lines(
+                                    file.is_combined,
+                                    changes,
+                                    &mut highlight_budget,
+                                    blame.as_ref(),
+                                );
+                                lines.push_str(&left);
+                                lines.push_str(&right);
+                            }
                         }
-                    } else if !old_lines.is_empty() || !new_lines.is_empty() {
-                        // Only deletions or only insertions
-                        let (left, right) = self.process_changed_lines(
-                            file,
-                            file.is_combined,
-                            &old_lines,
-                            &new_lines,
-                        );
-                        lines.push_str(&left);
-                        lines.push_str(&right);
                     }
                 }
 
                 lines
             })
             .collect::<Vec<_>>()
-            .join("\n")
-    }
-
-    /// Group lines in a block by type (context, deletions, insertions).
-    fn apply_line_grouping(
-        &self,
-        block: &DiffBlock,
-    ) -> Vec<(Vec<DiffLine>, Vec<DiffLine>, Vec<DiffLine>)> {
-        let mut groups: Vec<(Vec<DiffLine>, Vec<DiffLine>, Vec<DiffLine>)> = Vec::new();
-        let mut old_lines: Vec<DiffLine> = Vec::new();
-        let mut new_lines: Vec<DiffLine> = Vec::new();
-
-        for line in &block.lines {
-            // Flush accumulated lines when we hit a context line or switch patterns
-            if (line.line_type != LineType::Insert && !new_lines.is_empty())
-                || (line.line_type == LineType::Context && !old_lines.is_empty())
-            {
-                groups.push((Vec::new(), old_lines.clone(), new_lines.clone()));
-                old_lines.clear();
-                new_lines.clear();
-            }
-
-            match line.line_type {
-                LineType::Context => {
-                    groups.push((vec![line.clone()], Vec::new(), Vec::new()));
-                }
-                LineType::Insert if old_lines.is_empty() => {
-                    groups.push((Vec::new(), Vec::new(), vec![line.clone()]));
-                }
-                LineType::Insert => {
-                    new_lines.push(line.clone());
-                }
-                LineType::Delete => {
-                    old_lines.push(line.clone());
-                }
-            }
-        }
+            .join("\n");
 
-        // Flush any remaining lines
-        if !old_lines.is_empty() || !new_lines.is_empty() {
-            groups.push((Vec::new(), old_lines, new_lines));
+        if file.is_collapsed == Some(true) {
+            let toggle = make_collapsed_diff_html(
+                "d2h-code-linenumber",
+                "d2h-code-line",
+                true,
+                self.config.output_markup,
+            );
+            format!("{toggle}\n{blocks_html}")
+        } else {
+            blocks_html
         }
-
-        groups
     }
-
     /// Process changed lines by pairing deletions with insertions and highlighting differences.
+    ///
+    /// With `interleave_changes`, each insertion is written right after the
+    /// deletion it is paired with, so the inserted lines are all on the left.
+    /// Each highlighted pair uses one of `highlight_budget`, and pairs past
+    /// the budget are left without highlighting.
     fn process_changed_lines(
         &self,
-        _file: &DiffFile,
         is_combined: bool,
-        old_lines: &[DiffLine],
-        new_lines: &[DiffLine],
+        changes: MatchedChanges,
+        highlight_budget: &mut usize,
+        blame: Option<&FileBlame>,
     ) -> (String, String) {
+        let (old_lines, new_lines) = (changes.deleted, changes.inserted);
         let mut left = String::new();
         let mut right = String::new();
 
@@ -222,94 +387,147 @@ impl LineByLineRenderer {
             let old_line = old_lines.get(i);
             let new_line = new_lines.get(i);
 
-            let diff = match (old_line, new_line) {
-                (Some(old), Some(new)) => Some(diff_highlight(
-                    &old.content,
-                    &new.content,
-                    is_combined,
-                    &self.config.render,
-                )),
-                _ => None,
+            let (old_diff, new_diff) = match (old_line, new_line) {
+                (Some(old), Some(new))
+                    if take_highlight(
+                        old,
+                        new,
+                        &changes,
+                        highlight_budget,
+                        is_combined,
+                        &self.config,
+                    ) =>
+                {
+                    let diff = cached_diff_highlight(
+                        self.highlight_cache.as_deref(),
+                        &old.content,
+                        &new.content,
+                        is_combined,
+                        &self.config.render,
+                    );
+                    (Some(diff.old_line), Some(diff.new_line))
+                }
+                _ => (None, None),
+            };
+
+            let whitespace_only = match (old_line, new_line) {
+                (Some(old), Some(new))
+                    if self.config.whitespace_changes != WhitespaceChanges::Show =>
+                {
+                    is_whitespace_only_change(old, new, is_combined)
+                }
+                _ => false,
             };
+            if whitespace_only
+                && self.config.whitespace_changes == WhitespaceChanges::Hide
+                && let (Some(old), Some(new)) = (old_line, new_line)
+            {
+                // Render the new line as a context line, numbered in both
+                // versions, after the insertions before it
+                left.push_str(&std::mem::take(&mut right));
+                let line = DiffLine {
+                    line_type: LineType::Context,
+                    old_number: old.old_number,
+                    ..new.clone()
+                };
+                let parts = deconstruct_line(&line.content, is_combined, true);
+                let content = apply_line_content_hooks(
+                    &self.config,
+                    &line,
+                    with_truncated_content(parts.content, &line, is_combined, &self.config),
+                );
+                self.generate_single_line_html(
+                    &mut left,
+                    CSSLineClass::Context,
+                    &" ".repeat(parts.prefix.len()),
+                    &with_no_newline_marker(content, &line),
+                    &line,
+                    blame,
+                );
+                continue;
+            }
 
             // Process old line
             if let Some(old) = old_line.filter(|o| o.old_number.is_some()) {
-                let (css_class, prefix, content) = if let Some(ref diff) = diff {
-                    (
-                        CSSLineClass::DeleteChanges,
-                        diff.old_line.prefix.clone(),
-                        diff.old_line.content.clone(),
-                    )
+                let (css_class, prefix, content) = if let Some(parts) = old_diff {
+                    let css_class = if whitespace_only {
+                        CSSLineClass::DeleteWhitespaceOnly
+                    } else {
+                        CSSLineClass::DeleteChanges
+                    };
+                    (css_class, parts.prefix, parts.content)
                 } else {
-                    let parts = deconstruct_line(&old.content, is_combined, true);
-                    (to_css_class(old.line_type), parts.prefix, parts.content)
+                    let (css_class, parts) = prepare_changed_line(old, is_combined);
+                    (css_class, parts.prefix, parts.content)
                 };
+                let content = apply_line_content_hooks(
+                    &self.config,
+                    old,
+                    with_truncated_content(content, old, is_combined, &self.config),
+                );
+                let content = with_no_newline_marker(content, old);
 
-                left.push_str(&self.generate_single_line_html(
-                    css_class,
-                    &prefix,
-                    &content,
-                    old.old_number,
-                    old.new_number,
-                ));
+                self.generate_single_line_html(&mut left, css_class, &prefix, &content, old, blame);
             }
 
             // Process new line
             if let Some(new) = new_line.filter(|n| n.new_number.is_some()) {
-                let (css_class, prefix, content) = if let Some(ref diff) = diff {
-                    (
-                        CSSLineClass::InsertChanges,
-                        diff.new_line.prefix.clone(),
-                        diff.new_line.content.clone(),
-                    )
+                let (css_class, prefix, content) = if let Some(parts) = new_diff {
+                    let css_class = if whitespace_only {
+                        CSSLineClass::InsertWhitespaceOnly
+                    } else {
+                        CSSLineClass::InsertChanges
+                    };
+                    (css_class, parts.prefix, parts.content)
                 } else {
-                    let parts = deconstruct_line(&new.content, is_combined, true);
-                    (to_css_class(new.line_type), parts.prefix, parts.content)
+                    let (css_class, parts) = prepare_changed_line(new, is_combined);
+                    (css_class, parts.prefix, parts.content)
                 };
+                let content = apply_line_content_hooks(
+                    &self.config,
+                    new,
+                    with_truncated_content(content, new, is_combined, &self.config),
+                );
+                let content = with_no_newline_marker(content, new);
 
-                right.push_str(&self.generate_single_line_html(
-                    css_class,
-                    &prefix,
-                    &content,
-                    new.old_number,
-                    new.new_number,
-                ));
+                let out = if self.config.interleave_changes {
+                    &mut left
+                } else {
+                    &mut right
+                };
+                self.generate_single_line_html(out, css_class, &prefix, &content, new, blame);
             }
         }
 
         (left, right)
     }
 
-    /// Generate HTML for a single diff line.
+    /// Write the HTML for a single diff line into a buffer.
     fn generate_single_line_html(
         &self,
+        out: &mut String,
         css_class: CSSLineClass,
         prefix: &str,
         content: &str,
-        old_number: Option<u32>,
-        new_number: Option<u32>,
-    ) -> String {
-        let line_number_html = templates::render(
-            TemplateName::LineByLineNumbers,
-            &json!({
-                "oldNumber": old_number.map(|n| n.to_string()).unwrap_or_default(),
-                "newNumber": new_number.map(|n| n.to_string()).unwrap_or_default(),
-            }),
-        );
-
+        line: &DiffLine,
+        blame: Option<&FileBlame>,
+    ) {
         let display_prefix = if prefix == " " { "&nbsp;" } else { prefix };
-
-        templates::render(
-            TemplateName::GenericLine,
-            &json!({
-                "type": css_class.as_str(),
-                "lineClass": "d2h-code-linenumber",
-                "contentClass": "d2h-code-line",
-                "prefix": display_prefix,
-                "content": content,
-                "lineNumber": line_number_html,
-            }),
-        )
+        let gutter = make_blame_html(blame, line.old_number, line.new_number);
+
+        templates::write_generic_line(
+            out,
+            &GenericLine {
+                line_type: css_class.as_str(),
+                line_class: "d2h-code-linenumber",
+                content_class: "d2h-code-line",
+                prefix: display_prefix,
+                content,
+                gutter: &gutter,
+                line_numbers: LineNumbers::OldAndNew(line.old_number, line.new_number),
+                markup: self.config.output_markup,
+            },
+        );
     }
 }
 
@@ -317,6 +535,40 @@ impl LineByLineRenderer {
 mod tests {
     use super::*;
     use crate::parser::{DiffParserConfig, parse};
+    use crate::render::{FileConfigOverride, RenderConfig};
+    use crate::types::{GroupBy, LineMatchingType};
+
+    /// Get the classes of the code cell and the unescaped text of the
+    /// rendered lines.
+    fn code_lines(html: &str) -> Vec<(String, String)> {
+        let line = regex::Regex::new(
+            r#"(?s)<td class="([^"]*)">\s*<div class="d2h-code-line">\s*<span class="d2h-code-line-prefix">[^<]*</span>\s*<span class="d2h-code-line-ctn">(.*?)</span>\s*</div>"#,
+        )
+        .unwrap();
+        let tag = regex::Regex::new("<[^>]*>").unwrap();
+        line.captures_iter(html)
+            .map(|caps| {
+                let text = tag
+                    .replace_all(&caps[2], "")
+                    .replace("&lt;", "<")
+                    .replace("&gt;", ">")
+                    .replace("&quot;", "\"")
+                    .replace("&#x27;", "'")
+                    .replace("&#x2F;", "/")
+                    .replace("&amp;", "&");
+                (caps[1].to_string(), text)
+            })
+            .collect()
+    }
+
+    /// Get the text of the rendered lines with a class.
+    fn line_texts(html: &str, class: &str) -> Vec<String> {
+        code_lines(html)
+            .into_iter()
+            .filter(|(classes, _)| classes.split(' ').any(|name| name == class))
+            .map(|(_, text)| text)
+            .collect()
+    }
 
     fn sample_diff() -> &'static str {
         r#"diff --git a/test.txt b/test.txt
@@ -342,6 +594,28 @@ index 1234567..abcdefg 100644
         assert!(html.contains("test.txt"));
     }
 
+    #[test]
+    fn test_render_group_by() {
+        let diff = "--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1 +1 @@\n-a\n+b\n\
+                    diff --git a/new.txt b/new.txt\nnew file mode 100644\n\
+                    --- /dev/null\n+++ b/new.txt\n@@ -0,0 +1 @@\n+a\n";
+        let files = parse(diff, &DiffParserConfig::default());
+
+        let html = LineByLineRenderer::default().render(&files);
+        assert!(!html.contains("d2h-group-header"));
+
+        let renderer = LineByLineRenderer::new(RendererConfig {
+            group_by: GroupBy::Status,
+            ..Default::default()
+        });
+        let html = renderer.render(&files);
+        let added = html.find(">Added<").unwrap();
+        let modified = html.find(">Modified<").unwrap();
+        assert!(added < html.find("new.txt").unwrap());
+        assert!(html.find("new.txt").unwrap() < modified);
+        assert!(modified < html.find("src/lib.rs").unwrap());
+    }
+
     #[test]
     fn test_render_empty_files() {
         let renderer = LineByLineRenderer::default();
@@ -350,6 +624,181 @@ index 1234567..abcdefg 100644
         assert!(html.contains("d2h-wrapper"));
     }
 
+    #[test]
+    fn test_render_interleave_changes() {
+        let diff = "--- a/a.txt\n+++ b/a.txt\n@@ -1,3 +1,2 @@\n-a\n-b\n-c\n+A\n+B\n";
+        let files = parse(diff, &DiffParserConfig::default());
+        let lines = |renderer: LineByLineRenderer| -> Vec<String> {
+            code_lines(&renderer.render(&files))
+                .into_iter()
+                .map(|(_, text)| text)
+                .collect()
+        };
+
+        assert_eq!(
+            lines(LineByLineRenderer::default()),
+            ["a", "b", "c", "A", "B"]
+        );
+        let renderer = LineByLineRenderer::new(RendererConfig {
+            interleave_changes: true,
+            ..Default::default()
+        });
+        assert_eq!(lines(renderer), ["a", "A", "b", "B", "c"]);
+    }
+
+    #[test]
+    fn test_render_whitespace_changes() {
+        let diff = "--- a/a.txt\n+++ b/a.txt\n@@ -1,2 +1,2 @@\n-if x {\n-a\n+if  x {\n+b\n";
+        let files = parse(diff, &DiffParserConfig::default());
+        let render = |whitespace_changes| {
+            let renderer = LineByLineRenderer::new(RendererConfig {
+                whitespace_changes,
+                ..Default::default()
+            });
+            renderer.render(&files)
+        };
+
+        let html = render(WhitespaceChanges::Show);
+        assert!(!html.contains("d2h-whitespace-only"));
+
+        let html = render(WhitespaceChanges::Mark);
+        assert_eq!(
+            line_texts(&html, "d2h-whitespace-only"),
+            ["if x {", "if  x {"]
+        );
+
+        let html = render(WhitespaceChanges::Hide);
+        let lines: Vec<_> = code_lines(&html)
+            .into_iter()
+            .map(|(_, text)| text)
+            .collect();
+        assert_eq!(lines, ["if  x {", "a", "b"]);
+        assert_eq!(line_texts(&html, "d2h-cntx"), ["if  x {"]);
+        assert!(
+            regex::Regex::new(
+                r#"d2h-cntx">\s*<div class="line-num1">1</div>\s*<div class="line-num2">1</div>"#
+            )
+            .unwrap()
+            .is_match(&html)
+        );
+    }
+
+    #[test]
+    fn test_render_whitespace_changes_hide_after_insertion() {
+        let diff = "--- a/a.txt\n+++ b/a.txt\n@@ -1,2 +1,2 @@\n-a\n-if x {\n+b\n+if  x {\n";
+        let files = parse(diff, &DiffParserConfig::default());
+        let renderer = LineByLineRenderer::new(RendererConfig {
+            whitespace_changes: WhitespaceChanges::Hide,
+            ..Default::default()
+        });
+        let html = renderer.render(&files);
+
+        let new_numbers: Vec<_> = regex::Regex::new(r#"class="line-num2">\s*(\d*)\s*<"#)
+            .unwrap()
+            .captures_iter(&html)
+            .map(|caps| caps[1].to_string())
+            .collect();
+        assert_eq!(new_numbers, ["", "1", "2"]);
+    }
+
+    #[test]
+    fn test_render_matching_highlights_shifted_lines() {
+        let diff = "--- a/a.rs\n+++ b/a.rs\n@@ -1,2 +1,3 @@\n\
+                    -let x = 1;\n-let y = 2;\n+// values\n+let x = 10;\n+let y = 20;\n";
+        let files = parse(diff, &DiffParserConfig::default());
+        let changed = |matching| -> Vec<String> {
+            let renderer = LineByLineRenderer::new(RendererConfig {
+                render: RenderConfig {
+                    matching,
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+            line_texts(&renderer.render(&files), "d2h-change")
+        };
+
+        // By position, the comment is compared with the first deleted line
+        assert_eq!(
+            changed(LineMatchingType::None),
+            ["let x = 1;", "let y = 2;", "// values", "let x = 10;"]
+        );
+        assert_eq!(
+            changed(LineMatchingType::Lines),
+            ["let x = 1;", "let x = 10;", "let y = 2;", "let y = 20;"]
+        );
+    }
+
+    #[test]
+    fn test_render_max_highlight_pairs_per_block() {
+        let diff = "--- a/a.txt\n+++ b/a.txt\n@@ -1,3 +1,3 @@\n-a 1\n-b 1\n-c 1\n+a 2\n+b 2\n+c 2\n\
+                    @@ -10 +10 @@\n-d 1\n+d 2\n";
+        let files = parse(diff, &DiffParserConfig::default());
+        let renderer = LineByLineRenderer::new(RendererConfig {
+            max_highlight_pairs_per_block: Some(2),
+            ..Default::default()
+        });
+        let html = renderer.render(&files);
+
+        // Two pairs of the first block and the pair of the second one
+        assert_eq!(html.matches("<ins>").count(), 3);
+        assert_eq!(html.matches("class=\"d2h-ins d2h-change\"").count(), 3);
+        assert_eq!(html.matches("class=\"d2h-ins\"").count(), 1);
+    }
+
+    #[test]
+    fn test_render_per_file_overrides() {
+        let diff = "--- a/Cargo.lock\n+++ b/Cargo.lock\n@@ -1 +1 @@\n-a 1\n+a 2\n\
+                    --- a/src/main.rs\n+++ b/src/main.rs\n@@ -1 +1 @@\n-b 1\n+b 2\n";
+        let files = parse(diff, &DiffParserConfig::default());
+        let renderer = LineByLineRenderer::new(RendererConfig {
+            per_file_overrides: vec![(
+                "*.lock".to_string(),
+                FileConfigOverride {
+                    highlight: Some(false),
+                    collapsed: Some(true),
+                    ..Default::default()
+                },
+            )],
+            ..Default::default()
+        });
+        let html = renderer.render(&files);
+        let wrappers: Vec<_> = html.split("class=\"d2h-file-wrapper").skip(1).collect();
+        let checked = |index: usize| wrappers[index].contains(r#"value="viewed" checked>"#);
+
+        assert_eq!(wrappers.len(), 2);
+        assert!(!wrappers[0].contains("d2h-change\""));
+        assert!(checked(0));
+        assert!(wrappers[1].contains("d2h-change\""));
+        assert!(!checked(1));
+    }
+
+    #[test]
+    fn test_render_generated_file() {
+        let mut files = parse(
+            "--- a/gen.rs\n+++ b/gen.rs\n@@ -1 +1 @@\n-a\n+b\n",
+            &DiffParserConfig::default(),
+        );
+        files[0].is_generated = Some(true);
+        let html = LineByLineRenderer::new(RendererConfig::default()).render(&files);
+
+        assert!(html.contains(r#"<span class="d2h-tag d2h-generated-tag">GENERATED</span>"#));
+        assert!(html.contains(r#"value="viewed" checked>"#));
+    }
+
+    #[test]
+    fn test_render_no_wrapper() {
+        let diff = "--- a/a.txt\n+++ b/a.txt\n@@ -1 +1 @@\n-a\n+b\n";
+        let files = parse(diff, &DiffParserConfig::default());
+        let renderer = LineByLineRenderer::new(RendererConfig {
+            no_wrapper: true,
+            ..Default::default()
+        });
+        let html = renderer.render(&files);
+
+        assert!(html.starts_with("<div id=\"d2h-"));
+        assert!(!html.contains("class=\"d2h-wrapper"));
+    }
+
     #[test]
     fn test_render_nothing_when_empty() {
         let config = RendererConfig {
@@ -373,66 +822,42 @@ index 1234567..abcdefg 100644
     #[test]
     fn test_generate_empty_diff() {
         let renderer = LineByLineRenderer::default();
-        let html = renderer.generate_empty_diff();
+        let html = renderer.generate_empty_diff(&DiffFile::default());
 
         assert!(html.contains("File without changes"));
     }
 
     #[test]
-    fn test_line_grouping() {
-        let renderer = LineByLineRenderer::default();
-        let block = DiffBlock {
-            old_start_line: 1,
-            old_start_line2: None,
-            new_start_line: 1,
-            header: "@@ -1,3 +1,3 @@".to_string(),
-            lines: vec![
-                DiffLine {
-                    line_type: LineType::Context,
-                    content: " context".to_string(),
-                    old_number: Some(1),
-                    new_number: Some(1),
-                },
-                DiffLine {
-                    line_type: LineType::Delete,
-                    content: "-old".to_string(),
-                    old_number: Some(2),
-                    new_number: None,
-                },
-                DiffLine {
-                    line_type: LineType::Insert,
-                    content: "+new".to_string(),
-                    old_number: None,
-                    new_number: Some(2),
-                },
-                DiffLine {
-                    line_type: LineType::Context,
-                    content: " another context".to_string(),
-                    old_number: Some(3),
-                    new_number: Some(3),
-                },
-            ],
-        };
-
-        let groups = renderer.apply_line_grouping(&block);
+    fn test_render_messages() {
+        let diff = "Binary files a/logo.png and b/logo.png differ\n\
+                    diff --git a/a.txt b/b.txt\nsimilarity index 100%\n\
+                    rename from a.txt\nrename to b.txt\n";
+        let files = parse(diff, &DiffParserConfig::default());
 
-        // Should have: context, (old+new pair), context
-        assert_eq!(groups.len(), 3);
+        let html = LineByLineRenderer::default().render(&files);
+        assert!(html.contains("Binary files differ"));
+        assert!(html.contains("File without changes"));
 
-        // First group is context
-        assert_eq!(groups[0].0.len(), 1);
-        assert!(groups[0].1.is_empty());
-        assert!(groups[0].2.is_empty());
+        let renderer = LineByLineRenderer::new(RendererConfig {
+            empty_diff_message: "Keine Änderungen".to_string(),
+            binary_diff_message: "<Binär>".to_string(),
+            ..Default::default()
+        });
+        let html = renderer.render(&files);
+        assert!(html.contains("Keine Änderungen"));
+        assert!(html.contains("&lt;Binär&gt;"));
+        assert!(!html.contains("File without changes"));
+    }
 
-        // Second group is old+new
-        assert!(groups[1].0.is_empty());
-        assert_eq!(groups[1].1.len(), 1);
-        assert_eq!(groups[1].2.len(), 1);
+    #[test]
+    fn test_render_mode_only_change() {
+        let diff = "diff --git a/script.sh b/script.sh\nold mode 100644\nnew mode 100755\n";
+        let files = parse(diff, &DiffParserConfig::default());
+        let renderer = LineByLineRenderer::default();
+        let html = renderer.render(&files);
 
-        // Third group is context
-        assert_eq!(groups[2].0.len(), 1);
-        assert!(groups[2].1.is_empty());
-        assert!(groups[2].2.is_empty());
+        assert!(html.contains("File mode changed from 100644 to 100755"));
+        assert!(!html.contains("File without changes"));
     }
 
     #[test]
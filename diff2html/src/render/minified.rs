@@ -0,0 +1,132 @@
+//! Detection of minified files.
+//!
+//! Minified bundles and stylesheets are often a single line of hundreds of
+//! kilobytes, which freezes browsers once highlighted and laid out. The files
+//! detected by [`RendererConfig::minified_files`] are rendered with their
+//! lines truncated and without highlighting, and tagged in their header.
+//!
+//! [`RendererConfig::minified_files`]: super::RendererConfig::minified_files
+
+use super::utils::prefix_length;
+use crate::types::DiffFile;
+
+/// Thresholds of the minified file detection.
+///
+/// # Example
+///
+/// ```
+/// use diff2html::render::{LineByLineRenderer, MinifiedFileDetection, RendererConfig};
+/// use diff2html::{DiffParserConfig, parse};
+///
+/// let renderer = LineByLineRenderer::new(RendererConfig {
+///     minified_files: Some(MinifiedFileDetection {
+///         max_rendered_line_length: 10,
+///         ..Default::default()
+///     }),
+///     ..Default::default()
+/// });
+/// let line = "a=1;".repeat(100);
+/// let diff = format!("--- a/app.min.js\n+++ b/app.min.js\n@@ -1 +1 @@\n-{line}\n+{line}b\n");
+/// let html = renderer.render(&parse(&diff, &DiffParserConfig::default()));
+/// assert!(html.contains("d2h-minified-tag"));
+/// assert!(html.contains("d2h-truncated"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinifiedFileDetection {
+    /// Files whose lines are longer than this number of characters on
+    /// average are minified.
+    pub average_line_length: usize,
+    /// Files of a single line in both versions, with a single hunk covering
+    /// the whole file, are minified when the line is longer than this number
+    /// of characters.
+    pub single_line_length: usize,
+    /// Number of columns rendered of the lines of minified files, see
+    /// [`RendererConfig::max_rendered_line_length`](super::RendererConfig::max_rendered_line_length).
+    pub max_rendered_line_length: usize,
+}
+
+impl Default for MinifiedFileDetection {
+    fn default() -> Self {
+        Self {
+            average_line_length: 500,
+            single_line_length: 200,
+            max_rendered_line_length: 1000,
+        }
+    }
+}
+
+impl MinifiedFileDetection {
+    /// Check if a file looks minified, from the lines of its diff.
+    pub fn is_minified(&self, file: &DiffFile) -> bool {
+        let prefix = prefix_length(file.is_combined);
+        let lines = file.blocks.iter().flat_map(|block| &block.lines);
+        let (mut count, mut total_length, mut max_length) = (0, 0, 0);
+        for line in lines {
+            let length = line.content.get(prefix..).unwrap_or("").chars().count();
+            count += 1;
+            total_length += length;
+            max_length = max_length.max(length);
+        }
+        if count == 0 {
+            return false;
+        }
+        // The hunk of a one-line file starts at its first line, or at 0 for
+        // an added or deleted file
+        let single_line = matches!(
+            file.blocks.as_slice(),
+            [block] if block.old_start_line <= 1
+                && block.new_start_line <= 1
+                && block.old_lines.unwrap_or(1) <= 1
+                && block.new_lines.unwrap_or(1) <= 1
+        );
+
+        total_length / count > self.average_line_length
+            || (single_line && max_length > self.single_line_length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{DiffParserConfig, parse};
+
+    fn file(diff: &str) -> DiffFile {
+        parse(diff, &DiffParserConfig::default()).remove(0)
+    }
+
+    #[test]
+    fn test_is_minified() {
+        let detection = MinifiedFileDetection {
+            average_line_length: 20,
+            single_line_length: 10,
+            max_rendered_line_length: 5,
+        };
+        let long = "x".repeat(30);
+        let medium = "x".repeat(15);
+
+        // Long lines on average
+        let diff = format!("--- a/f\n+++ b/f\n@@ -1,2 +1,2 @@\n a\n-{long}\n+{long}{long}\n");
+        assert!(detection.is_minified(&file(&diff)));
+
+        // A single line
+        let diff = format!("--- a/f\n+++ b/f\n@@ -1 +1 @@\n-{medium}\n+{medium}y\n");
+        assert!(detection.is_minified(&file(&diff)));
+        let diff = format!("--- /dev/null\n+++ b/f\n@@ -0,0 +1 @@\n+{medium}\n");
+        assert!(detection.is_minified(&file(&diff)));
+
+        // A single changed line of a longer file
+        let diff = format!("--- a/f\n+++ b/f\n@@ -50 +50 @@\n-{medium}\n+{medium}y\n");
+        assert!(!detection.is_minified(&file(&diff)));
+        let diff =
+            format!("--- a/f\n+++ b/f\n@@ -1 +1 @@\n-{medium}\n+{medium}y\n@@ -9 +9 @@\n-a\n+b\n");
+        assert!(!detection.is_minified(&file(&diff)));
+
+        // Lines of medium length
+        let diff = format!("--- a/f\n+++ b/f\n@@ -1,2 +1,2 @@\n {medium}\n-{medium}\n+{medium}y\n");
+        assert!(!detection.is_minified(&file(&diff)));
+
+        // A short single line
+        let diff = "--- a/VERSION\n+++ b/VERSION\n@@ -1 +1 @@\n-1.0\n+1.1\n";
+        assert!(!detection.is_minified(&file(diff)));
+    }
+}
@@ -1,10 +1,11 @@
 //! HTML rendering for diff2html.
 //!
 //! This module provides renderers for converting parsed diff files into HTML.
-//! Three renderers are available:
+//! Four renderers are available:
 //!
 //! - [`LineByLineRenderer`]: Single-column view showing all changes sequentially
 //! - [`SideBySideRenderer`]: Two-column view showing old and new files side by side
+//! - [`SwitchableRenderer`]: Both views of each file, with buttons switching between them
 //! - [`FileListRenderer`]: Summary list of changed files with statistics
 //!
 //! # Example
@@ -36,16 +37,43 @@
 //! let html = renderer.render(&files);
 //! ```
 
+pub mod analysis;
+pub mod autolink;
+pub mod blame;
+mod cache;
 pub mod file_list;
+pub mod hooks;
+pub mod icons;
+pub mod image;
 pub mod line_by_line;
+pub mod minified;
+pub mod overrides;
+pub mod pretty;
 pub mod side_by_side;
+pub mod switchable;
+pub mod tree;
 pub mod utils;
 
-pub use file_list::{FileListConfig, FileListRenderer};
+pub use analysis::{
+    LineGroup, MatchedChanges, group_lines, is_whitespace_only_change, line_distance, match_changes,
+};
+pub use autolink::{AutolinkConfig, AutolinkReference};
+pub use blame::{BlameSource, FileBlame, LineBlame};
+pub use file_list::{FileListConfig, FileListRenderer, FilePage};
+pub use hooks::{FileHeaderExtra, RenderHook};
+pub use icons::{FileIcon, FileIconMatch};
+pub use image::{ImagePreview, ImageSource, image_data_uri, image_mime_type, is_image_file};
 pub use line_by_line::LineByLineRenderer;
+pub use minified::MinifiedFileDetection;
+pub use overrides::FileConfigOverride;
+pub use pretty::pretty_print_html;
 pub use side_by_side::SideBySideRenderer;
+pub use switchable::SwitchableRenderer;
+pub use tree::{RenderElement, RenderNode, RenderTree};
 pub use utils::{
-    CSSLineClass, HighlightedLines, RenderConfig, RendererConfig, color_scheme_to_css,
-    deconstruct_line, diff_highlight, escape_for_html, filename_diff, get_file_icon, get_html_id,
-    to_css_class,
+    CSSLineClass, FileModeChange, HighlightedLines, RenderConfig, RendererConfig,
+    binary_size_change, binary_size_summary, color_scheme_to_css, deconstruct_line, diff_highlight,
+    escape_for_html, filename_diff, format_byte_size, get_file_icon, get_file_mode_change,
+    get_html_id, get_slug_html_id, get_stable_html_id, make_html_id, make_html_ids,
+    make_omitted_files_html, path_diff, path_diff_with_separator, to_css_class,
 };
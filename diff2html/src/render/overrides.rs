@@ -0,0 +1,292 @@
+//! Configuration overrides for the files matching a pattern.
+//!
+//! [`RendererConfig::per_file_overrides`] pairs paths or globs with the
+//! options to change for the matching files, e.g. to render lock files
+//! collapsed and without highlighting while source files use line matching.
+//! The files matching [`RendererConfig::collapse_by_default`], and generated
+//! files with [`RendererConfig::collapse_generated`], are collapsed before the
+//! overrides are applied, and so are minified files rendered truncated and
+//! without highlighting, see [`RendererConfig::minified_files`].
+
+use std::borrow::Cow;
+
+use super::utils::RendererConfig;
+use crate::parser::path_matches;
+use crate::types::{DiffFile, DiffStyle, LineMatchingType, WhitespaceChanges};
+
+/// Options of the files matching a pattern. Options left to `None` keep the
+/// value of the configuration.
+///
+/// # Example
+///
+/// ```
+/// use diff2html::render::{FileConfigOverride, LineByLineRenderer, RendererConfig};
+/// use diff2html::{DiffParserConfig, parse};
+///
+/// let renderer = LineByLineRenderer::new(RendererConfig {
+///     per_file_overrides: vec![(
+///         "*.lock".to_string(),
+///         FileConfigOverride {
+///             collapsed: Some(true),
+///             highlight: Some(false),
+///             ..Default::default()
+///         },
+///     )],
+///     ..Default::default()
+/// });
+/// let diff = "--- a/Cargo.lock\n+++ b/Cargo.lock\n@@ -1 +1 @@\n-a 1\n+a 2\n";
+/// let html = renderer.render(&parse(diff, &DiffParserConfig::default()));
+/// assert!(!html.contains("<ins>"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FileConfigOverride {
+    pub diff_style: Option<DiffStyle>,
+    pub matching: Option<LineMatchingType>,
+    pub match_words_threshold: Option<f64>,
+    pub max_line_length_highlight: Option<usize>,
+    /// Whether to highlight the changes within lines, `false` for none and
+    /// `true` for the limit of
+    /// [`RendererConfig::max_highlight_pairs_per_block`], e.g. for minified
+    /// files.
+    pub highlight: Option<bool>,
+    pub whitespace_changes: Option<WhitespaceChanges>,
+    /// Whether to render the file collapsed, see [`RendererConfig::collapsed`].
+    pub collapsed: Option<bool>,
+}
+
+impl FileConfigOverride {
+    /// Apply the options that are set to a configuration derived from `base`.
+    fn apply(&self, config: &mut RendererConfig, base: &RendererConfig) {
+        if let Some(diff_style) = self.diff_style {
+            config.render.diff_style = diff_style;
+        }
+        if let Some(matching) = self.matching {
+            config.render.matching = matching;
+        }
+        if let Some(threshold) = self.match_words_threshold {
+            config.render.match_words_threshold = threshold;
+        }
+        if let Some(max_length) = self.max_line_length_highlight {
+            config.render.max_line_length_highlight = max_length;
+        }
+        if let Some(highlight) = self.highlight {
+            config.max_highlight_pairs_per_block = if highlight {
+                base.max_highlight_pairs_per_block.filter(|&max| max > 0)
+            } else {
+                Some(0)
+            };
+        }
+        if let Some(whitespace_changes) = self.whitespace_changes {
+            config.whitespace_changes = whitespace_changes;
+        }
+        if let Some(collapsed) = self.collapsed {
+            config.collapsed = collapsed;
+        }
+    }
+}
+
+/// Get the configuration of a file, with the overrides of the patterns it
+/// matches applied in order.
+pub(crate) fn file_config<'a>(
+    config: &'a RendererConfig,
+    file: &DiffFile,
+) -> Cow<'a, RendererConfig> {
+    let path = if file.new_name == "/dev/null" || file.new_name.is_empty() {
+        &file.old_name
+    } else {
+        &file.new_name
+    };
+    let collapse = !config.collapsed
+        && ((config.collapse_generated && file.is_generated == Some(true))
+            || config
+                .collapse_by_default
+                .iter()
+                .any(|pattern| path_matches(path, pattern)));
+    let minified = config
+        .minified_files
+        .as_ref()
+        .filter(|detection| detection.is_minified(file));
+    let mut overrides = config
+        .per_file_overrides
+        .iter()
+        .filter(|(pattern, _)| path_matches(path, pattern))
+        .peekable();
+    if !collapse && minified.is_none() && overrides.peek().is_none() {
+        return Cow::Borrowed(config);
+    }
+
+    let mut file_config = config.clone();
+    file_config.collapsed |= collapse;
+    if let Some(detection) = minified {
+        file_config.max_highlight_pairs_per_block = Some(0);
+        file_config.max_rendered_line_length = Some(
+            config
+                .max_rendered_line_length
+                .map_or(detection.max_rendered_line_length, |max_length| {
+                    max_length.min(detection.max_rendered_line_length)
+                }),
+        );
+    }
+    for (_, file_override) in overrides {
+        file_override.apply(&mut file_config, config);
+    }
+    Cow::Owned(file_config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{DiffParserConfig, parse};
+    use crate::render::MinifiedFileDetection;
+
+    fn file(name: &str) -> DiffFile {
+        DiffFile {
+            old_name: name.to_string(),
+            new_name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_file_config() {
+        let config = RendererConfig {
+            per_file_overrides: vec![
+                (
+                    "*.lock".to_string(),
+                    FileConfigOverride {
+                        highlight: Some(false),
+                        collapsed: Some(true),
+                        ..Default::default()
+                    },
+                ),
+                (
+                    "src/".to_string(),
+                    FileConfigOverride {
+                        matching: Some(LineMatchingType::Lines),
+                        ..Default::default()
+                    },
+                ),
+                (
+                    "src/**/*.lock".to_string(),
+                    FileConfigOverride {
+                        collapsed: Some(false),
+                        ..Default::default()
+                    },
+                ),
+            ],
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            file_config(&config, &file("README.md")),
+            Cow::Borrowed(_)
+        ));
+
+        let lock = file_config(&config, &file("Cargo.lock"));
+        assert_eq!(lock.max_highlight_pairs_per_block, Some(0));
+        assert!(lock.collapsed);
+        assert_eq!(lock.render.matching, LineMatchingType::None);
+
+        // Later overrides win
+        let nested = file_config(&config, &file("src/a/b.lock"));
+        assert_eq!(nested.render.matching, LineMatchingType::Lines);
+        assert_eq!(nested.max_highlight_pairs_per_block, Some(0));
+        assert!(!nested.collapsed);
+    }
+
+    #[test]
+    fn test_file_config_collapse_by_default() {
+        let config = RendererConfig {
+            collapse_by_default: vec!["vendor/".to_string(), "*.snap".to_string()],
+            per_file_overrides: vec![(
+                "keep.snap".to_string(),
+                FileConfigOverride {
+                    collapsed: Some(false),
+                    ..Default::default()
+                },
+            )],
+            ..Default::default()
+        };
+
+        assert!(file_config(&config, &file("vendor/lib/a.js")).collapsed);
+        assert!(file_config(&config, &file("tests/a.snap")).collapsed);
+        assert!(!file_config(&config, &file("tests/keep.snap")).collapsed);
+        assert!(matches!(
+            file_config(&config, &file("src/vendor.rs")),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn test_file_config_generated_file() {
+        let generated = DiffFile {
+            is_generated: Some(true),
+            ..file("src/parser.generated.rs")
+        };
+
+        assert!(file_config(&RendererConfig::default(), &generated).collapsed);
+        let config = RendererConfig {
+            collapse_generated: false,
+            ..Default::default()
+        };
+        assert!(!file_config(&config, &generated).collapsed);
+    }
+
+    #[test]
+    fn test_file_config_minified_file() {
+        let diff = format!(
+            "--- a/dist/app.min.js\n+++ b/dist/app.min.js\n@@ -1 +1 @@\n-a\n+{}\n",
+            "a;".repeat(200)
+        );
+        let minified = parse(&diff, &DiffParserConfig::default()).remove(0);
+
+        let config = RendererConfig {
+            minified_files: Some(MinifiedFileDetection::default()),
+            ..Default::default()
+        };
+        let minified_config = file_config(&config, &minified);
+        assert_eq!(minified_config.max_highlight_pairs_per_block, Some(0));
+        assert_eq!(minified_config.max_rendered_line_length, Some(1000));
+
+        // Overrides still apply to minified files
+        let config = RendererConfig {
+            per_file_overrides: vec![(
+                "dist/".to_string(),
+                FileConfigOverride {
+                    highlight: Some(true),
+                    ..Default::default()
+                },
+            )],
+            minified_files: Some(MinifiedFileDetection::default()),
+            max_highlight_pairs_per_block: Some(50),
+            ..Default::default()
+        };
+        assert_eq!(
+            file_config(&config, &minified).max_highlight_pairs_per_block,
+            Some(50)
+        );
+
+        let config = RendererConfig::default();
+        assert!(matches!(file_config(&config, &minified), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_file_config_deleted_file() {
+        let config = RendererConfig {
+            per_file_overrides: vec![(
+                "*.lock".to_string(),
+                FileConfigOverride {
+                    collapsed: Some(true),
+                    ..Default::default()
+                },
+            )],
+            ..Default::default()
+        };
+        let deleted = DiffFile {
+            new_name: "/dev/null".to_string(),
+            ..file("yarn.lock")
+        };
+
+        assert!(file_config(&config, &deleted).collapsed);
+    }
+}
@@ -0,0 +1,293 @@
+//! Pretty-printing of generated HTML.
+//!
+//! The templates leave whitespace with inconsistent indentation in the output.
+//! [`pretty_print_html`] re-indents the HTML by nesting depth without changing
+//! how it renders: only existing whitespace between tags and text is replaced
+//! by a line break and indentation, and the content of elements preserving
+//! whitespace, like the line contents, is kept as is.
+
+/// Indentation for each nesting level.
+const INDENT: &str = "  ";
+
+/// Elements without a closing tag.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// Elements whose content is kept as is.
+const PRESERVE_ELEMENTS: &[&str] = &["pre", "script", "style", "textarea"];
+
+/// Class of the line contents, rendered with `white-space: pre`.
+const LINE_CONTENT_CLASS: &str = "d2h-code-line-ctn";
+
+/// A piece of HTML.
+enum Token<'a> {
+    /// An opening tag and the element name.
+    Open(&'a str, String),
+    /// A closing tag and the element name.
+    Close(&'a str, String),
+    /// A comment, doctype or void element.
+    Other(&'a str),
+    /// Text between tags.
+    Text(&'a str),
+}
+
+/// Split HTML into tags and text.
+fn tokenize(html: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        let text_end = rest
+            .match_indices('<')
+            .map(|(i, _)| i)
+            .find(|&i| {
+                rest[i + 1..]
+                    .chars()
+                    .next()
+                    .is_some_and(|c| c.is_ascii_alphabetic() || matches!(c, '/' | '!' | '?'))
+            })
+            .unwrap_or(rest.len());
+        if text_end > 0 {
+            tokens.push(Token::Text(&rest[..text_end]));
+            rest = &rest[text_end..];
+            continue;
+        }
+
+        let tag_end = if rest.starts_with("<!--") {
+            rest.find("-->").map_or(rest.len(), |i| i + 3)
+        } else {
+            find_tag_end(rest)
+        };
+        let tag = &rest[..tag_end];
+        rest = &rest[tag_end..];
+
+        let name_of = |start: usize| {
+            tag[start..]
+                .split(|c: char| c.is_ascii_whitespace() || c == '/' || c == '>')
+                .next()
+                .unwrap_or_default()
+                .to_ascii_lowercase()
+        };
+        if tag.starts_with("</") {
+            tokens.push(Token::Close(tag, name_of(2)));
+        } else if tag.starts_with("<!") || tag.starts_with("<?") || tag.ends_with("/>") {
+            tokens.push(Token::Other(tag));
+        } else {
+            let name = name_of(1);
+            if VOID_ELEMENTS.contains(&name.as_str()) {
+                tokens.push(Token::Other(tag));
+            } else {
+                tokens.push(Token::Open(tag, name));
+            }
+        }
+    }
+
+    tokens
+}
+
+/// Find the end of the tag at the start of `html`, skipping quoted attributes.
+fn find_tag_end(html: &str) -> usize {
+    let mut quote = None;
+    for (i, c) in html.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, '>') => return i + 1,
+            _ => {}
+        }
+    }
+    html.len()
+}
+
+/// Collapse whitespace between the attributes of a tag into single spaces.
+fn normalize_tag(tag: &str) -> String {
+    let mut result = String::with_capacity(tag.len());
+    let mut quote = None;
+    let mut whitespace = false;
+    for c in tag.chars() {
+        if quote.is_none() && c.is_ascii_whitespace() {
+            whitespace = true;
+            continue;
+        }
+        if whitespace && c != '>' {
+            result.push(' ');
+        }
+        whitespace = false;
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            _ => {}
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// Re-indent HTML by nesting depth.
+///
+/// Whitespace between tags and text is replaced by a line break and the
+/// indentation of the following token, so the HTML renders the same. Tags
+/// that are not separated by whitespace stay on the same line, and whitespace
+/// between the attributes of a tag is collapsed into single spaces.
+///
+/// # Example
+///
+/// ```
+/// use diff2html::render::pretty_print_html;
+///
+/// let html = "<div>\n<span>a</span>\n    <span>b</span></div>";
+/// assert_eq!(
+///     pretty_print_html(html),
+///     "<div>\n  <span>a</span>\n  <span>b</span></div>"
+/// );
+/// ```
+pub fn pretty_print_html(html: &str) -> String {
+    let mut result = String::with_capacity(html.len());
+    let mut open: Vec<String> = Vec::new();
+    // Nesting depth of the element whose content is kept as is
+    let mut preserve: Option<usize> = None;
+    let mut pending_whitespace = false;
+
+    let push = |result: &mut String, text: &str, depth: usize, whitespace: &mut bool| {
+        if *whitespace && !result.is_empty() {
+            result.push('\n');
+            result.push_str(&INDENT.repeat(depth));
+        }
+        *whitespace = false;
+        result.push_str(text);
+    };
+
+    for token in tokenize(html) {
+        if let Some(depth) = preserve {
+            match &token {
+                Token::Open(tag, name) => {
+                    open.push(name.clone());
+                    result.push_str(tag);
+                }
+                Token::Close(tag, name) => {
+                    close_element(&mut open, name);
+                    result.push_str(tag);
+                    if open.len() < depth {
+                        preserve = None;
+                    }
+                }
+                Token::Other(text) | Token::Text(text) => result.push_str(text),
+            }
+            continue;
+        }
+
+        match token {
+            Token::Open(tag, name) => {
+                push(
+                    &mut result,
+                    &normalize_tag(tag),
+                    open.len(),
+                    &mut pending_whitespace,
+                );
+                let keep_content =
+                    PRESERVE_ELEMENTS.contains(&name.as_str()) || tag.contains(LINE_CONTENT_CLASS);
+                open.push(name);
+                if keep_content {
+                    preserve = Some(open.len());
+                }
+            }
+            Token::Close(tag, name) => {
+                close_element(&mut open, &name);
+                push(&mut result, tag, open.len(), &mut pending_whitespace);
+            }
+            Token::Other(tag) if tag.starts_with("<!--") => {
+                push(&mut result, tag, open.len(), &mut pending_whitespace);
+            }
+            Token::Other(tag) => {
+                push(
+                    &mut result,
+                    &normalize_tag(tag),
+                    open.len(),
+                    &mut pending_whitespace,
+                );
+            }
+            Token::Text(text) => {
+                let trimmed = text.trim_ascii();
+                if trimmed.is_empty() {
+                    pending_whitespace = true;
+                    continue;
+                }
+                pending_whitespace |= text.starts_with(|c: char| c.is_ascii_whitespace());
+                push(&mut result, trimmed, open.len(), &mut pending_whitespace);
+                pending_whitespace = text.ends_with(|c: char| c.is_ascii_whitespace());
+            }
+        }
+    }
+
+    if pending_whitespace {
+        result.push('\n');
+    }
+    result
+}
+
+/// Close the innermost open element with the given name, if any.
+fn close_element(open: &mut Vec<String>, name: &str) {
+    if let Some(index) = open.iter().rposition(|open_name| open_name == name) {
+        open.truncate(index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pretty_print_html_indents_by_depth() {
+        let html =
+            "<div class=\"a\">\n      <table>\n<tr>\n    <td>1</td>\n</tr>\n\n</table>\n  </div>\n";
+        assert_eq!(
+            pretty_print_html(html),
+            "<div class=\"a\">\n  <table>\n    <tr>\n      <td>1</td>\n    </tr>\n  </table>\n</div>\n"
+        );
+    }
+
+    #[test]
+    fn test_pretty_print_html_keeps_adjacent_tags() {
+        let html = "<td class=\"x\"></td><td><span>-</span><span>a</span></td>";
+        assert_eq!(pretty_print_html(html), html);
+    }
+
+    #[test]
+    fn test_pretty_print_html_keeps_line_content() {
+        let html = "<div>\n<span class=\"d2h-code-line-ctn\">  a  <ins> b </ins>  </span>\n</div>";
+        assert_eq!(
+            pretty_print_html(html),
+            "<div>\n  <span class=\"d2h-code-line-ctn\">  a  <ins> b </ins>  </span>\n</div>"
+        );
+
+        let html = "<pre>\n  x\n</pre>\n<script>\nif (a < b) {}\n</script>";
+        assert_eq!(pretty_print_html(html), html);
+    }
+
+    #[test]
+    fn test_pretty_print_html_text_and_void_elements() {
+        let html = "<label>\n    <input type=\"checkbox\" title=\"a > b\">\n    Viewed\n</label>";
+        assert_eq!(
+            pretty_print_html(html),
+            "<label>\n  <input type=\"checkbox\" title=\"a > b\">\n  Viewed\n</label>"
+        );
+        assert_eq!(pretty_print_html("a &lt; b < c"), "a &lt; b < c");
+        assert_eq!(
+            pretty_print_html("<svg class=\"x\"\n     title=\"a  b\" >\n<path d=\"M1\" />\n</svg>"),
+            "<svg class=\"x\" title=\"a  b\">\n  <path d=\"M1\" />\n</svg>"
+        );
+        assert_eq!(
+            pretty_print_html("<!-- x -->\n<svg><path d=\"M1\"/>\n</svg>"),
+            "<!-- x -->\n<svg><path d=\"M1\"/>\n</svg>"
+        );
+    }
+
+    #[test]
+    fn test_pretty_print_html_is_idempotent() {
+        let html = "<div>\n<div>\n  <span>a</span>\n        text\n</div>\n</div>";
+        let pretty = pretty_print_html(html);
+        assert_eq!(pretty_print_html(&pretty), pretty);
+    }
+}
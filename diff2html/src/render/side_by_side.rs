@@ -3,14 +3,32 @@
 //! This module provides a two-column renderer that shows old (left) and new (right)
 //! file versions side by side.
 
+use std::borrow::Cow;
+use std::sync::{Arc, Mutex};
+
 use serde_json::json;
 
-use crate::templates::{self, TemplateName};
-use crate::types::{DiffBlock, DiffFile, DiffLine, LineType};
+use crate::templates::{self, GenericLine, LineNumbers, TemplateName};
+use crate::types::{DiffFile, WhitespaceChanges};
 
+use super::analysis::{
+    LineGroup, MatchedChanges, group_lines, is_whitespace_only_change, match_changes,
+    take_highlight,
+};
+use super::blame::{FileBlame, get_file_blame, make_blame_html};
+use super::cache::{HighlightCache, cached_diff_highlight};
+use super::hooks::{apply_file_html_hooks, apply_line_content_hooks};
+use super::image::{ImagePreview, get_image_preview, make_image_preview_html};
+use super::overrides::file_config;
+use super::tree::{
+    RenderElement, RenderTree, TreeLine, block_header_row, block_line_pairs, code_table,
+    collapsed_row, file_wrapper, line_row, message_row, tree_line_row, wrap_files,
+};
 use super::utils::{
-    CSSLineClass, RendererConfig, color_scheme_to_css, deconstruct_line, diff_highlight,
-    escape_for_html, filename_diff, get_file_icon, get_html_id, to_css_class,
+    CSSLineClass, FileModeChange, RendererConfig, color_scheme_to_css, deconstruct_line,
+    empty_diff_message, escape_for_html, format_block_header, get_file_mode_change,
+    join_file_diffs, make_collapsed_diff_html, make_file_path_html, make_html_ids,
+    prepare_changed_line, with_no_newline_marker, with_truncated_content,
 };
 
 /// HTML content for left and right columns.
@@ -21,8 +39,10 @@ struct FileHtml {
 }
 
 /// Side-by-side renderer for generating two-column diff HTML.
+#[derive(Debug)]
 pub struct SideBySideRenderer {
     config: RendererConfig,
+    highlight_cache: Option<Arc<Mutex<HighlightCache>>>,
 }
 
 impl Default for SideBySideRenderer {
@@ -34,23 +54,26 @@ impl Default for SideBySideRenderer {
 impl SideBySideRenderer {
     /// Create a new SideBySideRenderer with the given configuration.
     pub fn new(config: RendererConfig) -> Self {
-        Self { config }
+        let highlight_cache = HighlightCache::shared(config.highlight_cache_size);
+        Self {
+            config,
+            highlight_cache,
+        }
     }
 
     /// Render a list of diff files to HTML.
     pub fn render(&self, diff_files: &[DiffFile]) -> String {
-        let diffs_html: String = diff_files
-            .iter()
-            .map(|file| {
-                let diffs = if !file.blocks.is_empty() {
-                    self.generate_file_html(file)
-                } else {
-                    self.generate_empty_diff()
-                };
-                self.make_file_diff_html(file, &diffs)
-            })
-            .collect::<Vec<_>>()
-            .join("\n");
+        let html_ids = make_html_ids(
+            diff_files,
+            self.config.deterministic,
+            self.config.html_id_strategy,
+        );
+        let diffs_html = join_file_diffs(diff_files, self.config.group_by, |index, file| {
+            self.render_file(file, &html_ids[index])
+        });
+        if self.config.no_wrapper {
+            return diffs_html;
+        }
 
         templates::render(
             TemplateName::GenericWrapper,
@@ -61,171 +84,386 @@ impl SideBySideRenderer {
         )
     }
 
+    /// Render a list of diff files as a tree of elements instead of HTML.
+    ///
+    /// See [`RenderTree`] for the parts of the HTML left out.
+    pub fn render_tree(&self, diff_files: &[DiffFile]) -> RenderTree {
+        let html_ids = make_html_ids(
+            diff_files,
+            self.config.deterministic,
+            self.config.html_id_strategy,
+        );
+        let files = diff_files
+            .iter()
+            .zip(&html_ids)
+            .filter_map(|(file, html_id)| self.render_file_tree(file, html_id))
+            .collect();
+        wrap_files(files, &self.config)
+    }
+
+    /// Render a file as a tree with the given HTML ID, and with the
+    /// configuration overridden for it if any, or `None` if nothing is
+    /// rendered for it.
+    fn render_file_tree(&self, file: &DiffFile, html_id: &str) -> Option<RenderElement> {
+        match file_config(&self.config, file) {
+            Cow::Borrowed(_) => self.make_file_diff_tree(file, html_id),
+            Cow::Owned(config) => Self {
+                config,
+                highlight_cache: self.highlight_cache.clone(),
+            }
+            .make_file_diff_tree(file, html_id),
+        }
+    }
+
+    /// Render a file as a tree with the configuration of the renderer.
+    fn make_file_diff_tree(&self, file: &DiffFile, html_id: &str) -> Option<RenderElement> {
+        if self.config.render_nothing_when_empty
+            && file.blocks.is_empty()
+            && file.is_mode_change != Some(true)
+        {
+            return None;
+        }
+
+        let (left, right) = if !file.blocks.is_empty() {
+            self.generate_file_rows(file)
+        } else if let Some(change) =
+            get_file_mode_change(file).filter(|_| file.is_mode_change == Some(true))
+        {
+            let message = format!(
+                "File mode changed from {} to {}",
+                change.old_mode, change.new_mode
+            );
+            (
+                vec![message_row("d2h-code-side-line", &message)],
+                Vec::new(),
+            )
+        } else {
+            let message = empty_diff_message(file, &self.config);
+            (vec![message_row("d2h-code-side-line", message)], Vec::new())
+        };
+
+        let side = |rows| {
+            RenderElement::new("div")
+                .with_class("d2h-file-side-diff")
+                .with_child(code_table(rows))
+        };
+        let mut diff = RenderElement::new("div").with_class("d2h-files-diff");
+        if self.config.render.responsive_breakpoint.is_some() {
+            diff = diff.with_class("d2h-files-diff-responsive");
+        }
+        let diff = if self.is_single_column(file) {
+            // The column of the only version of an added or deleted file
+            let single = if file.is_new == Some(true) {
+                right
+            } else {
+                left
+            };
+            diff.with_class("d2h-files-diff-single")
+                .with_child(side(single))
+        } else {
+            diff.with_child(side(left)).with_child(side(right))
+        };
+        Some(file_wrapper(file, html_id, &self.config, diff))
+    }
+
+    /// Generate the rows of the left and right columns of all blocks in a
+    /// file for its tree.
+    fn generate_file_rows(&self, file: &DiffFile) -> (Vec<RenderElement>, Vec<RenderElement>) {
+        let (line_class, content_class) = ("d2h-code-side-linenumber", "d2h-code-side-line");
+        let (mut left, mut right) = (Vec::new(), Vec::new());
+        if file.is_collapsed == Some(true) {
+            left.push(collapsed_row(line_class, content_class, true));
+            right.push(collapsed_row(line_class, content_class, false));
+        }
+
+        // The block headers go to the column shown for an added file
+        let header_on_right = file.is_new == Some(true) && self.is_single_column(file);
+        let side_row = |line: Option<TreeLine>| match line {
+            Some(line) => {
+                let line_numbers = LineNumbers::Single(line.old_number.or(line.new_number));
+                tree_line_row(line, line_class, content_class, line_numbers)
+            }
+            None => line_row(
+                &format!("{} d2h-emptyplaceholder", CSSLineClass::Context.as_str()),
+                &format!("{} d2h-code-side-emptyplaceholder", line_class),
+                &format!("{} d2h-code-side-emptyplaceholder", content_class),
+                LineNumbers::Single(None),
+                "",
+                Vec::new(),
+            ),
+        };
+
+        for block in &file.blocks {
+            let header = block_header_row(
+                &block.header,
+                Some(file),
+                line_class,
+                content_class,
+                &self.config,
+            );
+            let empty_header = block_header_row("", None, line_class, content_class, &self.config);
+            let (left_header, right_header) = if header_on_right {
+                (empty_header, header)
+            } else {
+                (header, empty_header)
+            };
+            left.push(left_header);
+            right.push(right_header);
+
+            for pair in block_line_pairs(block, file.is_combined, &self.config)
+                .into_iter()
+                .flatten()
+            {
+                left.push(side_row(pair.old));
+                right.push(side_row(pair.new));
+            }
+        }
+        (left, right)
+    }
+
+    /// Render a file with the given HTML ID, and with the configuration
+    /// overridden for it if any, without wrapper.
+    pub(crate) fn render_file(&self, file: &DiffFile, html_id: &str) -> String {
+        match file_config(&self.config, file) {
+            Cow::Borrowed(_) => self.render_file_html(file, html_id),
+            Cow::Owned(config) => Self {
+                config,
+                highlight_cache: self.highlight_cache.clone(),
+            }
+            .render_file_html(file, html_id),
+        }
+    }
+
+    /// Render a file with the configuration of the renderer.
+    fn render_file_html(&self, file: &DiffFile, html_id: &str) -> String {
+        let diffs = if let Some(preview) = get_image_preview(file, &self.config) {
+            self.generate_image_diff(&preview)
+        } else if !file.blocks.is_empty() {
+            self.generate_file_html(file)
+        } else if let Some(change) =
+            get_file_mode_change(file).filter(|_| file.is_mode_change == Some(true))
+        {
+            self.generate_mode_change_diff(&change)
+        } else {
+            self.generate_empty_diff(file)
+        };
+        self.make_file_diff_html(file, &diffs, html_id)
+    }
+
     /// Generate the HTML for a single file diff.
-    fn make_file_diff_html(&self, file: &DiffFile, diffs: &FileHtml) -> String {
-        if self.config.render_nothing_when_empty && file.blocks.is_empty() {
+    fn make_file_diff_html(&self, file: &DiffFile, diffs: &FileHtml, html_id: &str) -> String {
+        if self.config.render_nothing_when_empty
+            && file.blocks.is_empty()
+            && file.is_mode_change != Some(true)
+        {
             return String::new();
         }
 
-        let file_icon = get_file_icon(file);
-        let file_icon_html = templates::render_by_name(&format!("icon-{}", file_icon), &json!({}));
-        let file_tag_html = templates::render_by_name(&format!("tag-{}", file_icon), &json!({}));
-
-        let file_path_html = templates::render(
-            TemplateName::GenericFilePath,
-            &json!({
-                "fileDiffName": filename_diff(file),
-                "fileIcon": file_icon_html,
-                "fileTag": file_tag_html,
-            }),
-        );
+        let file_path_html = make_file_path_html(file, &self.config);
+        let single_column = self.is_single_column(file);
+        // The column of the only version of an added or deleted file
+        let single = if file.is_new == Some(true) {
+            &diffs.right
+        } else {
+            &diffs.left
+        };
 
-        templates::render(
-            TemplateName::SideBySideFileDiff,
+        let html = templates::render(
+            TemplateName::SideBySideFileDiff.for_markup(self.config.output_markup),
             &json!({
                 "file": {
-                    "language": file.language,
+                    "language": escape_for_html(&file.language),
                 },
-                "fileHtmlId": get_html_id(file),
-                "diffs": {
-                    "left": diffs.left,
-                    "right": diffs.right,
+                "fileHtmlId": html_id,
+                "stickyHeaders": self.config.sticky_headers,
+                "wrapLines": self.config.wrap_lines,
+                "responsive": self.config.render.responsive_breakpoint.is_some(),
+                "singleColumn": single_column,
+                "diffs": if single_column {
+                    json!({ "left": single })
+                } else {
+                    json!({ "left": diffs.left, "right": diffs.right })
                 },
                 "filePath": file_path_html,
             }),
-        )
+        );
+
+        apply_file_html_hooks(&self.config, file, html)
+    }
+
+    /// Whether to render an added or deleted file in a single full-width
+    /// column instead of next to an empty one.
+    fn is_single_column(&self, file: &DiffFile) -> bool {
+        self.config.single_column_added_deleted
+            && !file.blocks.is_empty()
+            && (file.is_new == Some(true)) != (file.is_deleted == Some(true))
     }
 
     /// Generate HTML for an empty diff (file with no changes).
-    fn generate_empty_diff(&self) -> FileHtml {
+    fn generate_empty_diff(&self, file: &DiffFile) -> FileHtml {
+        FileHtml {
+            left: templates::render(
+                TemplateName::GenericEmptyDiff.for_markup(self.config.output_markup),
+                &json!({
+                    "contentClass": "d2h-code-side-line",
+                    "message": escape_for_html(empty_diff_message(file, &self.config)),
+                    "CSSLineClass": {
+                        "INFO": CSSLineClass::Info.as_str(),
+                    },
+                }),
+            ),
+            right: String::new(),
+        }
+    }
+
+    /// Generate HTML for a file whose permissions changed without content changes.
+    fn generate_mode_change_diff(&self, change: &FileModeChange) -> FileHtml {
         FileHtml {
             left: templates::render(
-                TemplateName::GenericEmptyDiff,
+                TemplateName::GenericModeChange.for_markup(self.config.output_markup),
                 &json!({
                     "contentClass": "d2h-code-side-line",
                     "CSSLineClass": {
                         "INFO": CSSLineClass::Info.as_str(),
                     },
+                    "oldMode": change.old_mode,
+                    "newMode": change.new_mode,
                 }),
             ),
             right: String::new(),
         }
     }
 
+    /// Generate HTML showing the old image on the left and the new image on the right.
+    fn generate_image_diff(&self, preview: &ImagePreview) -> FileHtml {
+        FileHtml {
+            left: make_image_preview_html(
+                preview.before.as_deref(),
+                None,
+                "d2h-code-side-line",
+                self.config.output_markup,
+            ),
+            right: make_image_preview_html(
+                None,
+                preview.after.as_deref(),
+                "d2h-code-side-line",
+                self.config.output_markup,
+            ),
+        }
+    }
+
     /// Generate HTML for all blocks in a file.
     fn generate_file_html(&self, file: &DiffFile) -> FileHtml {
+        let blame = get_file_blame(file, &self.config);
+        let mut collapsed = FileHtml::default();
+        if file.is_collapsed == Some(true) {
+            let line_class = "d2h-code-side-linenumber";
+            let markup = self.config.output_markup;
+            collapsed.left =
+                make_collapsed_diff_html(line_class, "d2h-code-side-line", true, markup);
+            collapsed.right =
+                make_collapsed_diff_html(line_class, "d2h-code-side-line", false, markup);
+        }
+
+        // The block headers go to the column shown for an added file
+        let header_on_right = file.is_new == Some(true) && self.is_single_column(file);
+
         file.blocks
             .iter()
             .map(|block| {
-                let mut file_html = FileHtml {
-                    left: self.make_header_html(&block.header, Some(file)),
-                    right: self.make_header_html("", None),
+                let header = self.make_header_html(&block.header, Some(file));
+                let empty_header = self.make_header_html("", None);
+                let mut file_html = if header_on_right {
+                    FileHtml {
+                        left: empty_header,
+                        right: header,
+                    }
+                } else {
+                    FileHtml {
+                        left: header,
+                        right: empty_header,
+                    }
                 };
 
-                for (context_lines, old_lines, new_lines) in self.apply_line_grouping(block) {
-                    if !old_lines.is_empty() && !new_lines.is_empty() && context_lines.is_empty() {
-                        // Changed lines - apply diff highlighting
-                        let result =
-                            self.process_changed_lines(file.is_combined, &old_lines, &new_lines);
-                        file_html.left.push_str(&result.left);
-                        file_html.right.push_str(&result.right);
-                    } else if !context_lines.is_empty() {
-                        // Context lines - show in both columns
-                        for line in &context_lines {
+                // Pairs of lines left to highlight in the block
+                let mut highlight_budget = self
+                    .config
+                    .max_highlight_pairs_per_block
+                    .unwrap_or(usize::MAX);
+                for group in group_lines(block) {
+                    match group {
+                        LineGroup::Context(line) => {
+                            // Context lines - show in both columns
                             let parts = deconstruct_line(&line.content, file.is_combined, true);
-                            let (left, right) = self.generate_line_html(
+                            let content = with_no_newline_marker(
+                                apply_line_content_hooks(
+                                    &self.config,
+                                    line,
+                                    with_truncated_content(
+                                        parts.content,
+                                        line,
+                                        file.is_combined,
+                                        &self.config,
+                                    ),
+                                ),
+                                line,
+                            );
+                            self.generate_line_html(
+                                &mut file_html,
                                 Some(PreparedLine {
                                     css_class: CSSLineClass::Context,
                                     prefix: parts.prefix.clone(),
-                                    content: parts.content.clone(),
+                                    content: content.clone(),
                                     number: line.old_number,
+                                    gutter: make_blame_html(blame.as_ref(), line.old_number, None),
                                 }),
                                 Some(PreparedLine {
                                     css_class: CSSLineClass::Context,
                                     prefix: parts.prefix,
-                                    content: parts.content,
+                                    content,
                                     number: line.new_number,
+                                    gutter: make_blame_html(blame.as_ref(), None, line.new_number),
                                 }),
                             );
-                            file_html.left.push_str(&left);
-                            file_html.right.push_str(&right);
                         }
-                    } else if !old_lines.is_empty() || !new_lines.is_empty() {
-                        // Only deletions or only insertions
-                        let result =
-                            self.process_changed_lines(file.is_combined, &old_lines, &new_lines);
-                        file_html.left.push_str(&result.left);
-                        file_html.right.push_str(&result.right);
+                        LineGroup::Changes { deleted, inserted } => {
+                            // Deletions paired with insertions get diff highlighting
+                            for changes in
+                                match_changes(deleted, inserted, file.is_combined, &self.config)
+                            {
+                                let result = self.process_changed_lines(
+                                    file.is_combined,
+                                    changes,
+                                    &mut highlight_budget,
+                                    blame.as_ref(),
+                                );
+                                file_html.left.push_str(&result.left);
+                                file_html.right.push_str(&result.right);
+                            }
+                        }
                     }
                 }
 
                 file_html
             })
-            .fold(FileHtml::default(), |mut acc, html| {
+            .fold(collapsed, |mut acc, html| {
                 acc.left.push_str(&html.left);
                 acc.right.push_str(&html.right);
                 acc
             })
     }
-
-    /// Group lines in a block by type (context, deletions, insertions).
-    fn apply_line_grouping(
-        &self,
-        block: &DiffBlock,
-    ) -> Vec<(Vec<DiffLine>, Vec<DiffLine>, Vec<DiffLine>)> {
-        let mut groups: Vec<(Vec<DiffLine>, Vec<DiffLine>, Vec<DiffLine>)> = Vec::new();
-        let mut old_lines: Vec<DiffLine> = Vec::new();
-        let mut new_lines: Vec<DiffLine> = Vec::new();
-
-        for line in &block.lines {
-            // Flush accumulated lines when we hit a context line or switch patterns
-            if (line.line_type != LineType::Insert && !new_lines.is_empty())
-                || (line.line_type == LineType::Context && !old_lines.is_empty())
-            {
-                groups.push((Vec::new(), old_lines.clone(), new_lines.clone()));
-                old_lines.clear();
-                new_lines.clear();
-            }
-
-            match line.line_type {
-                LineType::Context => {
-                    groups.push((vec![line.clone()], Vec::new(), Vec::new()));
-                }
-                LineType::Insert if old_lines.is_empty() => {
-                    groups.push((Vec::new(), Vec::new(), vec![line.clone()]));
-                }
-                LineType::Insert => {
-                    new_lines.push(line.clone());
-                }
-                LineType::Delete => {
-                    old_lines.push(line.clone());
-                }
-            }
-        }
-
-        // Flush any remaining lines
-        if !old_lines.is_empty() || !new_lines.is_empty() {
-            groups.push((Vec::new(), old_lines, new_lines));
-        }
-
-        groups
-    }
-
     /// Generate HTML for a block header row.
     fn make_header_html(&self, block_header: &str, file: Option<&DiffFile>) -> String {
-        let escaped_header = if file.is_some_and(|f| f.is_too_big == Some(true)) {
-            block_header.to_string()
-        } else {
-            escape_for_html(block_header)
-        };
+        let escaped_header = format_block_header(block_header, file, self.config.hardened);
 
         templates::render(
-            TemplateName::GenericBlockHeader,
+            TemplateName::GenericBlockHeader.for_markup(self.config.output_markup),
             &json!({
                 "CSSLineClass": {
                     "INFO": CSSLineClass::Info.as_str(),
                 },
                 "blockHeader": escaped_header,
+                "sticky": self.config.sticky_headers,
                 "lineClass": "d2h-code-side-linenumber",
                 "contentClass": "d2h-code-side-line",
             }),
@@ -233,12 +471,17 @@ impl SideBySideRenderer {
     }
 
     /// Process changed lines by pairing deletions with insertions and highlighting differences.
+    ///
+    /// Each highlighted pair uses one of `highlight_budget`, and pairs past
+    /// the budget are left without highlighting.
     fn process_changed_lines(
         &self,
         is_combined: bool,
-        old_lines: &[DiffLine],
-        new_lines: &[DiffLine],
+        changes: MatchedChanges,
+        highlight_budget: &mut usize,
+        blame: Option<&FileBlame>,
     ) -> FileHtml {
+        let (old_lines, new_lines) = (changes.deleted, changes.inserted);
         let mut result = FileHtml::default();
         let max_lines = old_lines.len().max(new_lines.len());
 
@@ -246,119 +489,178 @@ impl SideBySideRenderer {
             let old_line = old_lines.get(i);
             let new_line = new_lines.get(i);
 
-            let diff = match (old_line, new_line) {
-                (Some(old), Some(new)) => Some(diff_highlight(
-                    &old.content,
-                    &new.content,
-                    is_combined,
-                    &self.config.render,
-                )),
-                _ => None,
+            let (old_diff, new_diff) = match (old_line, new_line) {
+                (Some(old), Some(new))
+                    if take_highlight(
+                        old,
+                        new,
+                        &changes,
+                        highlight_budget,
+                        is_combined,
+                        &self.config,
+                    ) =>
+                {
+                    let diff = cached_diff_highlight(
+                        self.highlight_cache.as_deref(),
+                        &old.content,
+                        &new.content,
+                        is_combined,
+                        &self.config.render,
+                    );
+                    (Some(diff.old_line), Some(diff.new_line))
+                }
+                _ => (None, None),
+            };
+
+            let whitespace_only = match (old_line, new_line) {
+                (Some(old), Some(new))
+                    if self.config.whitespace_changes != WhitespaceChanges::Show =>
+                {
+                    is_whitespace_only_change(old, new, is_combined)
+                }
+                _ => false,
             };
+            if whitespace_only
+                && self.config.whitespace_changes == WhitespaceChanges::Hide
+                && let (Some(old), Some(new)) = (old_line, new_line)
+            {
+                // Render both lines as context lines
+                let [old, new] = [(old, old.old_number, None), (new, None, new.new_number)].map(
+                    |(line, old_number, new_number)| {
+                        let parts = deconstruct_line(&line.content, is_combined, true);
+                        let content = apply_line_content_hooks(
+                            &self.config,
+                            line,
+                            with_truncated_content(parts.content, line, is_combined, &self.config),
+                        );
+                        PreparedLine {
+                            css_class: CSSLineClass::Context,
+                            prefix: " ".repeat(parts.prefix.len()),
+                            content: with_no_newline_marker(content, line),
+                            number: old_number.or(new_number),
+                            gutter: make_blame_html(blame, old_number, new_number),
+                        }
+                    },
+                );
+                self.generate_line_html(&mut result, Some(old), Some(new));
+                continue;
+            }
 
             // Prepare old line
             let prepared_old = old_line.filter(|o| o.old_number.is_some()).map(|old| {
-                let (css_class, prefix, content) = if let Some(ref diff) = diff {
-                    (
-                        CSSLineClass::DeleteChanges,
-                        diff.old_line.prefix.clone(),
-                        diff.old_line.content.clone(),
-                    )
+                let (css_class, prefix, content) = if let Some(parts) = old_diff {
+                    let css_class = if whitespace_only {
+                        CSSLineClass::DeleteWhitespaceOnly
+                    } else {
+                        CSSLineClass::DeleteChanges
+                    };
+                    (css_class, parts.prefix, parts.content)
                 } else {
-                    let parts = deconstruct_line(&old.content, is_combined, true);
-                    (to_css_class(old.line_type), parts.prefix, parts.content)
+                    let (css_class, parts) = prepare_changed_line(old, is_combined);
+                    (css_class, parts.prefix, parts.content)
                 };
+                let content = apply_line_content_hooks(
+                    &self.config,
+                    old,
+                    with_truncated_content(content, old, is_combined, &self.config),
+                );
+                let content = with_no_newline_marker(content, old);
 
                 PreparedLine {
                     css_class,
                     prefix,
                     content,
                     number: old.old_number,
+                    gutter: make_blame_html(blame, old.old_number, None),
                 }
             });
 
             // Prepare new line
             let prepared_new = new_line.filter(|n| n.new_number.is_some()).map(|new| {
-                let (css_class, prefix, content) = if let Some(ref diff) = diff {
-                    (
-                        CSSLineClass::InsertChanges,
-                        diff.new_line.prefix.clone(),
-                        diff.new_line.content.clone(),
-                    )
+                let (css_class, prefix, content) = if let Some(parts) = new_diff {
+                    let css_class = if whitespace_only {
+                        CSSLineClass::InsertWhitespaceOnly
+                    } else {
+                        CSSLineClass::InsertChanges
+                    };
+                    (css_class, parts.prefix, parts.content)
                 } else {
-                    let parts = deconstruct_line(&new.content, is_combined, true);
-                    (to_css_class(new.line_type), parts.prefix, parts.content)
+                    let (css_class, parts) = prepare_changed_line(new, is_combined);
+                    (css_class, parts.prefix, parts.content)
                 };
+                let content = apply_line_content_hooks(
+                    &self.config,
+                    new,
+                    with_truncated_content(content, new, is_combined, &self.config),
+                );
+                let content = with_no_newline_marker(content, new);
 
                 PreparedLine {
                     css_class,
                     prefix,
                     content,
                     number: new.new_number,
+                    gutter: make_blame_html(blame, None, new.new_number),
                 }
             });
 
-            let (left, right) = self.generate_line_html(prepared_old, prepared_new);
-            result.left.push_str(&left);
-            result.right.push_str(&right);
+            self.generate_line_html(&mut result, prepared_old, prepared_new);
         }
 
         result
     }
 
-    /// Generate HTML for a pair of lines (left and right).
+    /// Write the HTML for a pair of lines into the left and right columns.
     fn generate_line_html(
         &self,
+        out: &mut FileHtml,
         old_line: Option<PreparedLine>,
         new_line: Option<PreparedLine>,
-    ) -> (String, String) {
-        (
-            self.generate_single_html(old_line),
-            self.generate_single_html(new_line),
-        )
+    ) {
+        self.generate_single_html(&mut out.left, old_line);
+        self.generate_single_html(&mut out.right, new_line);
     }
 
-    /// Generate HTML for a single side-by-side line.
-    fn generate_single_html(&self, line: Option<PreparedLine>) -> String {
+    /// Write the HTML for a single side-by-side line into a buffer.
+    fn generate_single_html(&self, out: &mut String, line: Option<PreparedLine>) {
         let line_class = "d2h-code-side-linenumber";
         let content_class = "d2h-code-side-line";
 
-        let (css_type, actual_line_class, actual_content_class, prefix, content, line_number) =
-            if let Some(line) = line {
-                (
-                    line.css_class.as_str().to_string(),
-                    line_class.to_string(),
-                    content_class.to_string(),
-                    if line.prefix == " " {
-                        "&nbsp;".to_string()
-                    } else {
-                        line.prefix
-                    },
-                    line.content,
-                    line.number.map(|n| n.to_string()).unwrap_or_default(),
-                )
-            } else {
-                (
-                    format!("{} d2h-emptyplaceholder", CSSLineClass::Context.as_str()),
-                    format!("{} d2h-code-side-emptyplaceholder", line_class),
-                    format!("{} d2h-code-side-emptyplaceholder", content_class),
-                    String::new(),
-                    String::new(),
-                    String::new(),
-                )
-            };
+        let Some(line) = line else {
+            templates::write_generic_line(
+                out,
+                &GenericLine {
+                    line_type: &format!("{} d2h-emptyplaceholder", CSSLineClass::Context.as_str()),
+                    line_class: &format!("{} d2h-code-side-emptyplaceholder", line_class),
+                    content_class: &format!("{} d2h-code-side-emptyplaceholder", content_class),
+                    prefix: "",
+                    content: "",
+                    gutter: "",
+                    line_numbers: LineNumbers::Single(None),
+                    markup: self.config.output_markup,
+                },
+            );
+            return;
+        };
 
-        templates::render(
-            TemplateName::GenericLine,
-            &json!({
-                "type": css_type,
-                "lineClass": actual_line_class,
-                "contentClass": actual_content_class,
-                "prefix": prefix,
-                "content": content,
-                "lineNumber": line_number,
-            }),
-        )
+        let prefix = if line.prefix == " " {
+            "&nbsp;"
+        } else {
+            &line.prefix
+        };
+        templates::write_generic_line(
+            out,
+            &GenericLine {
+                line_type: line.css_class.as_str(),
+                line_class,
+                content_class,
+                prefix,
+                content: &line.content,
+                gutter: &line.gutter,
+                line_numbers: LineNumbers::Single(line.number),
+                markup: self.config.output_markup,
+            },
+        );
     }
 }
 
@@ -375,12 +677,16 @@ struct PreparedLine {
     content: String,
     /// The line number in the file, or `None` for empty placeholder lines.
     number: Option<u32>,
+    /// The blame gutter of this line, empty if the file has no blame.
+    gutter: String,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::parser::{DiffParserConfig, parse};
+    use crate::render::RenderConfig;
+    use crate::types::{GroupBy, LineMatchingType};
 
     fn sample_diff() -> &'static str {
         r#"diff --git a/test.txt b/test.txt
@@ -408,6 +714,28 @@ index 1234567..abcdefg 100644
         assert!(html.contains("test.txt"));
     }
 
+    #[test]
+    fn test_render_group_by() {
+        let diff = "--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1 +1 @@\n-a\n+b\n\
+                    diff --git a/new.txt b/new.txt\nnew file mode 100644\n\
+                    --- /dev/null\n+++ b/new.txt\n@@ -0,0 +1 @@\n+a\n";
+        let files = parse(diff, &DiffParserConfig::default());
+
+        let html = SideBySideRenderer::default().render(&files);
+        assert!(!html.contains("d2h-group-header"));
+
+        let renderer = SideBySideRenderer::new(RendererConfig {
+            group_by: GroupBy::Status,
+            ..Default::default()
+        });
+        let html = renderer.render(&files);
+        let added = html.find(">Added<").unwrap();
+        let modified = html.find(">Modified<").unwrap();
+        assert!(added < html.find("new.txt").unwrap());
+        assert!(html.find("new.txt").unwrap() < modified);
+        assert!(modified < html.find("src/lib.rs").unwrap());
+    }
+
     #[test]
     fn test_render_empty_files() {
         let renderer = SideBySideRenderer::default();
@@ -416,6 +744,94 @@ index 1234567..abcdefg 100644
         assert!(html.contains("d2h-wrapper"));
     }
 
+    #[test]
+    fn test_render_whitespace_changes() {
+        let diff = "--- a/a.txt\n+++ b/a.txt\n@@ -3,2 +3,2 @@\n-if x {\n-a\n+if  x {\n+b\n";
+        let files = parse(diff, &DiffParserConfig::default());
+        let render = |whitespace_changes| {
+            let renderer = SideBySideRenderer::new(RendererConfig {
+                whitespace_changes,
+                ..Default::default()
+            });
+            renderer.render(&files)
+        };
+
+        let html = render(WhitespaceChanges::Mark);
+        assert_eq!(
+            html.matches("class=\"d2h-del d2h-change d2h-whitespace-only\"")
+                .count(),
+            1
+        );
+        assert_eq!(
+            html.matches("class=\"d2h-ins d2h-change d2h-whitespace-only\"")
+                .count(),
+            1
+        );
+
+        let html = render(WhitespaceChanges::Hide);
+        assert!(!html.contains("d2h-whitespace-only"));
+        assert!(html.contains("if  x {"));
+        assert!(html.contains("if x {"));
+        assert_eq!(html.matches("class=\"d2h-del d2h-change\"").count(), 1);
+        assert_eq!(html.matches("class=\"d2h-ins d2h-change\"").count(), 1);
+    }
+
+    #[test]
+    fn test_render_matching_threshold() {
+        let diff = "--- a/a.rs\n+++ b/a.rs\n@@ -1,2 +1,3 @@\n\
+                    -let x = 1;\n-fn f() {}\n+// values\n+let x = 10;\n+struct S;\n";
+        let files = parse(diff, &DiffParserConfig::default());
+        let render = |match_words_threshold| {
+            let renderer = SideBySideRenderer::new(RendererConfig {
+                render: RenderConfig {
+                    matching: LineMatchingType::Lines,
+                    match_words_threshold,
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+            renderer.render(&files)
+        };
+
+        // The shifted line is highlighted against its match
+        let html = render(0.25);
+        assert!(html.contains("<del>1;</del>"));
+        assert!(html.contains("<ins>10;</ins>"));
+
+        // Matched lines are not highlighted when less similar than the threshold
+        let html = render(0.01);
+        assert!(!html.contains("<del>1;</del>"));
+        assert!(!html.contains("<ins>10;</ins>"));
+    }
+
+    #[test]
+    fn test_render_max_highlight_pairs_per_block() {
+        let diff = "--- a/a.txt\n+++ b/a.txt\n@@ -1,2 +1,2 @@\n-a 1\n-b 1\n+a 2\n+b 2\n";
+        let files = parse(diff, &DiffParserConfig::default());
+        let renderer = SideBySideRenderer::new(RendererConfig {
+            max_highlight_pairs_per_block: Some(0),
+            ..Default::default()
+        });
+        let html = renderer.render(&files);
+
+        assert!(!html.contains("<ins>") && !html.contains("<del>"));
+        assert_eq!(html.matches("class=\"d2h-del\"").count(), 2);
+    }
+
+    #[test]
+    fn test_render_no_wrapper() {
+        let diff = "--- a/a.txt\n+++ b/a.txt\n@@ -1 +1 @@\n-a\n+b\n";
+        let files = parse(diff, &DiffParserConfig::default());
+        let renderer = SideBySideRenderer::new(RendererConfig {
+            no_wrapper: true,
+            ..Default::default()
+        });
+        let html = renderer.render(&files);
+
+        assert!(html.starts_with("<div id=\"d2h-"));
+        assert!(!html.contains("class=\"d2h-wrapper"));
+    }
+
     #[test]
     fn test_render_nothing_when_empty() {
         let config = RendererConfig {
@@ -438,12 +854,28 @@ index 1234567..abcdefg 100644
     #[test]
     fn test_generate_empty_diff() {
         let renderer = SideBySideRenderer::default();
-        let file_html = renderer.generate_empty_diff();
+        let file_html = renderer.generate_empty_diff(&DiffFile::default());
 
         assert!(file_html.left.contains("File without changes"));
         assert!(file_html.right.is_empty());
     }
 
+    #[test]
+    fn test_generate_mode_change_diff() {
+        let renderer = SideBySideRenderer::default();
+        let file_html = renderer.generate_mode_change_diff(&FileModeChange {
+            old_mode: "100644".to_string(),
+            new_mode: "100755".to_string(),
+        });
+
+        assert!(
+            file_html
+                .left
+                .contains("File mode changed from 100644 to 100755")
+        );
+        assert!(file_html.right.is_empty());
+    }
+
     #[test]
     fn test_make_header_html() {
         let renderer = SideBySideRenderer::default();
@@ -459,51 +891,11 @@ index 1234567..abcdefg 100644
         assert!(empty_header.contains("d2h-code-side-linenumber"));
     }
 
-    #[test]
-    fn test_line_grouping() {
-        let renderer = SideBySideRenderer::default();
-        let block = DiffBlock {
-            old_start_line: 1,
-            old_start_line2: None,
-            new_start_line: 1,
-            header: "@@ -1,3 +1,3 @@".to_string(),
-            lines: vec![
-                DiffLine {
-                    line_type: LineType::Context,
-                    content: " context".to_string(),
-                    old_number: Some(1),
-                    new_number: Some(1),
-                },
-                DiffLine {
-                    line_type: LineType::Delete,
-                    content: "-old".to_string(),
-                    old_number: Some(2),
-                    new_number: None,
-                },
-                DiffLine {
-                    line_type: LineType::Insert,
-                    content: "+new".to_string(),
-                    old_number: None,
-                    new_number: Some(2),
-                },
-            ],
-        };
-
-        let groups = renderer.apply_line_grouping(&block);
-        assert_eq!(groups.len(), 2);
-
-        // First group is context
-        assert_eq!(groups[0].0.len(), 1);
-
-        // Second group is old+new pair
-        assert_eq!(groups[1].1.len(), 1);
-        assert_eq!(groups[1].2.len(), 1);
-    }
-
     #[test]
     fn test_empty_placeholder_html() {
         let renderer = SideBySideRenderer::default();
-        let html = renderer.generate_single_html(None);
+        let mut html = String::new();
+        renderer.generate_single_html(&mut html, None);
 
         assert!(html.contains("d2h-emptyplaceholder"));
         assert!(html.contains("d2h-code-side-emptyplaceholder"));
@@ -0,0 +1,165 @@
+//! Renderer showing both views of each file.
+//!
+//! Every file is rendered line by line and side by side, with buttons
+//! switching between the two views. The side-by-side view is hidden until
+//! [`VIEW_TOGGLE_JS`](crate::templates::VIEW_TOGGLE_JS) switches to it.
+
+use serde_json::json;
+
+use crate::templates::{self, TemplateName};
+use crate::types::{DiffFile, GroupBy};
+
+use super::line_by_line::LineByLineRenderer;
+use super::side_by_side::SideBySideRenderer;
+use super::tree::{RenderTree, wrap_files};
+use super::utils::{RendererConfig, color_scheme_to_css, join_file_diffs, make_html_ids};
+
+/// Renderer for generating the HTML of both views, switchable per file.
+#[derive(Debug)]
+pub struct SwitchableRenderer {
+    config: RendererConfig,
+    line_by_line: LineByLineRenderer,
+    side_by_side: SideBySideRenderer,
+}
+
+impl Default for SwitchableRenderer {
+    fn default() -> Self {
+        Self::new(RendererConfig::default())
+    }
+}
+
+impl SwitchableRenderer {
+    /// Create a new SwitchableRenderer with the given configuration.
+    pub fn new(config: RendererConfig) -> Self {
+        // The files are grouped and wrapped once, around both views
+        let view_config = RendererConfig {
+            group_by: GroupBy::None,
+            no_wrapper: true,
+            ..config.clone()
+        };
+        Self {
+            line_by_line: LineByLineRenderer::new(view_config.clone()),
+            side_by_side: SideBySideRenderer::new(view_config),
+            config,
+        }
+    }
+
+    /// Render a list of diff files to HTML.
+    pub fn render(&self, diff_files: &[DiffFile]) -> String {
+        let html_ids = make_html_ids(
+            diff_files,
+            self.config.deterministic,
+            self.config.html_id_strategy,
+        );
+        let diffs_html = join_file_diffs(diff_files, self.config.group_by, |index, file| {
+            self.render_file(file, &html_ids[index])
+        });
+        if self.config.no_wrapper {
+            return diffs_html;
+        }
+
+        templates::render(
+            TemplateName::GenericWrapper,
+            &json!({
+                "colorScheme": color_scheme_to_css(self.config.render.color_scheme),
+                "content": diffs_html,
+            }),
+        )
+    }
+
+    /// Render a list of diff files as a tree of their line-by-line view, as
+    /// the tree has no switch between the views.
+    pub fn render_tree(&self, diff_files: &[DiffFile]) -> RenderTree {
+        let html_ids = make_html_ids(
+            diff_files,
+            self.config.deterministic,
+            self.config.html_id_strategy,
+        );
+        let files = diff_files
+            .iter()
+            .zip(&html_ids)
+            .filter_map(|(file, html_id)| self.line_by_line.render_file_tree(file, html_id))
+            .collect();
+        wrap_files(files, &self.config)
+    }
+
+    /// Render both views of a file with the given HTML ID, without wrapper.
+    pub(crate) fn render_file(&self, file: &DiffFile, html_id: &str) -> String {
+        // The switch takes the ID of the file, so links to it work in both
+        // views, and the views get an ID of their own
+        let line_by_line = self
+            .line_by_line
+            .render_file(file, &format!("{html_id}-line-by-line"));
+        if line_by_line.is_empty() {
+            return String::new();
+        }
+        let side_by_side = self
+            .side_by_side
+            .render_file(file, &format!("{html_id}-side-by-side"));
+        templates::render(
+            TemplateName::SwitchableFileDiff,
+            &json!({
+                "fileHtmlId": html_id,
+                "lineByLine": line_by_line,
+                "sideBySide": side_by_side,
+            }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{DiffParserConfig, parse};
+    use crate::render::make_html_id;
+    use crate::types::HtmlIdStrategy;
+
+    const DIFF: &str = "--- a/src/a.txt\n+++ b/src/a.txt\n@@ -1 +1 @@\n-a\n+b\n\
+                        --- a/b.txt\n+++ b/b.txt\n@@ -1 +1 @@\n-c\n+d\n";
+
+    #[test]
+    fn test_render() {
+        let files = parse(DIFF, &DiffParserConfig::default());
+        let html = SwitchableRenderer::default().render(&files);
+
+        assert!(html.contains("class=\"d2h-wrapper"));
+        assert_eq!(html.matches("class=\"d2h-view-switch\"").count(), 2);
+        assert_eq!(html.matches("class=\"d2h-file-diff\"").count(), 2);
+        assert_eq!(html.matches("class=\"d2h-files-diff\"").count(), 2);
+        assert_eq!(html.matches("d2h-view-side-by-side d2h-d-none").count(), 2);
+
+        let id = make_html_id(&files[0], false, HtmlIdStrategy::Hash);
+        assert_eq!(html.matches(&format!("id=\"{}\"", id)).count(), 1);
+        assert!(html.contains(&format!("id=\"{}-line-by-line\"", id)));
+        assert!(html.contains(&format!("id=\"{}-side-by-side\"", id)));
+    }
+
+    #[test]
+    fn test_render_group_by() {
+        let files = parse(DIFF, &DiffParserConfig::default());
+        let renderer = SwitchableRenderer::new(RendererConfig {
+            group_by: GroupBy::Directory,
+            ..Default::default()
+        });
+        let html = renderer.render(&files);
+
+        assert_eq!(html.matches("class=\"d2h-group-header\"").count(), 2);
+        assert!(html.find(">src<").unwrap() < html.find("src/a.txt").unwrap());
+    }
+
+    #[test]
+    fn test_render_nothing_when_empty() {
+        let file = DiffFile {
+            old_name: "a.txt".to_string(),
+            new_name: "a.txt".to_string(),
+            ..Default::default()
+        };
+        let renderer = SwitchableRenderer::new(RendererConfig {
+            render_nothing_when_empty: true,
+            no_wrapper: true,
+            ..Default::default()
+        });
+
+        assert_eq!(renderer.render(&[file]), "");
+    }
+}
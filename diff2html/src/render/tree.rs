@@ -0,0 +1,879 @@
+//! Rendered diffs as a tree of elements.
+//!
+//! [`LineByLineRenderer::render_tree`](super::LineByLineRenderer::render_tree)
+//! and [`SideBySideRenderer::render_tree`](super::SideBySideRenderer::render_tree)
+//! build a [`RenderTree`] from the parsed files instead of HTML, for consumers
+//! transforming the structure, e.g. into a virtual DOM, terminal widgets or
+//! PDF layouts, without parsing HTML. Its elements and classes are those of
+//! the default templates with the table markup, so the tree can be styled
+//! with [`CSS`](crate::CSS).
+//!
+//! The parts configured as HTML, i.e. custom templates and icons, hooks and
+//! autolinks, blame, image previews and file header extras, are left out, as
+//! are the headers of file groups.
+
+use serde::Serialize;
+use similar::ChangeTag;
+
+use crate::templates::LineNumbers;
+use crate::types::{DiffBlock, DiffFile, DiffLine, LineType, WhitespaceChanges};
+
+use super::analysis::{
+    LineGroup, MatchedChanges, group_lines, is_whitespace_only_change, match_changes,
+    take_highlight,
+};
+use super::utils::{
+    CSSLineClass, RendererConfig, binary_size_summary, changed_line_class, color_scheme_to_css,
+    deconstruct_line, diff_changes, escape_for_html, file_url, file_url_path, filename_diff,
+    get_file_icon, prefix_length, truncation_index,
+};
+
+/// Elements without content or closing tag.
+const VOID_ELEMENTS: &[&str] = &["br", "input"];
+
+/// Text of the cells left empty, a no-break space as `&nbsp;` in the HTML.
+const NBSP: &str = "\u{a0}";
+
+/// A tree of rendered elements and text.
+///
+/// # Example
+///
+/// ```
+/// use diff2html::{DiffParserConfig, LineByLineRenderer, parse};
+///
+/// let diff = "--- a/file.txt\n+++ b/file.txt\n@@ -1 +1 @@\n-old\n+new\n";
+/// let files = parse(diff, &DiffParserConfig::default());
+/// let tree = LineByLineRenderer::default().render_tree(&files);
+///
+/// let wrappers = tree.find_by_class("d2h-file-wrapper");
+/// assert_eq!(wrappers.len(), 1);
+/// assert_eq!(wrappers[0].tag, "div");
+/// assert_eq!(wrappers[0].find_by_class("d2h-file-name")[0].text(), "file.txt");
+/// let lines: Vec<_> = tree
+///     .find_by_class("d2h-code-line-ctn")
+///     .iter()
+///     .map(|line| line.text())
+///     .collect();
+/// assert_eq!(lines, ["old", "new"]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct RenderTree {
+    /// Top-level nodes, in document order.
+    pub nodes: Vec<RenderNode>,
+}
+
+/// An element or text of a [`RenderTree`].
+///
+/// Serialized as an object for elements and as a string for text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum RenderNode {
+    Element(RenderElement),
+    /// Text as shown, without character references.
+    Text(String),
+}
+
+/// An element of a [`RenderTree`].
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct RenderElement {
+    /// Lowercase tag name, e.g. `div`.
+    pub tag: String,
+    /// Classes of the `class` attribute, in order.
+    pub classes: Vec<String>,
+    /// Other attributes as name and value, in order. Attributes without
+    /// value, such as `hidden`, have an empty value.
+    pub attrs: Vec<(String, String)>,
+    pub children: Vec<RenderNode>,
+}
+
+impl RenderTree {
+    /// Write the tree as HTML.
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        write_nodes(&self.nodes, &mut html);
+        html
+    }
+
+    /// Get the elements with the given class, in document order.
+    pub fn find_by_class(&self, class: &str) -> Vec<&RenderElement> {
+        let mut found = Vec::new();
+        find_by_class(&self.nodes, class, &mut found);
+        found
+    }
+}
+
+impl RenderElement {
+    /// Create an element without classes, attributes or children.
+    pub fn new(tag: &str) -> Self {
+        Self {
+            tag: tag.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Add classes, separated by whitespace.
+    pub fn with_class(mut self, classes: &str) -> Self {
+        self.classes
+            .extend(classes.split_ascii_whitespace().map(str::to_string));
+        self
+    }
+
+    /// Add an attribute other than `class`.
+    pub fn with_attr(mut self, name: &str, value: &str) -> Self {
+        self.attrs.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Add a child element.
+    pub fn with_child(mut self, child: RenderElement) -> Self {
+        self.children.push(RenderNode::Element(child));
+        self
+    }
+
+    /// Add text, merged with the text before it if any.
+    pub fn with_text(mut self, text: &str) -> Self {
+        push_text(&mut self.children, text);
+        self
+    }
+
+    /// Add child nodes.
+    pub fn with_children(mut self, children: impl IntoIterator<Item = RenderNode>) -> Self {
+        self.children.extend(children);
+        self
+    }
+
+    /// Check whether the element has the given class.
+    pub fn has_class(&self, class: &str) -> bool {
+        self.classes.iter().any(|c| c == class)
+    }
+
+    /// Get the value of an attribute other than `class`.
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(attr, _)| attr == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Get the text of the element and its descendants.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        collect_text(&self.children, &mut text);
+        text
+    }
+
+    /// Get the descendants of the element with the given class, in document
+    /// order.
+    pub fn find_by_class(&self, class: &str) -> Vec<&RenderElement> {
+        let mut found = Vec::new();
+        find_by_class(&self.children, class, &mut found);
+        found
+    }
+}
+
+/// Add text to nodes, merged with the text node ending them if any.
+fn push_text(nodes: &mut Vec<RenderNode>, text: &str) {
+    match nodes.last_mut() {
+        Some(RenderNode::Text(previous)) => previous.push_str(text),
+        _ => nodes.push(RenderNode::Text(text.to_string())),
+    }
+}
+
+/// Write nodes as HTML.
+fn write_nodes(nodes: &[RenderNode], html: &mut String) {
+    for node in nodes {
+        match node {
+            RenderNode::Text(text) => html.push_str(&escape_for_html(text)),
+            RenderNode::Element(element) => {
+                html.push('<');
+                html.push_str(&element.tag);
+                if !element.classes.is_empty() {
+                    html.push_str(&format!(" class=\"{}\"", element.classes.join(" ")));
+                }
+                for (name, value) in &element.attrs {
+                    if value.is_empty() {
+                        html.push_str(&format!(" {}", name));
+                    } else {
+                        html.push_str(&format!(" {}=\"{}\"", name, escape_for_html(value)));
+                    }
+                }
+                html.push('>');
+                if !VOID_ELEMENTS.contains(&element.tag.as_str()) {
+                    write_nodes(&element.children, html);
+                    html.push_str(&format!("</{}>", element.tag));
+                }
+            }
+        }
+    }
+}
+
+/// Append the text of nodes and their descendants.
+fn collect_text(nodes: &[RenderNode], text: &mut String) {
+    for node in nodes {
+        match node {
+            RenderNode::Text(t) => text.push_str(t),
+            RenderNode::Element(element) => collect_text(&element.children, text),
+        }
+    }
+}
+
+/// Append the elements of nodes and their descendants with the given class.
+fn find_by_class<'a>(nodes: &'a [RenderNode], class: &str, found: &mut Vec<&'a RenderElement>) {
+    for node in nodes {
+        if let RenderNode::Element(element) = node {
+            if element.has_class(class) {
+                found.push(element);
+            }
+            find_by_class(&element.children, class, found);
+        }
+    }
+}
+
+/// A diff line ready to be laid out in a row of the tree.
+#[derive(Debug, Clone)]
+pub(crate) struct TreeLine {
+    pub(crate) css_class: CSSLineClass,
+    pub(crate) prefix: String,
+    pub(crate) content: Vec<RenderNode>,
+    pub(crate) old_number: Option<u32>,
+    pub(crate) new_number: Option<u32>,
+}
+
+/// A deleted and an inserted line shown next to each other, or a line shown
+/// in both versions.
+#[derive(Debug, Clone)]
+pub(crate) struct LinePair {
+    /// The line in the old version, numbered in it only.
+    pub(crate) old: Option<TreeLine>,
+    /// The line in the new version, numbered in it only.
+    pub(crate) new: Option<TreeLine>,
+    /// Whether both lines are shown as context, for a context line or a
+    /// change of whitespace hidden by `whitespace_changes`.
+    pub(crate) context: bool,
+}
+
+/// Prepare the lines of a block like the renderers, in groups of pairs: a
+/// group for each context line, and the changes paired by position.
+pub(crate) fn block_line_pairs(
+    block: &DiffBlock,
+    is_combined: bool,
+    config: &RendererConfig,
+) -> Vec<Vec<LinePair>> {
+    // Pairs of lines left to highlight in the block
+    let mut highlight_budget = config.max_highlight_pairs_per_block.unwrap_or(usize::MAX);
+    let mut groups = Vec::new();
+    for group in group_lines(block) {
+        match group {
+            LineGroup::Context(line) => {
+                let prefix = deconstruct_line(&line.content, is_combined, false).prefix;
+                groups.push(vec![context_pair(line, line, &prefix, is_combined, config)]);
+            }
+            LineGroup::Changes { deleted, inserted } => {
+                for changes in match_changes(deleted, inserted, is_combined, config) {
+                    groups.push(changed_line_pairs(
+                        &changes,
+                        &mut highlight_budget,
+                        is_combined,
+                        config,
+                    ));
+                }
+            }
+        }
+    }
+    groups
+}
+
+/// Prepare a pair of lines shown as context, with the old line numbered in
+/// the old version and the new line in the new version.
+fn context_pair(
+    old: &DiffLine,
+    new: &DiffLine,
+    prefix: &str,
+    is_combined: bool,
+    config: &RendererConfig,
+) -> LinePair {
+    let line = |line: &DiffLine, old_number, new_number| TreeLine {
+        css_class: CSSLineClass::Context,
+        prefix: prefix.to_string(),
+        content: line_content(line, None, is_combined, config),
+        old_number,
+        new_number,
+    };
+    LinePair {
+        old: Some(line(old, old.old_number, None)),
+        new: Some(line(new, None, new.new_number)),
+        context: true,
+    }
+}
+
+/// Prepare changed lines paired by position, highlighting the differences
+/// between the paired lines like
+/// [`LineByLineRenderer`](super::LineByLineRenderer).
+fn changed_line_pairs(
+    changes: &MatchedChanges,
+    highlight_budget: &mut usize,
+    is_combined: bool,
+    config: &RendererConfig,
+) -> Vec<LinePair> {
+    let max_lines = changes.deleted.len().max(changes.inserted.len());
+    (0..max_lines)
+        .map(|i| {
+            let old_line = changes.deleted.get(i);
+            let new_line = changes.inserted.get(i);
+
+            let highlight = match (old_line, new_line) {
+                (Some(old), Some(new))
+                    if take_highlight(old, new, changes, highlight_budget, is_combined, config) =>
+                {
+                    Some(highlighted_contents(old, new, is_combined, config))
+                }
+                _ => None,
+            };
+            let whitespace_only = match (old_line, new_line) {
+                (Some(old), Some(new)) if config.whitespace_changes != WhitespaceChanges::Show => {
+                    is_whitespace_only_change(old, new, is_combined)
+                }
+                _ => false,
+            };
+            if whitespace_only
+                && config.whitespace_changes == WhitespaceChanges::Hide
+                && let (Some(old), Some(new)) = (old_line, new_line)
+            {
+                let prefix = " ".repeat(prefix_length(is_combined));
+                return context_pair(old, new, &prefix, is_combined, config);
+            }
+
+            let (old_content, new_content) = highlight.unzip();
+            let (delete_class, insert_class) = if whitespace_only {
+                (
+                    CSSLineClass::DeleteWhitespaceOnly,
+                    CSSLineClass::InsertWhitespaceOnly,
+                )
+            } else {
+                (CSSLineClass::DeleteChanges, CSSLineClass::InsertChanges)
+            };
+            let changed_line = |line: &DiffLine, content: Option<Vec<RenderNode>>, class| {
+                let css_class = if content.is_some() {
+                    class
+                } else {
+                    changed_line_class(line)
+                };
+                TreeLine {
+                    css_class,
+                    prefix: deconstruct_line(&line.content, is_combined, false).prefix,
+                    content: line_content(line, content, is_combined, config),
+                    old_number: line.old_number,
+                    new_number: line.new_number,
+                }
+            };
+
+            LinePair {
+                old: old_line
+                    .filter(|old| old.old_number.is_some())
+                    .map(|old| changed_line(old, old_content, delete_class)),
+                new: new_line
+                    .filter(|new| new.new_number.is_some())
+                    .map(|new| changed_line(new, new_content, insert_class)),
+                context: false,
+            }
+        })
+        .collect()
+}
+
+/// Highlight the differences between a deleted and an inserted line, as the
+/// contents of both with the changed parts in `del` and `ins` elements.
+fn highlighted_contents(
+    old: &DiffLine,
+    new: &DiffLine,
+    is_combined: bool,
+    config: &RendererConfig,
+) -> (Vec<RenderNode>, Vec<RenderNode>) {
+    let old_text = deconstruct_line(&old.content, is_combined, false).content;
+    let new_text = deconstruct_line(&new.content, is_combined, false).content;
+    let mut old_content = Vec::new();
+    let mut new_content = Vec::new();
+    let highlighted = diff_changes(
+        &old_text,
+        &new_text,
+        &config.render,
+        |tag, value| match tag {
+            ChangeTag::Insert => new_content.push(changed_text("ins", value)),
+            ChangeTag::Delete => old_content.push(changed_text("del", value)),
+            ChangeTag::Equal => {
+                push_text(&mut old_content, value);
+                push_text(&mut new_content, value);
+            }
+        },
+    );
+    if !highlighted {
+        return (
+            vec![RenderNode::Text(old_text)],
+            vec![RenderNode::Text(new_text)],
+        );
+    }
+    (old_content, new_content)
+}
+
+/// Wrap highlighted text in an element.
+fn changed_text(tag: &str, text: &str) -> RenderNode {
+    RenderNode::Element(RenderElement::new(tag).with_text(text))
+}
+
+/// Get the content of a line without its prefix: the highlighted content if
+/// any, else its word diff or text, truncated to `max_rendered_line_length`
+/// and followed by the "No newline at end of file" indicator.
+fn line_content(
+    line: &DiffLine,
+    highlighted: Option<Vec<RenderNode>>,
+    is_combined: bool,
+    config: &RendererConfig,
+) -> Vec<RenderNode> {
+    let text = deconstruct_line(&line.content, is_combined, false).content;
+    let mut content = if let Some(index) = truncation_index(line, is_combined, config) {
+        let (visible, rest) = text.split_at(index);
+        let truncated = RenderElement::new("span")
+            .with_class("d2h-truncated")
+            .with_child(
+                RenderElement::new("label")
+                    .with_class("d2h-show-line")
+                    .with_attr("title", "Show full line")
+                    .with_child(
+                        RenderElement::new("input")
+                            .with_class("d2h-show-line-input")
+                            .with_attr("type", "checkbox"),
+                    )
+                    .with_text("\u{2026}"),
+            )
+            .with_child(
+                RenderElement::new("span")
+                    .with_class("d2h-truncated-rest")
+                    .with_attr("hidden", "")
+                    .with_text(rest),
+            );
+        vec![
+            RenderNode::Text(visible.to_string()),
+            RenderNode::Element(truncated),
+        ]
+    } else if let Some(highlighted) = highlighted {
+        highlighted
+    } else if let Some(segments) = &line.word_diff {
+        let tag = match line.line_type {
+            LineType::Delete => "del",
+            LineType::Insert => "ins",
+            LineType::Context => "",
+        };
+        let mut content = Vec::new();
+        for segment in segments {
+            if segment.changed && !tag.is_empty() {
+                content.push(changed_text(tag, &segment.text));
+            } else {
+                push_text(&mut content, &segment.text);
+            }
+        }
+        content
+    } else {
+        vec![RenderNode::Text(text)]
+    };
+    content.retain(|node| !matches!(node, RenderNode::Text(text) if text.is_empty()));
+
+    if line.no_newline_at_eof {
+        content.push(RenderNode::Element(
+            RenderElement::new("span")
+                .with_class("d2h-no-newline")
+                .with_attr("title", "No newline at end of file")
+                .with_text("\u{26d4} No newline at end of file"),
+        ));
+    }
+    content
+}
+
+/// Build the row of a diff line, with the line numbers in the first cell and
+/// the prefix and content in the second.
+pub(crate) fn line_row(
+    line_type: &str,
+    line_class: &str,
+    content_class: &str,
+    line_numbers: LineNumbers,
+    prefix: &str,
+    content: Vec<RenderNode>,
+) -> RenderElement {
+    let number = |class: Option<&str>, number: Option<u32>| {
+        let text = number.map(|n| n.to_string()).unwrap_or_default();
+        match class {
+            Some(class) => vec![RenderNode::Element(
+                RenderElement::new("div").with_class(class).with_text(&text),
+            )],
+            None if text.is_empty() => Vec::new(),
+            None => vec![RenderNode::Text(text)],
+        }
+    };
+    let numbers = match line_numbers {
+        LineNumbers::Single(n) => number(None, n),
+        LineNumbers::OldAndNew(old, new) => {
+            let mut numbers = number(Some("line-num1"), old);
+            numbers.extend(number(Some("line-num2"), new));
+            numbers
+        }
+    };
+    let prefix = if matches!(prefix, "" | " ") {
+        NBSP
+    } else {
+        prefix
+    };
+    let content = if content.is_empty() {
+        vec![RenderNode::Element(RenderElement::new("br"))]
+    } else {
+        content
+    };
+
+    RenderElement::new("tr")
+        .with_child(
+            RenderElement::new("td")
+                .with_class(line_class)
+                .with_class(line_type)
+                .with_children(numbers),
+        )
+        .with_child(
+            RenderElement::new("td").with_class(line_type).with_child(
+                RenderElement::new("div")
+                    .with_class(content_class)
+                    .with_child(
+                        RenderElement::new("span")
+                            .with_class("d2h-code-line-prefix")
+                            .with_text(prefix),
+                    )
+                    .with_child(
+                        RenderElement::new("span")
+                            .with_class("d2h-code-line-ctn")
+                            .with_children(content),
+                    ),
+            ),
+        )
+}
+
+/// Build the row of a diff line from a prepared line.
+pub(crate) fn tree_line_row(
+    line: TreeLine,
+    line_class: &str,
+    content_class: &str,
+    line_numbers: LineNumbers,
+) -> RenderElement {
+    line_row(
+        line.css_class.as_str(),
+        line_class,
+        content_class,
+        line_numbers,
+        &line.prefix,
+        line.content,
+    )
+}
+
+/// Build the row of a block header, shown as text. The headers of binary
+/// patches are replaced by a size summary like in the HTML.
+pub(crate) fn block_header_row(
+    header: &str,
+    file: Option<&DiffFile>,
+    line_class: &str,
+    content_class: &str,
+    config: &RendererConfig,
+) -> RenderElement {
+    let summary = file
+        .filter(|f| f.is_binary == Some(true) && header.starts_with("GIT binary patch"))
+        .and_then(binary_size_summary);
+    let header = summary.as_deref().unwrap_or(header);
+    let row = RenderElement::new("tr");
+    let row = if config.sticky_headers {
+        row.with_class("d2h-sticky-block-header")
+    } else {
+        row
+    };
+    row.with_child(
+        RenderElement::new("td")
+            .with_class(line_class)
+            .with_class(CSSLineClass::Info.as_str()),
+    )
+    .with_child(
+        RenderElement::new("td")
+            .with_class(CSSLineClass::Info.as_str())
+            .with_child(
+                RenderElement::new("div")
+                    .with_class(content_class)
+                    .with_text(if header.is_empty() { NBSP } else { header }),
+            ),
+    )
+}
+
+/// Build the row shown above the blocks of a collapsed too big file, with
+/// the toggle expanding them or as a placeholder for a second column.
+pub(crate) fn collapsed_row(line_class: &str, content_class: &str, toggle: bool) -> RenderElement {
+    let content = RenderElement::new("div").with_class(content_class);
+    let content = if toggle {
+        content.with_child(
+            RenderElement::new("label")
+                .with_class("d2h-collapsed-diff-toggle")
+                .with_child(
+                    RenderElement::new("input")
+                        .with_class("d2h-collapsed-diff-input")
+                        .with_attr("type", "checkbox"),
+                )
+                .with_text(" Diff too large \u{2014} click to expand"),
+        )
+    } else {
+        content.with_text(NBSP)
+    };
+    RenderElement::new("tr")
+        .with_class("d2h-collapsed-diff")
+        .with_child(
+            RenderElement::new("td")
+                .with_class(line_class)
+                .with_class(CSSLineClass::Info.as_str()),
+        )
+        .with_child(
+            RenderElement::new("td")
+                .with_class(CSSLineClass::Info.as_str())
+                .with_child(content),
+        )
+}
+
+/// Build the row of a message shown instead of the lines of a file.
+pub(crate) fn message_row(content_class: &str, message: &str) -> RenderElement {
+    RenderElement::new("tr").with_child(
+        RenderElement::new("td")
+            .with_class(CSSLineClass::Info.as_str())
+            .with_child(
+                RenderElement::new("div")
+                    .with_class(content_class)
+                    .with_text(message),
+            ),
+    )
+}
+
+/// Build the table of rows of a file diff, in its code wrapper.
+pub(crate) fn code_table(rows: Vec<RenderElement>) -> RenderElement {
+    RenderElement::new("div")
+        .with_class("d2h-code-wrapper")
+        .with_child(
+            RenderElement::new("table")
+                .with_class("d2h-diff-table")
+                .with_child(
+                    RenderElement::new("tbody")
+                        .with_class("d2h-diff-tbody")
+                        .with_children(rows.into_iter().map(RenderNode::Element)),
+                ),
+        )
+}
+
+/// Build the wrapper of a file diff, with its header followed by the diff.
+pub(crate) fn file_wrapper(
+    file: &DiffFile,
+    html_id: &str,
+    config: &RendererConfig,
+    diff: RenderElement,
+) -> RenderElement {
+    let mut wrapper = RenderElement::new("div").with_class("d2h-file-wrapper");
+    if config.sticky_headers {
+        wrapper = wrapper.with_class("d2h-sticky-headers");
+    }
+    if config.wrap_lines {
+        wrapper = wrapper.with_class("d2h-wrap-lines");
+    }
+    wrapper
+        .with_attr("id", html_id)
+        .with_attr("data-lang", &file.language)
+        .with_child(file_header(file, config))
+        .with_child(diff)
+}
+
+/// Build the header of a file diff, with the file name, its status tag and
+/// the toggle marking it as viewed.
+fn file_header(file: &DiffFile, config: &RendererConfig) -> RenderElement {
+    let name = filename_diff(file);
+    let name = match &config.file_url_template {
+        Some(template) => RenderElement::new("a")
+            .with_class("d2h-file-name")
+            .with_attr(
+                "href",
+                &file_url(
+                    template,
+                    file,
+                    file_url_path(file),
+                    config.file_url_revisions.as_ref(),
+                ),
+            )
+            .with_attr("target", "_blank")
+            .with_attr("rel", "noopener noreferrer")
+            .with_text(&name),
+        None => RenderElement::new("span")
+            .with_class("d2h-file-name")
+            .with_text(&name),
+    };
+    let (tag_class, tag) = match get_file_icon(file) {
+        "file-copied" => ("d2h-moved d2h-copied-tag", "COPIED"),
+        "file-renamed" => ("d2h-moved d2h-moved-tag", "RENAMED"),
+        "file-added" => ("d2h-added d2h-added-tag", "ADDED"),
+        "file-deleted" => ("d2h-deleted d2h-deleted-tag", "DELETED"),
+        _ => ("d2h-changed d2h-changed-tag", "CHANGED"),
+    };
+
+    let mut viewed = RenderElement::new("input")
+        .with_class("d2h-file-collapse-input")
+        .with_attr("type", "checkbox")
+        .with_attr("name", "viewed")
+        .with_attr("value", "viewed");
+    if config.collapsed {
+        viewed = viewed.with_attr("checked", "");
+    }
+
+    let header = RenderElement::new("div").with_class("d2h-file-header");
+    let header = if config.sticky_headers {
+        header.with_class("d2h-sticky-header")
+    } else {
+        header
+    };
+    header
+        .with_child(
+            RenderElement::new("span")
+                .with_class("d2h-file-name-wrapper")
+                .with_child(name)
+                .with_child(
+                    RenderElement::new("span")
+                        .with_class("d2h-tag")
+                        .with_class(tag_class)
+                        .with_text(tag),
+                ),
+        )
+        .with_child(
+            RenderElement::new("label")
+                .with_class("d2h-file-collapse")
+                .with_child(viewed)
+                .with_text("Viewed"),
+        )
+}
+
+/// Build the tree of rendered files, in the wrapper with the color scheme
+/// unless `no_wrapper` is set.
+pub(crate) fn wrap_files(files: Vec<RenderElement>, config: &RendererConfig) -> RenderTree {
+    let files = files.into_iter().map(RenderNode::Element);
+    if config.no_wrapper {
+        return RenderTree {
+            nodes: files.collect(),
+        };
+    }
+    let wrapper = RenderElement::new("div")
+        .with_class("d2h-wrapper")
+        .with_class(color_scheme_to_css(config.render.color_scheme))
+        .with_children(files);
+    RenderTree {
+        nodes: vec![RenderNode::Element(wrapper)],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{DiffParserConfig, parse};
+    use crate::render::{LineByLineRenderer, SideBySideRenderer};
+
+    #[test]
+    fn test_to_html() {
+        let element = RenderElement::new("div")
+            .with_class("a  b")
+            .with_attr("title", "\"x\"")
+            .with_attr("hidden", "")
+            .with_text("<1> ")
+            .with_text("& 2")
+            .with_child(RenderElement::new("br"))
+            .with_child(RenderElement::new("ins").with_text("3"));
+        assert_eq!(element.classes, ["a", "b"]);
+        assert_eq!(element.children.len(), 3);
+        assert_eq!(element.text(), "<1> & 23");
+        assert_eq!(
+            RenderTree {
+                nodes: vec![RenderNode::Element(element)]
+            }
+            .to_html(),
+            "<div class=\"a b\" title=\"&quot;x&quot;\" hidden>&lt;1&gt; &amp; 2<br><ins>3</ins></div>"
+        );
+    }
+
+    #[test]
+    fn test_render_tree_highlighting() {
+        let diff = "--- a/a.txt\n+++ b/a.txt\n@@ -1,2 +1,2 @@\n a\n-<old> x\n+\"new\" x\n\\ No newline at end of file\n";
+        let files = parse(diff, &DiffParserConfig::default());
+        let tree = LineByLineRenderer::default().render_tree(&files);
+
+        let rows = tree.find_by_class("d2h-code-line-ctn");
+        let texts: Vec<_> = rows.iter().map(|row| row.text()).collect();
+        assert_eq!(
+            texts,
+            [
+                "a",
+                "<old> x",
+                "\"new\" x\u{26d4} No newline at end of file"
+            ]
+        );
+        let RenderNode::Element(deleted) = &rows[1].children[0] else {
+            panic!("expected the deleted word first: {:?}", rows[1]);
+        };
+        assert_eq!(
+            (deleted.tag.as_str(), deleted.text()),
+            ("del", "<old>".to_string())
+        );
+        assert_eq!(rows[2].find_by_class("d2h-no-newline").len(), 1);
+
+        let numbers: Vec<_> = tree
+            .find_by_class("d2h-code-linenumber")
+            .iter()
+            .map(|cell| cell.text())
+            .collect();
+        assert_eq!(numbers, ["", "11", "2", "2"]);
+        assert_eq!(
+            tree.find_by_class("d2h-del")[0].classes,
+            ["d2h-code-linenumber", "d2h-del", "d2h-change"]
+        );
+    }
+
+    #[test]
+    fn test_render_tree_whitespace_changes_hide() {
+        let diff = "--- a/a.txt\n+++ b/a.txt\n@@ -1,2 +1,2 @@\n-a\n-if x {\n+b\n+if  x {\n";
+        let files = parse(diff, &DiffParserConfig::default());
+        let tree = LineByLineRenderer::new(RendererConfig {
+            whitespace_changes: WhitespaceChanges::Hide,
+            ..Default::default()
+        })
+        .render_tree(&files);
+
+        let rows: Vec<_> = tree
+            .find_by_class("d2h-code-line")
+            .iter()
+            .skip(1)
+            .map(|line| line.text())
+            .collect();
+        assert_eq!(rows, ["-a", "+b", "\u{a0}if  x {"]);
+        let context = tree.find_by_class("d2h-cntx");
+        assert_eq!(context[0].text(), "22");
+    }
+
+    #[test]
+    fn test_render_tree_side_by_side() {
+        let diff = "--- a/a.txt\n+++ b/a.txt\n@@ -1,2 +1 @@\n a\n-b\n";
+        let files = parse(diff, &DiffParserConfig::default());
+        let tree = SideBySideRenderer::default().render_tree(&files);
+
+        let sides = tree.find_by_class("d2h-file-side-diff");
+        assert_eq!(sides.len(), 2);
+        let lines = |side: &RenderElement| -> Vec<String> {
+            side.find_by_class("d2h-code-side-line")
+                .iter()
+                .map(|line| line.text())
+                .collect()
+        };
+        assert_eq!(lines(sides[0]), ["@@ -1,2 +1 @@", "\u{a0}a", "-b"]);
+        assert_eq!(lines(sides[1]), ["\u{a0}", "\u{a0}a", "\u{a0}"]);
+        assert_eq!(sides[1].find_by_class("d2h-emptyplaceholder").len(), 2);
+
+        let json = serde_json::to_value(&tree).unwrap();
+        assert_eq!(json["nodes"][0]["tag"], "div");
+        assert_eq!(json["nodes"][0]["classes"][0], "d2h-wrapper");
+    }
+}
@@ -3,13 +3,28 @@
 //! This module provides shared utilities used by the renderers including
 //! HTML escaping, line deconstruction, diff highlighting, and CSS class mappings.
 
-use regex::Regex;
+use serde_json::json;
 use similar::{ChangeTag, TextDiff};
 use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashSet};
 use std::hash::{Hash, Hasher};
-use std::sync::LazyLock;
-
-use crate::types::{ColorScheme, DiffFile, DiffLineParts, DiffStyle, LineMatchingType, LineType};
+use std::sync::Arc;
+
+use crate::defaults;
+use crate::templates::{self, TemplateName};
+use crate::text::width_index;
+
+use super::autolink::AutolinkConfig;
+use super::blame::BlameSource;
+use super::hooks::{FileHeaderExtra, RenderHook};
+use super::icons::{FileIcon, FileIconMatch, file_icon_html};
+use super::image::ImageSource;
+use super::minified::MinifiedFileDetection;
+use super::overrides::FileConfigOverride;
+use crate::types::{
+    Checksum, ColorScheme, DiffFile, DiffLine, DiffLineParts, DiffStyle, FileMode, GroupBy,
+    HtmlIdStrategy, LineMatchingType, LineType, OutputMarkup, RenameDisplay, WhitespaceChanges,
+};
 
 /// CSS class names for diff line types.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -20,6 +35,8 @@ pub enum CSSLineClass {
     Info,
     InsertChanges,
     DeleteChanges,
+    InsertWhitespaceOnly,
+    DeleteWhitespaceOnly,
 }
 
 impl CSSLineClass {
@@ -32,6 +49,8 @@ impl CSSLineClass {
             Self::Info => "d2h-info",
             Self::InsertChanges => "d2h-ins d2h-change",
             Self::DeleteChanges => "d2h-del d2h-change",
+            Self::InsertWhitespaceOnly => "d2h-ins d2h-change d2h-whitespace-only",
+            Self::DeleteWhitespaceOnly => "d2h-del d2h-change d2h-whitespace-only",
         }
     }
 }
@@ -49,6 +68,20 @@ pub struct HighlightedLines {
     pub new_line: DiffLineParts,
 }
 
+/// A change of file permissions between the old and new version of a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileModeChange {
+    pub old_mode: String,
+    pub new_mode: String,
+}
+
+impl FileModeChange {
+    /// Returns true if the executable bit differs between the two modes.
+    pub fn executable_changed(&self) -> bool {
+        is_executable_mode(&self.old_mode) != is_executable_mode(&self.new_mode)
+    }
+}
+
 /// Configuration for rendering.
 #[derive(Debug, Clone)]
 pub struct RenderConfig {
@@ -57,16 +90,23 @@ pub struct RenderConfig {
     pub max_line_length_highlight: usize,
     pub diff_style: DiffStyle,
     pub color_scheme: ColorScheme,
+    /// Link URLs and references in line content.
+    pub autolink: Option<AutolinkConfig>,
+    /// Viewport width in CSS pixels below which the side-by-side panels are
+    /// stacked, see [`css_responsive`](crate::css_responsive).
+    pub responsive_breakpoint: Option<u32>,
 }
 
 impl Default for RenderConfig {
     fn default() -> Self {
         Self {
             matching: LineMatchingType::None,
-            match_words_threshold: 0.25,
-            max_line_length_highlight: 10000,
+            match_words_threshold: defaults::MATCH_WORDS_THRESHOLD,
+            max_line_length_highlight: defaults::MAX_LINE_LENGTH_HIGHLIGHT,
             diff_style: DiffStyle::Word,
             color_scheme: ColorScheme::Light,
+            autolink: None,
+            responsive_breakpoint: None,
         }
     }
 }
@@ -78,6 +118,88 @@ pub struct RendererConfig {
     pub render_nothing_when_empty: bool,
     pub matching_max_comparisons: usize,
     pub max_line_size_in_block_for_comparison: usize,
+    /// Maximum number of line pairs highlighted in a block, the others are
+    /// rendered without highlighting.
+    pub max_highlight_pairs_per_block: Option<usize>,
+    /// Number of highlighted pairs of deleted and inserted lines cached by a
+    /// renderer, so identical pairs are only highlighted once, or 0 for none.
+    pub highlight_cache_size: usize,
+    /// Maximum number of columns rendered of a line, see
+    /// [`display_width`](crate::text::display_width). Longer lines are
+    /// truncated, without highlighting, and their rest is hidden behind a
+    /// "show full line" marker.
+    pub max_rendered_line_length: Option<usize>,
+    pub show_file_metadata: bool,
+    /// Render added and deleted files in a single column in the side-by-side view.
+    pub single_column_added_deleted: bool,
+    /// Alternate deleted and inserted lines in the line-by-line view.
+    pub interleave_changes: bool,
+    /// Handling of deleted and inserted lines differing only in whitespace.
+    pub whitespace_changes: WhitespaceChanges,
+    /// Display of the paths of renamed and copied files in the file header.
+    pub rename_display: RenameDisplay,
+    /// Message shown for files without changes.
+    pub empty_diff_message: String,
+    /// Message shown for binary files without image preview.
+    pub binary_diff_message: String,
+    /// Keep the file and block headers at the top of the page while scrolling.
+    pub sticky_headers: bool,
+    /// Wrap long lines instead of scrolling horizontally. Continuation lines
+    /// are indented and the line numbers stay in the gutter.
+    pub wrap_lines: bool,
+    /// Leave out the `d2h-wrapper` element around the rendered files.
+    pub no_wrapper: bool,
+    /// Render the files collapsed, with their "Viewed" checkbox checked. The
+    /// contents are hidden by the file content toggle script.
+    pub collapsed: bool,
+    /// Paths or globs of the files to render collapsed, like generated files,
+    /// snapshots or vendored code. Patterns are matched like
+    /// [`DiffParserConfig::exclude`].
+    ///
+    /// [`DiffParserConfig::exclude`]: crate::DiffParserConfig::exclude
+    pub collapse_by_default: Vec<String>,
+    /// Render the generated files collapsed, see [`DiffFile::is_generated`].
+    pub collapse_generated: bool,
+    /// Detection of the minified files, rendered with their lines truncated
+    /// and without highlighting, before the overrides are applied. `None`,
+    /// the default, renders them like other files.
+    pub minified_files: Option<MinifiedFileDetection>,
+    /// Options overridden for the files matching a path or glob, applied in
+    /// order. Patterns are matched like [`DiffParserConfig::exclude`].
+    ///
+    /// [`DiffParserConfig::exclude`]: crate::DiffParserConfig::exclude
+    pub per_file_overrides: Vec<(String, FileConfigOverride)>,
+    /// Icons and tags replacing the templates for the matching files, see
+    /// [`FileIcon`].
+    pub file_icons: Vec<(FileIconMatch, FileIcon)>,
+    /// URL of the files linked from their name in the file header, with the
+    /// placeholders `{path}`, `{old_path}` and `{new_path}`, and `{rev}`,
+    /// `{old_rev}` and `{new_rev}`, e.g.
+    /// `https://github.com/owner/repo/blob/{rev}/{path}`. `{path}` is the new
+    /// path, or the old one for deleted files, and `{rev}` the revision of
+    /// the version at `{path}`. The paths and revisions are percent-encoded
+    /// except for `/`.
+    pub file_url_template: Option<String>,
+    /// Old and new revisions compared by the diff, for the revision
+    /// placeholders of [`file_url_template`](Self::file_url_template).
+    /// Without them, the placeholders get the abbreviated blob hashes of the
+    /// `index` lines of git diffs, or nothing.
+    pub file_url_revisions: Option<(String, String)>,
+    /// Generate the same HTML across runs, platforms and Rust versions.
+    pub deterministic: bool,
+    /// HTML IDs of the file diffs.
+    pub html_id_strategy: HtmlIdStrategy,
+    /// Treat the diff files as untrusted, escaping the headers of too big
+    /// files like any other header.
+    pub hardened: bool,
+    /// HTML markup of the diff lines.
+    pub output_markup: OutputMarkup,
+    /// Grouping of the rendered files.
+    pub group_by: GroupBy,
+    pub image_source: Option<Arc<dyn ImageSource>>,
+    pub file_header_extra: Option<Arc<dyn FileHeaderExtra>>,
+    pub render_hooks: Vec<Arc<dyn RenderHook>>,
+    pub blame_source: Option<Arc<dyn BlameSource>>,
 }
 
 impl Default for RendererConfig {
@@ -85,13 +207,43 @@ impl Default for RendererConfig {
         Self {
             render: RenderConfig::default(),
             render_nothing_when_empty: false,
-            matching_max_comparisons: 2500,
-            max_line_size_in_block_for_comparison: 200,
+            matching_max_comparisons: defaults::MATCHING_MAX_COMPARISONS,
+            max_line_size_in_block_for_comparison: defaults::MAX_LINE_SIZE_IN_BLOCK_FOR_COMPARISON,
+            max_highlight_pairs_per_block: None,
+            highlight_cache_size: 0,
+            max_rendered_line_length: None,
+            show_file_metadata: false,
+            single_column_added_deleted: false,
+            interleave_changes: false,
+            whitespace_changes: WhitespaceChanges::Show,
+            rename_display: RenameDisplay::Compact,
+            empty_diff_message: defaults::EMPTY_DIFF_MESSAGE.to_string(),
+            binary_diff_message: defaults::BINARY_DIFF_MESSAGE.to_string(),
+            sticky_headers: false,
+            wrap_lines: false,
+            no_wrapper: false,
+            collapsed: false,
+            collapse_by_default: Vec::new(),
+            collapse_generated: true,
+            minified_files: None,
+            per_file_overrides: Vec::new(),
+            file_icons: Vec::new(),
+            file_url_template: None,
+            file_url_revisions: None,
+            deterministic: false,
+            html_id_strategy: HtmlIdStrategy::Hash,
+            hardened: false,
+            output_markup: OutputMarkup::Table,
+            group_by: GroupBy::None,
+            image_source: None,
+            file_header_extra: None,
+            render_hooks: Vec::new(),
+            blame_source: None,
         }
     }
 }
 
-const SEPARATOR: char = '/';
+const SEPARATOR: &str = "/";
 
 /// Check if a filename represents /dev/null.
 fn is_dev_null_name(name: &str) -> bool {
@@ -104,7 +256,7 @@ fn unify_path(path: &str) -> String {
 }
 
 /// Get the prefix length for a diff line based on whether it's a combined diff.
-fn prefix_length(is_combined: bool) -> usize {
+pub(crate) fn prefix_length(is_combined: bool) -> usize {
     if is_combined { 2 } else { 1 }
 }
 
@@ -125,6 +277,85 @@ pub fn escape_for_html(s: &str) -> String {
     result
 }
 
+/// Escape a path or other text interpolated in the templates, like the
+/// templates of diff2html do. Unlike [`escape_for_html`], `/` is kept for
+/// readable paths, since it cannot break out of text or a quoted attribute.
+pub(crate) fn escape_path_for_html(s: &str) -> String {
+    s.split('/')
+        .map(escape_for_html)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Expand a [file URL template](RendererConfig::file_url_template) for a
+/// file.
+///
+/// `{path}` is the given path, `{old_path}` and `{new_path}` the paths of the
+/// old and new versions, both the path of the existing version for added and
+/// deleted files. `{old_rev}` and `{new_rev}` are the given revisions, or the
+/// checksums of the file, and `{rev}` the one of the version at `{path}`: the
+/// old one for deleted files and the old path of renamed files. Paths and
+/// revisions are percent-encoded, except for `/`.
+pub(crate) fn file_url(
+    template: &str,
+    file: &DiffFile,
+    path: &str,
+    revisions: Option<&(String, String)>,
+) -> String {
+    let (old_path, new_path) = if is_dev_null_name(&file.old_name) {
+        (&file.new_name, &file.new_name)
+    } else if is_dev_null_name(&file.new_name) {
+        (&file.old_name, &file.old_name)
+    } else {
+        (&file.old_name, &file.new_name)
+    };
+    let (old_rev, new_rev) = match revisions {
+        Some((old_rev, new_rev)) => (old_rev.as_str(), new_rev.as_str()),
+        None => (
+            match &file.checksum_before {
+                Some(Checksum::Single(checksum)) => checksum.as_str(),
+                Some(Checksum::Multiple(checksums)) => checksums.first().map_or("", String::as_str),
+                None => "",
+            },
+            file.checksum_after.as_deref().unwrap_or(""),
+        ),
+    };
+    let is_old_path =
+        is_dev_null_name(&file.new_name) || (path == file.old_name && path != file.new_name);
+    let rev = if is_old_path { old_rev } else { new_rev };
+    template
+        .replace("{path}", &encode_url_path(path))
+        .replace("{old_path}", &encode_url_path(old_path))
+        .replace("{new_path}", &encode_url_path(new_path))
+        .replace("{rev}", &encode_url_path(rev))
+        .replace("{old_rev}", &encode_url_path(old_rev))
+        .replace("{new_rev}", &encode_url_path(new_rev))
+}
+
+/// Path of the version of a file linked by [`file_url`], the new one unless
+/// the file is deleted.
+pub(crate) fn file_url_path(file: &DiffFile) -> &str {
+    if is_dev_null_name(&file.new_name) {
+        &file.old_name
+    } else {
+        &file.new_name
+    }
+}
+
+/// Percent-encode the bytes of a path other than unreserved URL characters
+/// and `/`.
+fn encode_url_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in unify_path(path).bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
 /// Deconstruct a diff line by separating the prefix from the content.
 ///
 /// # Arguments
@@ -163,80 +394,114 @@ pub fn filename_diff(file: &DiffFile) -> String {
     let old_filename = unify_path(&file.old_name);
     let new_filename = unify_path(&file.new_name);
 
-    if old_filename != new_filename
-        && !is_dev_null_name(&old_filename)
-        && !is_dev_null_name(&new_filename)
-    {
-        let old_parts: Vec<&str> = old_filename.split(SEPARATOR).collect();
-        let new_parts: Vec<&str> = new_filename.split(SEPARATOR).collect();
-
-        let old_len = old_parts.len();
-        let new_len = new_parts.len();
+    if is_dev_null_name(&new_filename) {
+        old_filename
+    } else if is_dev_null_name(&old_filename) {
+        new_filename
+    } else {
+        path_diff(&old_filename, &new_filename, "\u{2192}")
+    }
+}
 
-        // Find common prefix
-        let mut i = 0;
-        let mut j = old_len.saturating_sub(1);
-        let mut k = new_len.saturating_sub(1);
+/// Format a pair of paths like [`filename_diff`], with the changed part
+/// between braces and the given arrow, for paths that are not those of a
+/// [`DiffFile`]. Backslashes are unified to `/`.
+///
+/// # Example
+///
+/// ```
+/// use diff2html::render::path_diff;
+///
+/// assert_eq!(path_diff("src/a/lib.rs", "src/b/lib.rs", "->"), "src/{a -> b}/lib.rs");
+/// assert_eq!(path_diff("docs\\old.md", "docs/new.md", "=>"), "docs/{old.md => new.md}");
+/// assert_eq!(path_diff("a.rs", "b/c.rs", "->"), "a.rs -> b/c.rs");
+/// assert_eq!(path_diff("same.rs", "same.rs", "->"), "same.rs");
+/// ```
+pub fn path_diff(old: &str, new: &str, arrow: &str) -> String {
+    path_diff_with_separator(&unify_path(old), &unify_path(new), arrow, SEPARATOR)
+}
 
-        let mut prefix_paths: Vec<&str> = Vec::new();
-        let mut suffix_paths: Vec<&str> = Vec::new();
+/// Format a pair of paths like [`path_diff`], with another separator than `/`,
+/// e.g. `::` for Rust items or `.` for Java packages.
+///
+/// # Example
+///
+/// ```
+/// use diff2html::render::path_diff_with_separator;
+///
+/// assert_eq!(
+///     path_diff_with_separator("crate::a::Item", "crate::b::Item", "->", "::"),
+///     "crate::{a -> b}::Item"
+/// );
+/// ```
+pub fn path_diff_with_separator(old: &str, new: &str, arrow: &str, separator: &str) -> String {
+    if old == new {
+        return new.to_string();
+    }
 
-        while i < j && i < k {
-            if old_parts[i] == new_parts[i] {
-                prefix_paths.push(new_parts[i]);
-                i += 1;
-            } else {
-                break;
-            }
-        }
+    let old_parts: Vec<&str> = old.split(separator).collect();
+    let new_parts: Vec<&str> = new.split(separator).collect();
 
-        // Find common suffix
-        while j > i && k > i {
-            if old_parts[j] == new_parts[k] {
-                suffix_paths.insert(0, new_parts[k]);
-                j = j.saturating_sub(1);
-                k = k.saturating_sub(1);
-            } else {
-                break;
-            }
-        }
+    let old_len = old_parts.len();
+    let new_len = new_parts.len();
 
-        let final_prefix = prefix_paths.join(&SEPARATOR.to_string());
-        let final_suffix = suffix_paths.join(&SEPARATOR.to_string());
+    // Find common prefix
+    let mut i = 0;
+    let mut j = old_len.saturating_sub(1);
+    let mut k = new_len.saturating_sub(1);
 
-        let old_remaining: Vec<&str> = old_parts[i..=j].to_vec();
-        let new_remaining: Vec<&str> = new_parts[i..=k].to_vec();
+    let mut prefix_paths: Vec<&str> = Vec::new();
+    let mut suffix_paths: Vec<&str> = Vec::new();
 
-        let old_remaining_path = old_remaining.join(&SEPARATOR.to_string());
-        let new_remaining_path = new_remaining.join(&SEPARATOR.to_string());
+    while i < j && i < k {
+        if old_parts[i] == new_parts[i] {
+            prefix_paths.push(new_parts[i]);
+            i += 1;
+        } else {
+            break;
+        }
+    }
 
-        if !final_prefix.is_empty() && !final_suffix.is_empty() {
-            format!(
-                "{}{}{{{} \u{2192} {}}}{}{}",
-                final_prefix,
-                SEPARATOR,
-                old_remaining_path,
-                new_remaining_path,
-                SEPARATOR,
-                final_suffix
-            )
-        } else if !final_prefix.is_empty() {
-            format!(
-                "{}{}{{{} \u{2192} {}}}",
-                final_prefix, SEPARATOR, old_remaining_path, new_remaining_path
-            )
-        } else if !final_suffix.is_empty() {
-            format!(
-                "{{{} \u{2192} {}}}{}{}",
-                old_remaining_path, new_remaining_path, SEPARATOR, final_suffix
-            )
+    // Find common suffix
+    while j > i && k > i {
+        if old_parts[j] == new_parts[k] {
+            suffix_paths.insert(0, new_parts[k]);
+            j = j.saturating_sub(1);
+            k = k.saturating_sub(1);
         } else {
-            format!("{} \u{2192} {}", old_filename, new_filename)
+            break;
         }
-    } else if !is_dev_null_name(&new_filename) {
-        new_filename
+    }
+
+    let final_prefix = prefix_paths.join(separator);
+    let final_suffix = suffix_paths.join(separator);
+
+    let old_remaining_path = old_parts[i..=j].join(separator);
+    let new_remaining_path = new_parts[i..=k].join(separator);
+
+    if !final_prefix.is_empty() && !final_suffix.is_empty() {
+        format!(
+            "{}{}{{{} {} {}}}{}{}",
+            final_prefix,
+            separator,
+            old_remaining_path,
+            arrow,
+            new_remaining_path,
+            separator,
+            final_suffix
+        )
+    } else if !final_prefix.is_empty() {
+        format!(
+            "{}{}{{{} {} {}}}",
+            final_prefix, separator, old_remaining_path, arrow, new_remaining_path
+        )
+    } else if !final_suffix.is_empty() {
+        format!(
+            "{{{} {} {}}}{}{}",
+            old_remaining_path, arrow, new_remaining_path, separator, final_suffix
+        )
     } else {
-        old_filename
+        format!("{} {} {}", old, arrow, new)
     }
 }
 
@@ -253,9 +518,91 @@ pub fn get_html_id(file: &DiffFile) -> String {
     format!("d2h-{:06}", hash % 1_000_000)
 }
 
+/// Generate an HTML ID for a file diff that is the same on every run.
+///
+/// Unlike [`get_html_id`], the hash is computed with 64-bit FNV-1a, which
+/// does not depend on the Rust version.
+pub fn get_stable_html_id(file: &DiffFile) -> String {
+    let hash = filename_diff(file)
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+    format!("d2h-{:06}", hash % 1_000_000)
+}
+
+/// Generate a readable HTML ID for a file diff from the path of its new
+/// version, or the old one for deleted files, e.g. `d2h-src-lib-rs` for
+/// `src/lib.rs`.
+///
+/// Runs of characters other than letters, digits and `_` are replaced by a
+/// `-`, so the IDs are valid URL fragments and CSS selectors. Paths without
+/// letters or digits fall back to [`get_stable_html_id`].
+pub fn get_slug_html_id(file: &DiffFile) -> String {
+    let mut slug = String::from("d2h-");
+    let mut separated = false;
+    for c in file_url_path(file).chars() {
+        if c.is_alphanumeric() || c == '_' {
+            if separated && slug.len() > "d2h-".len() {
+                slug.push('-');
+            }
+            separated = false;
+            slug.push(c);
+        } else {
+            separated = true;
+        }
+    }
+    if slug.len() == "d2h-".len() {
+        return get_stable_html_id(file);
+    }
+    slug
+}
+
+/// Generate the HTML ID for a file diff with a strategy, the hash being
+/// stable across runs if `deterministic`.
+///
+/// The renderers and the file list use the same ID, so this is also the
+/// anchor to link to a file from outside the HTML.
+pub fn make_html_id(file: &DiffFile, deterministic: bool, strategy: HtmlIdStrategy) -> String {
+    match strategy {
+        HtmlIdStrategy::Slug => get_slug_html_id(file),
+        HtmlIdStrategy::Hash if deterministic => get_stable_html_id(file),
+        HtmlIdStrategy::Hash => get_html_id(file),
+    }
+}
+
+/// Generate the HTML IDs of the diff files with [`make_html_id`], adding a
+/// numeric suffix to the IDs already taken by a previous file, e.g.
+/// `d2h-a-b-2` for `a/b` after `a.b`.
+///
+/// The renderers and the file list number the files of a render the same
+/// way, so their links always point to the right file.
+pub fn make_html_ids(
+    diff_files: &[DiffFile],
+    deterministic: bool,
+    strategy: HtmlIdStrategy,
+) -> Vec<String> {
+    let mut taken = HashSet::new();
+    diff_files
+        .iter()
+        .map(|file| {
+            let id = make_html_id(file, deterministic, strategy);
+            let mut unique = id.clone();
+            let mut count = 1;
+            while !taken.insert(unique.clone()) {
+                count += 1;
+                unique = format!("{}-{}", id, count);
+            }
+            unique
+        })
+        .collect()
+}
+
 /// Get the icon template name for a file based on its status.
 pub fn get_file_icon(file: &DiffFile) -> &'static str {
-    if file.is_rename == Some(true) || file.is_copy == Some(true) {
+    if file.is_copy == Some(true) {
+        "file-copied"
+    } else if file.is_rename == Some(true) {
         "file-renamed"
     } else if file.is_new == Some(true) {
         "file-added"
@@ -268,6 +615,497 @@ pub fn get_file_icon(file: &DiffFile) -> &'static str {
     }
 }
 
+/// Check if an octal file mode has any executable bit set.
+fn is_executable_mode(mode: &str) -> bool {
+    u32::from_str_radix(mode, 8).is_ok_and(|m| m & 0o111 != 0)
+}
+
+/// Get the file mode change of a file, if its old and new modes differ.
+///
+/// For combined diffs, the parent modes are joined with a comma.
+pub fn get_file_mode_change(file: &DiffFile) -> Option<FileModeChange> {
+    let old_mode = match file.old_mode.as_ref()? {
+        FileMode::Single(mode) => mode.clone(),
+        FileMode::Multiple(modes) => modes.join(","),
+    };
+    let new_mode = file.new_mode.clone()?;
+
+    (old_mode != new_mode).then_some(FileModeChange { old_mode, new_mode })
+}
+
+/// Number of characters kept when abbreviating checksums.
+const ABBREVIATED_CHECKSUM_LENGTH: usize = 7;
+
+/// Abbreviate a checksum to its first few characters, like `git log --abbrev`.
+fn abbreviate_checksum(checksum: &str) -> &str {
+    checksum
+        .get(..ABBREVIATED_CHECKSUM_LENGTH)
+        .unwrap_or(checksum)
+}
+
+/// Describe the similarity index of a renamed or copied file, if known.
+fn file_similarity(file: &DiffFile) -> Option<String> {
+    if let Some(percentage) = file.unchanged_percentage {
+        Some(format!("similarity {}%", percentage))
+    } else {
+        file.changed_percentage
+            .map(|percentage| format!("dissimilarity {}%", percentage))
+    }
+}
+
+/// Generate the "R98%" badge of a renamed or copied file with a similarity
+/// index, as reported by `git diff -M`.
+pub(crate) fn make_similarity_tag_html(file: &DiffFile) -> String {
+    let status = if file.is_rename == Some(true) {
+        "R"
+    } else if file.is_copy == Some(true) {
+        "C"
+    } else {
+        return String::new();
+    };
+    file.unchanged_percentage
+        .map(|percentage| {
+            templates::render(
+                TemplateName::TagSimilarity,
+                &json!({
+                    "status": status,
+                    "percentage": percentage,
+                }),
+            )
+        })
+        .unwrap_or_default()
+}
+
+/// Describe the abbreviated before and after checksums of a file, if known.
+fn file_checksums(file: &DiffFile) -> Option<String> {
+    let before = match file.checksum_before.as_ref()? {
+        Checksum::Single(checksum) => abbreviate_checksum(checksum).to_string(),
+        Checksum::Multiple(checksums) => checksums
+            .iter()
+            .map(|checksum| abbreviate_checksum(checksum))
+            .collect::<Vec<_>>()
+            .join(","),
+    };
+    let after = abbreviate_checksum(file.checksum_after.as_deref()?);
+
+    Some(format!("{}..{}", before, after))
+}
+
+/// Format a byte count as a human readable size (e.g. "12.4 KB").
+pub fn format_byte_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["KB", "MB", "GB", "TB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// Describe the sizes of a file with a `GIT binary patch`, if known.
+///
+/// Returns e.g. "12.4 KB → 13.1 KB", or "13.1 KB" without the old size.
+pub fn binary_size_change(file: &DiffFile) -> Option<String> {
+    match (file.binary_size_before, file.binary_size_after) {
+        (Some(before), Some(after)) => Some(format!(
+            "{} \u{2192} {}",
+            format_byte_size(before),
+            format_byte_size(after)
+        )),
+        (None, Some(after)) => Some(format_byte_size(after)),
+        _ => None,
+    }
+}
+
+/// Describe the size change of a file with a `GIT binary patch`, if known.
+///
+/// Returns e.g. "Binary file changed (12.4 KB → 13.1 KB)".
+pub fn binary_size_summary(file: &DiffFile) -> Option<String> {
+    binary_size_change(file).map(|sizes| format!("Binary file changed ({})", sizes))
+}
+
+/// Prepare a block header for rendering.
+///
+/// Headers of too big files are trusted messages and are not escaped, unless
+/// the message is plain text (see `is_too_big_text`), the file kept its blocks
+/// (see `is_collapsed`) or the files are untrusted (`hardened`). Binary patch
+/// headers are replaced by a size summary when the sizes are known.
+pub(crate) fn format_block_header(
+    block_header: &str,
+    file: Option<&DiffFile>,
+    hardened: bool,
+) -> String {
+    if let Some(summary) = file
+        .filter(|f| f.is_binary == Some(true) && block_header.starts_with("GIT binary patch"))
+        .and_then(binary_size_summary)
+    {
+        escape_for_html(&summary)
+    } else if !hardened
+        && file.is_some_and(|f| {
+            f.is_too_big == Some(true)
+                && f.is_too_big_text != Some(true)
+                && f.is_collapsed != Some(true)
+        })
+    {
+        block_header.to_string()
+    } else {
+        escape_for_html(block_header)
+    }
+}
+
+/// Generate the row shown above the blocks of a collapsed too big file.
+///
+/// The row with the toggle hides the other rows of the file until it is
+/// checked. The row without toggle is a placeholder for the second column of
+/// the side-by-side view.
+pub(crate) fn make_collapsed_diff_html(
+    line_class: &str,
+    content_class: &str,
+    toggle: bool,
+    markup: OutputMarkup,
+) -> String {
+    templates::render(
+        TemplateName::GenericCollapsedDiff.for_markup(markup),
+        &json!({
+            "CSSLineClass": {
+                "INFO": CSSLineClass::Info.as_str(),
+            },
+            "lineClass": line_class,
+            "contentClass": content_class,
+            "toggle": toggle,
+        }),
+    )
+}
+
+/// Get the configured message for a file without blocks to show.
+pub(crate) fn empty_diff_message<'a>(file: &DiffFile, config: &'a RendererConfig) -> &'a str {
+    if file.is_binary == Some(true) {
+        &config.binary_diff_message
+    } else {
+        &config.empty_diff_message
+    }
+}
+
+/// Join the HTML of the rendered files, grouped by [`GroupBy`].
+///
+/// Each group starts with a header showing its name and number of files.
+/// Files keep the order of the diff within their group, and are rendered
+/// with their index in `diff_files`.
+pub(crate) fn join_file_diffs(
+    diff_files: &[DiffFile],
+    group_by: GroupBy,
+    render_file: impl Fn(usize, &DiffFile) -> String,
+) -> String {
+    let mut parts = Vec::new();
+    for (name, indexes) in group_files(diff_files, group_by) {
+        parts.extend(name.map(|name| make_group_header_html(&name, indexes.len())));
+        parts.extend(
+            indexes
+                .into_iter()
+                .map(|index| render_file(index, &diff_files[index])),
+        );
+    }
+    parts.join("\n")
+}
+
+/// Group files by [`GroupBy`], getting the name of each group, or none if
+/// the files are not grouped, and the indexes of its files in `diff_files`.
+pub(crate) fn group_files(
+    diff_files: &[DiffFile],
+    group_by: GroupBy,
+) -> Vec<(Option<String>, Vec<usize>)> {
+    let key: fn(&DiffFile) -> (usize, String) = match group_by {
+        GroupBy::None => return vec![(None, (0..diff_files.len()).collect())],
+        GroupBy::Directory => |file| {
+            let path = if file.is_deleted == Some(true) {
+                &file.old_name
+            } else {
+                &file.new_name
+            };
+            let directory = path
+                .rsplit_once('/')
+                .map_or(".", |(directory, _)| directory);
+            (0, directory.to_string())
+        },
+        // Added, modified, renamed, copied then deleted files
+        GroupBy::Status => |file| {
+            let order = "AMRCD".find(file.status()).unwrap_or_default();
+            (order, file.status_label().to_string())
+        },
+    };
+
+    let mut groups: BTreeMap<(usize, String), Vec<usize>> = BTreeMap::new();
+    for (index, file) in diff_files.iter().enumerate() {
+        groups.entry(key(file)).or_default().push(index);
+    }
+
+    groups
+        .into_iter()
+        .map(|((_, name), indexes)| (Some(name), indexes))
+        .collect()
+}
+
+/// Render the header of a group of files, showing its name and number of
+/// files.
+pub(crate) fn make_group_header_html(name: &str, files: usize) -> String {
+    let count = match files {
+        1 => "1 file".to_string(),
+        count => format!("{} files", count),
+    };
+    templates::render(
+        TemplateName::GenericGroupHeader,
+        &json!({
+            "name": escape_for_html(name),
+            "count": count,
+        }),
+    )
+}
+
+/// Get the start and end of the wrapper of the rendered files, for the
+/// HTML rendered separately in between.
+#[cfg(feature = "web")]
+pub(crate) fn wrapper_html(color_scheme: ColorScheme) -> (String, String) {
+    const CONTENT: &str = "<!--diff2html-content-->";
+    let html = templates::render(
+        TemplateName::GenericWrapper,
+        &json!({
+            "colorScheme": color_scheme_to_css(color_scheme),
+            "content": CONTENT,
+        }),
+    );
+    let (start, end) = html
+        .split_once(CONTENT)
+        .expect("wrapper template renders its content");
+    (start.to_string(), end.to_string())
+}
+
+/// Render the notice of the files left out by the parser file limits, see
+/// [`ParseResult::omitted_files`](crate::ParseResult::omitted_files), or
+/// nothing if no file was left out.
+pub fn make_omitted_files_html(omitted_files: usize, color_scheme: ColorScheme) -> String {
+    let message = match omitted_files {
+        0 => return String::new(),
+        1 => "1 more file not shown".to_string(),
+        count => format!("{} more files not shown", count),
+    };
+    templates::render(
+        TemplateName::GenericOmittedFiles,
+        &json!({
+            "colorScheme": color_scheme_to_css(color_scheme),
+            "message": message,
+        }),
+    )
+}
+
+/// Get the CSS class and display parts of a changed line that is not paired
+/// with an intra-line diff.
+///
+/// Lines parsed from `--word-diff=porcelain` input carry their own word-level
+/// segments, which are highlighted directly.
+pub(crate) fn prepare_changed_line(
+    line: &DiffLine,
+    is_combined: bool,
+) -> (CSSLineClass, DiffLineParts) {
+    let css_class = changed_line_class(line);
+    let Some(segments) = &line.word_diff else {
+        let parts = deconstruct_line(&line.content, is_combined, true);
+        return (css_class, parts);
+    };
+
+    let tag = match line.line_type {
+        LineType::Delete => "del",
+        LineType::Insert => "ins",
+        LineType::Context => "",
+    };
+    let content = segments
+        .iter()
+        .map(|segment| {
+            let text = escape_for_html(&segment.text);
+            if segment.changed && !tag.is_empty() {
+                format!("<{tag}>{text}</{tag}>")
+            } else {
+                text
+            }
+        })
+        .collect();
+
+    (
+        css_class,
+        DiffLineParts {
+            prefix: deconstruct_line(&line.content, is_combined, false).prefix,
+            content,
+        },
+    )
+}
+
+/// Get the CSS class of a changed line that is not paired with an intra-line
+/// diff, marked as changed if it has a word diff.
+pub(crate) fn changed_line_class(line: &DiffLine) -> CSSLineClass {
+    match (line.line_type, &line.word_diff) {
+        (LineType::Delete, Some(_)) => CSSLineClass::DeleteChanges,
+        (LineType::Insert, Some(_)) => CSSLineClass::InsertChanges,
+        (line_type, _) => to_css_class(line_type),
+    }
+}
+
+/// Replace the rendered content of a line longer than
+/// `max_rendered_line_length` with its truncated text and a marker revealing
+/// the rest, which is in the HTML but hidden.
+pub(crate) fn with_truncated_content(
+    content: String,
+    line: &DiffLine,
+    is_combined: bool,
+    config: &RendererConfig,
+) -> String {
+    let Some(index) = truncation_index(line, is_combined, config) else {
+        return content;
+    };
+    let text = deconstruct_line(&line.content, is_combined, false).content;
+    let (visible, rest) = text.split_at(index);
+    templates::render(
+        TemplateName::GenericTruncatedLine,
+        &json!({
+            "visible": escape_for_html(visible),
+            "rest": escape_for_html(rest),
+        }),
+    )
+}
+
+/// Check if a line is longer than `max_rendered_line_length`.
+pub(crate) fn is_truncated_line(
+    line: &DiffLine,
+    is_combined: bool,
+    config: &RendererConfig,
+) -> bool {
+    truncation_index(line, is_combined, config).is_some()
+}
+
+/// Byte index in the line content, after the prefix, where the line is
+/// truncated, so lines of wide characters take as many columns as others.
+pub(crate) fn truncation_index(
+    line: &DiffLine,
+    is_combined: bool,
+    config: &RendererConfig,
+) -> Option<usize> {
+    let max_length = config.max_rendered_line_length?;
+    let content = line.content.get(prefix_length(is_combined)..)?;
+    width_index(content, max_length)
+}
+
+/// Append the "No newline at end of file" indicator to rendered line content
+/// when the line is missing its trailing newline.
+pub(crate) fn with_no_newline_marker(content: String, line: &DiffLine) -> String {
+    if line.no_newline_at_eof {
+        content + &templates::render(TemplateName::GenericNoNewline, &json!({}))
+    } else {
+        content
+    }
+}
+
+/// Generate the file path HTML shown in a file diff header.
+///
+/// This includes the file icon, the (possibly renamed) file name, the status
+/// tag and, when the permissions changed, the file mode transition. The
+/// similarity index and checksums are included if `show_file_metadata` is set.
+pub(crate) fn make_file_path_html(file: &DiffFile, config: &RendererConfig) -> String {
+    let (file_icon_html, file_tag_html) = file_icon_html(file, &config.file_icons);
+    let full_paths = (config.rename_display == RenameDisplay::Full
+        && file.old_name != file.new_name
+        && !is_dev_null_name(&file.old_name)
+        && !is_dev_null_name(&file.new_name))
+    .then(|| {
+        let url = |path: &str| {
+            config.file_url_template.as_ref().map(|template| {
+                escape_path_for_html(&file_url(
+                    template,
+                    file,
+                    path,
+                    config.file_url_revisions.as_ref(),
+                ))
+            })
+        };
+        json!({
+            "oldName": escape_path_for_html(&file.old_name),
+            "newName": escape_path_for_html(&file.new_name),
+            "oldUrl": url(&file.old_name),
+            "newUrl": url(&file.new_name),
+        })
+    });
+    let file_url_html = config.file_url_template.as_ref().map(|template| {
+        escape_path_for_html(&file_url(
+            template,
+            file,
+            file_url_path(file),
+            config.file_url_revisions.as_ref(),
+        ))
+    });
+
+    let file_mode_html = get_file_mode_change(file)
+        .map(|change| {
+            let executable_tag_html = if change.executable_changed() {
+                templates::render(TemplateName::TagExecutableChanged, &json!({}))
+            } else {
+                String::new()
+            };
+
+            templates::render(
+                TemplateName::GenericFileMode,
+                &json!({
+                    "oldMode": escape_path_for_html(&change.old_mode),
+                    "newMode": escape_path_for_html(&change.new_mode),
+                    "executableTag": executable_tag_html,
+                }),
+            )
+        })
+        .unwrap_or_default();
+
+    let similarity = file_similarity(file);
+    let checksums = file_checksums(file);
+    let file_metadata_html =
+        if config.show_file_metadata && (similarity.is_some() || checksums.is_some()) {
+            templates::render(
+                TemplateName::GenericFileMetadata,
+                &json!({
+                    "similarity": similarity.map(|s| escape_for_html(&s)),
+                    "checksums": checksums.map(|c| escape_for_html(&c)),
+                }),
+            )
+        } else {
+            String::new()
+        };
+
+    templates::render(
+        TemplateName::GenericFilePath,
+        &json!({
+            "fileDiffName": escape_path_for_html(&filename_diff(file)),
+            "fullPaths": full_paths,
+            "fileUrl": file_url_html,
+            "fileIcon": file_icon_html,
+            "fileTag": file_tag_html,
+            "similarityTag": make_similarity_tag_html(file),
+            "fileMode": file_mode_html,
+            "fileMetadata": file_metadata_html,
+            "collapsed": config.collapsed,
+            "generated": file.is_generated == Some(true),
+            "minified": config
+                .minified_files
+                .as_ref()
+                .is_some_and(|detection| detection.is_minified(file)),
+            "fileHeaderExtra": config
+                .file_header_extra
+                .as_ref()
+                .map(|extra| extra.html(file))
+                .unwrap_or_default(),
+        }),
+    )
+}
+
 /// Convert a color scheme to CSS class.
 pub fn color_scheme_to_css(color_scheme: ColorScheme) -> &'static str {
     match color_scheme {
@@ -286,22 +1124,32 @@ pub fn to_css_class(line_type: LineType) -> CSSLineClass {
     }
 }
 
-/// Regex pattern to match <ins> elements in HTML.
-static INS_ELEMENT_REGEX: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"<ins[^>]*>(.|\n)*?</ins>").unwrap());
-
-/// Regex pattern to match <del> elements in HTML.
-static DEL_ELEMENT_REGEX: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"<del[^>]*>(.|\n)*?</del>").unwrap());
-
-/// Remove <ins> elements from HTML string.
-fn remove_ins_elements(line: &str) -> String {
-    INS_ELEMENT_REGEX.replace_all(line, "").to_string()
-}
+/// Call `on_change` with the parts of two line contents, without their
+/// prefixes, in order: the parts both have, deleted from the first and
+/// inserted in the second.
+///
+/// Returns `false` without calling it when a content is longer than
+/// `max_line_length_highlight`.
+pub(crate) fn diff_changes(
+    content1: &str,
+    content2: &str,
+    config: &RenderConfig,
+    mut on_change: impl FnMut(ChangeTag, &str),
+) -> bool {
+    if content1.len() > config.max_line_length_highlight
+        || content2.len() > config.max_line_length_highlight
+    {
+        return false;
+    }
 
-/// Remove <del> elements from HTML string.
-fn remove_del_elements(line: &str) -> String {
-    DEL_ELEMENT_REGEX.replace_all(line, "").to_string()
+    let diff = match config.diff_style {
+        DiffStyle::Char => TextDiff::from_chars(content1, content2),
+        DiffStyle::Word => TextDiff::from_words(content1, content2),
+    };
+    for change in diff.iter_all_changes() {
+        on_change(change.tag(), change.value());
+    }
+    true
 }
 
 /// Highlight differences between two diff lines.
@@ -317,10 +1165,33 @@ pub fn diff_highlight(
     let line1 = deconstruct_line(diff_line1, is_combined, false);
     let line2 = deconstruct_line(diff_line2, is_combined, false);
 
+    // Both lines are built in a single pass, the old one without the
+    // insertions and the new one without the deletions
+    let mut old_content = String::with_capacity(line1.content.len());
+    let mut new_content = String::with_capacity(line2.content.len());
+
+    let highlighted = diff_changes(&line1.content, &line2.content, config, |tag, value| {
+        let escaped_value = escape_for_html(value);
+        match tag {
+            ChangeTag::Insert => {
+                new_content.push_str("<ins>");
+                new_content.push_str(&escaped_value);
+                new_content.push_str("</ins>");
+            }
+            ChangeTag::Delete => {
+                old_content.push_str("<del>");
+                old_content.push_str(&escaped_value);
+                old_content.push_str("</del>");
+            }
+            ChangeTag::Equal => {
+                old_content.push_str(&escaped_value);
+                new_content.push_str(&escaped_value);
+            }
+        }
+    });
+
     // If lines are too long, skip highlighting
-    if line1.content.len() > config.max_line_length_highlight
-        || line2.content.len() > config.max_line_length_highlight
-    {
+    if !highlighted {
         return HighlightedLines {
             old_line: DiffLineParts {
                 prefix: line1.prefix,
@@ -333,40 +1204,14 @@ pub fn diff_highlight(
         };
     }
 
-    let diff = match config.diff_style {
-        DiffStyle::Char => TextDiff::from_chars(&line1.content, &line2.content),
-        DiffStyle::Word => TextDiff::from_words(&line1.content, &line2.content),
-    };
-
-    let mut highlighted_line = String::new();
-
-    for change in diff.iter_all_changes() {
-        let escaped_value = escape_for_html(change.value());
-        match change.tag() {
-            ChangeTag::Insert => {
-                highlighted_line.push_str("<ins>");
-                highlighted_line.push_str(&escaped_value);
-                highlighted_line.push_str("</ins>");
-            }
-            ChangeTag::Delete => {
-                highlighted_line.push_str("<del>");
-                highlighted_line.push_str(&escaped_value);
-                highlighted_line.push_str("</del>");
-            }
-            ChangeTag::Equal => {
-                highlighted_line.push_str(&escaped_value);
-            }
-        }
-    }
-
     HighlightedLines {
         old_line: DiffLineParts {
             prefix: line1.prefix,
-            content: remove_ins_elements(&highlighted_line),
+            content: old_content,
         },
         new_line: DiffLineParts {
             prefix: line2.prefix,
-            content: remove_del_elements(&highlighted_line),
+            content: new_content,
         },
     }
 }
@@ -385,6 +1230,82 @@ mod tests {
         assert_eq!(escape_for_html("a/b"), "a&#x2F;b");
     }
 
+    #[test]
+    fn test_escape_path_for_html() {
+        assert_eq!(escape_path_for_html("src/lib.rs"), "src/lib.rs");
+        assert_eq!(
+            escape_path_for_html("a/<b>/\"c'"),
+            "a/&lt;b&gt;/&quot;c&#x27;"
+        );
+    }
+
+    #[test]
+    fn test_file_url() {
+        let file = |old_name: &str, new_name: &str| DiffFile {
+            old_name: old_name.to_string(),
+            new_name: new_name.to_string(),
+            ..Default::default()
+        };
+        let template = "https://host/blob/main/{path}?from={old_path}&to={new_path}";
+
+        let renamed = file("src/a b.rs", "src/c#d.rs");
+        assert_eq!(
+            file_url(template, &renamed, file_url_path(&renamed), None),
+            "https://host/blob/main/src/c%23d.rs?from=src/a%20b.rs&to=src/c%23d.rs"
+        );
+        let deleted = file("docs/ü.md", "/dev/null");
+        assert_eq!(
+            file_url(template, &deleted, file_url_path(&deleted), None),
+            "https://host/blob/main/docs/%C3%BC.md?from=docs/%C3%BC.md&to=docs/%C3%BC.md"
+        );
+        // Placeholders in the paths are not expanded
+        let braces = file("{new_path}", "{new_path}");
+        assert_eq!(
+            file_url("{path}", &braces, "{new_path}", None),
+            "%7Bnew_path%7D"
+        );
+    }
+
+    #[test]
+    fn test_file_url_revisions() {
+        let template = "{rev}:{path} {old_rev}..{new_rev}";
+        let renamed = DiffFile {
+            old_name: "old.rs".to_string(),
+            new_name: "new.rs".to_string(),
+            checksum_before: Some(Checksum::Single("1234567".to_string())),
+            checksum_after: Some("abcdefa".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            file_url(template, &renamed, "new.rs", None),
+            "abcdefa:new.rs 1234567..abcdefa"
+        );
+        assert_eq!(
+            file_url(template, &renamed, "old.rs", None),
+            "1234567:old.rs 1234567..abcdefa"
+        );
+
+        let revisions = ("v1.0".to_string(), "HEAD^".to_string());
+        assert_eq!(
+            file_url(template, &renamed, "new.rs", Some(&revisions)),
+            "HEAD%5E:new.rs v1.0..HEAD%5E"
+        );
+        let deleted = DiffFile {
+            old_name: "gone.rs".to_string(),
+            new_name: "/dev/null".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            file_url(
+                template,
+                &deleted,
+                file_url_path(&deleted),
+                Some(&revisions)
+            ),
+            "v1.0:gone.rs v1.0..HEAD%5E"
+        );
+    }
+
     #[test]
     fn test_deconstruct_line_regular() {
         let parts = deconstruct_line("+hello", false, true);
@@ -453,6 +1374,47 @@ mod tests {
         assert_eq!(filename_diff(&file), "my/path/{to \u{2192} for}/file.js");
     }
 
+    #[test]
+    fn test_get_slug_html_id() {
+        let file = |old_name: &str, new_name: &str| DiffFile {
+            old_name: old_name.to_string(),
+            new_name: new_name.to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            get_slug_html_id(&file("src/render/utils.rs", "src/render/utils.rs")),
+            "d2h-src-render-utils-rs"
+        );
+        assert_eq!(
+            get_slug_html_id(&file("a.rs", "docs/Read Me (v2).md")),
+            "d2h-docs-Read-Me-v2-md"
+        );
+        assert_eq!(
+            get_slug_html_id(&file(".github/ci.yml", "/dev/null")),
+            "d2h-github-ci-yml"
+        );
+        let symbols = file("+++", "+++");
+        assert_eq!(get_slug_html_id(&symbols), get_stable_html_id(&symbols));
+    }
+
+    #[test]
+    fn test_path_diff() {
+        assert_eq!(
+            path_diff("my/path/to/file.js", "my/path/to/new-file.js", "->"),
+            "my/path/to/{file.js -> new-file.js}"
+        );
+        assert_eq!(path_diff("a\\b\\c.rs", "a/b/c.rs", "->"), "a/b/c.rs");
+        assert_eq!(
+            path_diff_with_separator("org.a.b.Main", "org.c.b.Main", "\u{2192}", "."),
+            "org.{a \u{2192} c}.b.Main"
+        );
+        // Backslashes are kept with other separators
+        assert_eq!(
+            path_diff_with_separator("a\\b", "a\\c", "->", "\\"),
+            "a\\{b -> c}"
+        );
+    }
+
     #[test]
     fn test_filename_diff_completely_different() {
         let file = DiffFile {
@@ -492,6 +1454,57 @@ mod tests {
         assert_eq!(id.len(), 10); // "d2h-" + 6 digits
     }
 
+    #[test]
+    fn test_get_stable_html_id() {
+        let file = DiffFile {
+            old_name: "test.txt".to_string(),
+            new_name: "test.txt".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(get_stable_html_id(&file), "d2h-704099");
+        assert_eq!(
+            make_html_id(&file, true, HtmlIdStrategy::Hash),
+            get_stable_html_id(&file)
+        );
+        assert_eq!(
+            make_html_id(&file, false, HtmlIdStrategy::Hash),
+            get_html_id(&file)
+        );
+        assert_eq!(
+            make_html_id(&file, false, HtmlIdStrategy::Slug),
+            "d2h-test-txt"
+        );
+    }
+
+    #[test]
+    fn test_make_html_ids() {
+        let file = |name: &str| DiffFile {
+            old_name: name.to_string(),
+            new_name: name.to_string(),
+            ..Default::default()
+        };
+        let files = [
+            file("a.b"),
+            file("a/b"),
+            file("a-b-2"),
+            file("a_b"),
+            file("a.b"),
+        ];
+        assert_eq!(
+            make_html_ids(&files, true, HtmlIdStrategy::Slug),
+            [
+                "d2h-a-b",
+                "d2h-a-b-2",
+                "d2h-a-b-2-2",
+                "d2h-a_b",
+                "d2h-a-b-3"
+            ]
+        );
+        let ids = make_html_ids(&files, true, HtmlIdStrategy::Hash);
+        assert_eq!(ids[0], get_stable_html_id(&files[0]));
+        assert_eq!(ids[4], format!("{}-2", ids[0]));
+    }
+
     #[test]
     fn test_get_file_icon() {
         let mut file = DiffFile::default();
@@ -510,7 +1523,7 @@ mod tests {
 
         file.is_rename = None;
         file.is_copy = Some(true);
-        assert_eq!(get_file_icon(&file), "file-renamed");
+        assert_eq!(get_file_icon(&file), "file-copied");
 
         file.is_copy = None;
         file.old_name = "old.txt".to_string();
@@ -518,6 +1531,268 @@ mod tests {
         assert_eq!(get_file_icon(&file), "file-renamed");
     }
 
+    #[test]
+    fn test_get_file_mode_change() {
+        let mut file = DiffFile::default();
+        assert_eq!(get_file_mode_change(&file), None);
+
+        file.old_mode = Some(FileMode::Single("100644".to_string()));
+        file.new_mode = Some("100644".to_string());
+        assert_eq!(get_file_mode_change(&file), None);
+
+        file.new_mode = Some("100755".to_string());
+        let change = get_file_mode_change(&file).unwrap();
+        assert_eq!(change.old_mode, "100644");
+        assert_eq!(change.new_mode, "100755");
+        assert!(change.executable_changed());
+
+        file.old_mode = Some(FileMode::Multiple(vec![
+            "100644".to_string(),
+            "100755".to_string(),
+        ]));
+        let change = get_file_mode_change(&file).unwrap();
+        assert_eq!(change.old_mode, "100644,100755");
+    }
+
+    #[test]
+    fn test_file_mode_change_executable() {
+        let change = FileModeChange {
+            old_mode: "100644".to_string(),
+            new_mode: "120000".to_string(),
+        };
+        assert!(!change.executable_changed());
+
+        let change = FileModeChange {
+            old_mode: "100755".to_string(),
+            new_mode: "100644".to_string(),
+        };
+        assert!(change.executable_changed());
+    }
+
+    #[test]
+    fn test_make_file_path_html_mode_change() {
+        let file = DiffFile {
+            old_name: "script.sh".to_string(),
+            new_name: "script.sh".to_string(),
+            old_mode: Some(FileMode::Single("100644".to_string())),
+            new_mode: Some("100755".to_string()),
+            ..Default::default()
+        };
+        let html = make_file_path_html(&file, &RendererConfig::default());
+        assert!(html.contains("100644 → 100755"));
+        assert!(html.contains("EXECUTABLE BIT CHANGED"));
+
+        let file = DiffFile {
+            old_name: "test.txt".to_string(),
+            new_name: "test.txt".to_string(),
+            ..Default::default()
+        };
+        let html = make_file_path_html(&file, &RendererConfig::default());
+        assert!(!html.contains("d2h-file-mode"));
+    }
+
+    #[test]
+    fn test_file_similarity() {
+        let mut file = DiffFile::default();
+        assert_eq!(file_similarity(&file), None);
+
+        file.changed_percentage = Some(40);
+        assert_eq!(file_similarity(&file).as_deref(), Some("dissimilarity 40%"));
+
+        file.unchanged_percentage = Some(95);
+        assert_eq!(file_similarity(&file).as_deref(), Some("similarity 95%"));
+    }
+
+    #[test]
+    fn test_file_checksums() {
+        let mut file = DiffFile::default();
+        assert_eq!(file_checksums(&file), None);
+
+        file.checksum_before = Some(Checksum::Single("1234567890abcdef".to_string()));
+        file.checksum_after = Some("abc".to_string());
+        assert_eq!(file_checksums(&file).as_deref(), Some("1234567..abc"));
+
+        file.checksum_before = Some(Checksum::Multiple(vec![
+            "aaaaaaaaaa".to_string(),
+            "bbbbbbbbbb".to_string(),
+        ]));
+        assert_eq!(
+            file_checksums(&file).as_deref(),
+            Some("aaaaaaa,bbbbbbb..abc")
+        );
+    }
+
+    #[test]
+    fn test_make_file_path_html_metadata() {
+        let file = DiffFile {
+            old_name: "old.txt".to_string(),
+            new_name: "new.txt".to_string(),
+            unchanged_percentage: Some(90),
+            checksum_before: Some(Checksum::Single("1234567890".to_string())),
+            checksum_after: Some("abcdefabcd".to_string()),
+            ..Default::default()
+        };
+
+        let html = make_file_path_html(&file, &RendererConfig::default());
+        assert!(!html.contains("d2h-file-metadata"));
+
+        let config = RendererConfig {
+            show_file_metadata: true,
+            ..Default::default()
+        };
+        let html = make_file_path_html(&file, &config);
+        assert!(html.contains("similarity 90%"));
+        assert!(html.contains("1234567..abcdefa"));
+    }
+
+    #[test]
+    fn test_join_file_diffs() {
+        let file = |name: &str, is_new: bool, is_deleted: bool| DiffFile {
+            old_name: name.to_string(),
+            new_name: name.to_string(),
+            is_new: Some(is_new),
+            is_deleted: Some(is_deleted),
+            ..Default::default()
+        };
+        let files = [
+            file("src/b.rs", false, true),
+            file("README.md", false, false),
+            file("src/a.rs", true, false),
+            file("docs/a.md", false, false),
+        ];
+        let render =
+            |group_by| join_file_diffs(&files, group_by, |_, file| format!("<{}>", file.new_name));
+        let outline = |html: String| {
+            html.lines()
+                .map(str::trim)
+                .filter(|line| line.starts_with('<') && !line.contains("d2h-group-header"))
+                .filter(|line| !line.starts_with("</div>"))
+                .map(|line| {
+                    line.strip_prefix("<span class=\"d2h-group-name\">")
+                        .or_else(|| line.strip_prefix("<span class=\"d2h-group-count\">"))
+                        .map_or(line, |line| line.trim_end_matches("</span>"))
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        assert_eq!(
+            render(GroupBy::None),
+            "<src/b.rs>\n<README.md>\n<src/a.rs>\n<docs/a.md>"
+        );
+        assert_eq!(
+            outline(render(GroupBy::Directory)),
+            ". 1 file <README.md> docs 1 file <docs/a.md> \
+             src 2 files <src/b.rs> <src/a.rs>"
+        );
+        assert_eq!(
+            outline(render(GroupBy::Status)),
+            "Added 1 file <src/a.rs> Modified 2 files <README.md> <docs/a.md> \
+             Deleted 1 file <src/b.rs>"
+        );
+    }
+
+    #[test]
+    fn test_make_similarity_tag_html() {
+        let mut file = DiffFile {
+            unchanged_percentage: Some(98),
+            ..Default::default()
+        };
+        assert_eq!(make_similarity_tag_html(&file), "");
+
+        file.is_rename = Some(true);
+        assert!(make_similarity_tag_html(&file).contains(">R98%<"));
+
+        file.is_rename = None;
+        file.is_copy = Some(true);
+        assert!(make_similarity_tag_html(&file).contains(">C98%<"));
+
+        let html = make_file_path_html(&file, &RendererConfig::default());
+        assert!(html.contains(">C98%<"));
+
+        file.unchanged_percentage = None;
+        assert_eq!(make_similarity_tag_html(&file), "");
+    }
+
+    #[test]
+    fn test_format_byte_size() {
+        assert_eq!(format_byte_size(0), "0 B");
+        assert_eq!(format_byte_size(1023), "1023 B");
+        assert_eq!(format_byte_size(1024), "1.0 KB");
+        assert_eq!(format_byte_size(12698), "12.4 KB");
+        assert_eq!(format_byte_size(5 * 1024 * 1024), "5.0 MB");
+    }
+
+    #[test]
+    fn test_binary_size_summary() {
+        let mut file = DiffFile::default();
+        assert_eq!(binary_size_summary(&file), None);
+
+        file.binary_size_after = Some(13414);
+        assert_eq!(binary_size_change(&file).as_deref(), Some("13.1 KB"));
+        assert_eq!(
+            binary_size_summary(&file).as_deref(),
+            Some("Binary file changed (13.1 KB)")
+        );
+
+        file.binary_size_before = Some(12698);
+        assert_eq!(
+            binary_size_summary(&file).as_deref(),
+            Some("Binary file changed (12.4 KB \u{2192} 13.1 KB)")
+        );
+    }
+
+    #[test]
+    fn test_format_block_header() {
+        let file = DiffFile {
+            is_binary: Some(true),
+            binary_size_before: Some(100),
+            binary_size_after: Some(200),
+            ..Default::default()
+        };
+        assert_eq!(
+            format_block_header("GIT binary patch", Some(&file), false),
+            "Binary file changed (100 B \u{2192} 200 B)"
+        );
+        assert_eq!(
+            format_block_header("@@ -1 +1 @@ <a>", Some(&DiffFile::default()), false),
+            "@@ -1 +1 @@ &lt;a&gt;"
+        );
+
+        let too_big = DiffFile {
+            is_too_big: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(
+            format_block_header("<b>big</b>", Some(&too_big), false),
+            "<b>big</b>"
+        );
+        assert_eq!(
+            format_block_header("<b>big</b>", Some(&too_big), true),
+            "&lt;b&gt;big&lt;&#x2F;b&gt;"
+        );
+        let too_big_text = DiffFile {
+            is_too_big_text: Some(true),
+            ..too_big.clone()
+        };
+        for hardened in [false, true] {
+            assert_eq!(
+                format_block_header("a < b", Some(&too_big_text), hardened),
+                "a &lt; b"
+            );
+        }
+        let collapsed = DiffFile {
+            is_too_big: Some(true),
+            is_collapsed: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(
+            format_block_header("@@ -1 +1 @@ <a>", Some(&collapsed), false),
+            "@@ -1 +1 @@ &lt;a&gt;"
+        );
+        assert_eq!(format_block_header("<b>", None, false), "&lt;b&gt;");
+    }
+
     #[test]
     fn test_color_scheme_to_css() {
         assert_eq!(
@@ -562,6 +1837,72 @@ mod tests {
         assert!(result.new_line.content.contains("<ins>"));
     }
 
+    #[test]
+    fn test_prepare_changed_line_word_diff() {
+        let mut line = DiffLine {
+            line_type: LineType::Insert,
+            content: "+a <b>".to_string(),
+            old_number: None,
+            new_number: Some(1),
+            no_newline_at_eof: false,
+            word_diff: None,
+        };
+        let (css_class, parts) = prepare_changed_line(&line, false);
+        assert_eq!(css_class, CSSLineClass::Inserts);
+        assert_eq!(parts.content, "a &lt;b&gt;");
+
+        line.word_diff = Some(vec![
+            crate::types::WordDiffSegment {
+                changed: false,
+                text: "a ".to_string(),
+            },
+            crate::types::WordDiffSegment {
+                changed: true,
+                text: "<b>".to_string(),
+            },
+        ]);
+        let (css_class, parts) = prepare_changed_line(&line, false);
+        assert_eq!(css_class, CSSLineClass::InsertChanges);
+        assert_eq!(parts.prefix, "+");
+        assert_eq!(parts.content, "a <ins>&lt;b&gt;</ins>");
+    }
+
+    #[test]
+    fn test_with_truncated_content() {
+        let line = DiffLine {
+            line_type: LineType::Context,
+            content: " äöü<b>".to_string(),
+            old_number: Some(1),
+            new_number: Some(1),
+            no_newline_at_eof: false,
+            word_diff: None,
+        };
+        let mut config = RendererConfig::default();
+        assert_eq!(
+            with_truncated_content("html".to_string(), &line, false, &config),
+            "html"
+        );
+
+        config.max_rendered_line_length = Some(6);
+        assert!(!is_truncated_line(&line, false, &config));
+        config.max_rendered_line_length = Some(3);
+        assert!(is_truncated_line(&line, false, &config));
+        let content = with_truncated_content("html".to_string(), &line, false, &config);
+        assert!(content.starts_with("äöü<span class=\"d2h-truncated\">"));
+        assert!(content.contains(">&lt;b&gt;</span>"));
+
+        // Wide characters take two columns
+        let line = DiffLine {
+            content: " 差分表示".to_string(),
+            ..line
+        };
+        config.max_rendered_line_length = Some(8);
+        assert!(!is_truncated_line(&line, false, &config));
+        config.max_rendered_line_length = Some(5);
+        let content = with_truncated_content("html".to_string(), &line, false, &config);
+        assert!(content.starts_with("差分<span class=\"d2h-truncated\">"));
+    }
+
     #[test]
     fn test_diff_highlight_long_lines() {
         let config = RenderConfig {
@@ -576,17 +1917,18 @@ mod tests {
     }
 
     #[test]
-    fn test_remove_ins_elements() {
-        let input = "hello <ins>world</ins> test";
-        let result = remove_ins_elements(input);
-        assert_eq!(result, "hello  test");
-    }
-
-    #[test]
-    fn test_remove_del_elements() {
-        let input = "hello <del>world</del> test";
-        let result = remove_del_elements(input);
-        assert_eq!(result, "hello  test");
+    fn test_diff_highlight_splits_changes() {
+        let result = diff_highlight(
+            "-hello <world> test",
+            "+hello there test",
+            false,
+            &RenderConfig::default(),
+        );
+        assert_eq!(
+            result.old_line.content,
+            "hello <del>&lt;world&gt;</del> test"
+        );
+        assert_eq!(result.new_line.content, "hello <ins>there</ins> test");
     }
 
     #[test]
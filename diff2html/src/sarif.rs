@@ -0,0 +1,331 @@
+//! SARIF export of the changes of a diff.
+//!
+//! [`sarif_from_diff_files`] reports each changed file as a result of a SARIF
+//! 2.1.0 log, so code scanning and review platforms ingesting SARIF can show
+//! the changes. The fix of a result turns the old version of the file into the
+//! new one: each run of deleted and inserted lines replaces the deleted lines
+//! of the old file with the inserted lines.
+
+use serde_json::{Value, json};
+
+use crate::render::utils::deconstruct_line;
+use crate::types::{DiffBlock, DiffFile, LineType};
+
+/// URI of the JSON schema of SARIF 2.1.0 logs.
+pub const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Id of the rule of the results, one per changed file.
+const RULE_ID: &str = "diff2html/changed-file";
+
+/// Convert parsed diff files to a SARIF log.
+///
+/// Binary and too big files are reported without fix.
+///
+/// # Example
+///
+/// ```
+/// use diff2html::{DiffParserConfig, parse, sarif_from_diff_files};
+///
+/// let diff = "--- a/file.txt\n+++ b/file.txt\n@@ -1 +1 @@\n-old\n+new\n";
+/// let files = parse(diff, &DiffParserConfig::default());
+/// let sarif = sarif_from_diff_files(&files);
+/// assert!(sarif.contains("\"artifactChanges\""));
+/// ```
+pub fn sarif_from_diff_files(diff_files: &[DiffFile]) -> String {
+    let results: Vec<_> = diff_files.iter().map(file_result).collect();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "diff2html",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": [{
+                        "id": RULE_ID,
+                        "shortDescription": { "text": "Changed file" },
+                    }],
+                },
+            },
+            "results": results,
+        }],
+    })
+    .to_string()
+}
+
+/// Make the result of a changed file, located at its changed lines.
+fn file_result(file: &DiffFile) -> Value {
+    let deleted = file.is_deleted == Some(true);
+    let action = file.status_label();
+    let path = if deleted {
+        &file.old_name
+    } else {
+        &file.new_name
+    };
+
+    let mut location = json!({ "artifactLocation": { "uri": path } });
+    let changed_lines = || {
+        file.blocks
+            .iter()
+            .flat_map(|block| &block.lines)
+            .filter(|line| line.line_type == LineType::Insert)
+            .filter_map(|line| line.new_number)
+    };
+    if !deleted && let (Some(start), Some(end)) = (changed_lines().min(), changed_lines().max()) {
+        location["region"] = json!({ "startLine": start, "endLine": end });
+    }
+
+    let mut result = json!({
+        "ruleId": RULE_ID,
+        "level": "note",
+        "message": {
+            "text": format!(
+                "{} {} (+{} -{})",
+                action, path, file.added_lines, file.deleted_lines
+            ),
+        },
+        "locations": [location],
+    });
+
+    let replacements: Vec<_> = file
+        .blocks
+        .iter()
+        .flat_map(|block| block_replacements(block, file.is_combined))
+        .collect();
+    if !replacements.is_empty() {
+        let original = if file.is_new == Some(true) {
+            &file.new_name
+        } else {
+            &file.old_name
+        };
+        result["fixes"] = json!([{
+            "description": { "text": format!("Apply the changes to {}", path) },
+            "artifactChanges": [{
+                "artifactLocation": { "uri": original },
+                "replacements": replacements,
+            }],
+        }]);
+    }
+    result
+}
+
+/// Make the replacements of the runs of changed lines of a block.
+///
+/// Deleted regions span whole lines of the old file, from the start of the
+/// first deleted line to the start of the line after the last one. Runs of
+/// inserted lines only are inserted at the start of the next old line.
+///
+/// The last run of a block may end the file, so it does not reach past its
+/// last line: it ends at the end of its last deleted line, removing the
+/// newline before the run instead, or it is inserted at the end of the line
+/// before it. Columns count UTF-16 code units, the default of SARIF.
+fn block_replacements(block: &DiffBlock, is_combined: bool) -> Vec<Value> {
+    let mut replacements = Vec::new();
+    // Old line before which the current run of changes starts
+    let mut next_old_line = block.old_start_line.max(1);
+    // Number and length of the last old line, and of the one before the run
+    let mut last_old_line: Option<(u32, usize)> = None;
+    let mut before_run: Option<(u32, usize)> = None;
+    let mut deleted: Option<(u32, u32)> = None;
+    let mut inserted = String::new();
+
+    let mut flush = |next_old_line: u32,
+                     last_old_line: Option<(u32, usize)>,
+                     before_run: Option<(u32, usize)>,
+                     deleted: &mut Option<(u32, u32)>,
+                     inserted: &mut String,
+                     is_last_run: bool| {
+        if deleted.is_none() && inserted.is_empty() {
+            return;
+        }
+        let mut text = std::mem::take(inserted);
+        let region = |start: (u32, usize), end: (u32, usize)| {
+            json!({
+                "startLine": start.0,
+                "startColumn": start.1,
+                "endLine": end.0,
+                "endColumn": end.1,
+            })
+        };
+        let deleted_region = match (deleted.take(), last_old_line) {
+            (Some((start, end)), Some((_, length))) if is_last_run => {
+                let end = (end, length + 1);
+                match before_run {
+                    Some((line, length)) if text.is_empty() => region((line, length + 1), end),
+                    _ => {
+                        text.pop();
+                        region((start, 1), end)
+                    }
+                }
+            }
+            (Some((start, end)), _) => region((start, 1), (end + 1, 1)),
+            (None, Some((line, length))) if is_last_run && line + 1 == next_old_line => {
+                text.pop();
+                text.insert(0, '\n');
+                region((line, length + 1), (line, length + 1))
+            }
+            (None, _) => region((next_old_line, 1), (next_old_line, 1)),
+        };
+        let mut replacement = json!({ "deletedRegion": deleted_region });
+        if !text.is_empty() {
+            replacement["insertedContent"] = json!({ "text": text });
+        }
+        replacements.push(replacement);
+    };
+
+    for line in &block.lines {
+        let length = || {
+            deconstruct_line(&line.content, is_combined, false)
+                .content
+                .encode_utf16()
+                .count()
+        };
+        match line.line_type {
+            LineType::Context => {
+                flush(
+                    next_old_line,
+                    last_old_line,
+                    before_run,
+                    &mut deleted,
+                    &mut inserted,
+                    false,
+                );
+                next_old_line = line.old_number.map_or(next_old_line, |number| number + 1);
+                last_old_line = line.old_number.map(|number| (number, length()));
+                before_run = last_old_line;
+            }
+            LineType::Delete => {
+                if let Some(number) = line.old_number {
+                    deleted = Some(deleted.map_or((number, number), |(start, _)| (start, number)));
+                    next_old_line = number + 1;
+                    last_old_line = Some((number, length()));
+                }
+            }
+            LineType::Insert => {
+                inserted.push_str(&deconstruct_line(&line.content, is_combined, false).content);
+                inserted.push('\n');
+            }
+        }
+    }
+    flush(
+        next_old_line,
+        last_old_line,
+        before_run,
+        &mut deleted,
+        &mut inserted,
+        true,
+    );
+    replacements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{DiffParserConfig, parse};
+
+    fn sarif(diff: &str) -> Value {
+        let files = parse(diff, &DiffParserConfig::default());
+        serde_json::from_str(&sarif_from_diff_files(&files)).unwrap()
+    }
+
+    #[test]
+    fn test_sarif_modified_file() {
+        let log = sarif("--- a/a.txt\n+++ b/a.txt\n@@ -1,5 +1,5 @@\n a\n-b\n+B\n c\n+x\n d\n-e\n");
+        assert_eq!(log["version"], "2.1.0");
+        let result = &log["runs"][0]["results"][0];
+        assert_eq!(result["message"]["text"], "Modified a.txt (+2 -2)");
+        assert_eq!(
+            result["locations"][0]["region"],
+            json!({ "startLine": 2, "endLine": 4 })
+        );
+
+        let change = &result["fixes"][0]["artifactChanges"][0];
+        assert_eq!(change["artifactLocation"]["uri"], "a.txt");
+        // The deletion of the last line removes the newline before it
+        assert_eq!(
+            replacements(&log),
+            [
+                ((2, 1), (3, 1), Some("B\n".to_string())),
+                ((4, 1), (4, 1), Some("x\n".to_string())),
+                ((4, 2), (5, 2), None),
+            ]
+        );
+    }
+
+    /// Start and end of a deleted region, as line and column, and the
+    /// inserted text of its replacement.
+    type Replacement = ((u64, u64), (u64, u64), Option<String>);
+
+    /// Get the replacements of the first result.
+    fn replacements(log: &Value) -> Vec<Replacement> {
+        log["runs"][0]["results"][0]["fixes"][0]["artifactChanges"][0]["replacements"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|replacement| {
+                let region = &replacement["deletedRegion"];
+                let position = |line: &str, column: &str| {
+                    (
+                        region[line].as_u64().unwrap(),
+                        region[column].as_u64().unwrap(),
+                    )
+                };
+                (
+                    position("startLine", "startColumn"),
+                    position("endLine", "endColumn"),
+                    replacement["insertedContent"]["text"]
+                        .as_str()
+                        .map(str::to_string),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sarif_end_of_file() {
+        // Lines appended after the last one
+        let log = sarif("--- a/a.txt\n+++ b/a.txt\n@@ -1,2 +1,3 @@\n a\n é\n+c\n");
+        assert_eq!(
+            replacements(&log),
+            [((2, 2), (2, 2), Some("\nc".to_string()))]
+        );
+
+        // The last line replaced, without newline at the end
+        let log = sarif(
+            "--- a/a.txt\n+++ b/a.txt\n@@ -1,2 +1,2 @@\n a\n-\u{1F600}\n\\ No newline at end of file\n+b\n",
+        );
+        assert_eq!(
+            replacements(&log),
+            [((2, 1), (2, 3), Some("b".to_string()))]
+        );
+
+        // All the lines deleted
+        let log = sarif("--- a/a.txt\n+++ b/a.txt\n@@ -1,2 +0,0 @@\n-ab\n-c\n");
+        assert_eq!(replacements(&log), [((1, 1), (2, 2), None)]);
+    }
+
+    #[test]
+    fn test_sarif_added_and_binary_files() {
+        let log = sarif(
+            "diff --git a/new.txt b/new.txt\nnew file mode 100644\n\
+             --- /dev/null\n+++ b/new.txt\n@@ -0,0 +1,2 @@\n+a\n+b\n\
+             diff --git a/logo.png b/logo.png\n\
+             Binary files a/logo.png and b/logo.png differ\n",
+        );
+        let results = log["runs"][0]["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+
+        assert_eq!(results[0]["message"]["text"], "Added new.txt (+2 -0)");
+        let replacement = &results[0]["fixes"][0]["artifactChanges"][0]["replacements"][0];
+        assert_eq!(replacement["deletedRegion"]["startLine"], 1);
+        assert_eq!(replacement["deletedRegion"]["endLine"], 1);
+        assert_eq!(replacement["insertedContent"]["text"], "a\nb\n");
+
+        assert_eq!(
+            results[1]["locations"][0]["artifactLocation"]["uri"],
+            "logo.png"
+        );
+        assert!(results[1].get("fixes").is_none());
+    }
+}
@@ -0,0 +1,198 @@
+//! Selection of files and blocks to render.
+//!
+//! UIs that paginate large diffs or load them lazily can render just the
+//! fragment they need with [`Diff2Html::render_selection`](crate::Diff2Html::render_selection).
+//! A [`Selection`] picks files by index or name, and optionally a range of the
+//! blocks of each file.
+
+use std::ops::Range;
+
+use crate::types::DiffFile;
+
+/// A file of a diff, by index or name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileSelector {
+    /// Index of the file in the parsed diff.
+    Index(usize),
+    /// Old or new name of the file.
+    Name(String),
+}
+
+impl FileSelector {
+    /// Checks if the selector matches the file at the given index.
+    fn matches(&self, index: usize, file: &DiffFile) -> bool {
+        match self {
+            Self::Index(selected) => *selected == index,
+            Self::Name(name) => file.new_name == *name || file.old_name == *name,
+        }
+    }
+}
+
+impl From<usize> for FileSelector {
+    fn from(index: usize) -> Self {
+        Self::Index(index)
+    }
+}
+
+impl From<&str> for FileSelector {
+    fn from(name: &str) -> Self {
+        Self::Name(name.to_string())
+    }
+}
+
+impl From<String> for FileSelector {
+    fn from(name: String) -> Self {
+        Self::Name(name)
+    }
+}
+
+/// A selected file and the blocks to keep.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileSelection {
+    pub file: FileSelector,
+    /// Indices of the blocks to keep, or `None` for all blocks.
+    pub blocks: Option<Range<usize>>,
+}
+
+/// Files and blocks to render.
+///
+/// Files are rendered in the order they are selected. Selectors matching no
+/// file and block ranges past the end of a file are ignored. Selected files
+/// keep their line counts and, with deterministic ids, the same HTML ids as
+/// in the full diff.
+///
+/// # Example
+///
+/// ```
+/// use diff2html::{DiffParserConfig, Selection, parse};
+///
+/// let diff = "--- a/a.txt\n+++ b/a.txt\n@@ -1 +1 @@\n-a\n+b\n\
+///             --- a/b.txt\n+++ b/b.txt\n@@ -1 +1 @@\n-a\n+b\n@@ -9 +9 @@\n-a\n+b\n";
+/// let files = parse(diff, &DiffParserConfig::default());
+///
+/// let selection = Selection::new().file_blocks("b.txt", 1..2);
+/// let selected = selection.apply(&files);
+/// assert_eq!(selected.len(), 1);
+/// assert_eq!(selected[0].blocks[0].header, "@@ -9 +9 @@");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Selection {
+    pub files: Vec<FileSelection>,
+}
+
+impl Selection {
+    /// Create an empty selection.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Select all blocks of a file.
+    pub fn file(mut self, file: impl Into<FileSelector>) -> Self {
+        self.files.push(FileSelection {
+            file: file.into(),
+            blocks: None,
+        });
+        self
+    }
+
+    /// Select a range of the blocks of a file.
+    pub fn file_blocks(mut self, file: impl Into<FileSelector>, blocks: Range<usize>) -> Self {
+        self.files.push(FileSelection {
+            file: file.into(),
+            blocks: Some(blocks),
+        });
+        self
+    }
+
+    /// Get the selected files, keeping only the selected blocks.
+    pub fn apply(&self, diff_files: &[DiffFile]) -> Vec<DiffFile> {
+        self.files
+            .iter()
+            .filter_map(|selection| {
+                let (_, file) = diff_files
+                    .iter()
+                    .enumerate()
+                    .find(|(index, file)| selection.file.matches(*index, file))?;
+                let mut file = file.clone();
+                if let Some(blocks) = &selection.blocks {
+                    let end = blocks.end.min(file.blocks.len());
+                    let start = blocks.start.min(end);
+                    file.blocks = file.blocks.drain(start..end).collect();
+                }
+                Some(file)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files() -> Vec<DiffFile> {
+        ["a.txt", "b.txt"]
+            .into_iter()
+            .map(|name| DiffFile {
+                old_name: format!("old/{}", name),
+                new_name: name.to_string(),
+                added_lines: 3,
+                blocks: ["@@ -1 +1 @@", "@@ -5 +5 @@", "@@ -9 +9 @@"]
+                    .into_iter()
+                    .map(|header| crate::types::DiffBlock {
+                        old_start_line: 1,
+                        old_start_line2: None,
+                        new_start_line: 1,
+                        old_lines: None,
+                        new_lines: None,
+                        header: header.to_string(),
+                        lines: Vec::new(),
+                        source_lines: None,
+                    })
+                    .collect(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn names(files: &[DiffFile]) -> Vec<(&str, usize)> {
+        files
+            .iter()
+            .map(|file| (file.new_name.as_str(), file.blocks.len()))
+            .collect()
+    }
+
+    #[test]
+    fn test_selection_files() {
+        let files = files();
+
+        let selection = Selection::new().file(1).file("a.txt");
+        assert_eq!(
+            names(&selection.apply(&files)),
+            [("b.txt", 3), ("a.txt", 3)]
+        );
+
+        let selection = Selection::new().file("old/b.txt").file(5).file("c.txt");
+        assert_eq!(names(&selection.apply(&files)), [("b.txt", 3)]);
+
+        assert!(Selection::new().apply(&files).is_empty());
+    }
+
+    #[test]
+    fn test_selection_blocks() {
+        let files = files();
+
+        let selected = Selection::new().file_blocks(0, 1..3).apply(&files);
+        let headers: Vec<_> = selected[0].blocks.iter().map(|b| &b.header).collect();
+        assert_eq!(headers, ["@@ -5 +5 @@", "@@ -9 +9 @@"]);
+        assert_eq!(selected[0].added_lines, 3);
+
+        let selection = Selection::new()
+            .file_blocks(0, 2..10)
+            .file_blocks(1, 5..6)
+            .file(1);
+        assert_eq!(
+            names(&selection.apply(&files)),
+            [("a.txt", 1), ("b.txt", 0), ("b.txt", 3)]
+        );
+    }
+}
@@ -0,0 +1,183 @@
+//! Per-file statistics as CSV or TSV, e.g. for spreadsheets and dashboards.
+//!
+//! Each row holds the path, status, added and deleted lines, whether the file
+//! is binary and its language, after a header row. The totals and the
+//! changed lines by language are also available as JSON.
+
+use serde::Serialize;
+
+use crate::types::{DiffFile, DiffStats, LanguageStats};
+
+/// Totals of the files with their changed lines by language, as serialized by
+/// [`stats_json`].
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StatsJson {
+    #[serde(flatten)]
+    stats: DiffStats,
+    languages: Vec<LanguageStats>,
+}
+
+/// Names of the columns, in order.
+const COLUMNS: [&str; 6] = [
+    "path",
+    "status",
+    "additions",
+    "deletions",
+    "binary",
+    "language",
+];
+
+/// Get the per-file statistics as CSV, quoting fields as in RFC 4180.
+///
+/// # Example
+///
+/// ```
+/// use diff2html::{DiffParserConfig, parse, stats_csv};
+///
+/// let diff = "--- a/file.txt\n+++ b/file.txt\n@@ -1 +1,2 @@\n-old\n+new\n+line\n";
+/// let files = parse(diff, &DiffParserConfig::default());
+/// assert_eq!(
+///     stats_csv(&files),
+///     "path,status,additions,deletions,binary,language\nfile.txt,modified,2,1,false,txt\n"
+/// );
+/// ```
+pub fn stats_csv(diff_files: &[DiffFile]) -> String {
+    stats_table(diff_files, ',', |field| {
+        if field.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_string()
+        }
+    })
+}
+
+/// Get the per-file statistics as TSV. Tabs and line breaks in fields, which
+/// TSV cannot hold, are replaced by spaces.
+pub fn stats_tsv(diff_files: &[DiffFile]) -> String {
+    stats_table(diff_files, '\t', |field| {
+        field.replace(['\t', '\n', '\r'], " ")
+    })
+}
+
+/// Get the totals of the files and their changed lines by
+/// [language](DiffFile::language) as a JSON object, the languages most
+/// changed first.
+///
+/// # Example
+///
+/// ```
+/// use diff2html::{DiffParserConfig, parse, stats_json};
+///
+/// let diff = "--- a/file.txt\n+++ b/file.txt\n@@ -1 +1,2 @@\n-old\n+new\n+line\n";
+/// let files = parse(diff, &DiffParserConfig::default());
+/// assert_eq!(
+///     stats_json(&files).unwrap(),
+///     r#"{"files":1,"addedLines":2,"deletedLines":1,"languages":[{"language":"txt","files":1,"addedLines":2,"deletedLines":1}]}"#
+/// );
+/// ```
+pub fn stats_json(diff_files: &[DiffFile]) -> Result<String, serde_json::Error> {
+    serde_json::to_string(&StatsJson {
+        stats: DiffStats::from_files(diff_files),
+        languages: LanguageStats::from_files(diff_files),
+    })
+}
+
+/// Join the header and the rows of the files with the given separator.
+fn stats_table(
+    diff_files: &[DiffFile],
+    separator: char,
+    escape: impl Fn(&str) -> String,
+) -> String {
+    let mut table = String::new();
+    let mut push_row = |fields: &[&str]| {
+        let row: Vec<_> = fields.iter().map(|field| escape(field)).collect();
+        table.push_str(&row.join(&separator.to_string()));
+        table.push('\n');
+    };
+
+    push_row(&COLUMNS);
+    for file in diff_files {
+        let path = if file.is_deleted == Some(true) {
+            &file.old_name
+        } else {
+            &file.new_name
+        };
+        push_row(&[
+            path,
+            &file.status_label().to_ascii_lowercase(),
+            &file.added_lines.to_string(),
+            &file.deleted_lines.to_string(),
+            if file.is_binary == Some(true) {
+                "true"
+            } else {
+                "false"
+            },
+            &file.language,
+        ]);
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files() -> Vec<DiffFile> {
+        vec![
+            DiffFile {
+                old_name: "a,\"b\".rs".to_string(),
+                new_name: "a,\"b\".rs".to_string(),
+                added_lines: 3,
+                deleted_lines: 1,
+                language: "rs".to_string(),
+                ..Default::default()
+            },
+            DiffFile {
+                old_name: "old\tlogo.png".to_string(),
+                new_name: "/dev/null".to_string(),
+                is_deleted: Some(true),
+                is_binary: Some(true),
+                language: "png".to_string(),
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn test_stats_csv() {
+        assert_eq!(
+            stats_csv(&files()),
+            "path,status,additions,deletions,binary,language\n\
+             \"a,\"\"b\"\".rs\",modified,3,1,false,rs\n\
+             old\tlogo.png,deleted,0,0,true,png\n"
+        );
+    }
+
+    #[test]
+    fn test_stats_tsv() {
+        assert_eq!(
+            stats_tsv(&files()),
+            "path\tstatus\tadditions\tdeletions\tbinary\tlanguage\n\
+             a,\"b\".rs\tmodified\t3\t1\tfalse\trs\n\
+             old logo.png\tdeleted\t0\t0\ttrue\tpng\n"
+        );
+    }
+
+    #[test]
+    fn test_stats_json() {
+        let json: serde_json::Value = serde_json::from_str(&stats_json(&files()).unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "files": 2,
+                "addedLines": 3,
+                "deletedLines": 1,
+                "languages": [
+                    {"language": "rs", "files": 1, "addedLines": 3, "deletedLines": 1},
+                    {"language": "png", "files": 1, "addedLines": 0, "deletedLines": 0},
+                ],
+            })
+        );
+    }
+}
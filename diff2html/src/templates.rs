@@ -6,32 +6,147 @@
 
 use handlebars::Handlebars;
 use serde::Serialize;
+use std::fmt::Write;
 use std::sync::LazyLock;
 use thiserror::Error;
 
+use crate::types::OutputMarkup;
+
 /// CSS stylesheet for diff2html rendering.
 pub const CSS: &str = include_str!("../css/diff2html.css");
 
+/// Wrap the [`CSS`] stylesheet in a `<style>` element.
+///
+/// The nonce is added as the `nonce` attribute, allowing the stylesheet under
+/// a `Content-Security-Policy` with `style-src 'nonce-<nonce>'`. It can also be
+/// a placeholder replaced by the server for each response.
+pub fn css_style_element(nonce: Option<&str>) -> String {
+    match nonce {
+        Some(nonce) => format!(
+            "<style nonce=\"{}\">\n{CSS}\n</style>",
+            crate::render::escape_for_html(nonce)
+        ),
+        None => format!("<style>\n{CSS}\n</style>"),
+    }
+}
+
+/// Script synchronising the horizontal scroll of the two sides of
+/// side-by-side diffs, without depending on diff2html-ui.
+///
+/// It can be included anywhere on the page, also before the diffs.
+pub const SYNCHRONISED_SCROLL_JS: &str = include_str!("../js/synchronised-scroll.js");
+
+/// Script making the "Viewed" checkbox of the file headers collapse the file
+/// content, without depending on diff2html-ui.
+///
+/// The checkboxes are hidden by [`CSS`] until the script runs.
+pub const FILE_CONTENT_TOGGLE_JS: &str = include_str!("../js/file-content-toggle.js");
+
+/// Script making the buttons of the [switchable](crate::OutputFormat::Switchable)
+/// output switch the view of a file, without depending on diff2html-ui.
+///
+/// The buttons are hidden by [`CSS`] until the script runs.
+pub const VIEW_TOGGLE_JS: &str = include_str!("../js/view-toggle.js");
+
+/// Script making the buttons of the file headers rendered with
+/// [`RenameDisplay::Full`](crate::RenameDisplay::Full) copy the old or the new
+/// path to the clipboard.
+///
+/// The buttons are hidden by [`CSS`] until the script runs.
+pub const COPY_PATH_JS: &str = include_str!("../js/copy-path.js");
+
+/// Wrap the [`SYNCHRONISED_SCROLL_JS`] script in a `<script>` element.
+///
+/// The nonce is added as the `nonce` attribute, like in [`css_style_element`].
+pub fn synchronised_scroll_script_element(nonce: Option<&str>) -> String {
+    script_element(SYNCHRONISED_SCROLL_JS, "", nonce)
+}
+
+/// Wrap the [`FILE_CONTENT_TOGGLE_JS`] script in a `<script>` element.
+///
+/// With `persist`, the viewed files are remembered in `localStorage` by the
+/// ID of their file diff, which is only stable across renders in
+/// [deterministic](crate::Diff2HtmlConfig::deterministic) mode. The nonce is
+/// added as the `nonce` attribute, like in [`css_style_element`].
+pub fn file_content_toggle_script_element(persist: bool, nonce: Option<&str>) -> String {
+    let attributes = if persist { " data-d2h-persist" } else { "" };
+    script_element(FILE_CONTENT_TOGGLE_JS, attributes, nonce)
+}
+
+/// Wrap the [`VIEW_TOGGLE_JS`] script in a `<script>` element.
+///
+/// The nonce is added as the `nonce` attribute, like in [`css_style_element`].
+pub fn view_toggle_script_element(nonce: Option<&str>) -> String {
+    script_element(VIEW_TOGGLE_JS, "", nonce)
+}
+
+/// Wrap the [`COPY_PATH_JS`] script in a `<script>` element.
+///
+/// The nonce is added as the `nonce` attribute, like in [`css_style_element`].
+pub fn copy_path_script_element(nonce: Option<&str>) -> String {
+    script_element(COPY_PATH_JS, "", nonce)
+}
+
+/// Wrap a script in a `<script>` element with the given attributes.
+fn script_element(script: &str, attributes: &str, nonce: Option<&str>) -> String {
+    match nonce {
+        Some(nonce) => format!(
+            "<script nonce=\"{}\"{attributes}>\n{script}</script>",
+            crate::render::escape_for_html(nonce)
+        ),
+        None => format!("<script{attributes}>\n{script}</script>"),
+    }
+}
+
 // Embed all templates at compile time
 const GENERIC_WRAPPER: &str = include_str!("../templates/generic-wrapper.mustache");
 const FILE_SUMMARY_WRAPPER: &str = include_str!("../templates/file-summary-wrapper.mustache");
 const FILE_SUMMARY_LINE: &str = include_str!("../templates/file-summary-line.mustache");
+const FILE_LANGUAGE_BAR: &str = include_str!("../templates/file-language-bar.mustache");
+const FILE_JUMP_MENU: &str = include_str!("../templates/file-jump-menu.mustache");
+const FILE_TOC: &str = include_str!("../templates/file-toc.mustache");
+const FILE_TOC_DIRECTORY: &str = include_str!("../templates/file-toc-directory.mustache");
+const FILE_TOC_FILE: &str = include_str!("../templates/file-toc-file.mustache");
 const LINE_BY_LINE_FILE_DIFF: &str = include_str!("../templates/line-by-line-file-diff.mustache");
 const SIDE_BY_SIDE_FILE_DIFF: &str = include_str!("../templates/side-by-side-file-diff.mustache");
+const SWITCHABLE_FILE_DIFF: &str = include_str!("../templates/switchable-file-diff.mustache");
 const GENERIC_FILE_PATH: &str = include_str!("../templates/generic-file-path.mustache");
 const GENERIC_LINE: &str = include_str!("../templates/generic-line.mustache");
 const LINE_BY_LINE_NUMBERS: &str = include_str!("../templates/line-by-line-numbers.mustache");
 const GENERIC_BLOCK_HEADER: &str = include_str!("../templates/generic-block-header.mustache");
 const GENERIC_EMPTY_DIFF: &str = include_str!("../templates/generic-empty-diff.mustache");
+const GENERIC_FILE_MODE: &str = include_str!("../templates/generic-file-mode.mustache");
+const GENERIC_FILE_METADATA: &str = include_str!("../templates/generic-file-metadata.mustache");
+const GENERIC_MODE_CHANGE: &str = include_str!("../templates/generic-mode-change.mustache");
+const GENERIC_IMAGE_PREVIEW: &str = include_str!("../templates/generic-image-preview.mustache");
+const GENERIC_NO_NEWLINE: &str = include_str!("../templates/generic-no-newline.mustache");
+const GENERIC_OMITTED_FILES: &str = include_str!("../templates/generic-omitted-files.mustache");
+const GENERIC_TRUNCATED_LINE: &str = include_str!("../templates/generic-truncated-line.mustache");
+const GENERIC_COLLAPSED_DIFF: &str = include_str!("../templates/generic-collapsed-diff.mustache");
+const GENERIC_GROUP_HEADER: &str = include_str!("../templates/generic-group-header.mustache");
+const SEMANTIC_LINE_BY_LINE_FILE_DIFF: &str =
+    include_str!("../templates/semantic-line-by-line-file-diff.mustache");
+const SEMANTIC_SIDE_BY_SIDE_FILE_DIFF: &str =
+    include_str!("../templates/semantic-side-by-side-file-diff.mustache");
+const SEMANTIC_LINE: &str = include_str!("../templates/semantic-line.mustache");
+const SEMANTIC_BLOCK_HEADER: &str = include_str!("../templates/semantic-block-header.mustache");
+const SEMANTIC_EMPTY_DIFF: &str = include_str!("../templates/semantic-empty-diff.mustache");
+const SEMANTIC_MODE_CHANGE: &str = include_str!("../templates/semantic-mode-change.mustache");
+const SEMANTIC_IMAGE_PREVIEW: &str = include_str!("../templates/semantic-image-preview.mustache");
+const SEMANTIC_COLLAPSED_DIFF: &str = include_str!("../templates/semantic-collapsed-diff.mustache");
 const ICON_FILE: &str = include_str!("../templates/icon-file.mustache");
 const ICON_FILE_ADDED: &str = include_str!("../templates/icon-file-added.mustache");
 const ICON_FILE_CHANGED: &str = include_str!("../templates/icon-file-changed.mustache");
 const ICON_FILE_DELETED: &str = include_str!("../templates/icon-file-deleted.mustache");
 const ICON_FILE_RENAMED: &str = include_str!("../templates/icon-file-renamed.mustache");
+const ICON_FILE_COPIED: &str = include_str!("../templates/icon-file-copied.mustache");
 const TAG_FILE_ADDED: &str = include_str!("../templates/tag-file-added.mustache");
 const TAG_FILE_CHANGED: &str = include_str!("../templates/tag-file-changed.mustache");
 const TAG_FILE_DELETED: &str = include_str!("../templates/tag-file-deleted.mustache");
 const TAG_FILE_RENAMED: &str = include_str!("../templates/tag-file-renamed.mustache");
+const TAG_FILE_COPIED: &str = include_str!("../templates/tag-file-copied.mustache");
+const TAG_EXECUTABLE_CHANGED: &str = include_str!("../templates/tag-executable-changed.mustache");
+const TAG_SIMILARITY: &str = include_str!("../templates/tag-similarity.mustache");
 
 /// Template names for use with the renderer.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -39,22 +154,49 @@ pub enum TemplateName {
     GenericWrapper,
     FileSummaryWrapper,
     FileSummaryLine,
+    FileLanguageBar,
+    FileJumpMenu,
+    FileToc,
+    FileTocDirectory,
+    FileTocFile,
     LineByLineFileDiff,
     SideBySideFileDiff,
+    SwitchableFileDiff,
     GenericFilePath,
     GenericLine,
     LineByLineNumbers,
     GenericBlockHeader,
     GenericEmptyDiff,
+    GenericFileMode,
+    GenericFileMetadata,
+    GenericModeChange,
+    GenericImagePreview,
+    GenericNoNewline,
+    GenericTruncatedLine,
+    GenericOmittedFiles,
+    GenericCollapsedDiff,
+    GenericGroupHeader,
+    SemanticLineByLineFileDiff,
+    SemanticSideBySideFileDiff,
+    SemanticLine,
+    SemanticBlockHeader,
+    SemanticEmptyDiff,
+    SemanticModeChange,
+    SemanticImagePreview,
+    SemanticCollapsedDiff,
     IconFile,
     IconFileAdded,
     IconFileChanged,
     IconFileDeleted,
     IconFileRenamed,
+    IconFileCopied,
     TagFileAdded,
     TagFileChanged,
     TagFileDeleted,
     TagFileRenamed,
+    TagFileCopied,
+    TagExecutableChanged,
+    TagSimilarity,
 }
 
 impl TemplateName {
@@ -64,22 +206,68 @@ impl TemplateName {
             Self::GenericWrapper => "generic-wrapper",
             Self::FileSummaryWrapper => "file-summary-wrapper",
             Self::FileSummaryLine => "file-summary-line",
+            Self::FileLanguageBar => "file-language-bar",
+            Self::FileJumpMenu => "file-jump-menu",
+            Self::FileToc => "file-toc",
+            Self::FileTocDirectory => "file-toc-directory",
+            Self::FileTocFile => "file-toc-file",
             Self::LineByLineFileDiff => "line-by-line-file-diff",
             Self::SideBySideFileDiff => "side-by-side-file-diff",
+            Self::SwitchableFileDiff => "switchable-file-diff",
             Self::GenericFilePath => "generic-file-path",
             Self::GenericLine => "generic-line",
             Self::LineByLineNumbers => "line-by-line-numbers",
             Self::GenericBlockHeader => "generic-block-header",
             Self::GenericEmptyDiff => "generic-empty-diff",
+            Self::GenericFileMode => "generic-file-mode",
+            Self::GenericFileMetadata => "generic-file-metadata",
+            Self::GenericModeChange => "generic-mode-change",
+            Self::GenericImagePreview => "generic-image-preview",
+            Self::GenericNoNewline => "generic-no-newline",
+            Self::GenericTruncatedLine => "generic-truncated-line",
+            Self::GenericOmittedFiles => "generic-omitted-files",
+            Self::GenericCollapsedDiff => "generic-collapsed-diff",
+            Self::GenericGroupHeader => "generic-group-header",
+            Self::SemanticLineByLineFileDiff => "semantic-line-by-line-file-diff",
+            Self::SemanticSideBySideFileDiff => "semantic-side-by-side-file-diff",
+            Self::SemanticLine => "semantic-line",
+            Self::SemanticBlockHeader => "semantic-block-header",
+            Self::SemanticEmptyDiff => "semantic-empty-diff",
+            Self::SemanticModeChange => "semantic-mode-change",
+            Self::SemanticImagePreview => "semantic-image-preview",
+            Self::SemanticCollapsedDiff => "semantic-collapsed-diff",
             Self::IconFile => "icon-file",
             Self::IconFileAdded => "icon-file-added",
             Self::IconFileChanged => "icon-file-changed",
             Self::IconFileDeleted => "icon-file-deleted",
             Self::IconFileRenamed => "icon-file-renamed",
+            Self::IconFileCopied => "icon-file-copied",
             Self::TagFileAdded => "tag-file-added",
             Self::TagFileChanged => "tag-file-changed",
             Self::TagFileDeleted => "tag-file-deleted",
             Self::TagFileRenamed => "tag-file-renamed",
+            Self::TagFileCopied => "tag-file-copied",
+            Self::TagExecutableChanged => "tag-executable-changed",
+            Self::TagSimilarity => "tag-similarity",
+        }
+    }
+
+    /// Returns the template rendering the same part of the diff with the
+    /// given markup.
+    ///
+    /// Templates without a table row or cell are the same for all markups.
+    pub fn for_markup(self, markup: OutputMarkup) -> Self {
+        match (markup, self) {
+            (OutputMarkup::Table, _) => self,
+            (OutputMarkup::Semantic, Self::LineByLineFileDiff) => Self::SemanticLineByLineFileDiff,
+            (OutputMarkup::Semantic, Self::SideBySideFileDiff) => Self::SemanticSideBySideFileDiff,
+            (OutputMarkup::Semantic, Self::GenericLine) => Self::SemanticLine,
+            (OutputMarkup::Semantic, Self::GenericBlockHeader) => Self::SemanticBlockHeader,
+            (OutputMarkup::Semantic, Self::GenericEmptyDiff) => Self::SemanticEmptyDiff,
+            (OutputMarkup::Semantic, Self::GenericModeChange) => Self::SemanticModeChange,
+            (OutputMarkup::Semantic, Self::GenericImagePreview) => Self::SemanticImagePreview,
+            (OutputMarkup::Semantic, Self::GenericCollapsedDiff) => Self::SemanticCollapsedDiff,
+            (OutputMarkup::Semantic, _) => self,
         }
     }
 }
@@ -112,10 +300,22 @@ fn register_templates(hbs: &mut Handlebars<'static>) {
         .expect("Failed to register file-summary-wrapper template");
     hbs.register_template_string("file-summary-line", FILE_SUMMARY_LINE)
         .expect("Failed to register file-summary-line template");
+    hbs.register_template_string("file-language-bar", FILE_LANGUAGE_BAR)
+        .expect("Failed to register file-language-bar template");
+    hbs.register_template_string("file-jump-menu", FILE_JUMP_MENU)
+        .expect("Failed to register file-jump-menu template");
+    hbs.register_template_string("file-toc", FILE_TOC)
+        .expect("Failed to register file-toc template");
+    hbs.register_template_string("file-toc-directory", FILE_TOC_DIRECTORY)
+        .expect("Failed to register file-toc-directory template");
+    hbs.register_template_string("file-toc-file", FILE_TOC_FILE)
+        .expect("Failed to register file-toc-file template");
     hbs.register_template_string("line-by-line-file-diff", LINE_BY_LINE_FILE_DIFF)
         .expect("Failed to register line-by-line-file-diff template");
     hbs.register_template_string("side-by-side-file-diff", SIDE_BY_SIDE_FILE_DIFF)
         .expect("Failed to register side-by-side-file-diff template");
+    hbs.register_template_string("switchable-file-diff", SWITCHABLE_FILE_DIFF)
+        .expect("Failed to register switchable-file-diff template");
     hbs.register_template_string("generic-file-path", GENERIC_FILE_PATH)
         .expect("Failed to register generic-file-path template");
     hbs.register_template_string("generic-line", GENERIC_LINE)
@@ -126,6 +326,48 @@ fn register_templates(hbs: &mut Handlebars<'static>) {
         .expect("Failed to register generic-block-header template");
     hbs.register_template_string("generic-empty-diff", GENERIC_EMPTY_DIFF)
         .expect("Failed to register generic-empty-diff template");
+    hbs.register_template_string("generic-file-mode", GENERIC_FILE_MODE)
+        .expect("Failed to register generic-file-mode template");
+    hbs.register_template_string("generic-file-metadata", GENERIC_FILE_METADATA)
+        .expect("Failed to register generic-file-metadata template");
+    hbs.register_template_string("generic-mode-change", GENERIC_MODE_CHANGE)
+        .expect("Failed to register generic-mode-change template");
+    hbs.register_template_string("generic-image-preview", GENERIC_IMAGE_PREVIEW)
+        .expect("Failed to register generic-image-preview template");
+    hbs.register_template_string("generic-no-newline", GENERIC_NO_NEWLINE)
+        .expect("Failed to register generic-no-newline template");
+    hbs.register_template_string("generic-truncated-line", GENERIC_TRUNCATED_LINE)
+        .expect("Failed to register generic-truncated-line template");
+    hbs.register_template_string("generic-omitted-files", GENERIC_OMITTED_FILES)
+        .expect("Failed to register generic-omitted-files template");
+    hbs.register_template_string("generic-collapsed-diff", GENERIC_COLLAPSED_DIFF)
+        .expect("Failed to register generic-collapsed-diff template");
+    hbs.register_template_string("generic-group-header", GENERIC_GROUP_HEADER)
+        .expect("Failed to register generic-group-header template");
+
+    // Semantic markup templates
+    hbs.register_template_string(
+        "semantic-line-by-line-file-diff",
+        SEMANTIC_LINE_BY_LINE_FILE_DIFF,
+    )
+    .expect("Failed to register semantic-line-by-line-file-diff template");
+    hbs.register_template_string(
+        "semantic-side-by-side-file-diff",
+        SEMANTIC_SIDE_BY_SIDE_FILE_DIFF,
+    )
+    .expect("Failed to register semantic-side-by-side-file-diff template");
+    hbs.register_template_string("semantic-line", SEMANTIC_LINE)
+        .expect("Failed to register semantic-line template");
+    hbs.register_template_string("semantic-block-header", SEMANTIC_BLOCK_HEADER)
+        .expect("Failed to register semantic-block-header template");
+    hbs.register_template_string("semantic-empty-diff", SEMANTIC_EMPTY_DIFF)
+        .expect("Failed to register semantic-empty-diff template");
+    hbs.register_template_string("semantic-mode-change", SEMANTIC_MODE_CHANGE)
+        .expect("Failed to register semantic-mode-change template");
+    hbs.register_template_string("semantic-image-preview", SEMANTIC_IMAGE_PREVIEW)
+        .expect("Failed to register semantic-image-preview template");
+    hbs.register_template_string("semantic-collapsed-diff", SEMANTIC_COLLAPSED_DIFF)
+        .expect("Failed to register semantic-collapsed-diff template");
 
     // Icon templates (used as partials)
     hbs.register_template_string("icon-file", ICON_FILE)
@@ -138,6 +380,8 @@ fn register_templates(hbs: &mut Handlebars<'static>) {
         .expect("Failed to register icon-file-deleted template");
     hbs.register_template_string("icon-file-renamed", ICON_FILE_RENAMED)
         .expect("Failed to register icon-file-renamed template");
+    hbs.register_template_string("icon-file-copied", ICON_FILE_COPIED)
+        .expect("Failed to register icon-file-copied template");
 
     // Tag templates (used as partials)
     hbs.register_template_string("tag-file-added", TAG_FILE_ADDED)
@@ -148,6 +392,12 @@ fn register_templates(hbs: &mut Handlebars<'static>) {
         .expect("Failed to register tag-file-deleted template");
     hbs.register_template_string("tag-file-renamed", TAG_FILE_RENAMED)
         .expect("Failed to register tag-file-renamed template");
+    hbs.register_template_string("tag-file-copied", TAG_FILE_COPIED)
+        .expect("Failed to register tag-file-copied template");
+    hbs.register_template_string("tag-executable-changed", TAG_EXECUTABLE_CHANGED)
+        .expect("Failed to register tag-executable-changed template");
+    hbs.register_template_string("tag-similarity", TAG_SIMILARITY)
+        .expect("Failed to register tag-similarity template");
 }
 
 /// Render a template with the given data.
@@ -199,6 +449,105 @@ pub fn render_by_name<T: Serialize>(name: &str, data: &T) -> String {
         .unwrap_or_else(|e| panic!("Failed to render template '{}': {}", name, e))
 }
 
+/// Line number cell contents of a [`GenericLine`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum LineNumbers {
+    /// A single line number, as in the side-by-side view.
+    Single(Option<u32>),
+    /// Old and new line numbers, rendered like [`TemplateName::LineByLineNumbers`].
+    OldAndNew(Option<u32>, Option<u32>),
+}
+
+/// Data of a diff line, rendered like [`TemplateName::GenericLine`] or
+/// [`TemplateName::SemanticLine`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct GenericLine<'a> {
+    pub line_type: &'a str,
+    pub line_class: &'a str,
+    pub content_class: &'a str,
+    pub prefix: &'a str,
+    pub content: &'a str,
+    /// HTML written before the line numbers, e.g. the blame gutter.
+    pub gutter: &'a str,
+    pub line_numbers: LineNumbers,
+    pub markup: OutputMarkup,
+}
+
+/// Write a diff line into a buffer.
+///
+/// The output is the same as rendering [`TemplateName::GenericLine`] or
+/// [`TemplateName::SemanticLine`] for the markup of the line, but skips
+/// building the template data and going through Handlebars, since diff lines
+/// make up most of the rendered output.
+pub(crate) fn write_generic_line(out: &mut String, line: &GenericLine<'_>) {
+    let prefix = if line.prefix.is_empty() {
+        "&nbsp;"
+    } else {
+        line.prefix
+    };
+    let content = if line.content.is_empty() {
+        "<br>"
+    } else {
+        line.content
+    };
+
+    // Markup around the two cells of the row
+    let (row_start, row_end, cell_start, code_cell_start, cell_attrs, cell_end) = match line.markup
+    {
+        OutputMarkup::Table => ("<tr>", "</tr>", "<td class=\"", "<td class=\"", "", "</td>"),
+        OutputMarkup::Semantic => (
+            "<div class=\"d2h-diff-row\" role=\"row\">",
+            "</div>",
+            "<div class=\"",
+            "<div class=\"d2h-code-cell ",
+            " role=\"cell\"",
+            "</div>",
+        ),
+    };
+
+    let _ = write!(
+        out,
+        "{row_start}\n    {cell_start}{} {}\"{cell_attrs}>\n      ",
+        line.line_class, line.line_type
+    );
+    out.push_str(line.gutter);
+    match line.line_numbers {
+        LineNumbers::Single(number) => write_number(out, number),
+        LineNumbers::OldAndNew(old_number, new_number) => {
+            write_line_by_line_numbers(out, old_number, new_number)
+        }
+    }
+    let _ = write!(
+        out,
+        "\n    {cell_end}\n    {code_cell_start}{}\"{cell_attrs}>\n        <div class=\"{}\">\n            \
+         <span class=\"d2h-code-line-prefix\">{}</span>\n            \
+         <span class=\"d2h-code-line-ctn\">{}</span>\n        </div>\n    {cell_end}\n{row_end}\n",
+        line.line_type, line.content_class, prefix, content
+    );
+}
+
+/// Write old and new line numbers into a buffer.
+///
+/// The output is the same as rendering [`TemplateName::LineByLineNumbers`].
+pub(crate) fn write_line_by_line_numbers(
+    out: &mut String,
+    old_number: Option<u32>,
+    new_number: Option<u32>,
+) {
+    out.push_str("<div class=\"line-num1\">");
+    write_number(out, old_number);
+    out.push_str("</div>\n<div class=\"line-num2\">");
+    write_number(out, new_number);
+    out.push_str("</div>\n");
+}
+
+/// Write a line number into a buffer, or nothing if there is none.
+fn write_number(out: &mut String, number: Option<u32>) {
+    if let Some(number) = number {
+        let _ = write!(out, "{number}");
+    }
+}
+
 /// Get access to the global Handlebars registry.
 ///
 /// This is useful for advanced use cases where you need direct access
@@ -243,6 +592,22 @@ mod tests {
         assert!(result.contains("<li>file1.txt</li>"));
     }
 
+    #[test]
+    fn test_render_file_jump_menu() {
+        let result = render(
+            TemplateName::FileJumpMenu,
+            &json!({
+                "colorScheme": "d2h-light-color-scheme",
+                "files": [{"fileHtmlId": "d2h-123456", "fileName": "src/lib.rs"}],
+            }),
+        );
+
+        assert!(result.contains("d2h-file-jump d2h-light-color-scheme"));
+        assert!(
+            result.contains(r##"<a href="#d2h-123456" class="d2h-file-jump-link">src/lib.rs</a>"##)
+        );
+    }
+
     #[test]
     fn test_render_line_by_line_numbers() {
         let result = render(
@@ -298,6 +663,88 @@ mod tests {
         assert!(result.contains("<br>"));
     }
 
+    #[test]
+    fn test_write_line_by_line_numbers_matches_template() {
+        for (old_number, new_number) in [(Some(10), Some(15)), (Some(3), None), (None, Some(7))] {
+            let mut out = String::new();
+            write_line_by_line_numbers(&mut out, old_number, new_number);
+
+            let expected = render(
+                TemplateName::LineByLineNumbers,
+                &json!({
+                    "oldNumber": old_number.map(|n| n.to_string()).unwrap_or_default(),
+                    "newNumber": new_number.map(|n| n.to_string()).unwrap_or_default(),
+                }),
+            );
+            assert_eq!(out, expected);
+        }
+    }
+
+    #[test]
+    fn test_write_generic_line_matches_template() {
+        let cases = [
+            (
+                "+",
+                "new <ins>line</ins>",
+                "",
+                LineNumbers::OldAndNew(None, Some(2)),
+            ),
+            ("-", "", "", LineNumbers::OldAndNew(Some(1), None)),
+            (
+                "",
+                "content",
+                r#"<div class="d2h-blame">Jane</div>"#,
+                LineNumbers::Single(Some(12)),
+            ),
+            ("", "", "", LineNumbers::Single(None)),
+        ];
+
+        let markups = [
+            (OutputMarkup::Table, TemplateName::GenericLine),
+            (OutputMarkup::Semantic, TemplateName::SemanticLine),
+        ];
+        for ((prefix, content, gutter, line_numbers), (markup, template)) in cases
+            .into_iter()
+            .flat_map(|case| markups.map(|markup| (case, markup)))
+        {
+            let line = GenericLine {
+                line_type: "d2h-ins",
+                line_class: "d2h-code-linenumber",
+                content_class: "d2h-code-line",
+                prefix,
+                content,
+                gutter,
+                line_numbers,
+                markup,
+            };
+            let mut out = String::new();
+            write_generic_line(&mut out, &line);
+
+            let line_number = match line_numbers {
+                LineNumbers::Single(number) => number.map(|n| n.to_string()).unwrap_or_default(),
+                LineNumbers::OldAndNew(old_number, new_number) => render(
+                    TemplateName::LineByLineNumbers,
+                    &json!({
+                        "oldNumber": old_number.map(|n| n.to_string()).unwrap_or_default(),
+                        "newNumber": new_number.map(|n| n.to_string()).unwrap_or_default(),
+                    }),
+                ),
+            };
+            let expected = render(
+                template,
+                &json!({
+                    "type": line.line_type,
+                    "lineClass": line.line_class,
+                    "contentClass": line.content_class,
+                    "prefix": prefix,
+                    "content": content,
+                    "lineNumber": format!("{gutter}{line_number}"),
+                }),
+            );
+            assert_eq!(out, expected);
+        }
+    }
+
     #[test]
     fn test_render_tag_file_added() {
         let result = render(TemplateName::TagFileAdded, &json!({}));
@@ -333,9 +780,66 @@ mod tests {
         assert!(!CSS.is_empty());
         assert!(CSS.contains(".d2h-wrapper"));
         assert!(CSS.contains(".d2h-file-header"));
+    }
+
+    #[test]
+    fn test_css_style_element() {
+        assert_eq!(css_style_element(None), format!("<style>\n{CSS}\n</style>"));
+        assert!(css_style_element(Some("{{nonce}}")).starts_with("<style nonce=\"{{nonce}}\">\n"));
+        assert!(css_style_element(Some("\"x")).starts_with("<style nonce=\"&quot;x\">"));
         assert!(CSS.contains("--d2h-bg-color"));
     }
 
+    #[test]
+    fn test_synchronised_scroll_script_element() {
+        assert_eq!(
+            synchronised_scroll_script_element(None),
+            format!("<script>\n{SYNCHRONISED_SCROLL_JS}</script>")
+        );
+        assert!(
+            synchronised_scroll_script_element(Some("{{nonce}}"))
+                .starts_with("<script nonce=\"{{nonce}}\">\n")
+        );
+        assert!(SYNCHRONISED_SCROLL_JS.contains("d2h-file-side-diff"));
+        assert!(!SYNCHRONISED_SCROLL_JS.contains("</script"));
+    }
+
+    #[test]
+    fn test_file_content_toggle_script_element() {
+        assert_eq!(
+            file_content_toggle_script_element(false, None),
+            format!("<script>\n{FILE_CONTENT_TOGGLE_JS}</script>")
+        );
+        assert!(
+            file_content_toggle_script_element(true, Some("n"))
+                .starts_with("<script nonce=\"n\" data-d2h-persist>\n")
+        );
+        assert!(FILE_CONTENT_TOGGLE_JS.contains("d2h-file-collapse-input"));
+        assert!(!FILE_CONTENT_TOGGLE_JS.contains("</script"));
+    }
+
+    #[test]
+    fn test_view_toggle_script_element() {
+        assert_eq!(
+            view_toggle_script_element(None),
+            format!("<script>\n{VIEW_TOGGLE_JS}</script>")
+        );
+        assert!(view_toggle_script_element(Some("n")).starts_with("<script nonce=\"n\">\n"));
+        assert!(VIEW_TOGGLE_JS.contains("d2h-view-toggle-button"));
+        assert!(!VIEW_TOGGLE_JS.contains("</script"));
+    }
+
+    #[test]
+    fn test_copy_path_script_element() {
+        assert_eq!(
+            copy_path_script_element(None),
+            format!("<script>\n{COPY_PATH_JS}</script>")
+        );
+        assert!(copy_path_script_element(Some("n")).starts_with("<script nonce=\"n\">\n"));
+        assert!(COPY_PATH_JS.contains("d2h-copy-path"));
+        assert!(!COPY_PATH_JS.contains("</script"));
+    }
+
     #[test]
     fn test_template_name_as_str() {
         assert_eq!(TemplateName::GenericWrapper.as_str(), "generic-wrapper");
@@ -346,6 +850,26 @@ mod tests {
         assert_eq!(TemplateName::IconFileAdded.as_str(), "icon-file-added");
     }
 
+    #[test]
+    fn test_template_name_for_markup() {
+        assert_eq!(
+            TemplateName::GenericLine.for_markup(OutputMarkup::Table),
+            TemplateName::GenericLine
+        );
+        assert_eq!(
+            TemplateName::GenericLine.for_markup(OutputMarkup::Semantic),
+            TemplateName::SemanticLine
+        );
+        assert_eq!(
+            TemplateName::SideBySideFileDiff.for_markup(OutputMarkup::Semantic),
+            TemplateName::SemanticSideBySideFileDiff
+        );
+        assert_eq!(
+            TemplateName::GenericFilePath.for_markup(OutputMarkup::Semantic),
+            TemplateName::GenericFilePath
+        );
+    }
+
     #[test]
     fn test_render_line_by_line_file_diff() {
         let result = render(
@@ -397,13 +921,122 @@ mod tests {
                 "CSSLineClass": {
                     "INFO": "d2h-info"
                 },
-                "contentClass": "d2h-code-line"
+                "contentClass": "d2h-code-line",
+                "message": "File without changes"
             }),
         );
 
         assert!(result.contains("File without changes"));
     }
 
+    #[test]
+    fn test_render_generic_file_mode() {
+        let result = render(
+            TemplateName::GenericFileMode,
+            &json!({
+                "oldMode": "100644",
+                "newMode": "100755",
+                "executableTag": "<span>tag</span>"
+            }),
+        );
+
+        assert!(result.contains("d2h-file-mode"));
+        assert!(result.contains("100644 → 100755"));
+        assert!(result.contains("<span>tag</span>"));
+    }
+
+    #[test]
+    fn test_render_generic_file_metadata() {
+        let result = render(
+            TemplateName::GenericFileMetadata,
+            &json!({
+                "similarity": "similarity 95%",
+                "checksums": "abc1234..def5678"
+            }),
+        );
+
+        assert!(result.contains("d2h-file-similarity"));
+        assert!(result.contains("similarity 95%"));
+        assert!(result.contains("d2h-file-checksums"));
+        assert!(result.contains("abc1234..def5678"));
+    }
+
+    #[test]
+    fn test_render_generic_no_newline() {
+        let result = render(TemplateName::GenericNoNewline, &json!({}));
+
+        assert!(result.contains("d2h-no-newline"));
+        assert!(result.contains("No newline at end of file"));
+    }
+
+    #[test]
+    fn test_render_generic_truncated_line() {
+        let result = render(
+            TemplateName::GenericTruncatedLine,
+            &json!({"visible": "abc", "rest": "def"}),
+        );
+
+        assert!(result.starts_with("abc<span class=\"d2h-truncated\">"));
+        assert!(result.contains(r#"<span class="d2h-truncated-rest" hidden>def</span>"#));
+    }
+
+    #[test]
+    fn test_render_generic_collapsed_diff() {
+        let result = render(
+            TemplateName::GenericCollapsedDiff,
+            &json!({
+                "lineClass": "d2h-code-linenumber",
+                "contentClass": "d2h-code-line",
+                "toggle": true,
+            }),
+        );
+        assert!(result.contains("d2h-collapsed-diff-input"));
+        assert!(result.contains("click to expand"));
+
+        let result = render(TemplateName::GenericCollapsedDiff, &json!({}));
+        assert!(result.contains("d2h-collapsed-diff"));
+        assert!(!result.contains("click to expand"));
+    }
+
+    #[test]
+    fn test_render_generic_mode_change() {
+        let result = render(
+            TemplateName::GenericModeChange,
+            &json!({
+                "CSSLineClass": {
+                    "INFO": "d2h-info"
+                },
+                "contentClass": "d2h-code-line",
+                "oldMode": "100644",
+                "newMode": "100755"
+            }),
+        );
+
+        assert!(result.contains("d2h-info"));
+        assert!(result.contains("File mode changed from 100644 to 100755"));
+    }
+
+    #[test]
+    fn test_render_generic_image_preview() {
+        let result = render(
+            TemplateName::GenericImagePreview,
+            &json!({
+                "CSSLineClass": {
+                    "INFO": "d2h-info"
+                },
+                "contentClass": "d2h-code-line",
+                "images": [
+                    {"className": "d2h-image-before", "src": "old.png", "label": "Before"},
+                    {"className": "d2h-image-after", "src": "new.png", "label": "After"}
+                ]
+            }),
+        );
+
+        assert!(result.contains("d2h-image-preview"));
+        assert!(result.contains(r#"<img src="old.png" alt="Before">"#));
+        assert!(result.contains(r#"<img src="new.png" alt="After">"#));
+    }
+
     #[test]
     fn test_render_generic_block_header() {
         let result = render(
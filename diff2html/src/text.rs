@@ -0,0 +1,134 @@
+//! Display width of text, for the columns taken by non-ASCII content.
+//!
+//! CJK ideographs, Hangul, fullwidth forms and most emoji take two columns in
+//! a monospace font, and combining marks and other zero-width characters take
+//! none, following the East Asian Width property of Unicode as measured by
+//! the `unicode-width` crate. Tabs go to the next multiple of [`TAB_WIDTH`]
+//! columns.
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Number of columns between tab stops, the default `tab-size` of browsers
+/// and the tab width of terminals.
+pub const TAB_WIDTH: usize = 8;
+
+/// Get the number of columns taken by a character: [`TAB_WIDTH`] for a tab at
+/// a tab stop, 0 for the other control characters and for zero-width
+/// characters, 2 for wide characters, and 1 for the others.
+///
+/// # Example
+///
+/// ```
+/// use diff2html::text::char_width;
+///
+/// assert_eq!(char_width('a'), 1);
+/// assert_eq!(char_width('中'), 2);
+/// assert_eq!(char_width('\u{301}'), 0);
+/// assert_eq!(char_width('\t'), 8);
+/// ```
+pub fn char_width(c: char) -> usize {
+    if c == '\t' {
+        TAB_WIDTH
+    } else {
+        c.width().unwrap_or(0)
+    }
+}
+
+/// Get the column following a character shown at `column`, going to the
+/// next tab stop for a tab.
+fn next_column(column: usize, c: char) -> usize {
+    if c == '\t' {
+        (column / TAB_WIDTH + 1) * TAB_WIDTH
+    } else {
+        column + char_width(c)
+    }
+}
+
+/// Get the number of columns taken by a string, from the start of a line.
+///
+/// Emoji sequences, e.g. joined by zero-width joiners, are measured as the
+/// single picture they show.
+///
+/// # Example
+///
+/// ```
+/// use diff2html::text::display_width;
+///
+/// assert_eq!(display_width("diff"), 4);
+/// assert_eq!(display_width("差分"), 4);
+/// assert_eq!(display_width("cafe\u{301}"), 4);
+/// assert_eq!(display_width("ab\tc"), 9);
+/// ```
+pub fn display_width(s: &str) -> usize {
+    let mut segments = s.split('\t');
+    let mut width = segments.next().map_or(0, UnicodeWidthStr::width);
+    for segment in segments {
+        width = next_column(width, '\t') + segment.width();
+    }
+    width
+}
+
+/// Get the byte index where a string, from the start of a line, goes past
+/// `width` columns, or `None` if all of it fits. The zero-width characters
+/// following the last character that fits, such as its combining marks, are
+/// kept before the index.
+///
+/// # Example
+///
+/// ```
+/// use diff2html::text::width_index;
+///
+/// assert_eq!(width_index("abcdef", 4), Some(4));
+/// assert_eq!(width_index("差分表示", 5), Some(6));
+/// assert_eq!(width_index("abc", 4), None);
+/// ```
+pub fn width_index(s: &str, width: usize) -> Option<usize> {
+    let mut column = 0;
+    for (index, c) in s.char_indices() {
+        column = next_column(column, c);
+        if column > width {
+            return Some(index);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_char_width() {
+        assert_eq!(char_width(' '), 1);
+        assert_eq!(char_width('\n'), 0);
+        assert_eq!(char_width('é'), 1);
+        assert_eq!(char_width('ж'), 1);
+        assert_eq!(char_width('あ'), 2);
+        assert_eq!(char_width('한'), 2);
+        assert_eq!(char_width('Ａ'), 2);
+        assert_eq!(char_width('😀'), 2);
+        assert_eq!(char_width('\u{200D}'), 0);
+        assert_eq!(char_width('\u{FE0F}'), 0);
+    }
+
+    #[test]
+    fn test_display_width_tabs() {
+        assert_eq!(display_width("\t"), 8);
+        assert_eq!(display_width("\t\t"), 16);
+        assert_eq!(display_width("1234567\tx"), 9);
+        assert_eq!(display_width("12345678\tx"), 17);
+        assert_eq!(display_width("差分\t"), 8);
+        assert_eq!(display_width("\u{1F469}\u{200D}\u{1F4BB}"), 2);
+    }
+
+    #[test]
+    fn test_width_index() {
+        assert_eq!(width_index("", 0), None);
+        assert_eq!(width_index("a", 0), Some(0));
+        assert_eq!(width_index("ab\u{301}c", 2), Some(4));
+        assert_eq!(width_index("a差", 2), Some(1));
+        assert_eq!(width_index("😀😀", 3), Some(4));
+        assert_eq!(width_index("a\tb", 8), Some(2));
+        assert_eq!(width_index("a\tb", 7), Some(1));
+    }
+}
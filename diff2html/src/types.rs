@@ -1,7 +1,14 @@
 //! Core types for diff parsing and rendering.
 
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Range;
+
 use serde::{Deserialize, Serialize};
 
+use crate::render::utils::{escape_for_html, filename_diff};
+
 /// Parts of a diff line split by prefix and content.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DiffLineParts {
@@ -18,6 +25,15 @@ pub enum LineType {
     Context,
 }
 
+/// A word-level segment of a line parsed from `git diff --word-diff=porcelain`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WordDiffSegment {
+    /// Whether the segment was removed (on deleted lines) or added (on inserted lines).
+    pub changed: bool,
+    pub text: String,
+}
+
 /// A single line in a diff.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -27,6 +43,18 @@ pub struct DiffLine {
     pub content: String,
     pub old_number: Option<u32>,
     pub new_number: Option<u32>,
+    /// Set when the line is followed by a `\ No newline at end of file` marker.
+    ///
+    /// The affected side follows from the line type: deletions for the old
+    /// file, insertions for the new file and context lines for both.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub no_newline_at_eof: bool,
+    /// Word-level segments of the line for `--word-diff=porcelain` input.
+    ///
+    /// When set, the renderers highlight the changed segments directly instead
+    /// of computing an intra-line diff.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub word_diff: Option<Vec<WordDiffSegment>>,
 }
 
 /// A block (hunk) in a diff file.
@@ -37,8 +65,73 @@ pub struct DiffBlock {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub old_start_line2: Option<u32>,
     pub new_start_line: u32,
+    /// Number of old lines declared by the hunk header (`b` in `@@ -a,b +c,d @@`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old_lines: Option<u32>,
+    /// Number of new lines declared by the hunk header (`d` in `@@ -a,b +c,d @@`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_lines: Option<u32>,
     pub header: String,
     pub lines: Vec<DiffLine>,
+    /// Lines of the diff input the block was parsed from, from the hunk header
+    /// to the last line of the block.
+    ///
+    /// The range holds 0-based line indices, ending lines at `\n`, `\r\n` or
+    /// `\r`. Only set by the diff parser.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_lines: Option<Range<usize>>,
+}
+
+/// HTML markup rendered as is, e.g. a message with a link.
+///
+/// Only wrap markup from a trusted source, never text interpolating user input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawHtml(pub String);
+
+/// A message shown in the HTML, either plain text, which is escaped, or
+/// explicitly [`RawHtml`].
+///
+/// # Example
+///
+/// ```
+/// use diff2html::{Message, RawHtml};
+///
+/// assert_eq!(Message::from("a < b").to_html(), "a &lt; b");
+/// let link = RawHtml(r#"see <a href="raw">raw</a>"#.to_string());
+/// assert_eq!(Message::from(link).to_html(), r#"see <a href="raw">raw</a>"#);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Text(String),
+    Html(RawHtml),
+}
+
+impl Message {
+    /// Get the HTML of the message.
+    pub fn to_html(&self) -> String {
+        match self {
+            Self::Text(text) => escape_for_html(text),
+            Self::Html(RawHtml(html)) => html.clone(),
+        }
+    }
+}
+
+impl From<&str> for Message {
+    fn from(text: &str) -> Self {
+        Self::Text(text.to_string())
+    }
+}
+
+impl From<String> for Message {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
+impl From<RawHtml> for Message {
+    fn from(html: RawHtml) -> Self {
+        Self::Html(html)
+    }
 }
 
 /// File mode representation that can be a single mode or multiple (for combined diffs).
@@ -88,8 +181,23 @@ pub struct DiffFile {
     pub is_rename: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub is_binary: Option<bool>,
+    /// Set when only the file permissions changed and there are no hunks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_mode_change: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub is_too_big: Option<bool>,
+    /// Set on too big files whose message is plain text, escaped when
+    /// rendered, rather than HTML.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_too_big_text: Option<bool>,
+    /// Set on too big files whose blocks were kept, so they can be rendered
+    /// collapsed instead of being replaced by the "too big" message.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_collapsed: Option<bool>,
+    /// Set on generated or vendored files, e.g. marked with the
+    /// `linguist-generated` or `linguist-vendored` git attributes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_generated: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub unchanged_percentage: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -98,8 +206,175 @@ pub struct DiffFile {
     pub checksum_before: Option<Checksum>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub checksum_after: Option<String>,
+    /// Size in bytes of the old version's `GIT binary patch` data.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub binary_size_before: Option<u64>,
+    /// Size in bytes of the new version's `GIT binary patch` data.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub binary_size_after: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mode: Option<String>,
+    /// Timestamp stripped from the file name of the `---` line, see
+    /// [`TimestampHandling`](crate::TimestampHandling).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old_timestamp: Option<String>,
+    /// Timestamp stripped from the file name of the `+++` line.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_timestamp: Option<String>,
+    /// Lines of the diff input the file was parsed from, from the file header
+    /// to the last line of its last block, like [`DiffBlock::source_lines`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_lines: Option<Range<usize>>,
+}
+
+impl DiffFile {
+    /// Get the status letter of the file, like `git diff --name-status`:
+    /// `A` for added, `D` for deleted, `R` for renamed, `C` for copied and `M`
+    /// for modified files.
+    pub fn status(&self) -> char {
+        if self.is_new == Some(true) {
+            'A'
+        } else if self.is_deleted == Some(true) {
+            'D'
+        } else if self.is_rename == Some(true) {
+            'R'
+        } else if self.is_copy == Some(true) {
+            'C'
+        } else {
+            'M'
+        }
+    }
+
+    /// Get the name of the [status](Self::status) of the file: `Added`,
+    /// `Deleted`, `Renamed`, `Copied` or `Modified`.
+    pub fn status_label(&self) -> &'static str {
+        match self.status() {
+            'A' => "Added",
+            'D' => "Deleted",
+            'R' => "Renamed",
+            'C' => "Copied",
+            _ => "Modified",
+        }
+    }
+
+    /// Get the line counts of the file.
+    pub fn stats(&self) -> DiffStats {
+        DiffStats {
+            files: 1,
+            added_lines: self.added_lines,
+            deleted_lines: self.deleted_lines,
+        }
+    }
+}
+
+/// One-line summary of the file, e.g. `M src/lib.rs (+12 −3)`.
+///
+/// Renamed and copied files show both names, like in the file list.
+impl fmt::Display for DiffFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} (+{} \u{2212}{})",
+            self.status(),
+            filename_diff(self),
+            self.added_lines,
+            self.deleted_lines
+        )
+    }
+}
+
+/// Number of changed files and lines of a diff.
+///
+/// # Example
+///
+/// ```
+/// use diff2html::{DiffParserConfig, DiffStats, parse};
+///
+/// let diff = "--- a/f\n+++ b/f\n@@ -1 +1,2 @@\n-old\n+new\n+line\n";
+/// let files = parse(diff, &DiffParserConfig::default());
+///
+/// let stats = DiffStats::from_files(&files);
+/// assert_eq!(stats.to_string(), "1 file (+2 \u{2212}1)");
+/// assert_eq!(files[0].to_string(), "M f (+2 \u{2212}1)");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffStats {
+    pub files: usize,
+    pub added_lines: u32,
+    pub deleted_lines: u32,
+}
+
+/// Number of changed lines of the files of a language.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguageStats {
+    pub language: String,
+    pub files: usize,
+    pub added_lines: u32,
+    pub deleted_lines: u32,
+}
+
+impl LanguageStats {
+    /// Number of added and deleted lines.
+    pub fn changed_lines(&self) -> u32 {
+        self.added_lines + self.deleted_lines
+    }
+
+    /// Sum up the line counts of parsed files by [language](DiffFile::language),
+    /// most changed first.
+    ///
+    /// Files without a language are not counted. Languages with as many
+    /// changed lines are sorted by name.
+    pub fn from_files(files: &[DiffFile]) -> Vec<Self> {
+        let mut languages: BTreeMap<&str, LanguageStats> = BTreeMap::new();
+        for file in files.iter().filter(|file| !file.language.is_empty()) {
+            let language = languages
+                .entry(&file.language)
+                .or_insert_with(|| LanguageStats {
+                    language: file.language.clone(),
+                    ..Default::default()
+                });
+            language.files += 1;
+            language.added_lines += file.added_lines;
+            language.deleted_lines += file.deleted_lines;
+        }
+        let mut languages: Vec<_> = languages.into_values().collect();
+        // Stable sort, keeping languages with as many changes by name
+        languages.sort_by_key(|language| Reverse(language.changed_lines()));
+        languages
+    }
+}
+
+impl DiffStats {
+    /// Sum up the line counts of parsed files.
+    ///
+    /// Files left out because of the parser's file limits are not among the
+    /// parsed files, see [`ParseResult::omitted_files`], and too big files
+    /// whose blocks were dropped count no lines.
+    ///
+    /// [`ParseResult::omitted_files`]: crate::ParseResult::omitted_files
+    pub fn from_files(files: &[DiffFile]) -> Self {
+        Self {
+            files: files.len(),
+            added_lines: files.iter().map(|file| file.added_lines).sum(),
+            deleted_lines: files.iter().map(|file| file.deleted_lines).sum(),
+        }
+    }
+}
+
+/// One-line summary of the stats, e.g. `3 files (+12 −3)`.
+impl fmt::Display for DiffStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} (+{} \u{2212}{})",
+            self.files,
+            if self.files == 1 { "file" } else { "files" },
+            self.added_lines,
+            self.deleted_lines
+        )
+    }
 }
 
 /// Output format for HTML rendering.
@@ -109,6 +384,33 @@ pub enum OutputFormat {
     #[default]
     LineByLine,
     SideBySide,
+    /// Both views of each file, with buttons switching between them.
+    Switchable,
+}
+
+/// HTML markup of the rendered diff lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputMarkup {
+    /// Lines are rows of a `<table>`.
+    #[default]
+    Table,
+    /// Lines are `<div>` rows with ARIA roles, laid out with CSS grid.
+    Semantic,
+}
+
+/// Grouping of the rendered files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupBy {
+    /// Files are rendered in the order of the diff.
+    #[default]
+    None,
+    /// Files are grouped by parent directory, in alphabetical order.
+    Directory,
+    /// Files are grouped by status: added, modified, renamed, copied, then
+    /// deleted.
+    Status,
 }
 
 /// Line matching algorithm type.
@@ -122,7 +424,7 @@ pub enum LineMatchingType {
 }
 
 /// Diff style for highlighting changes within lines.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum DiffStyle {
     #[default]
@@ -139,3 +441,128 @@ pub enum ColorScheme {
     Dark,
     Light,
 }
+
+/// Handling of deleted and inserted line pairs differing only in whitespace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WhitespaceChanges {
+    /// Render them like other changes.
+    #[default]
+    Show,
+    /// Add the `d2h-whitespace-only` class to them.
+    Mark,
+    /// Render them as context lines, like `git diff -w`.
+    Hide,
+}
+
+/// HTML IDs of the file diffs, linked from the file list and the jump menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HtmlIdStrategy {
+    /// A hash of the file name, e.g. `d2h-123456`.
+    #[default]
+    Hash,
+    /// A readable slug of the file path, e.g. `d2h-src-lib-rs`, the same on
+    /// every run, for links to the files in shared URLs.
+    Slug,
+}
+
+/// Display of the paths of renamed and copied files in the file header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RenameDisplay {
+    /// A single path with the changed part, e.g. `src/{a → b}/lib.rs`.
+    #[default]
+    Compact,
+    /// The old and the new path on separate lines, each with a button copying
+    /// it, enabled by [`COPY_PATH_JS`](crate::templates::COPY_PATH_JS).
+    Full,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(old_name: &str, new_name: &str) -> DiffFile {
+        DiffFile {
+            old_name: old_name.to_string(),
+            new_name: new_name.to_string(),
+            added_lines: 12,
+            deleted_lines: 3,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diff_file_display() {
+        assert_eq!(
+            file("src/lib.rs", "src/lib.rs").to_string(),
+            "M src/lib.rs (+12 \u{2212}3)"
+        );
+
+        let added = DiffFile {
+            is_new: Some(true),
+            ..file("/dev/null", "new.rs")
+        };
+        assert_eq!(added.to_string(), "A new.rs (+12 \u{2212}3)");
+
+        let deleted = DiffFile {
+            is_deleted: Some(true),
+            ..file("old.rs", "/dev/null")
+        };
+        assert_eq!(deleted.to_string(), "D old.rs (+12 \u{2212}3)");
+
+        let renamed = DiffFile {
+            is_rename: Some(true),
+            ..file("src/old.rs", "src/new.rs")
+        };
+        assert_eq!(
+            renamed.to_string(),
+            "R src/{old.rs \u{2192} new.rs} (+12 \u{2212}3)"
+        );
+    }
+
+    #[test]
+    fn test_diff_stats() {
+        let files = [file("a", "a"), file("b", "b")];
+
+        let stats = DiffStats::from_files(&files);
+        assert_eq!(
+            stats,
+            DiffStats {
+                files: 2,
+                added_lines: 24,
+                deleted_lines: 6,
+            }
+        );
+        assert_eq!(stats.to_string(), "2 files (+24 \u{2212}6)");
+        assert_eq!(files[0].stats().to_string(), "1 file (+12 \u{2212}3)");
+        assert_eq!(DiffStats::default().to_string(), "0 files (+0 \u{2212}0)");
+    }
+
+    #[test]
+    fn test_language_stats() {
+        let with_language = |name: &str, language: &str, added_lines| DiffFile {
+            language: language.to_string(),
+            added_lines,
+            ..file(name, name)
+        };
+        let files = [
+            with_language("a.rs", "rs", 1),
+            with_language("b.md", "md", 40),
+            with_language("c.rs", "rs", 30),
+            with_language("d.js", "js", 17),
+            file("Makefile", "Makefile"),
+        ];
+
+        let stats = LanguageStats::from_files(&files);
+        let languages: Vec<_> = stats
+            .iter()
+            .map(|language| {
+                let name = language.language.as_str();
+                (name, language.files, language.changed_lines())
+            })
+            .collect();
+        assert_eq!(languages, [("md", 1, 43), ("rs", 2, 37), ("js", 1, 20),]);
+    }
+}
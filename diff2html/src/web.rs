@@ -0,0 +1,220 @@
+//! Responses for serving rendered diffs from web services.
+//!
+//! Enabled with the `web` feature. The helpers do not depend on a web
+//! framework: a [`HtmlResponse`] is a content type and a body, and a
+//! [`HtmlStream`] yields the page in chunks to send as they are rendered.
+//! With axum, for example:
+//!
+//! ```ignore
+//! async fn diff(body: String) -> impl IntoResponse {
+//!     let response = diff2html::web::html_response(&body, &Diff2HtmlConfig::default());
+//!     ([(header::CONTENT_TYPE, response.content_type)], response.body)
+//! }
+//! ```
+//!
+//! See `examples/web_server.rs` for a server streaming the page.
+
+use crate::render::FileListRenderer;
+use crate::render::utils::{
+    group_files, make_group_header_html, make_html_ids, make_omitted_files_html, wrapper_html,
+};
+use crate::types::DiffFile;
+use crate::{Diff2Html, Diff2HtmlConfig};
+
+/// Content type of the rendered pages.
+pub const HTML_CONTENT_TYPE: &str = "text/html; charset=utf-8";
+
+/// A standalone HTML page of a rendered diff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HtmlResponse {
+    /// Value of the `Content-Type` header.
+    pub content_type: &'static str,
+    /// The HTML page, with its stylesheet inlined.
+    pub body: String,
+}
+
+/// Render a diff as a standalone HTML page, with the stylesheet inlined.
+///
+/// # Example
+///
+/// ```
+/// use diff2html::Diff2HtmlConfig;
+/// use diff2html::web::{HTML_CONTENT_TYPE, html_response};
+///
+/// let diff = "--- a/a.txt\n+++ b/a.txt\n@@ -1 +1 @@\n-a\n+b\n";
+/// let response = html_response(diff, &Diff2HtmlConfig::default());
+/// assert_eq!(response.content_type, HTML_CONTENT_TYPE);
+/// assert!(response.body.starts_with("<!DOCTYPE html>"));
+/// ```
+pub fn html_response(diff_input: &str, config: &Diff2HtmlConfig) -> HtmlResponse {
+    HtmlResponse {
+        content_type: HTML_CONTENT_TYPE,
+        body: html_stream(diff_input, config).collect(),
+    }
+}
+
+/// Render a diff as a standalone HTML page, one file at a time.
+///
+/// The diff is parsed up front, and each file is rendered when its chunk is
+/// requested, so the first bytes of large diffs are sent right away. The
+/// chunks put together are the page of [`html_response`], with the files
+/// rendered as by [`Diff2Html::html`].
+pub fn html_stream(diff_input: &str, config: &Diff2HtmlConfig) -> HtmlStream {
+    let engine = Diff2Html::new(Diff2HtmlConfig {
+        draw_file_list: false,
+        draw_jump_menu: false,
+        ..config.clone()
+    });
+    let result = engine.parse_result(diff_input);
+    let files = result.files;
+
+    let lists = FileListRenderer::new(config.to_file_list_config());
+    let mut head = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>Diff</title>\n<style>\n{}\n</style>\n</head>\n<body>\n",
+        engine.css()
+    );
+    if config.draw_jump_menu {
+        head.push_str(&lists.render_jump_menu(&files));
+    }
+    if config.draw_file_list {
+        head.push_str(&lists.render(&files));
+    }
+
+    // The files are wrapped and grouped once, around all the chunks
+    let (wrapper_start, wrapper_end) = wrapper_html(config.color_scheme);
+    head.push_str(&wrapper_start);
+    let tail = wrapper_end
+        + &make_omitted_files_html(result.omitted_files, config.color_scheme)
+        + "\n</body>\n</html>\n";
+    let order: Vec<_> = group_files(&files, config.group_by)
+        .into_iter()
+        .flat_map(|(name, indexes)| {
+            let mut header = name.map(|name| make_group_header_html(&name, indexes.len()));
+            indexes.into_iter().map(move |index| (header.take(), index))
+        })
+        .collect();
+
+    HtmlStream {
+        html_ids: make_html_ids(&files, config.deterministic, config.html_id_strategy),
+        engine,
+        files,
+        order: order.into_iter(),
+        started: false,
+        head: Some(head),
+        tail: Some(tail),
+    }
+}
+
+/// Chunks of a standalone HTML page: the head and file list, each file, then
+/// the end of the page. Created with [`html_stream`].
+#[derive(Debug)]
+pub struct HtmlStream {
+    engine: Diff2Html,
+    files: Vec<DiffFile>,
+    /// IDs of the files, unique across the chunks.
+    html_ids: Vec<String>,
+    /// Indexes of the files still to render, in the order of their groups,
+    /// with the header of the group they start.
+    order: std::vec::IntoIter<(Option<String>, usize)>,
+    /// Whether a file or group header was rendered, to separate the next one.
+    started: bool,
+    head: Option<String>,
+    tail: Option<String>,
+}
+
+impl Iterator for HtmlStream {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        if let Some(head) = self.head.take() {
+            return Some(head);
+        }
+        let Some((header, index)) = self.order.next() else {
+            return self.tail.take();
+        };
+
+        let mut chunk = String::new();
+        for part in header.into_iter().chain([self
+            .engine
+            .render_file(&self.files[index], &self.html_ids[index])])
+        {
+            if self.started {
+                chunk.push('\n');
+            }
+            self.started = true;
+            chunk.push_str(&part);
+        }
+        Some(chunk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{GroupBy, HtmlIdStrategy};
+
+    const DIFF: &str = "--- a/a.txt\n+++ b/a.txt\n@@ -1 +1 @@\n-a\n+b\n\
+                        --- a/b.txt\n+++ b/b.txt\n@@ -1 +1 @@\n-c\n+d\n";
+
+    #[test]
+    fn test_html_stream() {
+        let chunks: Vec<_> = html_stream(DIFF, &Diff2HtmlConfig::default()).collect();
+
+        assert_eq!(chunks.len(), 4);
+        assert!(chunks[0].contains("<style>"));
+        assert!(chunks[0].contains("Files changed (2)"));
+        assert!(chunks[1].contains("a.txt") && !chunks[1].contains("b.txt"));
+        assert!(chunks[2].contains("b.txt") && !chunks[2].contains("a.txt"));
+        assert!(chunks[3].ends_with("</html>\n"));
+        assert_eq!(chunks.concat().matches("class=\"d2h-wrapper").count(), 1);
+    }
+
+    #[test]
+    fn test_html_stream_whole_page() {
+        let diff = format!("{DIFF}--- a/a.txt\n+++ b/a.txt\n@@ -1 +1 @@\n-e\n+f\n");
+        let configs = [
+            Diff2HtmlConfig::default(),
+            Diff2HtmlConfig {
+                group_by: GroupBy::Status,
+                html_id_strategy: HtmlIdStrategy::Slug,
+                ..Default::default()
+            },
+            Diff2HtmlConfig {
+                diff_max_files: Some(2),
+                output_format: crate::OutputFormat::Switchable,
+                ..Default::default()
+            },
+        ];
+        for config in configs {
+            let config = Diff2HtmlConfig {
+                draw_file_list: false,
+                ..config
+            };
+            let page = html_response(&diff, &config).body;
+            let html = Diff2Html::new(config).html(&diff);
+            assert!(page.contains(&html), "{html}");
+        }
+
+        // Empty diffs get the wrapper too
+        let page = html_response("", &Diff2HtmlConfig::default()).body;
+        assert!(page.contains(&Diff2Html::default().html("")));
+    }
+
+    #[test]
+    fn test_html_response() {
+        let config = Diff2HtmlConfig {
+            draw_file_list: false,
+            ..Default::default()
+        };
+        let response = html_response(DIFF, &config);
+
+        assert_eq!(response.content_type, "text/html; charset=utf-8");
+        assert!(!response.body.contains("Files changed"));
+        assert_eq!(
+            response.body.matches("class=\"d2h-file-wrapper\"").count(),
+            2
+        );
+    }
+}
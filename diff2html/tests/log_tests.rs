@@ -0,0 +1,64 @@
+//! Tests for the log messages of the library.
+
+#![cfg(feature = "log")]
+
+use std::sync::Mutex;
+
+use diff2html::{Diff2HtmlConfig, html};
+use log::{Level, Log, Metadata, Record};
+
+/// Logger keeping the debug messages of the library.
+struct CaptureLogger {
+    messages: Mutex<Vec<(String, String)>>,
+}
+
+impl Log for CaptureLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Debug && metadata.target().starts_with("diff2html")
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            self.messages
+                .lock()
+                .unwrap()
+                .push((record.target().to_string(), record.args().to_string()));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: CaptureLogger = CaptureLogger {
+    messages: Mutex::new(Vec::new()),
+};
+
+#[test]
+fn test_parse_and_render_are_logged() {
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(log::LevelFilter::Debug);
+
+    let diff = "--- a/f\n+++ b/f\n@@ -1,3 +1,3 @@\n-old\n+new\n";
+    html(diff, &Diff2HtmlConfig::default());
+
+    let messages = LOGGER.messages.lock().unwrap();
+    let targets: Vec<&str> = messages.iter().map(|(target, _)| target.as_str()).collect();
+    assert_eq!(
+        targets,
+        [
+            "diff2html::parser",
+            "diff2html::parser",
+            "diff2html::engine"
+        ]
+    );
+    assert_eq!(
+        messages[0].1,
+        "line 3: hunk header declares 3 old and 3 new lines, found 1 and 1"
+    );
+    assert!(
+        messages[1]
+            .1
+            .starts_with("parsed 1 files with 1 problems from 6 lines in ")
+    );
+    assert!(messages[2].1.starts_with("rendered 1 files to "));
+}
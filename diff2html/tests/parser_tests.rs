@@ -2,7 +2,10 @@
 //!
 //! These tests are ported from the TypeScript diff2html test suite.
 
-use diff2html::{Checksum, DiffParserConfig, FileMode, LineType, parse};
+use diff2html::{
+    Checksum, DiffParserConfig, FileMode, FilenameDecoding, LineType, ParseMode, PrefixMode,
+    TimestampHandling, parse, parse_with_diagnostics,
+};
 
 /// Helper to load a test fixture
 fn load_fixture(name: &str) -> String {
@@ -236,6 +239,21 @@ fn test_parse_binary_file() {
     assert!(file.blocks[0].lines.is_empty());
 }
 
+#[test]
+fn test_parse_git_binary_patch() {
+    let diff = load_fixture("binary_patch.diff");
+    let result = parse(&diff, &DiffParserConfig::default());
+
+    assert_eq!(result.len(), 1);
+    let file = &result[0];
+    assert_eq!(file.new_name, "logo.png");
+    assert_eq!(file.is_binary, Some(true));
+    assert_eq!(file.binary_size_after, Some(13414));
+    assert_eq!(file.binary_size_before, Some(12698));
+    assert_eq!(file.blocks.len(), 1);
+    assert!(file.blocks[0].lines.is_empty());
+}
+
 // =============================================================================
 // Combined Diff Tests
 // =============================================================================
@@ -287,6 +305,42 @@ fn test_parse_unified_with_timestamps() {
     // Timestamps should be stripped
     assert_eq!(file.old_name, "sample.js");
     assert_eq!(file.new_name, "sample.js");
+    assert_eq!(
+        file.old_timestamp.as_deref(),
+        Some("2016-10-25 11:37:14.000000000 +0200")
+    );
+}
+
+#[test]
+fn test_parse_unified_with_timestamps_kept() {
+    let diff = load_fixture("unified_with_timestamps.diff");
+    let config = DiffParserConfig {
+        timestamps: TimestampHandling::Never,
+        ..Default::default()
+    };
+    let file = &parse(&diff, &config)[0];
+
+    assert_eq!(
+        file.old_name,
+        "sample.js  2016-10-25 11:37:14.000000000 +0200"
+    );
+    assert_eq!(file.old_timestamp, None);
+}
+
+#[test]
+fn test_parse_unified_with_epoch_timestamps() {
+    let diff = "--- a.txt\t1477388234\n+++ b.txt\tTue Oct 25 11:37:14 2016\n@@ -1 +1 @@\n-a\n+b\n";
+    let file = &parse(diff, &DiffParserConfig::default())[0];
+
+    assert_eq!(
+        (file.old_name.as_str(), file.new_name.as_str()),
+        ("a.txt", "b.txt")
+    );
+    assert_eq!(file.old_timestamp.as_deref(), Some("1477388234"));
+    assert_eq!(
+        file.new_timestamp.as_deref(),
+        Some("Tue Oct 25 11:37:14 2016")
+    );
 }
 
 // =============================================================================
@@ -310,6 +364,33 @@ fn test_parse_special_characters() {
     assert_eq!(file.deleted_lines, 1);
 }
 
+#[test]
+fn test_parse_quoted_filenames() {
+    let diff = load_fixture("quoted_filenames.diff");
+    let result = parse(&diff, &DiffParserConfig::default());
+
+    assert_eq!(result.len(), 2);
+    assert_eq!(result[0].old_name, "docs/café menu.md");
+    assert_eq!(result[0].new_name, "docs/café menu.md");
+    assert_eq!(result[0].language, "md");
+    assert_eq!(result[1].old_name, "old \"name\".txt");
+    assert_eq!(result[1].new_name, "new.txt");
+    assert_eq!(result[1].is_rename, Some(true));
+}
+
+#[test]
+fn test_parse_quoted_filenames_without_decoding() {
+    let diff = load_fixture("quoted_filenames.diff");
+    let config = DiffParserConfig {
+        filename_decoding: FilenameDecoding::Keep,
+        ..Default::default()
+    };
+    let result = parse(&diff, &config);
+
+    assert_eq!(result[0].new_name, r"docs/caf\303\251 menu.md");
+    assert_eq!(result[1].old_name, r#"old \"name\".txt"#);
+}
+
 // =============================================================================
 // Line Number Tests
 // =============================================================================
@@ -434,6 +515,99 @@ fn test_parse_diff_max_changes() {
     assert_eq!(result[0].is_too_big, Some(true));
 }
 
+#[test]
+fn test_parse_diff_max_files() {
+    let diff = load_fixture("multiple_files.diff");
+    let config = DiffParserConfig {
+        diff_max_files: Some(1),
+        ..Default::default()
+    };
+    let result = parse_with_diagnostics(&diff, &config).unwrap();
+
+    assert_eq!(result.files.len(), 1);
+    assert_eq!(result.files[0].new_name, "src/core/init.js");
+    assert_eq!(result.omitted_files, 1);
+
+    // Without a limit every file is kept
+    let result = parse(&diff, &DiffParserConfig::default());
+    assert_eq!(result.len(), 2);
+    assert_eq!(result[1].new_name, "src/event.js");
+}
+
+#[test]
+fn test_parse_language_overrides() {
+    let diff = "diff --git a/Dockerfile b/Dockerfile\n\
+                --- a/Dockerfile\n\
+                +++ b/Dockerfile\n\
+                @@ -1 +1 @@\n\
+                -FROM alpine:3.18\n\
+                +FROM alpine:3.19\n\
+                diff --git a/infra/main.tf b/infra/main.tf\n\
+                --- a/infra/main.tf\n\
+                +++ b/infra/main.tf\n\
+                @@ -1 +1 @@\n\
+                -region = \"eu-west-1\"\n\
+                +region = \"eu-west-2\"\n";
+    let config = DiffParserConfig {
+        language_overrides: [("Dockerfile", "dockerfile"), ("*.tf", "hcl")]
+            .into_iter()
+            .map(|(key, language)| (key.to_string(), language.to_string()))
+            .collect(),
+        ..Default::default()
+    };
+
+    let result = parse(diff, &config);
+    assert_eq!(result[0].language, "dockerfile");
+    assert_eq!(result[1].language, "hcl");
+
+    let result = parse(diff, &DiffParserConfig::default());
+    assert_eq!(result[0].language, "");
+    assert_eq!(result[1].language, "tf");
+}
+
+#[test]
+fn test_parse_no_prefix_diff() {
+    let diff = "diff --git b/script.sh b/script.sh\n\
+                old mode 100644\n\
+                new mode 100755\n\
+                diff --git c/main.c c/main.c\n\
+                index 1e6b3a1..5c4d2e9 100644\n\
+                --- c/main.c\n\
+                +++ c/main.c\n\
+                @@ -1 +1 @@\n\
+                -int x;\n\
+                +int y;\n";
+    let config = DiffParserConfig {
+        prefix_mode: PrefixMode::Configured,
+        ..Default::default()
+    };
+    let result = parse(diff, &config);
+
+    assert_eq!(result.len(), 2);
+    assert_eq!(result[0].old_name, "b/script.sh");
+    assert_eq!(result[0].new_name, "b/script.sh");
+    assert_eq!(result[1].old_name, "c/main.c");
+    assert_eq!(result[1].new_name, "c/main.c");
+}
+
+#[test]
+fn test_parse_custom_prefixes() {
+    let diff = "diff --git left/run.sh right/run.sh\n\
+                old mode 100644\n\
+                new mode 100755\n";
+    let config = DiffParserConfig {
+        src_prefix: Some("left/".to_string()),
+        dst_prefix: Some("right/".to_string()),
+        ..Default::default()
+    };
+    let result = parse(diff, &config);
+
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].old_name, "run.sh");
+    assert_eq!(result[0].new_name, "run.sh");
+    assert_eq!(result[0].is_mode_change, Some(true));
+}
+
 // =============================================================================
 // Edge Cases
 // =============================================================================
@@ -460,6 +634,9 @@ fn test_parse_no_newline_at_eof() {
     // The "no newline" marker should be handled
     let block = &result[0].blocks[0];
     assert!(!block.lines.is_empty());
+    assert_eq!(block.lines.len(), 2);
+    assert!(block.lines[0].no_newline_at_eof);
+    assert!(!block.lines[1].no_newline_at_eof);
 }
 
 #[test]
@@ -566,3 +743,126 @@ fn test_parse_nested_diff_content() {
     // Should only parse as one file, not be confused by nested diff content
     assert_eq!(result[0].old_name, "src/offset.js");
 }
+
+// =============================================================================
+// Word Diff Tests
+// =============================================================================
+
+#[test]
+fn test_parse_word_diff_porcelain() {
+    let diff = load_fixture("word_diff.diff");
+    let result = parse(&diff, &DiffParserConfig::default());
+
+    assert_eq!(result.len(), 1);
+    let file = &result[0];
+    assert_eq!(file.added_lines, 2);
+    assert_eq!(file.deleted_lines, 2);
+
+    let lines = &file.blocks[0].lines;
+    let contents: Vec<_> = lines.iter().map(|l| l.content.as_str()).collect();
+    assert_eq!(
+        contents,
+        [
+            "-Hello world!",
+            "+Hello there!",
+            " ",
+            "-Goodbye",
+            "+See you",
+            " unchanged line"
+        ]
+    );
+
+    let old_segments = lines[0].word_diff.as_ref().unwrap();
+    assert_eq!(old_segments.len(), 3);
+    assert!(!old_segments[0].changed);
+    assert!(old_segments[1].changed);
+    assert_eq!(old_segments[1].text, "world");
+
+    assert_eq!(lines[2].line_type, LineType::Context);
+    assert_eq!(lines[2].old_number, Some(2));
+    assert_eq!(lines[2].new_number, Some(2));
+    assert_eq!(lines[5].old_number, Some(4));
+    assert_eq!(lines[5].new_number, Some(4));
+    assert_eq!(lines[5].word_diff, None);
+}
+
+// =============================================================================
+// Recursive Diff Tests
+// =============================================================================
+
+#[test]
+fn test_parse_recursive_diff_only_in() {
+    let diff = load_fixture("recursive.diff");
+    let result = parse(&diff, &DiffParserConfig::default());
+
+    assert_eq!(result.len(), 4);
+
+    assert_eq!(result[0].old_name, "old/removed.txt");
+    assert_eq!(result[0].new_name, "/dev/null");
+    assert_eq!(result[0].is_deleted, Some(true));
+    assert!(result[0].blocks.is_empty());
+
+    assert_eq!(result[1].old_name, "old/src/main.c");
+    assert_eq!(result[1].new_name, "new/src/main.c");
+    assert_eq!(result[1].added_lines, 1);
+
+    assert_eq!(result[2].old_name, "/dev/null");
+    assert_eq!(result[2].new_name, "new/src/util.c");
+    assert_eq!(result[2].is_new, Some(true));
+    assert_eq!(result[2].language, "c");
+
+    assert_eq!(result[3].new_name, "new/docs");
+    assert_eq!(result[3].is_new, Some(true));
+}
+
+// =============================================================================
+// Diagnostics Tests
+// =============================================================================
+
+#[test]
+fn test_parse_with_diagnostics_truncated_hunk() {
+    let diff = load_fixture("rename.diff");
+    let result = parse_with_diagnostics(&diff, &DiffParserConfig::default()).unwrap();
+
+    // Lenient mode recovers the same files as parse()
+    assert_eq!(result.files, parse(&diff, &DiffParserConfig::default()));
+    assert_eq!(result.diagnostics.len(), 1);
+    assert_eq!(result.diagnostics[0].line, 8);
+    assert_eq!(
+        result.diagnostics[0].message,
+        "hunk header declares 4 old and 32 new lines, found 3 and 3"
+    );
+
+    let config = DiffParserConfig {
+        mode: ParseMode::Strict,
+        ..Default::default()
+    };
+    assert!(parse_with_diagnostics(&diff, &config).is_err());
+}
+
+#[test]
+fn test_parse_with_diagnostics_strict_valid_diff() {
+    let diff = load_fixture("simple.diff");
+    let config = DiffParserConfig {
+        mode: ParseMode::Strict,
+        ..Default::default()
+    };
+    let result = parse_with_diagnostics(&diff, &config).unwrap();
+
+    assert_eq!(result.files.len(), 1);
+    assert!(result.diagnostics.is_empty());
+}
+
+#[test]
+fn test_parse_hunk_line_counts_mismatch() {
+    let diff = load_fixture("multiple_blocks.diff");
+    let result = parse_with_diagnostics(&diff, &DiffParserConfig::default()).unwrap();
+
+    let block = &result.files[0].blocks[0];
+    assert_eq!(block.old_lines, Some(10));
+    assert_eq!(block.new_lines, Some(9));
+
+    // The blank context lines of the fixture lost their leading space
+    let lines: Vec<_> = result.diagnostics.iter().map(|d| d.line).collect();
+    assert_eq!(lines, [5, 17]);
+}
@@ -2,9 +2,15 @@
 //!
 //! These tests are ported from the TypeScript diff2html test suite.
 
+use std::sync::Arc;
+
+use diff2html::render::{MinifiedFileDetection, get_stable_html_id, pretty_print_html};
 use diff2html::{
-    ColorScheme, Diff2HtmlConfig, DiffParserConfig, DiffStyle, LineMatchingType, OutputFormat,
-    html, html_from_diff_files, json, json_from_diff_files, parse,
+    AutolinkConfig, AutolinkReference, BlameSource, ColorScheme, Diff2HtmlConfig, DiffFile,
+    DiffLine, DiffParserConfig, DiffStyle, FileBlame, FileHeaderExtra, FileListConfig,
+    FileListRenderer, FileMode, HtmlIdStrategy, ImagePreview, ImageSource, LineBlame,
+    LineMatchingType, OutputFormat, OutputMarkup, RawHtml, RenameDisplay, RenderHook, html,
+    html_from_diff_files, json, json_from_diff_files, parse, parse_summary,
 };
 
 /// Helper to load a test fixture
@@ -84,6 +90,85 @@ fn test_html_with_file_list() {
     assert!(result.contains("Files changed"));
 }
 
+#[test]
+fn test_html_with_max_files() {
+    let diff = load_fixture("multiple_files.diff");
+    let config = Diff2HtmlConfig {
+        diff_max_files: Some(1),
+        ..Default::default()
+    };
+    let result = html(&diff, &config);
+
+    assert!(result.contains("src/core/init.js"));
+    assert!(!result.contains("src/event.js"));
+    assert!(result.contains(
+        r#"<div class="d2h-omitted-files d2h-light-color-scheme">1 more file not shown</div>"#
+    ));
+    // The omitted files are not listed as a file
+    assert_eq!(result.matches(r#"class="d2h-file-wrapper"#).count(), 1);
+    assert!(result.contains("Files changed (1)"));
+}
+
+#[test]
+fn test_html_keep_too_big_content() {
+    let diff = load_fixture("multiple_files.diff");
+    for output_format in [OutputFormat::LineByLine, OutputFormat::SideBySide] {
+        let config = Diff2HtmlConfig {
+            output_format,
+            diff_max_changes: Some(1),
+            keep_too_big_content: true,
+            ..Default::default()
+        };
+        let result = html(&diff, &config);
+
+        assert!(result.contains("Diff too large — click to expand"));
+        assert!(result.contains("Shortcut for document ready"));
+        assert!(!result.contains("Diff too big to be displayed"));
+    }
+
+    let config = Diff2HtmlConfig {
+        diff_max_changes: Some(1),
+        ..Default::default()
+    };
+    let result = html(&diff, &config);
+    assert!(result.contains("Diff too big to be displayed"));
+    assert!(!result.contains("click to expand"));
+}
+
+#[test]
+fn test_html_diff_too_big_message() {
+    let diff = load_fixture("multiple_files.diff");
+    let config = Diff2HtmlConfig {
+        diff_max_changes: Some(1),
+        diff_too_big_message: Some(
+            RawHtml("Too many changes, see <a href=\"/raw\">raw diff</a>".to_string()).into(),
+        ),
+        ..Default::default()
+    };
+    let result = html(&diff, &config);
+
+    assert!(result.contains(r#"Too many changes, see <a href="/raw">raw diff</a>"#));
+    assert!(!result.contains("Diff too big to be displayed"));
+
+    // Plain text is escaped
+    let config = Diff2HtmlConfig {
+        diff_too_big_message: Some("More than 1 change, see <raw>".into()),
+        ..config
+    };
+    let result = html(&diff, &config);
+    assert!(result.contains("More than 1 change, see &lt;raw&gt;"));
+    assert!(!result.contains("<raw>"));
+
+    // Escaped once when hardened
+    let config = Diff2HtmlConfig {
+        hardened: true,
+        ..config
+    };
+    let result = html(&diff, &config);
+    assert!(result.contains("More than 1 change, see &lt;raw&gt;"));
+    assert!(!result.contains("&amp;lt;"));
+}
+
 #[test]
 fn test_html_without_file_list() {
     let diff = load_fixture("multiple_files.diff");
@@ -97,6 +182,18 @@ fn test_html_without_file_list() {
     assert!(!result.contains("d2h-file-list-wrapper"));
 }
 
+#[test]
+fn test_file_list_from_numstat_summary() {
+    let files = parse_summary("12\t3\tsrc/main.rs\n-\t-\tassets/logo.png\n");
+    let result = FileListRenderer::new(FileListConfig::default()).render(&files);
+
+    assert!(result.contains("d2h-file-list"));
+    assert!(result.contains("src/main.rs"));
+    assert!(result.contains("+12"));
+    assert!(result.contains("-3"));
+    assert!(result.contains("assets/logo.png"));
+}
+
 // =============================================================================
 // Color Scheme Tests
 // =============================================================================
@@ -337,6 +434,269 @@ fn test_html_file_status_renamed() {
     );
 }
 
+#[test]
+fn test_html_file_status_copied() {
+    let diff = "diff --git a/src/a.rs b/src/b.rs\n\
+                similarity index 90%\n\
+                copy from src/a.rs\n\
+                copy to src/b.rs\n\
+                --- a/src/a.rs\n\
+                +++ b/src/b.rs\n\
+                @@ -1 +1 @@\n\
+                -a\n\
+                +b\n";
+    let result = html(diff, &Diff2HtmlConfig::default());
+
+    assert!(result.contains(r#"<span class="d2h-tag d2h-moved d2h-copied-tag">COPIED</span>"#));
+    assert!(result.contains(r#"title="copied""#));
+    assert!(!result.contains("RENAMED"));
+}
+
+#[test]
+fn test_html_rename_display_full() {
+    let diff = "diff --git a/src/old/<a>.rs b/src/new/<a>.rs\n\
+                similarity index 100%\n\
+                rename from src/old/<a>.rs\n\
+                rename to src/new/<a>.rs\n";
+    let compact = html(diff, &Diff2HtmlConfig::default());
+    assert!(compact.contains("src/{old → new}/&lt;a&gt;.rs"));
+    assert!(!compact.contains(r#"class="d2h-copy-path""#));
+
+    let config = Diff2HtmlConfig {
+        rename_display: RenameDisplay::Full,
+        ..Default::default()
+    };
+    let result = html(diff, &config);
+    assert!(result.contains(r#"<span class="d2h-file-name d2h-file-name-full">"#));
+    assert!(result.contains(
+        r#"<span class="d2h-file-path-name">src/old/&lt;a&gt;.rs</span><button type="button" class="d2h-copy-path" data-d2h-path="src/old/&lt;a&gt;.rs""#
+    ));
+    assert!(result.contains(r#"data-d2h-path="src/new/&lt;a&gt;.rs""#));
+    assert!(!result.contains(r#"<span class="d2h-file-name">src/{old → new}"#));
+
+    // Files that are not renamed keep a single path
+    let changed = html("--- a/a.rs\n+++ b/a.rs\n@@ -1 +1 @@\n-a\n+b\n", &config);
+    assert!(!changed.contains(r#"class="d2h-copy-path""#));
+}
+
+#[test]
+fn test_html_file_url_template() {
+    let diff = "diff --git a/src/old name.rs b/src/new.rs\n\
+                similarity index 90%\n\
+                rename from src/old name.rs\n\
+                rename to src/new.rs\n\
+                --- a/src/old name.rs\n\
+                +++ b/src/new.rs\n\
+                @@ -1 +1 @@\n\
+                -a\n\
+                +b\n";
+    let config = Diff2HtmlConfig {
+        file_url_template: Some("https://host/blob/main/{path}?was=\"{old_path}\"".to_string()),
+        ..Default::default()
+    };
+    let result = html(diff, &config);
+    let url = "https://host/blob/main/src/new.rs?was=&quot;src/old%20name.rs&quot;";
+
+    assert!(result.contains(&format!(
+        r#"<a class="d2h-file-name" href="{url}" target="_blank" rel="noopener noreferrer">src/{{old name.rs → new.rs}}</a>"#
+    )));
+    assert!(result.contains(&format!(r#"<a class="d2h-file-url" href="{url}""#)));
+    assert!(!html(diff, &Diff2HtmlConfig::default()).contains("d2h-file-url\""));
+
+    let full = html(
+        diff,
+        &Diff2HtmlConfig {
+            rename_display: RenameDisplay::Full,
+            ..config
+        },
+    );
+    assert!(full.contains(
+        r#"<a class="d2h-file-path-name" href="https://host/blob/main/src/old%20name.rs?was=&quot;src/old%20name.rs&quot;""#
+    ));
+}
+
+#[test]
+fn test_html_highlight_cache() {
+    let diff = "--- a/gen.rs\n+++ b/gen.rs\n@@ -1,3 +1,3 @@\n-let a = 1;\n-let a = 1;\n-let b = 2;\n+let a = 10;\n+let a = 10;\n+let b = 20;\n";
+    for output_format in [OutputFormat::LineByLine, OutputFormat::SideBySide] {
+        let config = Diff2HtmlConfig {
+            output_format,
+            ..Default::default()
+        };
+        let cached = Diff2HtmlConfig {
+            highlight_cache_size: 1,
+            ..config.clone()
+        };
+
+        assert_eq!(html(diff, &cached), html(diff, &config));
+    }
+}
+
+#[test]
+fn test_html_slug_html_ids() {
+    let diff = "--- a/src/main.rs\n+++ b/src/main.rs\n@@ -1 +1 @@\n-a\n+b\n";
+    for output_format in [OutputFormat::LineByLine, OutputFormat::SideBySide] {
+        let result = html(
+            diff,
+            &Diff2HtmlConfig {
+                output_format,
+                html_id_strategy: HtmlIdStrategy::Slug,
+                ..Default::default()
+            },
+        );
+
+        assert!(result.contains(r#"id="d2h-src-main-rs""#));
+        assert!(result.contains(r##"href="#d2h-src-main-rs""##));
+    }
+}
+
+#[test]
+fn test_html_slug_html_ids_duplicates() {
+    let diff = "--- a/a.b\n+++ b/a.b\n@@ -1 +1 @@\n-a\n+b\n\
+                --- a/a/b\n+++ b/a/b\n@@ -1 +1 @@\n-a\n+b\n";
+    for output_format in [
+        OutputFormat::LineByLine,
+        OutputFormat::SideBySide,
+        OutputFormat::Switchable,
+    ] {
+        let result = html(
+            diff,
+            &Diff2HtmlConfig {
+                output_format,
+                html_id_strategy: HtmlIdStrategy::Slug,
+                draw_jump_menu: true,
+                ..Default::default()
+            },
+        );
+
+        for id in ["d2h-a-b", "d2h-a-b-2"] {
+            assert_eq!(result.matches(&format!(r#"id="{id}""#)).count(), 1);
+            assert_eq!(result.matches(&format!(r##"href="#{id}""##)).count(), 2);
+        }
+    }
+}
+
+// =============================================================================
+// File Metadata Tests
+// =============================================================================
+
+#[test]
+fn test_html_file_metadata_hidden_by_default() {
+    let diff = load_fixture("rename.diff");
+    let result = html(&diff, &Diff2HtmlConfig::default());
+
+    assert!(!result.contains("d2h-file-metadata"));
+}
+
+#[test]
+fn test_html_file_metadata_shown() {
+    let diff = load_fixture("rename.diff");
+    let config = Diff2HtmlConfig {
+        show_file_metadata: true,
+        draw_file_list: false,
+        ..Default::default()
+    };
+    let result = html(&diff, &config);
+
+    assert!(result.contains("d2h-file-metadata"));
+    assert!(result.contains("similarity 98%"));
+    assert!(result.contains("e01513b..f14a870"));
+}
+
+// =============================================================================
+// File Mode Tests
+// =============================================================================
+
+#[test]
+fn test_html_file_mode_change() {
+    let diff = "diff --git a/script.sh b/script.sh\n\
+                old mode 100644\n\
+                new mode 100755\n\
+                --- a/script.sh\n\
+                +++ b/script.sh\n\
+                @@ -1 +1 @@\n\
+                -echo old\n\
+                +echo new\n";
+
+    for output_format in [OutputFormat::LineByLine, OutputFormat::SideBySide] {
+        let config = Diff2HtmlConfig {
+            output_format,
+            draw_file_list: false,
+            ..Default::default()
+        };
+        let result = html(diff, &config);
+
+        assert!(result.contains("d2h-file-mode"));
+        assert!(result.contains("100644 → 100755"));
+        assert!(result.contains("EXECUTABLE BIT CHANGED"));
+    }
+}
+
+#[test]
+fn test_html_mode_only_change() {
+    let diff = "diff --git a/script.sh b/script.sh\n\
+                old mode 100644\n\
+                new mode 100755\n";
+    let config = Diff2HtmlConfig {
+        draw_file_list: false,
+        render_nothing_when_empty: true,
+        ..Default::default()
+    };
+    let result = html(diff, &config);
+
+    assert!(result.contains("script.sh"));
+    assert!(result.contains("File mode changed from 100644 to 100755"));
+    assert!(!result.contains("File without changes"));
+}
+
+#[test]
+fn test_html_word_diff_porcelain() {
+    let diff = load_fixture("word_diff.diff");
+
+    for output_format in [OutputFormat::LineByLine, OutputFormat::SideBySide] {
+        let config = Diff2HtmlConfig {
+            output_format,
+            ..Default::default()
+        };
+        let result = html(&diff, &config);
+
+        assert!(result.contains("Hello <del>world</del>!"));
+        assert!(result.contains("Hello <ins>there</ins>!"));
+        assert!(result.contains("<del>Goodbye</del>"));
+        assert!(!result.contains("~"));
+    }
+}
+
+#[test]
+fn test_html_no_newline_at_eof() {
+    let diff = "diff --git a/a.txt b/a.txt\n\
+                --- a/a.txt\n\
+                +++ b/a.txt\n\
+                @@ -1 +1 @@\n\
+                -old\n\
+                \\ No newline at end of file\n\
+                +new\n";
+
+    for output_format in [OutputFormat::LineByLine, OutputFormat::SideBySide] {
+        let config = Diff2HtmlConfig {
+            output_format,
+            ..Default::default()
+        };
+        let result = html(diff, &config);
+
+        assert_eq!(result.matches("d2h-no-newline").count(), 1);
+        assert!(result.contains("⛔ No newline at end of file"));
+    }
+}
+
+#[test]
+fn test_html_no_file_mode_without_change() {
+    let diff = load_fixture("simple.diff");
+    let result = html(&diff, &Diff2HtmlConfig::default());
+
+    assert!(!result.contains("d2h-file-mode"));
+}
+
 // =============================================================================
 // HTML Escaping Tests
 // =============================================================================
@@ -393,6 +753,167 @@ fn test_html_does_not_double_escape() {
     assert!(!result.contains("&amp;gt;"));
 }
 
+/// Fragments that break out of the HTML context when not escaped, as an
+/// element or as an attribute of the element they are rendered in.
+const INJECTIONS: &[&str] = &[
+    "<x-evil>",
+    "</td></tr><x-evil>",
+    "\"x-evil=\"",
+    "'x-evil='",
+    "&",
+    "{{x}}",
+    " ",
+    "/",
+    "\t",
+    "\u{0}",
+    "\u{202e}",
+    "é",
+    "-",
+    "+",
+    "@@",
+];
+
+/// Small xorshift generator, so the randomized tests are reproducible.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self, bound: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % bound as u64) as usize
+    }
+
+    /// Random text made of injections and printable bytes.
+    fn text(&mut self) -> String {
+        (0..1 + self.next(6))
+            .map(|_| match self.next(3) {
+                0 => char::from(b' ' + self.next(95) as u8).to_string(),
+                _ => INJECTIONS[self.next(INJECTIONS.len())].to_string(),
+            })
+            .collect()
+    }
+
+    /// Random text without line breaks, usable in a file path.
+    fn path(&mut self) -> String {
+        self.text().replace(['\t', ' '], "_")
+    }
+}
+
+/// Assert no injection escaped the HTML context.
+fn assert_escaped(html: &str, diff: &str) {
+    for broken in ["<x-evil", "\"x-evil", "'x-evil"] {
+        if let Some(index) = html.find(broken) {
+            let context = html[..index].chars().rev().take(200).collect::<Vec<_>>();
+            let context: String = context.into_iter().rev().collect();
+            panic!("{broken:?} not escaped after {context:?} for the diff:\n{diff}");
+        }
+    }
+}
+
+/// Generate a random diff over the injections, in file names, hunk headers,
+/// line contents and git extended headers.
+fn random_diff(rng: &mut Rng) -> String {
+    let mut diff = String::new();
+    for _ in 0..1 + rng.next(3) {
+        let (old, new) = (rng.path(), rng.path());
+        diff.push_str(&format!("diff --git a/{old} b/{new}\n"));
+        match rng.next(4) {
+            0 => diff.push_str(&format!(
+                "similarity index 90%\nrename from {old}\nrename to {new}\n"
+            )),
+            1 => diff.push_str("new file mode 100755\n"),
+            _ => {}
+        }
+        diff.push_str(&format!("--- a/{old}\n+++ b/{new}\n"));
+        for block in 0..1 + rng.next(2) {
+            let start = 1 + block * 10;
+            diff.push_str(&format!("@@ -{start},3 +{start},3 @@ {}\n", rng.text()));
+            for prefix in [' ', '-', '-', '+', '+', ' '] {
+                diff.push_str(&format!("{prefix}{}\n", rng.text()));
+            }
+        }
+    }
+    diff
+}
+
+#[test]
+fn test_html_escapes_random_diffs() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for _ in 0..300 {
+        let diff = random_diff(&mut rng);
+        for (output_format, output_markup) in [
+            (OutputFormat::LineByLine, OutputMarkup::Table),
+            (OutputFormat::SideBySide, OutputMarkup::Table),
+            (OutputFormat::Switchable, OutputMarkup::Table),
+            (OutputFormat::LineByLine, OutputMarkup::Semantic),
+            (OutputFormat::SideBySide, OutputMarkup::Semantic),
+        ] {
+            // Some files are too big, with the default message
+            let config = Diff2HtmlConfig {
+                output_format,
+                output_markup,
+                draw_jump_menu: true,
+                draw_language_bar: true,
+                matching: LineMatchingType::Lines,
+                diff_max_changes: Some(10),
+                ..Default::default()
+            };
+            assert_escaped(&html(&diff, &config), &diff);
+        }
+    }
+}
+
+#[test]
+fn test_html_escapes_random_file_metadata() {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    for _ in 0..300 {
+        let diff = random_diff(&mut rng);
+        let mut files = parse(&diff, &DiffParserConfig::default());
+        for file in &mut files {
+            file.language = rng.text();
+            file.old_mode = Some(FileMode::Single(rng.text()));
+            file.new_mode = Some(rng.text());
+            file.is_too_big = Some(rng.next(2) == 0);
+            for block in &mut file.blocks {
+                block.header = rng.text();
+            }
+        }
+        let config = Diff2HtmlConfig {
+            draw_jump_menu: true,
+            draw_language_bar: true,
+            show_file_metadata: true,
+            hardened: true,
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&files).unwrap();
+        let result = html_from_diff_files(&files, &config);
+        assert_escaped(&result, &json);
+        assert_escaped(&FileListRenderer::default().render_toc(&files), &json);
+        assert_eq!(result, html_from_diff_files(&files, &config));
+    }
+}
+
+#[test]
+fn test_html_hardened_escapes_too_big_header() {
+    let mut files = parse(&load_fixture("simple.diff"), &DiffParserConfig::default());
+    files[0].is_too_big = Some(true);
+    files[0].blocks[0].header = "<img src=x onerror=alert(1)>".to_string();
+
+    let config = Diff2HtmlConfig {
+        draw_file_list: false,
+        ..Default::default()
+    };
+    assert!(html_from_diff_files(&files, &config).contains("<img src=x"));
+    let config = Diff2HtmlConfig {
+        hardened: true,
+        ..config
+    };
+    let result = html_from_diff_files(&files, &config);
+    assert!(!result.contains("<img src=x"));
+    assert!(result.contains("&lt;img src=x onerror=alert(1)&gt;"));
+}
+
 // =============================================================================
 // JSON Output Tests
 // =============================================================================
@@ -532,6 +1053,536 @@ fn test_html_binary_file() {
     );
 }
 
+#[test]
+fn test_html_git_binary_patch_sizes() {
+    let diff = load_fixture("binary_patch.diff");
+
+    for output_format in [OutputFormat::LineByLine, OutputFormat::SideBySide] {
+        let config = Diff2HtmlConfig {
+            output_format,
+            draw_file_list: false,
+            ..Default::default()
+        };
+        let result = html(&diff, &config);
+
+        assert!(result.contains("Binary file changed (12.4 KB → 13.1 KB)"));
+        assert!(!result.contains("GIT binary patch"));
+    }
+}
+
+#[derive(Debug)]
+struct FixedImages;
+
+impl ImageSource for FixedImages {
+    fn preview(&self, file: &DiffFile) -> Option<ImagePreview> {
+        Some(ImagePreview {
+            before: Some(format!("before/{}", file.old_name)),
+            after: Some(format!("after/{}", file.new_name)),
+        })
+    }
+}
+
+#[test]
+fn test_html_binary_image_preview() {
+    let diff = load_fixture("binary_patch.diff");
+
+    for output_format in [OutputFormat::LineByLine, OutputFormat::SideBySide] {
+        let config = Diff2HtmlConfig {
+            output_format,
+            draw_file_list: false,
+            image_source: Some(Arc::new(FixedImages)),
+            ..Default::default()
+        };
+        let result = html(&diff, &config);
+
+        assert!(result.contains("d2h-image-preview"));
+        assert!(result.contains(r#"src="before&#x2F;logo.png""#));
+        assert!(result.contains(r#"src="after&#x2F;logo.png""#));
+        assert!(!result.contains("Binary file changed"));
+    }
+}
+
+#[derive(Debug)]
+struct OwnerBadge;
+
+impl FileHeaderExtra for OwnerBadge {
+    fn html(&self, file: &DiffFile) -> String {
+        if file.new_name.ends_with(".js") {
+            format!(r#"<span class="owner">{}</span>"#, file.new_name.len())
+        } else {
+            String::new()
+        }
+    }
+}
+
+#[test]
+fn test_html_file_header_extra() {
+    let diff = load_fixture("multiple_files.diff");
+
+    for output_format in [OutputFormat::LineByLine, OutputFormat::SideBySide] {
+        let config = Diff2HtmlConfig {
+            output_format,
+            file_header_extra: Some(Arc::new(OwnerBadge)),
+            ..Default::default()
+        };
+        let result = html(&diff, &config);
+
+        assert_eq!(result.matches("d2h-file-header-extra").count(), 2);
+        assert!(result.contains(r#"<span class="owner">16</span>"#));
+        assert!(result.contains(r#"<span class="owner">12</span>"#));
+    }
+
+    let result = html(&diff, &Diff2HtmlConfig::default());
+    assert!(!result.contains("d2h-file-header-extra"));
+}
+
+#[derive(Debug)]
+struct MarkHook;
+
+impl RenderHook for MarkHook {
+    fn on_line_content(&self, line: &DiffLine, html: String) -> String {
+        format!("{html}<i>{}</i>", line.new_number.unwrap_or_default())
+    }
+
+    fn on_file_html(&self, file: &DiffFile, html: String) -> String {
+        format!("<section data-file=\"{}\">{html}</section>", file.new_name)
+    }
+}
+
+#[test]
+fn test_html_render_hooks() {
+    let diff = load_fixture("simple.diff");
+
+    for output_format in [OutputFormat::LineByLine, OutputFormat::SideBySide] {
+        let config = Diff2HtmlConfig {
+            output_format,
+            draw_file_list: false,
+            render_hooks: vec![Arc::new(MarkHook)],
+            ..Default::default()
+        };
+        let result = html(&diff, &config);
+
+        assert!(result.contains("<section data-file=\"sample\"><div id=\"d2h-"));
+        assert!(result.contains("<i>1</i></span>"));
+        // Deleted lines have no new line number
+        assert!(result.contains("<i>0</i></span>"));
+    }
+}
+
+#[test]
+fn test_html_autolink() {
+    let diff = "--- a/notes.md\n+++ b/notes.md\n@@ -1 +1 @@\n\
+                -See https://example.com/docs for #12\n\
+                +See https://example.com/guide for #13\n";
+
+    for output_format in [OutputFormat::LineByLine, OutputFormat::SideBySide] {
+        let config = Diff2HtmlConfig {
+            output_format,
+            autolink: Some(AutolinkConfig {
+                urls: true,
+                references: vec![
+                    AutolinkReference::new(r"#(\d+)", "https://example.com/issues/$1").unwrap(),
+                ],
+            }),
+            ..Default::default()
+        };
+        let result = html(diff, &config);
+
+        assert!(result.contains(r#"href="https:&#x2F;&#x2F;example.com&#x2F;issues&#x2F;13""#));
+        assert!(result.contains("d2h-autolink"));
+    }
+
+    let result = html(diff, &Diff2HtmlConfig::default());
+    assert!(!result.contains("d2h-autolink"));
+}
+
+#[derive(Debug)]
+struct FixedBlame;
+
+impl BlameSource for FixedBlame {
+    fn blame(&self, _file: &DiffFile) -> Option<FileBlame> {
+        let line = |author: &str| LineBlame {
+            author: author.to_string(),
+            commit: "0123456789abcdef".to_string(),
+            summary: Some("Update sample".to_string()),
+        };
+        let mut blame = FileBlame::default();
+        blame.old_lines.insert(1, line("Old Author"));
+        blame.new_lines.insert(1, line("New Author"));
+        Some(blame)
+    }
+}
+
+#[test]
+fn test_html_blame() {
+    let diff = load_fixture("simple.diff");
+
+    for output_format in [OutputFormat::LineByLine, OutputFormat::SideBySide] {
+        let config = Diff2HtmlConfig {
+            output_format,
+            blame_source: Some(Arc::new(FixedBlame)),
+            ..Default::default()
+        };
+        let result = html(&diff, &config);
+
+        assert!(
+            result.contains(
+                r#"<div class="d2h-blame" title="0123456 Update sample">Old Author</div>"#
+            )
+        );
+        assert!(
+            result.contains(
+                r#"<div class="d2h-blame" title="0123456 Update sample">New Author</div>"#
+            )
+        );
+    }
+
+    let result = html(&diff, &Diff2HtmlConfig::default());
+    assert!(!result.contains("d2h-blame"));
+}
+
+#[test]
+fn test_html_single_column_added_deleted() {
+    let config = Diff2HtmlConfig {
+        output_format: OutputFormat::SideBySide,
+        single_column_added_deleted: true,
+        ..Default::default()
+    };
+
+    for (fixture, line) in [
+        ("new_file.diff", "d2h-ins"),
+        ("deleted_file.diff", "d2h-del"),
+    ] {
+        let diff = load_fixture(fixture);
+        let result = html(&diff, &config);
+        assert!(result.contains("d2h-files-diff d2h-files-diff-single"));
+        assert_eq!(
+            result
+                .matches(r#"<div class="d2h-file-side-diff">"#)
+                .count(),
+            1
+        );
+        assert!(result.contains(&format!(r#"<td class="d2h-code-side-linenumber {line}">"#)));
+        assert!(!result.contains("d2h-emptyplaceholder"), "{fixture}");
+        assert!(result.contains("@@ -"), "{fixture}: block header");
+    }
+
+    // Files with both versions keep two columns
+    let result = html(&load_fixture("simple.diff"), &config);
+    assert!(!result.contains("d2h-files-diff-single"));
+    assert_eq!(
+        result
+            .matches(r#"<div class="d2h-file-side-diff">"#)
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn test_html_max_rendered_line_length() {
+    let long = "x".repeat(50);
+    let diff = format!(
+        "--- a/bundle.min.js\n+++ b/bundle.min.js\n@@ -1,2 +1,2 @@\n short\n-{long}a\n+{long}b\n"
+    );
+
+    for output_format in [OutputFormat::LineByLine, OutputFormat::SideBySide] {
+        let config = Diff2HtmlConfig {
+            output_format,
+            max_rendered_line_length: Some(20),
+            ..Default::default()
+        };
+        let result = html(&diff, &config);
+
+        assert_eq!(result.matches(r#"<span class="d2h-truncated">"#).count(), 2);
+        let rest = format!(
+            r#"<span class="d2h-truncated-rest" hidden>{}a</span>"#,
+            "x".repeat(30)
+        );
+        assert!(result.contains(&rest));
+        assert!(result.contains(&format!("{}<span", "x".repeat(20))));
+        // Truncated lines are not highlighted
+        assert!(!result.contains("<ins>") && !result.contains("<del>"));
+        assert!(result.contains(">short</span>"));
+    }
+}
+
+#[test]
+fn test_html_minified_files() {
+    let bundle = "var a=1;".repeat(200);
+    let diff = format!("--- a/app.min.js\n+++ b/app.min.js\n@@ -1 +1 @@\n-{bundle}\n+{bundle}b\n");
+
+    for output_format in [OutputFormat::LineByLine, OutputFormat::SideBySide] {
+        let config = Diff2HtmlConfig {
+            output_format,
+            minified_files: Some(MinifiedFileDetection::default()),
+            ..Default::default()
+        };
+        let result = html(&diff, &config);
+        assert!(result.contains(r#"class="d2h-tag d2h-minified-tag""#));
+        assert_eq!(result.matches(r#"<span class="d2h-truncated">"#).count(), 2);
+        assert!(!result.contains("<ins>"));
+
+        let result = html(
+            &diff,
+            &Diff2HtmlConfig {
+                minified_files: None,
+                ..config
+            },
+        );
+        assert!(!result.contains(r#"class="d2h-tag d2h-minified-tag""#));
+        assert!(result.contains("<ins>"));
+    }
+}
+
+#[test]
+fn test_html_wrap_lines() {
+    let diff = load_fixture("multiple_blocks.diff");
+    let files = parse(&diff, &DiffParserConfig::default());
+
+    for output_format in [OutputFormat::LineByLine, OutputFormat::SideBySide] {
+        for output_markup in [OutputMarkup::Table, OutputMarkup::Semantic] {
+            let config = Diff2HtmlConfig {
+                output_format,
+                output_markup,
+                ..Default::default()
+            };
+            assert!(!html(&diff, &config).contains("d2h-file-wrapper d2h-wrap-lines"));
+
+            let result = html(
+                &diff,
+                &Diff2HtmlConfig {
+                    wrap_lines: true,
+                    ..config
+                },
+            );
+            assert_eq!(
+                result.matches("d2h-file-wrapper d2h-wrap-lines").count(),
+                files.len()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_html_sticky_headers() {
+    let diff = load_fixture("multiple_blocks.diff");
+
+    for output_format in [OutputFormat::LineByLine, OutputFormat::SideBySide] {
+        let config = Diff2HtmlConfig {
+            output_format,
+            ..Default::default()
+        };
+        let result = html(&diff, &config);
+        assert!(!result.contains("d2h-sticky"));
+
+        let result = html(
+            &diff,
+            &Diff2HtmlConfig {
+                sticky_headers: true,
+                ..config
+            },
+        );
+        let files = parse(&diff, &DiffParserConfig::default());
+        let blocks: usize = files.iter().map(|file| file.blocks.len()).sum();
+        assert_eq!(
+            result
+                .matches("d2h-file-wrapper d2h-sticky-headers")
+                .count(),
+            files.len()
+        );
+        assert_eq!(
+            result.matches("d2h-file-header d2h-sticky-header").count(),
+            files.len()
+        );
+        let sides = match output_format {
+            OutputFormat::LineByLine => 1,
+            OutputFormat::SideBySide => 2,
+            OutputFormat::Switchable => unreachable!(),
+        };
+        assert_eq!(
+            result
+                .matches(r#"<tr class="d2h-sticky-block-header">"#)
+                .count(),
+            blocks * sides
+        );
+    }
+}
+
+#[test]
+fn test_html_jump_menu() {
+    let diff = load_fixture("multiple_files.diff");
+    let config = Diff2HtmlConfig {
+        draw_jump_menu: true,
+        deterministic: true,
+        ..Default::default()
+    };
+    let result = html(&diff, &config);
+
+    assert!(result.starts_with(r#"<details class="d2h-file-jump d2h-light-color-scheme">"#));
+    for file in parse(&diff, &config.to_parser_config()) {
+        let link = format!(
+            r##"<a href="#{}" class="d2h-file-jump-link">"##,
+            get_stable_html_id(&file)
+        );
+        assert_eq!(result.matches(&link).count(), 1);
+    }
+    assert!(!html(&diff, &Diff2HtmlConfig::default()).contains("d2h-file-jump"));
+}
+
+#[test]
+fn test_html_responsive_breakpoint() {
+    let diff = load_fixture("simple.diff");
+    let config = Diff2HtmlConfig {
+        output_format: OutputFormat::SideBySide,
+        ..Default::default()
+    };
+    assert!(!html(&diff, &config).contains("d2h-files-diff-responsive"));
+
+    let result = html(
+        &diff,
+        &Diff2HtmlConfig {
+            responsive_breakpoint: Some(768),
+            ..config
+        },
+    );
+    assert!(result.contains(r#"<div class="d2h-files-diff d2h-files-diff-responsive">"#));
+}
+
+#[test]
+fn test_html_deterministic() {
+    let diff = load_fixture("multiple_files.diff");
+
+    for output_format in [OutputFormat::LineByLine, OutputFormat::SideBySide] {
+        let config = Diff2HtmlConfig {
+            output_format,
+            deterministic: true,
+            ..Default::default()
+        };
+        let result = html(&diff, &config);
+
+        assert_eq!(result, html(&diff, &config));
+        for file in parse(&diff, &config.to_parser_config()) {
+            // The file list links to the file
+            let id = format!(r#"id="{}""#, get_stable_html_id(&file));
+            assert_eq!(result.matches(&id).count(), 1);
+            assert!(result.contains(&format!(r##"href="#{}""##, get_stable_html_id(&file))));
+        }
+    }
+}
+
+#[test]
+fn test_html_pretty_print() {
+    let diff = load_fixture("multiple_files.diff");
+
+    for output_format in [OutputFormat::LineByLine, OutputFormat::SideBySide] {
+        let config = Diff2HtmlConfig {
+            output_format,
+            ..Default::default()
+        };
+        let raw = html(&diff, &config);
+        let pretty = html(
+            &diff,
+            &Diff2HtmlConfig {
+                pretty_print: true,
+                ..config
+            },
+        );
+
+        assert_ne!(pretty, raw);
+        assert_eq!(pretty_print_html(&pretty), pretty);
+        // Only whitespace changes
+        let strip = |html: &str| html.split_whitespace().collect::<String>();
+        assert_eq!(strip(&pretty), strip(&raw));
+        for line in pretty.lines() {
+            let indent = line.len() - line.trim_start().len();
+            assert_eq!(indent % 2, 0, "unexpected indentation: {line:?}");
+        }
+    }
+}
+
+#[test]
+fn test_html_has_no_inline_styles_or_scripts() {
+    let fixtures = std::fs::read_dir(format!("{}/tests/fixtures", env!("CARGO_MANIFEST_DIR")))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|name| name.ends_with(".diff"));
+
+    for fixture in fixtures {
+        let diff = load_fixture(&fixture);
+        for output_format in [OutputFormat::LineByLine, OutputFormat::SideBySide] {
+            for keep_too_big_content in [false, true] {
+                let config = Diff2HtmlConfig {
+                    output_format,
+                    diff_max_changes: Some(2),
+                    keep_too_big_content,
+                    show_file_metadata: true,
+                    image_source: Some(Arc::new(FixedImages)),
+                    blame_source: Some(Arc::new(FixedBlame)),
+                    autolink: Some(AutolinkConfig {
+                        urls: true,
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                };
+                let result = html(&diff, &config).to_lowercase();
+
+                assert!(!result.contains("style="), "{fixture}: inline style");
+                assert!(!result.contains("<style"), "{fixture}: style element");
+                assert!(!result.contains("<script"), "{fixture}: script element");
+                assert!(!result.contains("javascript:"), "{fixture}: script URL");
+                assert!(
+                    !result
+                        .split(|c: char| c.is_ascii_whitespace())
+                        .any(|word| word.starts_with("on") && word.contains('=')),
+                    "{fixture}: event handler"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_html_semantic_markup() {
+    let fixtures = std::fs::read_dir(format!("{}/tests/fixtures", env!("CARGO_MANIFEST_DIR")))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|name| name.ends_with(".diff"));
+
+    for fixture in fixtures {
+        let diff = load_fixture(&fixture);
+        for output_format in [OutputFormat::LineByLine, OutputFormat::SideBySide] {
+            let config = Diff2HtmlConfig {
+                output_format,
+                diff_max_changes: Some(2),
+                image_source: Some(Arc::new(FixedImages)),
+                ..Default::default()
+            };
+            let table = html(&diff, &config);
+            let semantic = html(
+                &diff,
+                &Diff2HtmlConfig {
+                    output_markup: OutputMarkup::Semantic,
+                    ..config
+                },
+            );
+
+            for tag in ["<table", "<tbody", "<tr", "<td"] {
+                assert!(!semantic.contains(tag), "{fixture}: {tag}");
+            }
+            assert_eq!(
+                semantic.matches(r#"role="row""#).count(),
+                table.matches("<tr").count(),
+                "{fixture}: rows"
+            );
+            assert_eq!(
+                semantic.matches("d2h-code-line-ctn").count(),
+                table.matches("d2h-code-line-ctn").count(),
+                "{fixture}: lines"
+            );
+        }
+    }
+}
+
 // =============================================================================
 // Rename File HTML Tests
 // =============================================================================